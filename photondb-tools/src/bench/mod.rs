@@ -116,6 +116,11 @@ pub(crate) struct Args {
     #[arg(long, default_value_t = 8192)]
     page_size: u64,
 
+    /// Store keys and values of a page separately, so that binary searches
+    /// only touch the keys.
+    #[arg(long, default_value_t = false)]
+    separated_page_layout: bool,
+
     /// Size for read page cache.
     #[arg(long, default_value_t = 134217728)]
     cache_size: u64,
@@ -144,10 +149,26 @@ pub(crate) struct Args {
     #[arg(long, default_value_t = 1)]
     verify_checksum: u8,
 
+    /// Verify pages served from the page cache.
+    #[arg(long, default_value_t = false)]
+    verify_checksums_on_cache_hit: bool,
+
     /// Enable compression or not.
     #[arg(long, default_value_t = false)]
     enable_compression: bool,
 
+    /// Number of pages to prefetch ahead during sequential reads.
+    #[arg(long, default_value_t = 0)]
+    readahead: usize,
+
+    /// Use O_DIRECT to read and write page files.
+    #[arg(long, default_value_t = false)]
+    use_direct_io: bool,
+
+    /// Serve page reads from memory mappings of page files.
+    #[arg(long, default_value_t = false)]
+    mmap_reads: bool,
+
     /// Does report error when no enough memory.
     #[arg(long, default_value_t = false)]
     cache_strict_capacity_limit: bool,
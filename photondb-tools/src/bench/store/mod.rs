@@ -16,6 +16,9 @@ pub(crate) trait Store<E>: std::fmt::Debug + Clone + Sync + Send + 'static {
 
     async fn get(&self, key: &[u8], lsn: u64) -> Result<Option<Vec<u8>>>;
 
+    /// Scans all entries and returns the number of entries and bytes read.
+    async fn scan(&self, readahead: usize) -> Result<(u64, u64)>;
+
     async fn flush(&self);
 
     async fn wait_for_reclaiming(&self);
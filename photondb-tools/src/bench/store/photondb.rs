@@ -2,7 +2,8 @@ use std::sync::Arc;
 
 use async_trait::async_trait;
 use photondb::{
-    env::Env, raw::Table, ChecksumType, Compression, FlushOptions, TableOptions, TableStats,
+    env::Env, raw::Table, ChecksumType, Compression, FlushOptions, PageLayout, TableOptions,
+    TableStats,
 };
 
 use super::Store;
@@ -32,12 +33,20 @@ where
         options.page_store.disable_space_reclaiming = config.disable_space_reclaiming;
         options.page_store.max_space_amplification_percent = config.max_space_amplification_percent;
         options.page_store.space_used_high = config.space_used_high;
+        options.page_store.use_direct_io = config.use_direct_io;
+        options.page_store.mmap_reads = config.mmap_reads;
         options.page_size = config.page_size as usize;
+        options.page_layout = if config.separated_page_layout {
+            PageLayout::Separated
+        } else {
+            PageLayout::Interleaved
+        };
         options.page_store.page_checksum_type = if config.verify_checksum == 1 {
             ChecksumType::CRC32
         } else {
             ChecksumType::NONE
         };
+        options.page_store.verify_checksums_on_cache_hit = config.verify_checksums_on_cache_hit;
         if !config.enable_compression {
             options.page_store.compression_on_cold_compact = Compression::NONE;
             options.page_store.compression_on_flush = Compression::NONE;
@@ -61,6 +70,19 @@ where
         Ok(r)
     }
 
+    async fn scan(&self, readahead: usize) -> Result<(u64, u64)> {
+        let guard = self.table.pin();
+        let mut pages = guard.pages().with_readahead(readahead);
+        let (mut entries, mut bytes) = (0, 0);
+        while let Some(page) = pages.next().await.expect("scan fail") {
+            for (key, value) in page {
+                entries += 1;
+                bytes += (key.len() + value.len() + std::mem::size_of::<u64>()) as u64;
+            }
+        }
+        Ok((entries, bytes))
+    }
+
     async fn flush(&self) {
         self.table.flush(&FlushOptions::default()).await;
     }
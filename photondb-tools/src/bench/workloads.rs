@@ -172,6 +172,7 @@ impl<S: Store<E>, E: Env> Workloads<S, E> {
                     BenchmarkType::Fillseq => {
                         Self::do_write(&mut task_ctx, GenMode::Sequence).await
                     }
+                    BenchmarkType::ReadSeq => Self::do_read_seq(&mut task_ctx).await,
                     BenchmarkType::ReadRandom => Self::do_read_random(&mut task_ctx).await,
                     BenchmarkType::UpdateRandom => Self::do_update_random(&mut task_ctx).await,
                     BenchmarkType::ReadRandomWriteRandom => {
@@ -264,6 +265,16 @@ impl<S: Store<E>, E: Env> Workloads<S, E> {
         }
     }
 
+    async fn do_read_seq(ctx: &mut TaskCtx<S, E>) {
+        let table = ctx.table.clone();
+        let (entries, bytes) = table.scan(ctx.config.readahead).await.expect("scan fail");
+        ctx.stats
+            .borrow_mut()
+            .finish_operation(OpType::Read, entries, 0, bytes);
+        let msg = format!("(entries:{entries} readahead:{})", ctx.config.readahead);
+        ctx.stats.borrow_mut().add_msg(&msg);
+    }
+
     async fn do_read_random(ctx: &mut TaskCtx<S, E>) {
         let table = ctx.table.clone();
         let cfg = ctx.config.to_owned();
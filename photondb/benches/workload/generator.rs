@@ -0,0 +1,246 @@
+use std::ops::RangeInclusive;
+
+/// The distribution of keys accessed by a [`Workload`].
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum KeyDistribution {
+    /// Keys are accessed uniformly at random.
+    Uniform,
+    /// Keys are accessed with a Zipfian distribution, so that a few keys are
+    /// much hotter than the others.
+    ///
+    /// The hottest keys are scattered over the key space, instead of being
+    /// next to each other.
+    Zipfian {
+        /// The skew of the distribution, from 0 to 1 exclusively. A larger
+        /// theta makes hot keys hotter.
+        theta: f64,
+    },
+}
+
+/// Options to generate a [`Workload`].
+#[derive(Clone, Debug)]
+pub struct WorkloadOptions {
+    /// The number of distinct keys.
+    ///
+    /// Default: 1 << 20
+    pub num_keys: u64,
+    /// The size of keys in bytes, at least 8.
+    ///
+    /// Keys are the big-endian encoding of the key index, padded with zeros.
+    ///
+    /// Default: 16
+    pub key_size: usize,
+    /// The sizes of values in bytes, sizes are picked uniformly from the
+    /// range.
+    ///
+    /// Default: 100..=100
+    pub value_size: RangeInclusive<usize>,
+    /// The distribution of accessed keys.
+    ///
+    /// Default: [`KeyDistribution::Uniform`]
+    pub distribution: KeyDistribution,
+    /// The percentage of reads in operations, from 0 to 100.
+    ///
+    /// Default: 50
+    pub read_percent: u8,
+    /// The seed of random numbers, workloads with the same options generate
+    /// the same operations.
+    ///
+    /// Default: 0
+    pub seed: u64,
+}
+
+impl Default for WorkloadOptions {
+    fn default() -> Self {
+        Self {
+            num_keys: 1 << 20,
+            key_size: 16,
+            value_size: 100..=100,
+            distribution: KeyDistribution::Uniform,
+            read_percent: 50,
+            seed: 0,
+        }
+    }
+}
+
+/// An operation generated by a [`Workload`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Operation<'a> {
+    /// Reads the value of the key.
+    Get(&'a [u8]),
+    /// Writes the value of the key.
+    Put(&'a [u8], &'a [u8]),
+}
+
+/// A generator of reads and writes to benchmark tables with realistic
+/// workloads.
+///
+/// Keys and values are generated into internal buffers without allocation,
+/// so the generation is cheap compared to the operations.
+#[derive(Clone, Debug)]
+pub struct Workload {
+    options: WorkloadOptions,
+    rng: Rng,
+    zipfian: Option<Zipfian>,
+    key: Vec<u8>,
+    values: Vec<u8>,
+}
+
+impl Workload {
+    /// Creates a workload with the options.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the options are invalid.
+    pub fn new(options: WorkloadOptions) -> Self {
+        assert!(options.num_keys > 0, "num_keys must be positive");
+        assert!(options.key_size >= 8, "key_size must be at least 8");
+        assert!(
+            !options.value_size.is_empty(),
+            "value_size must not be empty"
+        );
+        assert!(
+            options.read_percent <= 100,
+            "read_percent must be at most 100"
+        );
+        let zipfian = match options.distribution {
+            KeyDistribution::Zipfian { theta } => {
+                assert!(theta > 0.0 && theta < 1.0, "theta must be in (0, 1)");
+                Some(Zipfian::new(options.num_keys, theta))
+            }
+            _ => None,
+        };
+        let mut rng = Rng::new(options.seed);
+        // Values are slices of random printable bytes, so that they are not
+        // trivially compressible.
+        let values = (0..*options.value_size.end() * 2)
+            .map(|_| b' ' + rng.below(95) as u8)
+            .collect();
+        Self {
+            key: vec![0; options.key_size],
+            options,
+            rng,
+            zipfian,
+            values,
+        }
+    }
+
+    /// Returns the options of the workload.
+    pub fn options(&self) -> &WorkloadOptions {
+        &self.options
+    }
+
+    /// Returns the key and a value of the index, which can be used to load
+    /// all keys before running the workload.
+    pub fn record(&mut self, index: u64) -> (&[u8], &[u8]) {
+        self.key[..8].copy_from_slice(&index.to_be_bytes());
+        let value = Self::value(&mut self.rng, &self.values, &self.options);
+        (&self.key, value)
+    }
+
+    /// Generates the next operation.
+    pub fn next_op(&mut self) -> Operation<'_> {
+        let index = self.next_index();
+        self.key[..8].copy_from_slice(&index.to_be_bytes());
+        if self.rng.below(100) < self.options.read_percent as u64 {
+            Operation::Get(&self.key)
+        } else {
+            let value = Self::value(&mut self.rng, &self.values, &self.options);
+            Operation::Put(&self.key, value)
+        }
+    }
+
+    fn next_index(&mut self) -> u64 {
+        let num_keys = self.options.num_keys;
+        match self.options.distribution {
+            KeyDistribution::Uniform => self.rng.below(num_keys),
+            KeyDistribution::Zipfian { .. } => {
+                let zipfian = self.zipfian.as_ref().unwrap();
+                let rank = zipfian.sample(self.rng.next_f64());
+                mix(rank) % num_keys
+            }
+        }
+    }
+
+    fn value<'a>(rng: &mut Rng, values: &'a [u8], options: &WorkloadOptions) -> &'a [u8] {
+        let (min, max) = (*options.value_size.start(), *options.value_size.end());
+        let size = min + rng.below((max - min + 1) as u64) as usize;
+        let start = rng.below((values.len() - size + 1) as u64) as usize;
+        &values[start..start + size]
+    }
+}
+
+/// A SplitMix64 generator, which is fast and good enough for benchmarks.
+#[derive(Clone, Debug)]
+struct Rng(u64);
+
+impl Rng {
+    fn new(seed: u64) -> Self {
+        Self(seed)
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        mix(self.0)
+    }
+
+    /// Returns a number in `[0, n)`.
+    fn below(&mut self, n: u64) -> u64 {
+        ((self.next_u64() as u128 * n as u128) >> 64) as u64
+    }
+
+    /// Returns a number in `[0, 1)`.
+    fn next_f64(&mut self) -> f64 {
+        (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64
+    }
+}
+
+/// The finalizer of SplitMix64, which scatters close numbers over the whole
+/// range.
+fn mix(mut z: u64) -> u64 {
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    z ^ (z >> 31)
+}
+
+/// Samples ranks from a Zipfian distribution, as described in "Quickly
+/// Generating Billion-Record Synthetic Databases" by Gray et al.
+#[derive(Clone, Debug)]
+struct Zipfian {
+    n: u64,
+    theta: f64,
+    alpha: f64,
+    zeta_n: f64,
+    eta: f64,
+}
+
+impl Zipfian {
+    fn new(n: u64, theta: f64) -> Self {
+        let zeta = |n: u64| (1..=n).map(|i| 1.0 / (i as f64).powf(theta)).sum::<f64>();
+        let zeta_n = zeta(n);
+        let zeta_2 = zeta(2);
+        let alpha = 1.0 / (1.0 - theta);
+        let eta = (1.0 - (2.0 / n as f64).powf(1.0 - theta)) / (1.0 - zeta_2 / zeta_n);
+        Self {
+            n,
+            theta,
+            alpha,
+            zeta_n,
+            eta,
+        }
+    }
+
+    /// Returns a rank in `[0, n)` for a uniform number in `[0, 1)`, where
+    /// smaller ranks are more likely.
+    fn sample(&self, u: f64) -> u64 {
+        let uz = u * self.zeta_n;
+        if uz < 1.0 {
+            return 0;
+        }
+        if uz < 1.0 + 0.5f64.powf(self.theta) {
+            return 1.min(self.n - 1);
+        }
+        let rank = self.n as f64 * (self.eta * u - self.eta + 1.0).powf(self.alpha);
+        (rank as u64).min(self.n - 1)
+    }
+}
@@ -0,0 +1,43 @@
+use criterion::{criterion_group, criterion_main, Criterion, Throughput};
+use photondb::{std::Table, TableOptions};
+use tempfile::tempdir;
+
+mod generator;
+use generator::{KeyDistribution, Operation, Workload, WorkloadOptions};
+
+fn zipfian_read_heavy(c: &mut Criterion) {
+    let path = tempdir().unwrap();
+    let table = Table::open(&path, TableOptions::default()).unwrap();
+    let mut workload = Workload::new(WorkloadOptions {
+        num_keys: 1 << 16,
+        distribution: KeyDistribution::Zipfian { theta: 0.99 },
+        read_percent: 95,
+        ..Default::default()
+    });
+
+    let mut lsn = 0;
+    for i in 0..workload.options().num_keys {
+        lsn += 1;
+        let (key, value) = workload.record(i);
+        table.put(key, lsn, value).unwrap();
+    }
+
+    let mut group = c.benchmark_group("workload");
+    group.throughput(Throughput::Elements(1));
+    group.bench_function("zipfian_read_heavy", |b| {
+        b.iter(|| match workload.next_op() {
+            Operation::Get(key) => {
+                table.get(key, lsn).unwrap();
+            }
+            Operation::Put(key, value) => {
+                lsn += 1;
+                table.put(key, lsn, value).unwrap();
+            }
+        })
+    });
+    group.finish();
+    table.close().unwrap();
+}
+
+criterion_group!(benches, zipfian_read_heavy);
+criterion_main!(benches);
@@ -0,0 +1,192 @@
+use std::{
+    future::Future,
+    io::{Error, Result},
+    path::Path,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
+};
+
+use super::*;
+
+/// An implementation of [`Env`] that injects faults into another [`Env`].
+///
+/// Writes fail with `ENOSPC` once the disk space set by [`Fault::set_space`]
+/// is used up, and reads fail with `EIO` as set by [`Fault::fail_reads`].
+#[derive(Clone, Debug)]
+pub(crate) struct Fault<E> {
+    env: E,
+    space: Arc<AtomicU64>,
+    read_failures: Arc<AtomicU64>,
+}
+
+impl<E: Env> Fault<E> {
+    pub(crate) fn new(env: E) -> Self {
+        Fault {
+            env,
+            space: Arc::new(AtomicU64::new(u64::MAX)),
+            read_failures: Arc::default(),
+        }
+    }
+
+    /// Fails the next `count` reads.
+    pub(crate) fn fail_reads(&self, count: u64) {
+        self.read_failures.store(count, Ordering::SeqCst);
+    }
+
+    /// Sets the number of bytes that can be written before writes fail.
+    pub(crate) fn set_space(&self, bytes: u64) {
+        self.space.store(bytes, Ordering::SeqCst);
+    }
+}
+
+#[async_trait]
+impl<E: Env> Env for Fault<E> {
+    type PositionalReader = PositionalReader<E::PositionalReader>;
+    type SequentialWriter = SequentialWriter<E::SequentialWriter>;
+    type JoinHandle<T: Send> = E::JoinHandle<T>;
+    type Directory = E::Directory;
+
+    async fn open_positional_reader<P>(&self, path: P) -> Result<Self::PositionalReader>
+    where
+        P: AsRef<Path> + Send,
+    {
+        let reader = self.env.open_positional_reader(path).await?;
+        Ok(PositionalReader {
+            reader,
+            failures: self.read_failures.clone(),
+        })
+    }
+
+    async fn open_sequential_writer<P>(&self, path: P) -> Result<Self::SequentialWriter>
+    where
+        P: AsRef<Path> + Send,
+    {
+        let writer = self.env.open_sequential_writer(path).await?;
+        Ok(SequentialWriter {
+            writer,
+            space: self.space.clone(),
+        })
+    }
+
+    fn spawn_background<F>(&self, f: F) -> Self::JoinHandle<F::Output>
+    where
+        F: Future + Send + 'static,
+        F::Output: Send,
+    {
+        self.env.spawn_background(f)
+    }
+
+    async fn rename<P: AsRef<Path> + Send, Q: AsRef<Path> + Send>(
+        &self,
+        from: P,
+        to: Q,
+    ) -> Result<()> {
+        self.env.rename(from, to).await
+    }
+
+    async fn remove_file<P: AsRef<Path> + Send>(&self, path: P) -> Result<()> {
+        self.env.remove_file(path).await
+    }
+
+    async fn create_dir_all<P: AsRef<Path> + Send>(&self, path: P) -> Result<()> {
+        self.env.create_dir_all(path).await
+    }
+
+    async fn remove_dir_all<P: AsRef<Path> + Send>(&self, path: P) -> Result<()> {
+        self.env.remove_dir_all(path).await
+    }
+
+    fn read_dir<P: AsRef<Path>>(&self, path: P) -> Result<std::fs::ReadDir> {
+        self.env.read_dir(path)
+    }
+
+    async fn metadata<P: AsRef<Path> + Send>(&self, path: P) -> Result<Metadata> {
+        self.env.metadata(path).await
+    }
+
+    async fn open_dir<P: AsRef<Path> + Send>(&self, path: P) -> Result<Self::Directory> {
+        self.env.open_dir(path).await
+    }
+}
+
+pub(crate) struct PositionalReader<R> {
+    reader: R,
+    failures: Arc<AtomicU64>,
+}
+
+#[async_trait]
+impl<R: super::PositionalReader> super::PositionalReader for PositionalReader<R> {
+    type ReadAt<'a> = impl Future<Output = Result<usize>> + 'a + Send where Self: 'a;
+
+    fn read_at<'a>(&'a self, buf: &'a mut [u8], pos: u64) -> Self::ReadAt<'a> {
+        async move {
+            let fail = self
+                .failures
+                .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |n| n.checked_sub(1))
+                .is_ok();
+            if fail {
+                return Err(Error::from_raw_os_error(libc::EIO));
+            }
+            self.reader.read_at(buf, pos).await
+        }
+    }
+
+    fn direct_io_ify(&self) -> Result<()> {
+        self.reader.direct_io_ify()
+    }
+
+    fn mmap(&self) -> Result<Mmap> {
+        self.reader.mmap()
+    }
+}
+
+pub(crate) struct SequentialWriter<W> {
+    writer: W,
+    space: Arc<AtomicU64>,
+}
+
+impl<W> SequentialWriter<W> {
+    /// Takes up to `len` bytes of the disk space, returns the bytes taken.
+    fn take_space(&self, len: usize) -> usize {
+        let space = self
+            .space
+            .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |space| {
+                Some(space.saturating_sub(len as u64))
+            })
+            .expect("the update never fails");
+        space.min(len as u64) as usize
+    }
+}
+
+#[async_trait]
+impl<W: super::SequentialWriter> super::SequentialWriter for SequentialWriter<W> {
+    type Write<'a> = impl Future<Output = Result<usize>> + 'a + Send where Self: 'a;
+
+    fn write<'a>(&'a mut self, buf: &'a [u8]) -> Self::Write<'a> {
+        async move {
+            let len = self.take_space(buf.len());
+            if len == 0 && !buf.is_empty() {
+                return Err(Error::from_raw_os_error(libc::ENOSPC));
+            }
+            self.writer.write(&buf[..len]).await
+        }
+    }
+
+    async fn sync_data(&mut self) -> Result<()> {
+        self.writer.sync_data().await
+    }
+
+    async fn sync_all(&mut self) -> Result<()> {
+        self.writer.sync_all().await
+    }
+
+    async fn truncate(&self, len: u64) -> Result<()> {
+        self.writer.truncate(len).await
+    }
+
+    fn direct_io_ify(&self) -> Result<()> {
+        self.writer.direct_io_ify()
+    }
+}
@@ -11,6 +11,11 @@ pub use stdenv::Std;
 mod photon;
 pub use photon::Photon;
 
+#[cfg(test)]
+mod fault;
+#[cfg(test)]
+pub(crate) use fault::Fault;
+
 /// Provides an environment to interact with a specific platform.
 #[async_trait]
 pub trait Env: Clone + Send + Sync + 'static {
@@ -89,6 +94,15 @@ pub trait PositionalReader: Send + Sync + 'static {
     /// Enable direct_io for the reader.
     /// return error if direct_io unsupported.
     fn direct_io_ify(&self) -> Result<()>;
+
+    /// Maps the whole file into memory for reads.
+    /// return error if mmap unsupported.
+    fn mmap(&self) -> Result<Mmap> {
+        Err(std::io::Error::new(
+            std::io::ErrorKind::Unsupported,
+            "mmap is not supported",
+        ))
+    }
 }
 
 /// Extension methods for [`PositionalReader`].
@@ -227,6 +241,72 @@ pub(in crate::env) fn direct_io_ify(_: i32) -> Result<()> {
     ))
 }
 
+/// A read-only memory mapping of a whole file.
+///
+/// The mapping stays valid after the file is closed, and is unmapped on drop.
+pub struct Mmap {
+    ptr: *const u8,
+    len: usize,
+}
+
+// Safety: the mapping is read-only and only unmapped on drop.
+unsafe impl Send for Mmap {}
+unsafe impl Sync for Mmap {}
+
+impl Mmap {
+    /// Returns the mapped bytes of the file.
+    pub fn as_slice(&self) -> &[u8] {
+        // Safety: the mapping lives as long as self.
+        unsafe { std::slice::from_raw_parts(self.ptr, self.len) }
+    }
+}
+
+impl Drop for Mmap {
+    fn drop(&mut self) {
+        #[cfg(unix)]
+        unsafe {
+            libc::munmap(self.ptr as *mut libc::c_void, self.len);
+        }
+    }
+}
+
+#[cfg(unix)]
+pub(in crate::env) fn mmap(fd: i32) -> Result<Mmap> {
+    let mut stat = std::mem::MaybeUninit::<libc::stat>::uninit();
+    if unsafe { libc::fstat(fd, stat.as_mut_ptr()) } == -1 {
+        return Err(std::io::Error::last_os_error());
+    }
+    let len = unsafe { stat.assume_init() }.st_size as usize;
+    if len == 0 {
+        return Err(std::io::ErrorKind::UnexpectedEof.into());
+    }
+    let ptr = unsafe {
+        libc::mmap(
+            std::ptr::null_mut(),
+            len,
+            libc::PROT_READ,
+            libc::MAP_SHARED,
+            fd,
+            0,
+        )
+    };
+    if ptr == libc::MAP_FAILED {
+        return Err(std::io::Error::last_os_error());
+    }
+    Ok(Mmap {
+        ptr: ptr as *const u8,
+        len,
+    })
+}
+
+#[cfg(not(unix))]
+pub(in crate::env) fn mmap(_: i32) -> Result<Mmap> {
+    Err(std::io::Error::new(
+        std::io::ErrorKind::Unsupported,
+        "mmap is not supported",
+    ))
+}
+
 /// A handle to an opened directory.
 #[async_trait]
 pub trait Directory {
@@ -113,6 +113,10 @@ impl super::PositionalReader for PositionalReader {
     fn direct_io_ify(&self) -> Result<()> {
         super::direct_io_ify(self.0.as_raw_fd())
     }
+
+    fn mmap(&self) -> Result<Mmap> {
+        super::mmap(self.0.as_raw_fd())
+    }
 }
 
 pub struct SequentialWriter(File);
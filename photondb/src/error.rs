@@ -14,6 +14,80 @@ pub enum Error {
     /// Put data is too large.
     #[error("TooLargeSize")]
     TooLargeSize,
+    /// The operation didn't finish before its deadline.
+    #[error("Timeout")]
+    Timeout,
+    /// The write didn't finish before its deadline, because writes are
+    /// stalled until write buffers are flushed.
+    #[error("WriteStall")]
+    WriteStall,
+    /// The operation was retried too many times because of contention.
+    #[error("Busy")]
+    Busy,
+    /// The LSN to read with is smaller than the safe LSN of the table, so
+    /// entries visible to it may have been dropped.
+    #[error("Stale LSN {lsn}, the safe LSN is {safe_lsn}")]
+    StaleLsn {
+        /// The LSN to read with.
+        lsn: u64,
+        /// The safe LSN of the table.
+        safe_lsn: u64,
+    },
+    /// The LSN to read with is larger than the latest LSN of the table, and
+    /// [`TableOptions::future_lsn_policy`] is [`FutureLsnPolicy::Strict`].
+    ///
+    /// [`TableOptions::future_lsn_policy`]: crate::TableOptions::future_lsn_policy
+    /// [`FutureLsnPolicy::Strict`]: crate::FutureLsnPolicy::Strict
+    #[error("Invalid LSN {lsn}, the latest LSN is {latest_lsn}")]
+    InvalidLsn {
+        /// The LSN to read with.
+        lsn: u64,
+        /// The latest LSN of the table.
+        latest_lsn: u64,
+    },
+    /// A key is given more than once with the same LSN.
+    #[error("DuplicateKey at LSN {lsn}")]
+    DuplicateKey {
+        /// The duplicated key.
+        key: Vec<u8>,
+        /// The LSN of the duplicated entries.
+        lsn: u64,
+    },
+    /// Entries that must be sorted are not sorted.
+    #[error("UnsortedInput")]
+    UnsortedInput,
+    /// The table is opened read-only.
+    #[error("ReadOnly")]
+    ReadOnly,
+    /// A page file required by the operation is missing.
+    #[error("Missing page file {file_id}")]
+    MissingFile {
+        /// The id of the missing page file.
+        file_id: u32,
+    },
+    /// The manifest version to open the table at is not recorded.
+    #[error("Invalid manifest version {version}, the latest version is {latest}")]
+    InvalidManifestVersion {
+        /// The manifest version to open the table at.
+        version: usize,
+        /// The latest manifest version.
+        latest: usize,
+    },
+    /// Writes are rejected because a flush ran out of disk space.
+    ///
+    /// Writes are accepted again once the flush succeeds after space is
+    /// freed.
+    #[error("DiskFull")]
+    DiskFull,
+    /// Some options are invalid.
+    #[error("InvalidOptions: {0}")]
+    InvalidOptions(String),
+    /// An argument of the operation is invalid.
+    #[error("InvalidArgument: {0}")]
+    InvalidArgument(String),
+    /// An I/O error from the underlying environment.
+    #[error("Io: {0}")]
+    Io(#[from] std::io::Error),
 }
 
 impl From<PageError> for Error {
@@ -22,6 +96,16 @@ impl From<PageError> for Error {
             PageError::Corrupted => Self::Corrupted,
             PageError::MemoryLimit => Self::MemoryLimit,
             PageError::TooLargeSize => Self::TooLargeSize,
+            PageError::Timeout => Self::Timeout,
+            PageError::WriteStall => Self::WriteStall,
+            PageError::Busy => Self::Busy,
+            PageError::ReadOnly => Self::ReadOnly,
+            PageError::MissingFile { file_id } => Self::MissingFile { file_id },
+            PageError::InvalidManifestVersion { version, latest } => {
+                Self::InvalidManifestVersion { version, latest }
+            }
+            PageError::DiskFull => Self::DiskFull,
+            PageError::Io(err) => Self::Io(err),
             e => unreachable!("unexpected error: {:?}", e),
         }
     }
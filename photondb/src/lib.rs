@@ -38,24 +38,48 @@ pub mod raw;
 pub mod std;
 
 pub mod photon;
-pub use photon::{Table, TableStats};
+pub use photon::{
+    Engine, EngineOptions, EngineStats, FileEntryIter, KeyBounds, KeyIter, ResumeToken,
+    ScanCacheStats, Snapshot, Table, TableCursor, TableStats,
+};
 
 mod error;
 pub use error::{Error, Result};
 
 mod tree;
-pub use tree::{Options as TableOptions, PageIter, ReadOptions, TreeStats, WriteOptions};
+pub use tree::{
+    AppendMergeOperator, CasBackoff, CasStats, CompactionFilter, ConsolidationMode, FilterDecision,
+    FutureLsnPolicy, IntKeyComparator, MergeOperator, Options as TableOptions,
+    OptionsBuilder as TableOptionsBuilder, PageIter, PrefetchStats, ReadOptions, ReadaheadStats,
+    TombstoneStats, TreeStats, WriteOptions,
+};
 
 mod page_store;
 pub use page_store::{
-    ChecksumType, Compression, FlushOptions, Options as PageStoreOptions, StoreStats,
+    ChecksumType, Compression, DeltaChainHistogram, EvictCallback, EvictReason, FileSummary,
+    FlushOptions, LiveFile, LiveFilesGuard, MinDeclineRateStrategyBuilder,
+    Options as PageStoreOptions, PageHasher, PickedFile, ReadSourceStats, ReclaimPickStrategy,
+    ReclaimStrategy, SpaceReport, StatsDelta, StoreStats, StrategyBuilder,
 };
 
 mod page;
+pub use page::PageLayout;
+pub use uuid::Uuid;
+
 mod util;
 
 #[cfg(test)]
 mod tests {
+    use ::std::{
+        collections::BTreeMap,
+        ops::Bound,
+        path::Path,
+        sync::{
+            atomic::{AtomicBool, AtomicU64, Ordering},
+            Arc, Mutex,
+        },
+        time::{Duration, Instant},
+    };
     use rand::random;
     use tempfile::tempdir;
 
@@ -65,23 +89,54 @@ mod tests {
     const OPTIONS: TableOptions = TableOptions {
         page_size: 128,
         page_chain_length: 4,
+        leaf_target_entries: None,
+        index_target_entries: None,
+        page_layout: PageLayout::Interleaved,
+        overflow_value_size: None,
+        consolidate_delta_bytes_ratio: None,
+        consolidation_mode: ConsolidationMode::Lazy,
+        future_lsn_policy: FutureLsnPolicy::Allow,
+        truncate_split_separator: false,
+        scan_cache_capacity: 0,
+        min_leaf_fill_ratio: 0.0,
+        split_ratio: 0.5,
+        merge_operator: None,
+        compaction_filter: None,
+        cas_backoff: None,
         page_store: PageStoreOptions {
             write_buffer_capacity: 1 << 20,
             max_write_buffers: 8,
+            max_unflushed_files: None,
+            writebuf_disabled: false,
             use_direct_io: false,
+            mmap_reads: false,
+            read_retries: 0,
+            paranoid_checks: false,
             disable_space_reclaiming: false,
             max_space_amplification_percent: 10,
             space_used_high: u64::MAX,
+            min_files_retained: 1,
+            hot_range: None,
+            reclaim_strategy: None,
             file_base_size: 1 << 20,
+            target_file_size: None,
             cache_capacity: 2 << 10,
+            memory_budget: None,
+            index_cache_capacity: None,
             cache_estimated_entry_charge: 1,
+            hasher: PageHasher::Fnv,
+            on_evict: None,
             cache_file_reader_capacity: 1000,
+            max_open_files: -1,
             cache_strict_capacity_limit: false,
             prepopulate_cache_on_flush: true,
             compression_on_flush: Compression::SNAPPY,
             compression_on_cold_compact: Compression::ZSTD,
+            enable_compression_dict: false,
             page_checksum_type: ChecksumType::CRC32,
+            verify_checksums_on_cache_hit: false,
             avoid_flush_during_shutdown: false,
+            stats_log_interval: Duration::ZERO,
         },
     };
 
@@ -125,41 +180,2910 @@ mod tests {
     }
 
     #[photonio::test]
-    async fn random_crud() {
+    async fn page_hashers_and_checksums() {
+        let mut opts = OPTIONS;
+        opts.page_store.hasher = PageHasher::XxHash64 { seed: 42 };
+        opts.page_store.page_checksum_type = ChecksumType::XXHASH;
+        let paths = [tempdir().unwrap(), tempdir().unwrap()];
+        let options = [OPTIONS, opts];
+        const N: u64 = 1 << 10;
+        for (path, opts) in paths.iter().zip(&options) {
+            let table = Table::open(path, opts.clone()).await.unwrap();
+            for i in 0..N {
+                must_put(&table, i, i).await;
+            }
+            table.flush(&FlushOptions::default()).await;
+            table.close().await.unwrap();
+        }
+
+        // The tables are open at the same time, and pages are read back from
+        // files with their own hashers and checksums.
+        let mut tables = Vec::new();
+        for (path, opts) in paths.iter().zip(options) {
+            tables.push(Table::open(path, opts).await.unwrap());
+        }
+        for i in 0..N {
+            for table in &tables {
+                must_get(table, i, N, Some(i)).await;
+            }
+        }
+        for table in tables {
+            table.close().await.unwrap();
+        }
+    }
+
+    #[photonio::test]
+    async fn separated_page_layout() {
         let path = tempdir().unwrap();
+        let mut opts = OPTIONS;
+        opts.page_layout = PageLayout::Separated;
+        let table = Table::open(&path, opts).await.unwrap();
+        const N: u64 = 1 << 10;
+        for i in 0..N {
+            must_put(&table, i, i).await;
+        }
+        table.flush(&FlushOptions::default()).await;
+        table.close().await.unwrap();
+
+        // Pages with both layouts are readable after the layout is changed.
         let table = Table::open(&path, OPTIONS).await.unwrap();
+        for i in (0..N).step_by(2) {
+            table.delete(&i.to_be_bytes(), N + i).await.unwrap();
+        }
+        for i in 0..N {
+            must_get(&table, i, N * 2, (i % 2 == 1).then_some(i)).await;
+        }
+        table.close().await.unwrap();
+    }
+
+    #[photonio::test]
+    async fn overflow_values() {
+        let path = tempdir().unwrap();
+        let mut opts = OPTIONS;
+        opts.page_size = 1 << 10;
+        opts.overflow_value_size = Some(1 << 10);
+        let table = Table::open(&path, opts.clone()).await.unwrap();
+        let large: Vec<u8> = (0..(5 << 10) + 7).map(|i| i as u8).collect();
+        table.put(b"a", 1, b"a").await.unwrap();
+        table.put(b"b", 2, &large).await.unwrap();
+        table.put(b"c", 3, b"c").await.unwrap();
+        assert_eq!(table.get(b"b", 3).await.unwrap(), Some(large.clone()));
+        table.flush(&FlushOptions::default()).await;
+        table.close().await.unwrap();
+
+        // Overflow values are read from files after reopening.
+        let table = Table::open(&path, opts).await.unwrap();
+        assert_eq!(table.get(b"b", 3).await.unwrap(), Some(large.clone()));
+        let guard = table.pin();
+        assert_eq!(guard.get(b"b", 3).await.unwrap(), Some(large.as_slice()));
+        let mut pages = guard.pages();
+        let mut entries = Vec::new();
+        while let Some(page) = pages.next().await.unwrap() {
+            entries.extend(page.map(|(k, v)| (k.to_vec(), v.to_vec())));
+        }
+        assert_eq!(
+            entries,
+            vec![
+                (b"a".to_vec(), b"a".to_vec()),
+                (b"b".to_vec(), large.clone()),
+                (b"c".to_vec(), b"c".to_vec()),
+            ]
+        );
+        drop(pages);
+        drop(guard);
+
+        // The overflow pages of a deleted value are deallocated by compaction.
+        table.put(b"d", 4, &large).await.unwrap();
+        assert_eq!(table.get(b"d", 4).await.unwrap(), Some(large.clone()));
+        table.delete(b"d", 5).await.unwrap();
+        table.set_safe_lsn(5);
+        table.compact().await.unwrap();
+        assert_eq!(table.get(b"d", 5).await.unwrap(), None);
+        assert_eq!(table.get(b"b", 5).await.unwrap(), Some(large.clone()));
+        table.flush(&FlushOptions::default()).await;
+        let jobs = table.stats().store.jobs;
+        assert!(jobs.flush_discard_bytes >= large.len() as u64, "{jobs}");
+        table.close().await.unwrap();
+    }
+
+    #[photonio::test]
+    async fn scan_with_readahead() {
+        let path = tempdir().unwrap();
+        let mut opts = OPTIONS;
+        opts.page_size = 1 << 10;
+        opts.page_store.cache_capacity = 1 << 20;
+        let table = Table::open(&path, opts.clone()).await.unwrap();
         const N: u64 = 1 << 12;
-        for _ in 0..N {
-            let i = random();
+        for i in 0..N {
             must_put(&table, i, i).await;
-            must_get(&table, i, i, Some(i)).await;
         }
         table.close().await.unwrap();
+
+        // Reopen the table so that pages are read from files.
+        let table = Table::open(&path, opts).await.unwrap();
+        let guard = table.pin();
+        let mut pages = guard.pages().with_readahead(4);
+        let mut i = 0u64;
+        while let Some(page) = pages.next().await.unwrap() {
+            for (k, v) in page {
+                assert_eq!(k, &i.to_be_bytes());
+                assert_eq!(v, &i.to_be_bytes());
+                i += 1;
+            }
+        }
+        assert_eq!(i, N);
+        drop(pages);
+        drop(guard);
+
+        let stats = table.stats().tree.readahead;
+        assert!(stats.prefetch_pages > 0);
+        assert!(stats.prefetch_hits > 0);
+        assert!(stats.prefetch_hits > stats.prefetch_misses);
+        table.close().await.unwrap();
     }
 
     #[photonio::test]
-    async fn concurrent_crud() {
+    async fn delta_chain_hist() {
+        let path = tempdir().unwrap();
+        let mut opts = OPTIONS;
+        opts.page_chain_length = 64;
+        let table = Table::open(&path, opts).await.unwrap();
+        must_put(&table, 1, 1).await;
+
+        let stats = table.stats().store.delta_chain_hist;
+        must_get(&table, 1, 1, Some(1)).await;
+        let delta = table.stats().store.delta_chain_hist.sub(&stats);
+        assert_eq!(delta.len_1 + delta.len_2_4, 1);
+
+        // Hammer one key without consolidation.
+        for lsn in 2..=32 {
+            table
+                .put(&1u64.to_be_bytes(), lsn, &lsn.to_be_bytes())
+                .await
+                .unwrap();
+        }
+        let stats = table.stats().store.delta_chain_hist;
+        must_get(&table, 1, 32, Some(32)).await;
+        let delta = table.stats().store.delta_chain_hist.sub(&stats);
+        assert_eq!(delta.len_over_16, 1);
+        assert_eq!(
+            delta.len_1 + delta.len_2_4 + delta.len_5_8 + delta.len_9_16,
+            0
+        );
+
+        table.close().await.unwrap();
+    }
+
+    #[photonio::test]
+    async fn read_source_stats() {
+        let path = tempdir().unwrap();
+        let mut opts = OPTIONS;
+        opts.page_store.prepopulate_cache_on_flush = false;
+        let table = Table::open(&path, opts).await.unwrap();
+        must_put(&table, 1, 1).await;
+
+        let stats = table.stats().store.read_source;
+        must_get(&table, 1, 1, Some(1)).await;
+        let delta = table.stats().store.read_source.sub(&stats);
+        assert!(delta.from_writebuf > 0);
+        assert_eq!(delta.from_page_cache, 0);
+        assert_eq!(delta.from_disk, 0);
+
+        // The flushed pages are not in the page cache.
+        table.flush(&FlushOptions::default()).await;
+        let stats = table.stats().store.read_source;
+        must_get(&table, 1, 1, Some(1)).await;
+        let delta = table.stats().store.read_source.sub(&stats);
+        assert_eq!(delta.from_writebuf, 0);
+        assert!(delta.from_disk > 0);
+
+        table.close().await.unwrap();
+    }
+
+    #[photonio::test]
+    async fn job_stats_timing() {
         let path = tempdir().unwrap();
         let table = Table::open(&path, OPTIONS).await.unwrap();
-        let mut tasks = Vec::new();
-        for _ in 0..4 {
-            let table = table.clone();
-            let handle = photonio::task::spawn(async move {
-                const N: u64 = 1 << 10;
-                for _ in 0..N {
-                    let i = random();
-                    must_put(&table, i, i).await;
-                    must_get(&table, i, i, Some(i)).await;
+        for i in 0..1024 {
+            must_put(&table, i, i).await;
+        }
+        table.flush(&FlushOptions::default()).await;
+        let jobs = table.stats().store.jobs;
+        assert!(jobs.flush_nanos > 0, "{jobs}");
+        assert!(jobs.flush_throughput() > 0.0, "{jobs}");
+        assert!(jobs.flush_throughput().is_finite(), "{jobs}");
+        table.close().await.unwrap();
+    }
+
+    #[photonio::test]
+    async fn stats_delta() {
+        let path = tempdir().unwrap();
+        let mut opts = OPTIONS;
+        opts.page_store.prepopulate_cache_on_flush = false;
+        let table = Table::open(&path, opts).await.unwrap();
+        must_put(&table, 1, 1).await;
+        table.flush(&FlushOptions::default()).await;
+
+        // The first get loads the page into the page cache.
+        let stats = table.stats().store;
+        must_get(&table, 1, 1, Some(1)).await;
+        let delta = table.stats().store.delta(&stats);
+        assert!(delta.page_cache_misses() > 0, "{delta}");
+        assert!(delta.reads_from_disk() > 0, "{delta}");
+
+        // The second get should be exactly one cache hit.
+        let stats = table.stats().store;
+        must_get(&table, 1, 1, Some(1)).await;
+        let delta = table.stats().store.delta(&stats);
+        delta.assert_page_cache(1, 0);
+        delta.assert_no_disk_reads();
+        assert_eq!(delta.reads_from_writebuf(), 0, "{delta}");
+
+        assert!(table.stats().store.delta(&table.stats().store).is_empty());
+        table.close().await.unwrap();
+    }
+
+    #[photonio::test]
+    async fn warmup() {
+        const N: u64 = 1 << 10;
+        let path = tempdir().unwrap();
+        let mut opts = OPTIONS;
+        opts.page_store.cache_capacity = 1 << 20;
+        let table = Table::open(&path, opts.clone()).await.unwrap();
+        for i in 0..N {
+            must_put(&table, i, 0).await;
+        }
+        table.close().await.unwrap();
+
+        // The page cache is cold after reopening.
+        let table = Table::open(&path, opts.clone()).await.unwrap();
+        let (start, end) = ((N / 4).to_be_bytes(), (N / 2).to_be_bytes());
+        let stats = table.stats().store;
+        table
+            .warmup(&[(Bound::Included(&start), Bound::Excluded(&end))])
+            .await
+            .unwrap();
+        let delta = table.stats().store.delta(&stats);
+        let warmup_bytes = delta.stats().read_source.warmup_bytes;
+        assert!(warmup_bytes > 0, "{delta}");
+
+        let stats = table.stats().store;
+        for i in N / 4..N / 2 {
+            must_get(&table, i, 0, Some(i)).await;
+        }
+        let delta = table.stats().store.delta(&stats);
+        delta.assert_no_disk_reads();
+        assert!(delta.reads_from_page_cache() > 0, "{delta}");
+
+        // Keys out of the range are not warmed up.
+        let stats = table.stats().store;
+        for i in N / 2..N {
+            must_get(&table, i, 0, Some(i)).await;
+        }
+        let delta = table.stats().store.delta(&stats);
+        assert!(delta.reads_from_disk() > 0, "{delta}");
+        table.close().await.unwrap();
+
+        // Warmup stops when the page cache is full.
+        opts.page_store.cache_capacity = 4 << 10;
+        let table = Table::open(&path, opts).await.unwrap();
+        table
+            .warmup(&[(Bound::Unbounded, Bound::Unbounded)])
+            .await
+            .unwrap();
+        let stats = table.stats().store;
+        assert!(stats.read_source.warmup_bytes < warmup_bytes, "{stats}");
+        table.close().await.unwrap();
+    }
+
+    #[photonio::test]
+    async fn prefetch() {
+        const N: u64 = 1 << 10;
+        let path = tempdir().unwrap();
+        let mut opts = OPTIONS;
+        opts.page_store.cache_capacity = 1 << 20;
+        let table = Table::open(&path, opts.clone()).await.unwrap();
+        for i in 0..N {
+            must_put(&table, i, 0).await;
+        }
+        table.close().await.unwrap();
+
+        // The page cache is cold after reopening.
+        let table = Table::open(&path, opts).await.unwrap();
+        let keys = (N / 4..N / 2).map(|i| i.to_be_bytes()).collect::<Vec<_>>();
+        let keys = keys.iter().map(|k| k.as_slice()).collect::<Vec<_>>();
+        let stats = table.stats();
+        table.prefetch(&keys).await;
+        let delta = table.stats().tree.sub(&stats.tree);
+        assert_eq!(delta.prefetch.keys, keys.len() as u64);
+        assert_eq!(delta.prefetch.failures, 0);
+        assert!(delta.prefetch.pages > 0, "{delta}");
+
+        let stats = table.stats().store;
+        for i in N / 4..N / 2 {
+            must_get(&table, i, 0, Some(i)).await;
+        }
+        let delta = table.stats().store.delta(&stats);
+        delta.assert_no_disk_reads();
+        assert!(delta.reads_from_page_cache() > 0, "{delta}");
+        table.close().await.unwrap();
+    }
+
+    #[photonio::test]
+    async fn index_cache() {
+        const N: u64 = 1 << 12;
+        let path = tempdir().unwrap();
+        let mut opts = OPTIONS;
+        let table = Table::open(&path, opts.clone()).await.unwrap();
+        for i in 0..N {
+            must_put(&table, i, 0).await;
+        }
+        table.close().await.unwrap();
+
+        // The data cache is much smaller than the data pages.
+        opts.page_store.cache_capacity = 72 << 10;
+        opts.page_store.index_cache_capacity = Some(64 << 10);
+        opts.page_store.disable_space_reclaiming = true;
+        let table = Table::open(&path, opts).await.unwrap();
+        let stats = table.stats().store;
+        assert_eq!(stats.memory.page_cache, 8 << 10);
+        assert_eq!(stats.memory.index_cache, 64 << 10);
+
+        // Load all index pages.
+        for i in 0..N {
+            must_get(&table, i, 0, Some(i)).await;
+        }
+        let stats = table.stats().store;
+        assert!(stats.index_cache.insert > 0, "{stats}");
+
+        let keys = scan_keys(&table, Bound::Unbounded, Bound::Unbounded).await;
+        assert_eq!(keys.len() as u64, N);
+        for i in (0..N).step_by(64) {
+            must_get(&table, i, 0, Some(i)).await;
+        }
+        let delta = table.stats().store.sub(&stats);
+        // The scan churns the data cache, but index pages stay cached.
+        assert!(delta.page_cache.lookup_miss > 0);
+        assert!(delta.page_cache.active_evict + delta.page_cache.passive_evict > 0);
+        assert!(delta.index_cache.lookup_hit > 0);
+        assert_eq!(delta.index_cache.lookup_miss, 0);
+        assert_eq!(delta.index_cache.insert, 0);
+        table.close().await.unwrap();
+    }
+
+    #[photonio::test]
+    async fn scan_cache() {
+        let path = tempdir().unwrap();
+        let mut opts = OPTIONS;
+        opts.scan_cache_capacity = 2;
+        let table = Table::open(&path, opts).await.unwrap();
+        const N: u64 = 1 << 8;
+        for i in 0..N {
+            must_put(&table, i, 1).await;
+        }
+        let (start, end) = (8u64.to_be_bytes(), 16u64.to_be_bytes());
+        let (start, end) = (Bound::Included(&start[..]), Bound::Excluded(&end[..]));
+        let expect: Vec<_> = (8..16u64)
+            .map(|i| (i.to_be_bytes().to_vec(), i.to_be_bytes().to_vec()))
+            .collect();
+
+        assert_eq!(table.scan(start, end, 1).await.unwrap(), expect);
+        assert_eq!(table.scan(start, end, 1).await.unwrap(), expect);
+        let stats = table.stats().scan_cache;
+        assert_eq!((stats.hit, stats.miss), (1, 1));
+
+        // Writes at newer LSNs are invisible to the cached scan.
+        table.put(&8u64.to_be_bytes(), 2, b"new").await.unwrap();
+        assert_eq!(table.scan(start, end, 1).await.unwrap(), expect);
+        let stats = table.stats().scan_cache;
+        assert_eq!((stats.hit, stats.miss), (2, 1));
+
+        // A scan at a newer LSN misses and sees the new value.
+        let entries = table.scan(start, end, 2).await.unwrap();
+        assert_eq!(entries[0].1, b"new");
+        let stats = table.stats().scan_cache;
+        assert_eq!((stats.hit, stats.miss), (2, 2));
+
+        // Writes at older LSNs invalidate the cached scan.
+        table.delete(&9u64.to_be_bytes(), 1).await.unwrap();
+        let entries = table.scan(start, end, 2).await.unwrap();
+        assert_eq!(entries.len(), expect.len() - 1);
+        let stats = table.stats().scan_cache;
+        assert_eq!((stats.hit, stats.miss), (2, 3));
+
+        table.close().await.unwrap();
+    }
+
+    async fn scan_keys(table: &Table, start: Bound<u64>, end: Bound<u64>) -> Vec<u64> {
+        fn encode(bound: Bound<u64>) -> Bound<[u8; 8]> {
+            match bound {
+                Bound::Included(i) => Bound::Included(i.to_be_bytes()),
+                Bound::Excluded(i) => Bound::Excluded(i.to_be_bytes()),
+                Bound::Unbounded => Bound::Unbounded,
+            }
+        }
+        fn as_slice(bound: &Bound<[u8; 8]>) -> Bound<&[u8]> {
+            match bound {
+                Bound::Included(k) => Bound::Included(k),
+                Bound::Excluded(k) => Bound::Excluded(k),
+                Bound::Unbounded => Bound::Unbounded,
+            }
+        }
+        let (start, end) = (encode(start), encode(end));
+        let entries = table
+            .scan(as_slice(&start), as_slice(&end), 1)
+            .await
+            .unwrap();
+        let keys: Vec<_> = table
+            .scan_keys(as_slice(&start), as_slice(&end), 1)
+            .await
+            .unwrap()
+            .collect();
+        assert!(keys.iter().eq(entries.iter().map(|(k, _)| k)));
+        keys.into_iter()
+            .map(|k| u64::from_be_bytes(k.try_into().unwrap()))
+            .collect()
+    }
+
+    #[photonio::test]
+    async fn scan_keys_skip_tombstones() {
+        const N: u64 = 1 << 10;
+        let path = tempdir().unwrap();
+        let table = Table::open(&path, OPTIONS).await.unwrap();
+        for i in 0..N {
+            must_put(&table, i, 1).await;
+        }
+        for i in (0..N).step_by(3) {
+            table.delete(&i.to_be_bytes(), 2).await.unwrap();
+        }
+        let (start, end) = ((N / 4).to_be_bytes(), (N / 2).to_be_bytes());
+        let (start, end) = (Bound::Included(&start[..]), Bound::Excluded(&end[..]));
+        for lsn in [1, 2] {
+            let entries = table.scan(start, end, lsn).await.unwrap();
+            let keys: Vec<_> = table.scan_keys(start, end, lsn).await.unwrap().collect();
+            assert!(keys.iter().eq(entries.iter().map(|(k, _)| k)));
+            let expect: Vec<_> = (N / 4..N / 2)
+                .filter(|i| lsn == 1 || i % 3 != 0)
+                .map(|i| i.to_be_bytes().to_vec())
+                .collect();
+            assert_eq!(keys, expect);
+        }
+        table.close().await.unwrap();
+    }
+
+    #[photonio::test]
+    async fn cursor_next_and_prev() {
+        const N: u64 = 1 << 10;
+        let path = tempdir().unwrap();
+        let table = Table::open(&path, OPTIONS).await.unwrap();
+        for i in 0..N {
+            must_put(&table, i, 1).await;
+        }
+        for i in (0..N).step_by(3) {
+            table.delete(&i.to_be_bytes(), 2).await.unwrap();
+        }
+        let live: Vec<u64> = (0..N).filter(|i| i % 3 != 0).collect();
+        {
+            let mut cursor = table.cursor(2).unwrap();
+            assert!(!cursor.valid());
+            // Seeking to a deleted key positions at the next live key.
+            let target = N / 2 / 3 * 3;
+            cursor.seek(&target.to_be_bytes()).await.unwrap();
+            let mut pos = live.binary_search(&(target + 1)).unwrap();
+            for step in 0..256 {
+                if step % 3 == 2 {
+                    cursor.prev().await.unwrap();
+                    pos -= 1;
+                } else {
+                    cursor.next().await.unwrap();
+                    pos += 1;
                 }
-            });
-            tasks.push(handle);
+                assert_eq!(cursor.key(), live[pos].to_be_bytes());
+                assert_eq!(cursor.value(), live[pos].to_be_bytes());
+            }
+
+            // Steps past the last entry.
+            cursor.seek(&(N - 1).to_be_bytes()).await.unwrap();
+            assert_eq!(cursor.key(), (N - 1).to_be_bytes());
+            cursor.next().await.unwrap();
+            assert!(!cursor.valid());
+            cursor.prev().await.unwrap();
+            assert!(!cursor.valid());
+
+            // Steps backward through all entries and past the first one.
+            cursor.seek(&(N - 1).to_be_bytes()).await.unwrap();
+            for i in live.iter().rev() {
+                assert_eq!(cursor.key(), i.to_be_bytes());
+                cursor.prev().await.unwrap();
+            }
+            assert!(!cursor.valid());
+            cursor.next().await.unwrap();
+            assert!(!cursor.valid());
+
+            cursor.seek(&N.to_be_bytes()).await.unwrap();
+            assert!(!cursor.valid());
+            cursor.seek(&[]).await.unwrap();
+            assert_eq!(cursor.key(), live[0].to_be_bytes());
         }
-        for task in tasks {
-            task.await.unwrap();
+        table.close().await.unwrap();
+    }
+
+    #[photonio::test]
+    async fn scan_prefix_rev() {
+        const N: u64 = 1 << 10;
+        let path = tempdir().unwrap();
+        let table = Table::open(&path, OPTIONS).await.unwrap();
+        let prefixes: [&[u8]; 4] = [b"a", b"b", b"b\xff", b"\xff"];
+        for i in 0..N {
+            for prefix in prefixes {
+                let key = [prefix, &i.to_be_bytes()].concat();
+                table.put(&key, 1, &key).await.unwrap();
+            }
+        }
+        for i in (0..N).step_by(3) {
+            let key = [b"b".as_slice(), &i.to_be_bytes()].concat();
+            table.delete(&key, 2).await.unwrap();
+        }
+        for lsn in [1, 2] {
+            for (prefix, end) in [
+                (b"".as_slice(), Bound::Unbounded),
+                (b"a".as_slice(), Bound::Excluded(b"b".as_slice())),
+                (b"b".as_slice(), Bound::Excluded(b"c".as_slice())),
+                (b"b\xff".as_slice(), Bound::Excluded(b"c".as_slice())),
+                (b"\xff".as_slice(), Bound::Unbounded),
+                (b"c".as_slice(), Bound::Excluded(b"d".as_slice())),
+            ] {
+                let mut expect = table.scan(Bound::Included(prefix), end, lsn).await.unwrap();
+                expect.reverse();
+                let entries = table.scan_prefix_rev(prefix, lsn).await.unwrap();
+                assert_eq!(entries, expect);
+            }
+        }
+        let entries = table.scan_prefix_rev(b"b", 2).await.unwrap();
+        assert_eq!(entries.len() as u64, N * 2 - (N + 2) / 3);
+        assert_eq!(
+            entries[0].0,
+            [b"b\xff".as_slice(), &(N - 1).to_be_bytes()].concat()
+        );
+        table.close().await.unwrap();
+    }
+
+    #[photonio::test]
+    async fn scan_bounds() {
+        let path = tempdir().unwrap();
+        let table = Table::open(&path, OPTIONS).await.unwrap();
+        const N: u64 = 1 << 8;
+        for i in 0..N {
+            must_put(&table, i, 1).await;
+        }
+        let scan = |start, end| scan_keys(&table, start, end);
+
+        use Bound::*;
+        assert_eq!(scan(Included(8), Excluded(12)).await, [8, 9, 10, 11]);
+        assert_eq!(scan(Included(8), Included(12)).await, [8, 9, 10, 11, 12]);
+        assert_eq!(scan(Excluded(8), Excluded(12)).await, [9, 10, 11]);
+        assert_eq!(scan(Excluded(8), Included(12)).await, [9, 10, 11, 12]);
+        assert_eq!(scan(Unbounded, Excluded(3)).await, [0, 1, 2]);
+        assert_eq!(scan(Excluded(N - 3), Unbounded).await, [N - 2, N - 1]);
+        assert_eq!(scan(Unbounded, Unbounded).await.len(), N as usize);
+
+        // Degenerate ranges with the same start and end.
+        assert_eq!(scan(Included(8), Included(8)).await, [8]);
+        assert!(scan(Included(8), Excluded(8)).await.is_empty());
+        assert!(scan(Excluded(8), Included(8)).await.is_empty());
+        assert!(scan(Excluded(8), Excluded(8)).await.is_empty());
+
+        table.close().await.unwrap();
+    }
+
+    #[photonio::test]
+    async fn split_rightmost_page_with_ratio() {
+        async fn count_leaf_pages(split_ratio: f64) -> usize {
+            const N: u64 = 4096;
+            let path = tempdir().unwrap();
+            let opts = TableOptions {
+                page_size: 1 << 10,
+                split_ratio,
+                ..OPTIONS
+            };
+            let table = Table::open(&path, opts).await.unwrap();
+            for i in 0..N {
+                must_put(&table, i, 0).await;
+            }
+            let guard = table.pin();
+            let mut pages = guard.pages();
+            let mut n = 0;
+            while pages.next().await.unwrap().is_some() {
+                n += 1;
+            }
+            drop(guard);
+            table.close().await.unwrap();
+            n
         }
+
+        let half = count_leaf_pages(0.5).await;
+        let skewed = count_leaf_pages(0.9).await;
+        assert!(
+            skewed < half,
+            "{skewed} pages with 0.9, {half} pages with 0.5"
+        );
+    }
+
+    #[photonio::test]
+    async fn get_latest() {
+        let path = tempdir().unwrap();
+        let table = Table::open(&path, OPTIONS).await.unwrap();
+        assert_eq!(table.latest_lsn(), 0);
+        table.put(b"k", 1, b"v1").await.unwrap();
+        table.put(b"k", 3, b"v3").await.unwrap();
+        table.put(b"j", 2, b"v2").await.unwrap();
+        assert_eq!(table.latest_lsn(), 3);
+        assert_eq!(table.get_latest(b"k").await.unwrap(), Some(b"v3".to_vec()));
+        table.delete(b"k", 4).await.unwrap();
+        assert_eq!(table.latest_lsn(), 4);
+        assert_eq!(table.get_latest(b"k").await.unwrap(), None);
+        table.close().await.unwrap();
+
+        let table = Table::open(&path, OPTIONS).await.unwrap();
+        assert_eq!(table.latest_lsn(), 4);
+        assert_eq!(table.get_latest(b"j").await.unwrap(), Some(b"v2".to_vec()));
+        table.close().await.unwrap();
+    }
+
+    #[photonio::test]
+    async fn ingest_sorted_with_duplicate_keys() {
+        let path = tempdir().unwrap();
+        let table = Table::open(&path, OPTIONS).await.unwrap();
+        let entries: [(&[u8], u64, &[u8]); 3] = [(b"a", 1, b"1"), (b"a", 2, b"2"), (b"b", 3, b"3")];
+        table.ingest_sorted(&entries).await.unwrap();
+        assert_eq!(table.get(b"a", 3).await.unwrap(), Some(b"2".to_vec()));
+        assert_eq!(table.get(b"b", 3).await.unwrap(), Some(b"3".to_vec()));
+
+        let entries: [(&[u8], u64, &[u8]); 2] = [(b"c", 4, b"4"), (b"c", 4, b"5")];
+        assert!(matches!(
+            table.ingest_sorted(&entries).await,
+            Err(Error::DuplicateKey { lsn: 4, .. })
+        ));
+        let entries: [(&[u8], u64, &[u8]); 2] = [(b"d", 5, b"5"), (b"c", 6, b"6")];
+        assert!(matches!(
+            table.ingest_sorted(&entries).await,
+            Err(Error::UnsortedInput)
+        ));
+        let entries: [(&[u8], u64, &[u8]); 2] = [(b"c", 6, b"6"), (b"c", 5, b"5")];
+        assert!(matches!(
+            table.ingest_sorted(&entries).await,
+            Err(Error::UnsortedInput)
+        ));
+        // Nothing is written if the input is rejected.
+        assert_eq!(table.get(b"c", u64::MAX).await.unwrap(), None);
         table.close().await.unwrap();
     }
 
+    #[photonio::test]
+    async fn page_fill_factor() {
+        const N: u64 = 1 << 10;
+        let path = tempdir().unwrap();
+        let mut opts = OPTIONS;
+        opts.page_size = 1 << 10;
+        opts.split_ratio = 0.9;
+        let table = Table::open(&path, opts).await.unwrap();
+        let keys: Vec<_> = (0..N).map(u64::to_be_bytes).collect();
+        let value = [0; 64];
+        let entries: Vec<(&[u8], u64, &[u8])> = keys
+            .iter()
+            .map(|k| (k.as_slice(), 1, value.as_slice()))
+            .collect();
+        table.ingest_sorted(&entries).await.unwrap();
+        table.flush(&FlushOptions::default()).await;
+        let ingested = table.stats().store.files.page_fill_factor;
+        assert!(ingested > 0.5, "{ingested}");
+
+        // Consolidated pages shrink as entries are deleted.
+        for key in keys.iter().skip(1).step_by(2) {
+            table.delete(key, 2).await.unwrap();
+        }
+        for key in keys.iter().skip(2).step_by(4) {
+            table.delete(key, 3).await.unwrap();
+        }
+        table.flush(&FlushOptions::default()).await;
+        let deleted = table.stats().store.files.page_fill_factor;
+        assert!(deleted < ingested, "{deleted} >= {ingested}");
+        table.close().await.unwrap();
+    }
+
+    #[photonio::test]
+    async fn consolidation_mode() {
+        const N: u64 = 16;
+        async fn fill_factor_after_deletes(mode: ConsolidationMode) -> f64 {
+            let path = tempdir().unwrap();
+            let mut opts = OPTIONS;
+            opts.page_size = 2 << 10;
+            opts.consolidation_mode = mode;
+            let table = Table::open(&path, opts).await.unwrap();
+            let value = [0; 64];
+            for i in 0..N {
+                table.put(&i.to_be_bytes(), 1, &value).await.unwrap();
+            }
+            table.compact().await.unwrap();
+            table.set_safe_lsn(2);
+            for i in 0..N * 3 / 4 {
+                table.delete(&i.to_be_bytes(), 2).await.unwrap();
+            }
+            for i in 0..N {
+                let expect = (i >= N * 3 / 4).then_some(value.to_vec());
+                assert_eq!(table.get(&i.to_be_bytes(), 2).await.unwrap(), expect);
+            }
+            table.flush(&FlushOptions::default()).await;
+            let fill_factor = table.stats().store.files.page_fill_factor;
+            table.close().await.unwrap();
+            fill_factor
+        }
+
+        // Lazy consolidations keep the base page and fold the deletes into a
+        // delta page, while full consolidations drop them with the deleted
+        // entries.
+        let lazy = fill_factor_after_deletes(ConsolidationMode::Lazy).await;
+        let full = fill_factor_after_deletes(ConsolidationMode::Full).await;
+        assert!(full < lazy, "{full} >= {lazy}");
+    }
+
+    #[photonio::test]
+    async fn future_lsn_policy() {
+        async fn open_with(path: &Path, policy: FutureLsnPolicy) -> Table {
+            let mut opts = OPTIONS;
+            opts.future_lsn_policy = policy;
+            let table = Table::open(path, opts).await.unwrap();
+            table.put(b"k", 1, b"v1").await.unwrap();
+            table.put(b"k", 2, b"v2").await.unwrap();
+            table
+        }
+
+        let path = tempdir().unwrap();
+        let table = open_with(path.path(), FutureLsnPolicy::Allow).await;
+        let lsn = table.latest_lsn() + 100;
+        assert_eq!(table.get(b"k", lsn).await.unwrap(), Some(b"v2".to_vec()));
+        assert_eq!(
+            table
+                .scan(Bound::Unbounded, Bound::Unbounded, lsn)
+                .await
+                .unwrap(),
+            vec![(b"k".to_vec(), b"v2".to_vec())]
+        );
+        table.close().await.unwrap();
+
+        let path = tempdir().unwrap();
+        let table = open_with(path.path(), FutureLsnPolicy::Strict).await;
+        let latest_lsn = table.latest_lsn();
+        let lsn = latest_lsn + 100;
+        let err = table.get(b"k", lsn).await.unwrap_err();
+        assert!(
+            matches!(err, Error::InvalidLsn { lsn: l, latest_lsn: ll } if l == lsn && ll == latest_lsn)
+        );
+        let err = table
+            .scan(Bound::Unbounded, Bound::Unbounded, lsn)
+            .await
+            .unwrap_err();
+        assert!(matches!(err, Error::InvalidLsn { .. }));
+        let err = table.get_versions(b"k", lsn).await.unwrap_err();
+        assert!(matches!(err, Error::InvalidLsn { .. }));
+        let err = table.locate_key(b"k", lsn).await.unwrap_err();
+        assert!(matches!(err, Error::InvalidLsn { .. }));
+        let err = table.export(Vec::new(), lsn).await.unwrap_err();
+        assert!(matches!(err, Error::InvalidLsn { .. }));
+        assert!(matches!(table.cursor(lsn), Err(Error::InvalidLsn { .. })));
+        {
+            let guard = table.pin();
+            let err = guard.get(b"k", lsn).await.unwrap_err();
+            assert!(matches!(err, Error::InvalidLsn { .. }));
+            assert!(matches!(guard.pages_at(lsn), Err(Error::InvalidLsn { .. })));
+        }
+        assert_eq!(
+            table.get(b"k", latest_lsn).await.unwrap(),
+            Some(b"v2".to_vec())
+        );
+        assert_eq!(table.get_latest(b"k").await.unwrap(), Some(b"v2".to_vec()));
+        table.close().await.unwrap();
+    }
+
+    #[photonio::test]
+    async fn export_and_import() {
+        const N: u64 = 1 << 12;
+        let path = tempdir().unwrap();
+        let table = Table::open(&path, OPTIONS).await.unwrap();
+        for i in 0..N {
+            must_put(&table, i, i).await;
+        }
+        for i in (0..N).step_by(3) {
+            table.delete(&i.to_be_bytes(), N + i).await.unwrap();
+        }
+        let lsn = N * 2;
+        let mut dump = Vec::new();
+        let num_entries = table.export(&mut dump, lsn).await.unwrap();
+        let expect = table
+            .scan(Bound::Unbounded, Bound::Unbounded, lsn)
+            .await
+            .unwrap();
+        assert_eq!(num_entries, expect.len());
+        table.close().await.unwrap();
+
+        let other_path = tempdir().unwrap();
+        let other = Table::open(&other_path, OPTIONS).await.unwrap();
+        assert_eq!(other.import(dump.as_slice(), 1).await.unwrap(), num_entries);
+        let entries = other
+            .scan(Bound::Unbounded, Bound::Unbounded, 1)
+            .await
+            .unwrap();
+        assert_eq!(entries, expect);
+        for i in 0..N {
+            must_get(&other, i, 1, (i % 3 != 0).then_some(i)).await;
+        }
+
+        // A truncated dump is rejected.
+        assert!(matches!(
+            other.import(&dump[..dump.len() - 1], 2).await,
+            Err(Error::Corrupted)
+        ));
+        other.close().await.unwrap();
+    }
+
+    #[photonio::test]
+    async fn collect_tombstones() {
+        const N: u64 = 1 << 8;
+        let path = tempdir().unwrap();
+        let table = Table::open(&path, OPTIONS).await.unwrap();
+        for i in 0..N {
+            must_put(&table, i, i + 1).await;
+        }
+        for i in 0..N / 2 {
+            table.delete(&i.to_be_bytes(), N + i + 1).await.unwrap();
+        }
+        let snapshot = table.snapshot();
+        assert_eq!(snapshot.lsn(), N + N / 2);
+        for i in N / 2..N {
+            table.delete(&i.to_be_bytes(), N + i + 1).await.unwrap();
+        }
+        let stats = table.stats().tree.tombstones;
+        assert_eq!(stats.live_tombstones, N);
+        assert_eq!(stats.tombstones_collected, 0);
+
+        // Deletes that are not visible to the live snapshot are still needed.
+        table.collect_tombstones().await.unwrap();
+        let stats = table.stats().tree.tombstones;
+        assert_eq!(stats.live_tombstones, N / 2);
+        assert_eq!(stats.tombstones_collected, N / 2);
+        for i in 0..N {
+            let expect = (i >= N / 2).then_some(i);
+            must_get(&table, i, snapshot.lsn(), expect).await;
+        }
+
+        // All deletes are collected without live snapshots, regardless of the
+        // safe LSN.
+        drop(snapshot);
+        assert_eq!(table.safe_lsn(), 0);
+        table.collect_tombstones().await.unwrap();
+        let stats = table.stats().tree.tombstones;
+        assert_eq!(stats.live_tombstones, 0);
+        assert_eq!(stats.tombstones_collected, N);
+        for i in 0..N {
+            must_get(&table, i, N * 2, None).await;
+        }
+        table.close().await.unwrap();
+    }
+
+    #[photonio::test]
+    async fn compact_with_filter() {
+        const N: u64 = 1 << 10;
+        let path = tempdir().unwrap();
+        let filter = CompactionFilter::new(|key, _| {
+            if key.starts_with(b"tmp/") {
+                FilterDecision::Remove
+            } else if key.starts_with(b"old/") {
+                FilterDecision::ChangeValue(b"new".to_vec())
+            } else {
+                FilterDecision::Keep
+            }
+        });
+        let opts = TableOptions {
+            compaction_filter: Some(filter),
+            ..OPTIONS
+        };
+        let table = Table::open(&path, opts).await.unwrap();
+        for i in 0..N {
+            for prefix in ["keep/", "old/", "tmp/"] {
+                let key = format!("{prefix}{i:04}");
+                table.put(key.as_bytes(), i, b"v").await.unwrap();
+            }
+        }
+        table.set_safe_lsn(N);
+        table.compact().await.unwrap();
+
+        for i in 0..N {
+            for (prefix, expect) in [
+                ("keep/", Some(b"v".to_vec())),
+                ("old/", Some(b"new".to_vec())),
+                ("tmp/", None),
+            ] {
+                let key = format!("{prefix}{i:04}");
+                assert_eq!(table.get(key.as_bytes(), N).await.unwrap(), expect);
+            }
+        }
+        let start = Bound::Included(&b"tmp/"[..]);
+        let end = Bound::Excluded(&b"tmp0"[..]);
+        assert!(table.scan(start, end, N).await.unwrap().is_empty());
+        table.close().await.unwrap();
+    }
+
+    #[photonio::test]
+    async fn open_at_manifest_version() {
+        const N: u64 = 1 << 8;
+        let path = tempdir().unwrap();
+        let filter = CompactionFilter::new(|key, _| {
+            if key.starts_with(b"tmp/") {
+                FilterDecision::Remove
+            } else {
+                FilterDecision::Keep
+            }
+        });
+        let mut opts = TableOptions {
+            compaction_filter: Some(filter),
+            ..OPTIONS
+        };
+        opts.page_store.disable_space_reclaiming = true;
+        let table = Table::open(&path, opts.clone()).await.unwrap();
+        for i in 0..N {
+            for prefix in ["keep/", "tmp/"] {
+                let key = format!("{prefix}{i:04}");
+                table.put(key.as_bytes(), 1, b"v").await.unwrap();
+            }
+        }
+        table.flush(&FlushOptions::default()).await;
+        let version = table.manifest_version().await;
+        let files: Vec<_> = table.live_files().into_iter().map(|f| f.path).collect();
+        assert!(!files.is_empty());
+
+        table.set_safe_lsn(1);
+        table.compact().await.unwrap();
+        table.flush(&FlushOptions::default()).await;
+        assert!(table.manifest_version().await > version);
+        assert_eq!(table.get(b"tmp/0000", 1).await.unwrap(), None);
+        table.close().await.unwrap();
+        let list_dir = || {
+            let mut names: Vec<_> = ::std::fs::read_dir(&path)
+                .unwrap()
+                .map(|entry| entry.unwrap().file_name())
+                .collect();
+            names.sort();
+            names
+        };
+        let names = list_dir();
+
+        // The older version still has the entries removed by the filter.
+        let table = Table::open_at_manifest_version(&path, opts.clone(), version)
+            .await
+            .unwrap();
+        for i in 0..N {
+            for prefix in ["keep/", "tmp/"] {
+                let key = format!("{prefix}{i:04}");
+                assert_eq!(
+                    table.get(key.as_bytes(), 1).await.unwrap(),
+                    Some(b"v".to_vec())
+                );
+            }
+        }
+        assert!(matches!(
+            table.put(b"tmp/0000", 2, b"v").await,
+            Err(Error::ReadOnly)
+        ));
+        table.close().await.unwrap();
+        // Obsolete manifest files are not removed either.
+        assert_eq!(list_dir(), names);
+
+        for version in [0, usize::MAX] {
+            assert!(matches!(
+                Table::open_at_manifest_version(&path, opts.clone(), version).await,
+                Err(Error::InvalidManifestVersion { .. })
+            ));
+        }
+        ::std::fs::remove_file(&files[0]).unwrap();
+        assert!(matches!(
+            Table::open_at_manifest_version(&path, opts, version).await,
+            Err(Error::MissingFile { .. })
+        ));
+    }
+
+    #[photonio::test]
+    async fn manifest_version_across_reopen() {
+        let path = tempdir().unwrap();
+        let table = Table::open(&path, OPTIONS).await.unwrap();
+        must_put(&table, 0, 0).await;
+        table.flush(&FlushOptions::default()).await;
+        let version = table.manifest_version().await;
+        table.close().await.unwrap();
+
+        let table = Table::open(&path, OPTIONS).await.unwrap();
+        assert!(table.manifest_version().await >= version);
+        must_put(&table, 1, 1).await;
+        table.flush(&FlushOptions::default()).await;
+        let latest = table.manifest_version().await;
+        assert!(latest > version);
+        table.close().await.unwrap();
+
+        let table = Table::open_at_manifest_version(&path, OPTIONS, latest)
+            .await
+            .unwrap();
+        must_get(&table, 0, 1, Some(0)).await;
+        must_get(&table, 1, 1, Some(1)).await;
+        table.close().await.unwrap();
+    }
+
+    #[photonio::test]
+    async fn rebuild_mapping() {
+        const N: u64 = 1 << 10;
+        let path = tempdir().unwrap();
+        let table = Table::open(&path, OPTIONS).await.unwrap();
+        for i in 0..N {
+            must_put(&table, i, 0).await;
+            if i % 256 == 0 {
+                table.flush(&FlushOptions::default()).await;
+            }
+        }
+        // Overwrite some keys so that pages of older files are shadowed.
+        for i in (0..N).step_by(3) {
+            table
+                .put(&i.to_be_bytes(), 1, &(i + 1).to_be_bytes())
+                .await
+                .unwrap();
+        }
+        table.close().await.unwrap();
+
+        // Drop the manifest, which maps the table to its page files.
+        for entry in ::std::fs::read_dir(&path).unwrap() {
+            let entry = entry.unwrap();
+            let name = entry.file_name().into_string().unwrap();
+            if name == "CURRENT" || name.starts_with("MANIFEST") {
+                ::std::fs::remove_file(entry.path()).unwrap();
+            }
+        }
+
+        let skipped = Table::rebuild_mapping(&path, OPTIONS).await.unwrap();
+        assert!(skipped.is_empty());
+        let table = Table::open(&path, OPTIONS).await.unwrap();
+        for i in 0..N {
+            let expect = if i % 3 == 0 { i + 1 } else { i };
+            must_get(&table, i, 1, Some(expect)).await;
+        }
+        let keys = scan_keys(&table, Bound::Unbounded, Bound::Unbounded).await;
+        assert_eq!(keys, (0..N).collect::<Vec<_>>());
+        table.close().await.unwrap();
+    }
+
+    #[photonio::test]
+    async fn recover_with_missing_files() {
+        const N: u64 = 1 << 8;
+        let path = tempdir().unwrap();
+        let mut opts = OPTIONS;
+        opts.page_store.disable_space_reclaiming = true;
+        let table = Table::open(&path, opts.clone()).await.unwrap();
+        for lsn in 1..=3 {
+            for i in 0..N {
+                must_put(&table, i, lsn).await;
+            }
+            table.flush(&FlushOptions::default()).await;
+        }
+        let mut files = table.live_files();
+        files.sort_by_key(|f| f.file_id);
+        assert!(files.len() >= 3);
+        table.close().await.unwrap();
+
+        // A page file written after the last committed version is discarded.
+        let last = files.last().unwrap();
+        let orphan = last
+            .path
+            .with_file_name(format!("map_{}", last.file_id + 100));
+        ::std::fs::copy(&last.path, &orphan).unwrap();
+        let table = Table::open(&path, opts.clone()).await.unwrap();
+        for i in 0..N {
+            must_get(&table, i, 3, Some(i)).await;
+        }
+        table.close().await.unwrap();
+        assert!(!orphan.exists());
+
+        // A missing committed file leaves a gap in the data, which is detected
+        // instead of exposing a partial view.
+        let missing = &files[files.len() / 2];
+        ::std::fs::remove_file(&missing.path).unwrap();
+        assert!(matches!(
+            Table::open(&path, opts).await,
+            Err(Error::MissingFile { file_id }) if file_id == missing.file_id
+        ));
+    }
+
+    #[photonio::test]
+    async fn rename() {
+        const ROUNDS: u64 = 1 << 10;
+        let path = tempdir().unwrap();
+        let table = Table::open(&path, OPTIONS).await.unwrap();
+        let keys: [&[u8]; 2] = [b"a", b"b"];
+        table.put(keys[0], 1, b"v").await.unwrap();
+        assert!(!table.rename(b"c", b"d", 1).await.unwrap());
+        assert_eq!(table.get(b"d", 1).await.unwrap(), None);
+
+        let done = Arc::new(AtomicBool::new(false));
+        let reader = {
+            let table = table.clone();
+            let done = done.clone();
+            photonio::task::spawn(async move {
+                let mut reads = 0;
+                while !done.load(Ordering::Acquire) {
+                    let snapshot = table.snapshot();
+                    let values = table
+                        .multi_get_with_snapshot(&keys, &snapshot)
+                        .await
+                        .unwrap();
+                    let present = values.iter().filter(|v| v.is_some()).count();
+                    assert_eq!(present, 1, "{values:?} at {}", snapshot.lsn());
+                    reads += 1;
+                    photonio::task::yield_now().await;
+                }
+                reads
+            })
+        };
+        for lsn in 2..ROUNDS + 2 {
+            let (from, to) = if lsn % 2 == 0 {
+                (keys[0], keys[1])
+            } else {
+                (keys[1], keys[0])
+            };
+            assert!(table.rename(from, to, lsn).await.unwrap());
+            assert_eq!(table.latest_lsn(), lsn);
+            photonio::task::yield_now().await;
+        }
+        done.store(true, Ordering::Release);
+        assert!(reader.await.unwrap() > 0);
+
+        // Older versions are still visible to older LSNs.
+        assert_eq!(table.get(keys[0], 1).await.unwrap(), Some(b"v".to_vec()));
+        assert_eq!(table.get(keys[1], 1).await.unwrap(), None);
+        assert_eq!(table.get(keys[1], 2).await.unwrap(), Some(b"v".to_vec()));
+        table.close().await.unwrap();
+    }
+
+    #[photonio::test]
+    async fn rename_with_concurrent_writes() {
+        const ROUNDS: u64 = 1 << 10;
+        let path = tempdir().unwrap();
+        let table = Table::open(&path, OPTIONS).await.unwrap();
+        let keys: [&[u8]; 2] = [b"a", b"b"];
+        table.put(keys[0], 1, b"v").await.unwrap();
+
+        let done = Arc::new(AtomicBool::new(false));
+        let reader = {
+            let table = table.clone();
+            let done = done.clone();
+            photonio::task::spawn(async move {
+                let mut reads = 0;
+                while !done.load(Ordering::Acquire) {
+                    let snapshot = table.snapshot();
+                    let values = table
+                        .multi_get_with_snapshot(&keys, &snapshot)
+                        .await
+                        .unwrap();
+                    let present = values.iter().filter(|v| v.is_some()).count();
+                    assert_eq!(present, 1, "{values:?} at {}", snapshot.lsn());
+                    reads += 1;
+                    photonio::task::yield_now().await;
+                }
+                reads
+            })
+        };
+        for i in 0..ROUNDS {
+            let lsn = i * 2 + 2;
+            let (from, to) = if i % 2 == 0 {
+                (keys[0], keys[1])
+            } else {
+                (keys[1], keys[0])
+            };
+            // Another key is written with a larger LSN while the rename is in progress,
+            // which must not publish the rename halfway.
+            let (renamed, written) =
+                futures::future::join(table.rename(from, to, lsn), table.put(b"c", lsn + 1, b"v"))
+                    .await;
+            assert!(renamed.unwrap());
+            written.unwrap();
+            assert_eq!(table.latest_lsn(), lsn + 1);
+            photonio::task::yield_now().await;
+        }
+        done.store(true, Ordering::Release);
+        assert!(reader.await.unwrap() > 0);
+        table.close().await.unwrap();
+    }
+
+    #[photonio::test]
+    async fn delete_range() {
+        const N: u64 = 1 << 10;
+        let path = tempdir().unwrap();
+        let table = Table::open(&path, OPTIONS).await.unwrap();
+        for i in 0..N {
+            let key = i.to_be_bytes();
+            table.put(&key, 1, &key).await.unwrap();
+        }
+        let (start, end) = (N / 4, N / 2);
+        table
+            .delete_range(&start.to_be_bytes(), &end.to_be_bytes(), 2)
+            .await
+            .unwrap();
+        // A later insert in the range is visible.
+        let inserted = start + 1;
+        table.put(&inserted.to_be_bytes(), 3, b"new").await.unwrap();
+
+        for _ in 0..2 {
+            for i in 0..N {
+                let key = i.to_be_bytes();
+                let expect = if i == inserted {
+                    Some(b"new".to_vec())
+                } else if (start..end).contains(&i) {
+                    None
+                } else {
+                    Some(key.to_vec())
+                };
+                assert_eq!(table.get(&key, 3).await.unwrap(), expect);
+                // The range doesn't shadow reads before it.
+                assert_eq!(table.get(&key, 1).await.unwrap(), Some(key.to_vec()));
+            }
+            let entries = table
+                .scan(Bound::Unbounded, Bound::Unbounded, 3)
+                .await
+                .unwrap();
+            assert_eq!(entries.len() as u64, N - (end - start) + 1);
+
+            // Collapse the range into the pages.
+            table.set_safe_lsn(1);
+            table.compact().await.unwrap();
+        }
+        table.close().await.unwrap();
+    }
+
+    #[photonio::test]
+    async fn engine_shares_caches() {
+        const N: u64 = 1 << 10;
+        const CAPACITY: usize = 16 << 10;
+        let paths = [tempdir().unwrap(), tempdir().unwrap()];
+        for path in &paths {
+            let table = Table::open(path, OPTIONS).await.unwrap();
+            for i in 0..N {
+                must_put(&table, i, 0).await;
+            }
+            table.close().await.unwrap();
+        }
+
+        let mut engine_opts = EngineOptions::default();
+        engine_opts.cache_capacity = CAPACITY;
+        let engine = Engine::new(engine_opts);
+        let mut opts = OPTIONS;
+        opts.page_store.memory_budget = Some(1 << 20);
+        assert!(matches!(
+            engine.open_table(&paths[0], opts.clone()).await,
+            Err(Error::InvalidOptions(_))
+        ));
+        // The cache capacity of the tables is ignored.
+        opts.page_store.memory_budget = None;
+        opts.page_store.cache_capacity = 1 << 20;
+        let t1 = engine.open_table(&paths[0], opts.clone()).await.unwrap();
+        let t2 = engine.open_table(&paths[1], opts).await.unwrap();
+        assert_eq!(engine.stats().page_cache_capacity, CAPACITY);
+        assert_eq!(t1.stats().store.memory.page_cache, CAPACITY);
+        assert_eq!(t2.stats().store.memory.page_cache, CAPACITY);
+
+        // A small range of the first table fits in the cache.
+        for _ in 0..2 {
+            for i in 0..N / 8 {
+                must_get(&t1, i, 0, Some(i)).await;
+            }
+        }
+        let stats = t1.stats().store;
+        for i in 0..N / 8 {
+            must_get(&t1, i, 0, Some(i)).await;
+        }
+        t1.stats().store.delta(&stats).assert_no_disk_reads();
+
+        // Reading the second table evicts the pages of the first one.
+        for i in 0..N {
+            must_get(&t2, i, 0, Some(i)).await;
+        }
+        let stats = t1.stats().store;
+        for i in 0..N / 8 {
+            must_get(&t1, i, 0, Some(i)).await;
+        }
+        let delta = t1.stats().store.delta(&stats);
+        assert!(delta.reads_from_disk() > 0, "{delta}");
+
+        // Every disk read of both tables missed the shared cache.
+        let disk_reads =
+            t1.stats().store.read_source.from_disk + t2.stats().store.read_source.from_disk;
+        assert!(engine.stats().page_cache.lookup_miss >= disk_reads);
+        t1.close().await.unwrap();
+        t2.close().await.unwrap();
+    }
+
+    #[photonio::test]
+    async fn flush_and_checkpoint() {
+        const N: u64 = 1 << 10;
+        let path = tempdir().unwrap();
+        let table = Table::open(&path, OPTIONS).await.unwrap();
+        for i in 0..N {
+            must_put(&table, i, 1).await;
+        }
+        // The writes are still in the write buffer.
+        assert!(table.live_files().is_empty());
+        let dir = tempdir().unwrap();
+        let checkpoint = dir.path().join("checkpoint");
+        table.flush_and_checkpoint(&checkpoint).await.unwrap();
+        // Writes after the checkpoint are not included.
+        for i in 0..N {
+            table.put(&i.to_be_bytes(), 2, b"new").await.unwrap();
+        }
+        assert!(matches!(
+            table.flush_and_checkpoint(&checkpoint).await,
+            Err(Error::Io(_))
+        ));
+        table.close().await.unwrap();
+
+        let table = Table::open(&checkpoint, OPTIONS).await.unwrap();
+        assert_eq!(table.latest_lsn(), 1);
+        for i in 0..N {
+            must_get(&table, i, 2, Some(i)).await;
+        }
+        table.close().await.unwrap();
+    }
+
+    #[photonio::test]
+    async fn table_id() {
+        let path = tempdir().unwrap();
+        let table = Table::open(&path, OPTIONS).await.unwrap();
+        let id = table.id();
+        must_put(&table, 1, 1).await;
+        let dir = tempdir().unwrap();
+        let checkpoint = dir.path().join("checkpoint");
+        table.flush_and_checkpoint(&checkpoint).await.unwrap();
+        table.close().await.unwrap();
+
+        // Reopening keeps the id, even if nothing is written in between.
+        for _ in 0..2 {
+            let table = Table::open(&path, OPTIONS).await.unwrap();
+            assert_eq!(table.id(), id);
+            table.close().await.unwrap();
+        }
+
+        let table = Table::open(&checkpoint, OPTIONS).await.unwrap();
+        assert_eq!(table.id(), id);
+        table.close().await.unwrap();
+
+        let other_path = tempdir().unwrap();
+        let other = Table::open(&other_path, OPTIONS).await.unwrap();
+        assert_ne!(other.id(), id);
+        other.close().await.unwrap();
+    }
+
+    #[photonio::test]
+    async fn writebuf_disabled() {
+        const N: u64 = 1 << 8;
+        let path = tempdir().unwrap();
+        let mut options = OPTIONS;
+        options.page_store.writebuf_disabled = true;
+        let table = Table::open(&path, options.clone()).await.unwrap();
+        for i in 0..N {
+            must_put(&table, i, i).await;
+            must_get(&table, i, i, Some(i)).await;
+        }
+        let stats = table.stats().store.writebuf;
+        assert_eq!(stats.read_in_buf, 0);
+        assert!(stats.read_in_file > 0);
+        table.close().await.unwrap();
+
+        options.page_store.avoid_flush_during_shutdown = true;
+        let table = Table::open(&path, options).await.unwrap();
+        for i in 0..N {
+            must_get(&table, i, N, Some(i)).await;
+        }
+        table.close().await.unwrap();
+    }
+
+    #[photonio::test]
+    async fn compression_dict() {
+        const N: u64 = 1 << 10;
+        let value = |i: u64, lsn: u64| format!(r#"{{"id":{i},"lsn":{lsn},"status":"active"}}"#);
+        let path = tempdir().unwrap();
+        let mut options = OPTIONS;
+        options.page_store.compression_on_flush = Compression::ZSTD;
+        // The pages flushed before are not compressed with a dictionary.
+        let table = Table::open(&path, options.clone()).await.unwrap();
+        for i in 0..N {
+            let value = value(i, 1);
+            table
+                .put(&i.to_be_bytes(), 1, value.as_bytes())
+                .await
+                .unwrap();
+        }
+        table.flush(&FlushOptions::default()).await;
+        table.close().await.unwrap();
+
+        options.page_store.enable_compression_dict = true;
+        let table = Table::open(&path, options.clone()).await.unwrap();
+        for i in (0..N).step_by(2) {
+            let value = value(i, 2);
+            table
+                .put(&i.to_be_bytes(), 2, value.as_bytes())
+                .await
+                .unwrap();
+        }
+        table.flush(&FlushOptions::default()).await;
+        table.close().await.unwrap();
+
+        let table = Table::open(&path, options).await.unwrap();
+        for i in 0..N {
+            let lsn = if i % 2 == 0 { 2 } else { 1 };
+            let got = table.get(&i.to_be_bytes(), 2).await.unwrap();
+            assert_eq!(got, Some(value(i, lsn).into_bytes()));
+        }
+        table.close().await.unwrap();
+    }
+
+    #[photonio::test]
+    async fn merge_with_append_operator() {
+        let path = tempdir().unwrap();
+        let table = Table::open(&path, OPTIONS).await.unwrap();
+        assert!(matches!(
+            table.merge(b"log", 1, b"x").await,
+            Err(Error::InvalidOptions(_))
+        ));
+        table.close().await.unwrap();
+
+        let path = tempdir().unwrap();
+        let opts = TableOptions {
+            merge_operator: Some(Arc::new(AppendMergeOperator::with_separator(b','))),
+            ..OPTIONS
+        };
+        let table = Table::open(&path, opts).await.unwrap();
+        let mut expect = Vec::new();
+        for i in 0..1000u64 {
+            let operand = i.to_string();
+            table
+                .merge(b"log", i + 1, operand.as_bytes())
+                .await
+                .unwrap();
+            if i > 0 {
+                expect.push(b',');
+            }
+            expect.extend_from_slice(operand.as_bytes());
+        }
+        let value = table.get(b"log", 1000).await.unwrap();
+        assert_eq!(value, Some(expect));
+        assert_eq!(table.get(b"log", 2).await.unwrap(), Some(b"0,1".to_vec()));
+        table.close().await.unwrap();
+    }
+
+    #[photonio::test]
+    async fn scan_with_resume_tokens() {
+        let path = tempdir().unwrap();
+        let table = Table::open(&path, OPTIONS).await.unwrap();
+        const N: u64 = 1 << 10;
+        for i in 0..N {
+            must_put(&table, i, 1).await;
+        }
+        let (start, end) = (3u64.to_be_bytes(), (N - 3).to_be_bytes());
+        let (start, end) = (Bound::Included(&start[..]), Bound::Excluded(&end[..]));
+        let expect = table.scan(start, end, 1).await.unwrap();
+
+        // Entries written after the scan started are not visible to it.
+        let (mut actual, mut token) = table.scan_batch(start, end, 1, 100).await.unwrap();
+        for i in 0..N {
+            table.put(&i.to_be_bytes(), 2, b"v2").await.unwrap();
+        }
+        while let Some(t) = token {
+            let t = ResumeToken::decode(&t.encode()).unwrap();
+            assert_eq!(t.lsn(), 1);
+            let (entries, next) = table.scan_resume(&t, end, 100).await.unwrap();
+            assert!(entries.len() <= 100);
+            actual.extend(entries);
+            token = next;
+        }
+        assert_eq!(actual, expect);
+
+        // A batch that reaches the end of the range has no token.
+        let (entries, token) = table.scan_batch(start, end, 1, expect.len()).await.unwrap();
+        assert_eq!(entries, expect);
+        assert!(token.is_none());
+
+        let (_, token) = table.scan_batch(start, end, 1, 1).await.unwrap();
+        table.set_safe_lsn(2);
+        assert!(matches!(
+            table.scan_resume(&token.unwrap(), end, 1).await,
+            Err(Error::StaleLsn {
+                lsn: 1,
+                safe_lsn: 2
+            })
+        ));
+        assert!(matches!(
+            ResumeToken::decode(&[0; 7]),
+            Err(Error::InvalidArgument(_))
+        ));
+        assert!(matches!(
+            table.scan_batch(start, end, 1, 0).await,
+            Err(Error::InvalidArgument(_))
+        ));
+
+        table.close().await.unwrap();
+    }
+
+    #[photonio::test]
+    async fn get_versions() {
+        let path = tempdir().unwrap();
+        let table = Table::open(&path, OPTIONS).await.unwrap();
+        let key = 1u64.to_be_bytes();
+        must_put(&table, 0, 1).await;
+        table.put(&key, 1, b"v1").await.unwrap();
+        table.put(&key, 2, b"v2").await.unwrap();
+        table.delete(&key, 3).await.unwrap();
+        must_put(&table, 2, 4).await;
+
+        let versions = table.get_versions(&key, u64::MAX).await.unwrap();
+        assert_eq!(
+            versions,
+            vec![
+                (3, None),
+                (2, Some(b"v2".to_vec())),
+                (1, Some(b"v1".to_vec()))
+            ]
+        );
+        let versions = table.get_versions(&key, 2).await.unwrap();
+        assert_eq!(
+            versions,
+            vec![(2, Some(b"v2".to_vec())), (1, Some(b"v1".to_vec()))]
+        );
+        let versions = table.get_versions(&key, 0).await.unwrap();
+        assert!(versions.is_empty());
+
+        table.close().await.unwrap();
+    }
+
+    #[photonio::test]
+    async fn iter_file() {
+        let path = tempdir().unwrap();
+        let table = Table::open(&path, OPTIONS).await.unwrap();
+        const N: u64 = 1 << 10;
+        for i in 0..N {
+            must_put(&table, i, 1).await;
+        }
+        table.flush(&FlushOptions::default()).await;
+        for i in (0..N).step_by(2) {
+            table.put(&i.to_be_bytes(), 2, b"v2").await.unwrap();
+        }
+        for i in (0..N).step_by(3) {
+            table.delete(&i.to_be_bytes(), 3).await.unwrap();
+        }
+        table.flush(&FlushOptions::default()).await;
+
+        let mut latest = BTreeMap::new();
+        let file_ids = table.file_ids();
+        assert!(!file_ids.is_empty());
+        for file_id in file_ids {
+            for (key, value, lsn) in table.iter_file(file_id).await.unwrap() {
+                match latest.get(&key) {
+                    Some(&(latest_lsn, _)) if latest_lsn >= lsn => {}
+                    _ => {
+                        latest.insert(key, (lsn, value));
+                    }
+                }
+            }
+        }
+        let expect = latest
+            .into_iter()
+            .filter_map(|(k, (_, v))| v.map(|v| (k, v)))
+            .collect::<Vec<_>>();
+
+        let guard = table.pin();
+        let mut pages = guard.pages();
+        let mut actual = Vec::new();
+        while let Some(page) = pages.next().await.unwrap() {
+            for (k, v) in page {
+                actual.push((k.to_vec(), v.to_vec()));
+            }
+        }
+        assert_eq!(actual, expect);
+        drop(pages);
+        drop(guard);
+
+        table.close().await.unwrap();
+    }
+
+    #[photonio::test]
+    async fn pin_live_files() {
+        let path = tempdir().unwrap();
+        let mut opts = OPTIONS;
+        opts.page_size = 1 << 10;
+        opts.page_store.file_base_size = 1;
+        opts.page_store.max_space_amplification_percent = 40;
+        let table = Table::open(&path, opts.clone()).await.unwrap();
+        const N: u64 = 1 << 10;
+        for i in 0..N {
+            must_put(&table, i, 1).await;
+        }
+        table.flush(&FlushOptions::default()).await;
+
+        let guard = table.pin_live_files();
+        let files = guard.files().to_vec();
+        assert!(!files.is_empty());
+        assert_eq!(files, table.live_files());
+        for file in &files {
+            assert_eq!(file.path.metadata().unwrap().len(), file.size);
+            assert_eq!((file.min_lsn, file.max_lsn), (1, 1));
+        }
+
+        // Overwrite all entries, so that the pinned files are fully garbage and
+        // reclaimed.
+        for i in 0..N {
+            must_put(&table, i, 2).await;
+        }
+        table.flush(&FlushOptions::default()).await;
+        table.wait_for_reclaiming().await;
+        assert!(table.stats().store.jobs.reclaim_runs > 0);
+        let live_ids = table.file_ids();
+        assert!(files.iter().any(|f| !live_ids.contains(&f.file_id)));
+        for file in &files {
+            assert!(file.path.exists(), "{file:?}");
+        }
+        drop(guard);
+        table.close().await.unwrap();
+
+        // Obsoleted files are removed once they are unpinned.
+        let table = Table::open(&path, opts).await.unwrap();
+        let live_ids = table.file_ids();
+        for file in &files {
+            assert_eq!(file.path.exists(), live_ids.contains(&file.file_id));
+        }
+        table.close().await.unwrap();
+    }
+
+    #[photonio::test]
+    async fn compact_file() {
+        const N: u64 = 1 << 10;
+        let path = tempdir().unwrap();
+        let mut opts = OPTIONS;
+        opts.page_size = 1 << 10;
+        opts.page_store.disable_space_reclaiming = true;
+        let table = Table::open(&path, opts.clone()).await.unwrap();
+        for i in 0..N {
+            must_put(&table, i, 1).await;
+        }
+        table.flush(&FlushOptions::default()).await;
+        let file_id = *table.file_ids().iter().min().unwrap();
+        // Overwrite half of the entries, so that the file is partially live.
+        for i in (0..N).step_by(2) {
+            must_put(&table, i, 2).await;
+        }
+        table.flush(&FlushOptions::default()).await;
+
+        let file = PickedFile {
+            file_id,
+            active_size: 0,
+        };
+        table.compact_file(file).await.unwrap();
+        assert!(!table.file_ids().contains(&file_id));
+        assert!(matches!(
+            table.compact_file(file).await,
+            Err(Error::MissingFile { file_id: id }) if id == file_id
+        ));
+        for i in 0..N {
+            must_get(&table, i, 1, Some(i)).await;
+        }
+        table.close().await.unwrap();
+
+        let table = Table::open(&path, opts).await.unwrap();
+        assert!(!table.file_ids().contains(&file_id));
+        for i in 0..N {
+            must_get(&table, i, 2, Some(i)).await;
+        }
+        table.close().await.unwrap();
+    }
+
+    #[photonio::test]
+    async fn compact_file_with_dead_pages() {
+        const N: u64 = 1 << 10;
+        let path = tempdir().unwrap();
+        let mut opts = OPTIONS;
+        opts.page_size = 1 << 10;
+        opts.page_store.disable_space_reclaiming = true;
+        let table = Table::open(&path, opts.clone()).await.unwrap();
+        for i in 0..N {
+            must_put(&table, i, 1).await;
+        }
+        table.flush(&FlushOptions::default()).await;
+        let file_id = *table.file_ids().iter().min().unwrap();
+        let file = PickedFile {
+            file_id,
+            active_size: 0,
+        };
+        table.compact_file(file).await.unwrap();
+        // Returns the id and size of the file that is not in `file_ids`.
+        let new_file = |table: &Table, file_ids: &[u32]| {
+            let files = table.live_files();
+            let file = files.iter().find(|f| !file_ids.contains(&f.file_id));
+            (file.unwrap().file_id, file.unwrap().size)
+        };
+        let (map_file_id, map_file_size) = new_file(&table, &[]);
+
+        // Rewrite the first half of the entries until their pages in the map
+        // file are consolidated away, so that it is partially dead.
+        for lsn in 2..2 + opts.page_chain_length as u64 * 2 {
+            for i in 0..N / 2 {
+                must_put(&table, i, lsn).await;
+            }
+            table.flush(&FlushOptions::default()).await;
+        }
+        let file_ids = table.file_ids();
+        let file = PickedFile {
+            file_id: map_file_id,
+            active_size: 0,
+        };
+        table.compact_file(file).await.unwrap();
+        let (new_file_id, new_file_size) = new_file(&table, &file_ids);
+        assert!(!table.file_ids().contains(&map_file_id));
+        assert!(
+            new_file_size < map_file_size,
+            "file {new_file_id} of {new_file_size} bytes is not smaller than \
+             file {map_file_id} of {map_file_size} bytes"
+        );
+        for i in 0..N {
+            must_get(&table, i, u64::MAX, Some(i)).await;
+        }
+        table.close().await.unwrap();
+
+        let table = Table::open(&path, opts).await.unwrap();
+        for i in 0..N {
+            must_get(&table, i, u64::MAX, Some(i)).await;
+        }
+        table.close().await.unwrap();
+    }
+
+    #[photonio::test]
+    async fn locate_key() {
+        let path = tempdir().unwrap();
+        let mut opts = OPTIONS;
+        opts.page_store.disable_space_reclaiming = true;
+        let table = Table::open(&path, opts).await.unwrap();
+        let key = 1u64.to_be_bytes();
+        assert_eq!(table.locate_key(&key, 1).await.unwrap(), None);
+
+        // The version is only in the write buffer.
+        must_put(&table, 1, 1).await;
+        assert_eq!(table.locate_key(&key, 1).await.unwrap(), None);
+
+        table.flush(&FlushOptions::default()).await;
+        let page_file = table.locate_key(&key, 1).await.unwrap().unwrap();
+        assert!(table.file_ids().contains(&page_file.file_id));
+        assert!(page_file.active_size > 0);
+        // No version is visible to a smaller LSN.
+        assert_eq!(table.locate_key(&key, 0).await.unwrap(), None);
+
+        table.compact_file(page_file).await.unwrap();
+        let map_file = table.locate_key(&key, 1).await.unwrap().unwrap();
+        let file_ids = table.file_ids();
+        assert!(!file_ids.contains(&page_file.file_id));
+        assert!(file_ids.contains(&map_file.file_id));
+
+        // Deleted versions are not located.
+        table.delete(&key, 2).await.unwrap();
+        table.flush(&FlushOptions::default()).await;
+        assert_eq!(table.locate_key(&key, 2).await.unwrap(), None);
+        assert!(table.locate_key(&key, 1).await.unwrap().is_some());
+        let other = 2u64.to_be_bytes();
+        must_put(&table, 2, 3).await;
+        table
+            .delete_range(&other, &3u64.to_be_bytes(), 4)
+            .await
+            .unwrap();
+        table.flush(&FlushOptions::default()).await;
+        assert!(table.locate_key(&other, 3).await.unwrap().is_some());
+        assert_eq!(table.locate_key(&other, 4).await.unwrap(), None);
+        table.close().await.unwrap();
+    }
+
+    #[photonio::test]
+    async fn read_during_map_file_compaction() {
+        const N: u64 = 1 << 10;
+        const ROUNDS: u64 = 8;
+        let path = tempdir().unwrap();
+        let mut opts = OPTIONS;
+        opts.page_size = 1 << 10;
+        opts.page_store.disable_space_reclaiming = true;
+        let table = Table::open(&path, opts.clone()).await.unwrap();
+        for i in 0..N {
+            must_put(&table, i, 1).await;
+        }
+        table.flush(&FlushOptions::default()).await;
+
+        let done = Arc::new(AtomicBool::new(false));
+        let reader = {
+            let table = table.clone();
+            let done = done.clone();
+            photonio::task::spawn(async move {
+                let mut scans = 0;
+                while !done.load(Ordering::Acquire) {
+                    for i in 0..N {
+                        must_get(&table, i, u64::MAX, Some(i)).await;
+                    }
+                    scans += 1;
+                    photonio::task::yield_now().await;
+                }
+                scans
+            })
+        };
+        // Flushes deallocate pages of the files being compacted, and may
+        // obsolete them before the compaction commits.
+        let writer = {
+            let table = table.clone();
+            photonio::task::spawn(async move {
+                for lsn in 2..ROUNDS + 2 {
+                    for i in (lsn % 2..N).step_by(2) {
+                        must_put(&table, i, lsn).await;
+                    }
+                    table.flush(&FlushOptions::default()).await;
+                }
+            })
+        };
+
+        let mut compacted = 0;
+        for _ in 0..ROUNDS {
+            let file_id = *table.file_ids().iter().min().unwrap();
+            let file = PickedFile {
+                file_id,
+                active_size: 0,
+            };
+            match table.compact_file(file).await {
+                Ok(()) => compacted += 1,
+                Err(Error::MissingFile { .. }) => {}
+                Err(err) => panic!("compact file {file_id}: {err:?}"),
+            }
+            photonio::task::yield_now().await;
+        }
+        writer.await.unwrap();
+        done.store(true, Ordering::Release);
+        assert!(reader.await.unwrap() > 0);
+        assert!(compacted > 0);
+        for i in 0..N {
+            must_get(&table, i, u64::MAX, Some(i)).await;
+        }
+        table.close().await.unwrap();
+
+        let table = Table::open(&path, opts).await.unwrap();
+        for i in 0..N {
+            must_get(&table, i, u64::MAX, Some(i)).await;
+        }
+        table.close().await.unwrap();
+    }
+
+    #[photonio::test]
+    async fn custom_reclaim_strategy() {
+        // Picks files in ascending order of ids.
+        struct LowestFileIdBuilder;
+        #[derive(Default)]
+        struct LowestFileId(Vec<PickedFile>);
+
+        impl StrategyBuilder for LowestFileIdBuilder {
+            fn build(&self, _now: u32) -> Box<dyn ReclaimPickStrategy> {
+                Box::<LowestFileId>::default()
+            }
+        }
+
+        impl ReclaimPickStrategy for LowestFileId {
+            fn collect_file(&mut self, summary: &FileSummary) {
+                self.0.push(PickedFile {
+                    file_id: summary.file_id(),
+                    active_size: summary.active_size(),
+                });
+                self.0
+                    .sort_unstable_by_key(|f| ::std::cmp::Reverse(f.file_id));
+            }
+
+            fn apply(&mut self) -> Option<PickedFile> {
+                self.0.pop()
+            }
+        }
+
+        let path = tempdir().unwrap();
+        let mut opts = OPTIONS;
+        opts.page_size = 1 << 10;
+        opts.page_store.disable_space_reclaiming = true;
+        opts.page_store.reclaim_strategy = Some(Arc::new(LowestFileIdBuilder));
+        let table = Table::open(&path, opts).await.unwrap();
+        for lsn in 1..=4 {
+            for i in 0..(1 << 8) {
+                must_put(&table, i, lsn).await;
+            }
+            table.flush(&FlushOptions::default()).await;
+        }
+
+        let file_ids = table.file_ids();
+        assert!(file_ids.len() > 1);
+        let picked = table.reclaim_dry_run().await;
+        let picked_ids = picked.iter().map(|(f, _)| f.file_id).collect::<Vec<_>>();
+        assert_eq!(picked_ids, file_ids);
+
+        // The strategy can still be replaced at runtime.
+        table.set_reclaim_strategy(ReclaimStrategy::MaxFreeSpace);
+        let picked = table.reclaim_dry_run().await;
+        assert_eq!(picked.len(), file_ids.len() - 1);
+        table.close().await.unwrap();
+    }
+
+    #[photonio::test]
+    async fn page_heat() {
+        const N: u64 = 1 << 8;
+        let path = tempdir().unwrap();
+        let mut opts = OPTIONS;
+        opts.page_size = 1 << 10;
+        opts.page_store.disable_space_reclaiming = true;
+        let table = Table::open(&path, opts).await.unwrap();
+        for i in 0..N {
+            must_put(&table, i, 1).await;
+        }
+        table.flush(&FlushOptions::default()).await;
+        let cold_file = *table.file_ids().last().unwrap();
+        for i in N..N * 2 {
+            must_put(&table, i, 1).await;
+        }
+        table.flush(&FlushOptions::default()).await;
+        let hot_file = *table.file_ids().last().unwrap();
+        assert_ne!(cold_file, hot_file);
+
+        for i in 0..N {
+            must_get(&table, i, 1, Some(i)).await;
+        }
+        for _ in 0..16 {
+            for i in N..N * 2 {
+                must_get(&table, i, 1, Some(i)).await;
+            }
+        }
+        let heat = table.page_heat().into_iter().collect::<BTreeMap<_, _>>();
+        assert_eq!(heat.keys().copied().collect::<Vec<_>>(), table.file_ids());
+        assert!(heat[&hot_file] > heat[&cold_file], "{heat:?}");
+        table.close().await.unwrap();
+    }
+
+    #[photonio::test]
+    async fn empty_key_and_value() {
+        let path = tempdir().unwrap();
+        let mut opts = OPTIONS;
+        opts.page_size = 1 << 10;
+        let table = Table::open(&path, opts.clone()).await.unwrap();
+        table.put(b"", 1, b"empty key").await.unwrap();
+        table.put(b"a", 1, b"").await.unwrap();
+        // Split pages so that the empty key is kept in the leftmost one.
+        for i in 0..(1 << 10) {
+            must_put(&table, i, 1).await;
+        }
+        assert_eq!(
+            table.get(b"", 1).await.unwrap(),
+            Some(b"empty key".to_vec())
+        );
+        assert_eq!(table.get(b"a", 1).await.unwrap(), Some(Vec::new()));
+        assert_eq!(table.get(b"b", 1).await.unwrap(), None);
+
+        table.delete(b"a", 2).await.unwrap();
+        assert_eq!(table.get(b"a", 2).await.unwrap(), None);
+        assert_eq!(table.get(b"a", 1).await.unwrap(), Some(Vec::new()));
+
+        // The empty key is the smallest one.
+        let entries = table
+            .scan(Bound::Unbounded, Bound::Included(b"a"), 1)
+            .await
+            .unwrap();
+        assert_eq!(entries.first(), Some(&(Vec::new(), b"empty key".to_vec())));
+        assert_eq!(entries.last(), Some(&(b"a".to_vec(), Vec::new())));
+        table.close().await.unwrap();
+
+        let table = Table::open(&path, opts).await.unwrap();
+        assert_eq!(
+            table.get(b"", 1).await.unwrap(),
+            Some(b"empty key".to_vec())
+        );
+        assert_eq!(table.get(b"a", 1).await.unwrap(), Some(Vec::new()));
+        // Only the empty key is smaller than "\0".
+        table.delete_range(b"", b"\0", 3).await.unwrap();
+        assert_eq!(table.get(b"", 3).await.unwrap(), None);
+        assert_eq!(
+            table.get(b"", 2).await.unwrap(),
+            Some(b"empty key".to_vec())
+        );
+        must_get(&table, 0, 3, Some(0)).await;
+        table.close().await.unwrap();
+    }
+
+    #[photonio::test]
+    async fn space_report() {
+        const N: u64 = 1 << 10;
+        let path = tempdir().unwrap();
+        let mut opts = OPTIONS;
+        opts.page_size = 1 << 10;
+        opts.page_store.disable_space_reclaiming = true;
+        let table = Table::open(&path, opts).await.unwrap();
+        assert_eq!(table.space_report(), SpaceReport::default());
+
+        for lsn in 1..=4 {
+            for i in 0..N {
+                must_put(&table, i, lsn).await;
+            }
+            table.flush(&FlushOptions::default()).await;
+        }
+        let report = table.space_report();
+        assert!(report.live_bytes > 0, "{report}");
+        assert!(report.reclaimable_bytes > 0, "{report}");
+        assert!(report.reclaimable_percent() > 0.0, "{report}");
+        assert!(report.amplification > 1.0, "{report}");
+        // The rest is taken by the metadata of files.
+        let page_bytes = report.live_bytes + report.reclaimable_bytes;
+        assert!(page_bytes <= report.total_bytes, "{report}");
+        assert!(page_bytes * 10 >= report.total_bytes * 8, "{report}");
+        table.close().await.unwrap();
+    }
+
+    #[photonio::test]
+    async fn file_count() {
+        let path = tempdir().unwrap();
+        let mut opts = OPTIONS;
+        opts.page_size = 1 << 10;
+        opts.page_store.file_base_size = 1;
+        opts.page_store.disable_space_reclaiming = true;
+        let table = Table::open(&path, opts.clone()).await.unwrap();
+        assert_eq!(table.file_count(), (0, 0));
+        const N: u64 = 1 << 10;
+        let mut last = 0;
+        for lsn in 1..=3 {
+            for i in 0..N {
+                must_put(&table, i, lsn).await;
+            }
+            table.flush(&FlushOptions::default()).await;
+            let (page_files, map_files) = table.file_count();
+            assert!(page_files > last);
+            assert!(map_files > 0);
+            last = page_files;
+        }
+        assert!(table
+            .stats()
+            .to_string()
+            .contains(&format!("page_files: {last}")));
+        table.close().await.unwrap();
+
+        // Old files are fully overwritten, so they are reclaimed.
+        opts.page_store.disable_space_reclaiming = false;
+        opts.page_store.max_space_amplification_percent = 40;
+        let table = Table::open(&path, opts).await.unwrap();
+        for i in 0..N {
+            must_put(&table, i, 4).await;
+        }
+        table.flush(&FlushOptions::default()).await;
+        table.wait_for_reclaiming().await;
+        let (page_files, _) = table.file_count();
+        assert!(page_files < last, "{page_files} >= {last}");
+        table.close().await.unwrap();
+    }
+
+    #[photonio::test]
+    async fn pause_background() {
+        let path = tempdir().unwrap();
+        let mut opts = OPTIONS;
+        opts.page_size = 1 << 10;
+        opts.page_store.file_base_size = 1;
+        opts.page_store.max_space_amplification_percent = 40;
+        let table = Table::open(&path, opts).await.unwrap();
+        table.pause_background();
+        table.pause_background();
+
+        const N: u64 = 1 << 10;
+        for lsn in 1..=3 {
+            for i in 0..N {
+                must_put(&table, i, lsn).await;
+            }
+            table.flush(&FlushOptions::default()).await;
+        }
+        for i in (0..N).step_by(2) {
+            table.delete(&i.to_be_bytes(), 4).await.unwrap();
+        }
+        table.flush(&FlushOptions::default()).await;
+        table.wait_for_reclaiming().await;
+        assert_eq!(table.stats().store.jobs.compact_write_bytes, 0);
+
+        // Reclaiming is still paused by the other pause.
+        table.resume_background();
+        for i in (1..N).step_by(2) {
+            must_put(&table, i, 5).await;
+        }
+        table.flush(&FlushOptions::default()).await;
+        table.wait_for_reclaiming().await;
+        assert_eq!(table.stats().store.jobs.compact_write_bytes, 0);
+
+        table.resume_background();
+        table.wait_for_reclaiming().await;
+        assert!(table.stats().store.jobs.compact_write_bytes > 0);
+        for i in 0..N {
+            must_get(&table, i, 5, (i % 2 == 1).then_some(i)).await;
+        }
+
+        table.close().await.unwrap();
+    }
+
+    #[photonio::test]
+    async fn wait_for_background_idle() {
+        const N: u64 = 1 << 10;
+        let path = tempdir().unwrap();
+        let mut opts = OPTIONS;
+        opts.page_size = 1 << 10;
+        opts.page_store.write_buffer_capacity = 1 << 16;
+        opts.page_store.file_base_size = 1;
+        opts.page_store.max_space_amplification_percent = 40;
+        let table = Table::open(&path, opts).await.unwrap();
+
+        // Write buffers are sealed and flushed in the background.
+        for lsn in 1..=4 {
+            for i in 0..N {
+                must_put(&table, i, lsn).await;
+            }
+        }
+        table.wait_for_background_idle().await;
+        let jobs = table.stats().store.jobs;
+        assert!(jobs.flush_write_bytes > 0);
+        assert!(jobs.reclaim_runs > 0);
+        // Background jobs keep running on the executor while the test yields.
+        let deadline = Instant::now() + Duration::from_millis(50);
+        while Instant::now() < deadline {
+            photonio::task::yield_now().await;
+        }
+        let delta = table.stats().store.jobs.sub(&jobs);
+        assert_eq!(delta.flush_write_bytes, 0);
+        assert_eq!(delta.compact_write_bytes, 0);
+        assert_eq!(delta.reclaim_runs, 0);
+
+        // It returns while reclaiming is paused.
+        table.pause_background();
+        for i in 0..N {
+            must_put(&table, i, 5).await;
+        }
+        table.flush(&FlushOptions::default()).await;
+        table.wait_for_background_idle().await;
+        table.resume_background();
+        table.wait_for_background_idle().await;
+        for i in 0..N {
+            must_get(&table, i, 5, Some(i)).await;
+        }
+        table.close().await.unwrap();
+    }
+
+    #[photonio::test]
+    async fn scan_across_reclaiming() {
+        let path = tempdir().unwrap();
+        let mut opts = OPTIONS;
+        opts.page_size = 1 << 10;
+        opts.page_store.file_base_size = 1;
+        opts.page_store.max_space_amplification_percent = 40;
+        let table = Table::open(&path, opts).await.unwrap();
+        const N: u64 = 1 << 10;
+        for i in 0..N {
+            must_put(&table, i, 1).await;
+        }
+        table.flush(&FlushOptions::default()).await;
+
+        let guard = table.pin();
+        let mut pages = guard.pages_at(1).unwrap();
+        let mut i = N / 4;
+        for (k, v) in pages.seek(&i.to_be_bytes()).await.unwrap() {
+            assert_eq!(k, &i.to_be_bytes());
+            assert_eq!(v, &i.to_be_bytes());
+            i += 1;
+        }
+
+        // Overwrite and delete some entries after the scan started, so that the
+        // file under the scan is partially obsoleted and then reclaimed.
+        for j in N / 2..N * 3 / 4 {
+            table.put(&j.to_be_bytes(), 2, &[]).await.unwrap();
+            table.delete(&j.to_be_bytes(), 3).await.unwrap();
+        }
+        table.flush(&FlushOptions::default()).await;
+        table.wait_for_reclaiming().await;
+        assert!(table.stats().store.jobs.reclaim_runs > 0);
+
+        while let Some(page) = pages.next().await.unwrap() {
+            for (k, v) in page {
+                assert_eq!(k, &i.to_be_bytes());
+                assert_eq!(v, &i.to_be_bytes());
+                i += 1;
+            }
+        }
+        assert_eq!(i, N);
+        drop(pages);
+        drop(guard);
+
+        table.close().await.unwrap();
+    }
+
+    #[photonio::test]
+    async fn consolidate_large_deltas() {
+        async fn consolidate_pages(ratio: Option<f64>) -> u64 {
+            let path = tempdir().unwrap();
+            let mut opts = OPTIONS;
+            opts.page_size = 64 << 10;
+            opts.page_store.cache_capacity = 1 << 20;
+            opts.page_chain_length = 64;
+            opts.consolidate_delta_bytes_ratio = ratio;
+            let table = Table::open(&path, opts).await.unwrap();
+            let value = vec![0u8; 1 << 10];
+            for i in 0..16u64 {
+                table.put(&i.to_be_bytes(), i, &value).await.unwrap();
+            }
+            let consolidated = table.stats().tree.success.consolidate_page;
+            table.close().await.unwrap();
+            consolidated
+        }
+
+        assert_eq!(consolidate_pages(None).await, 0);
+        assert!(consolidate_pages(Some(1.0)).await > 0);
+    }
+
+    #[photonio::test]
+    async fn target_entries() {
+        const N: u64 = 48;
+        let mut opts = OPTIONS;
+        opts.page_size = 2 << 10;
+
+        // The pages are not large enough to be split by size.
+        let path = tempdir().unwrap();
+        let table = Table::open(&path, opts.clone()).await.unwrap();
+        for i in 0..N {
+            must_put(&table, i, i).await;
+        }
+        assert_eq!(table.stats().tree.success.split_page, 0);
+        table.close().await.unwrap();
+
+        let path = tempdir().unwrap();
+        opts.leaf_target_entries = Some(8);
+        opts.index_target_entries = Some(4);
+        let table = Table::open(&path, opts).await.unwrap();
+        for i in 0..N {
+            must_put(&table, i, i).await;
+        }
+        assert!(table.stats().tree.success.split_page >= N / 8);
+        for i in 0..N {
+            must_get(&table, i, N, Some(i)).await;
+        }
+        table.close().await.unwrap();
+    }
+
+    #[photonio::test]
+    async fn truncate_split_separator() {
+        let path = tempdir().unwrap();
+        let mut opts = OPTIONS;
+        opts.truncate_split_separator = true;
+        let table = Table::open(&path, opts).await.unwrap();
+        // Keys have a long common suffix so that truncated separators are much
+        // shorter.
+        let key = |i: u64| format!("{i:08}{:0>32}", "").into_bytes();
+        const N: u64 = 1 << 10;
+        for i in (0..N).map(|i| i * 2) {
+            table.put(&key(i), i, &i.to_be_bytes()).await.unwrap();
+        }
+        assert!(table.stats().tree.success.split_page > 0);
+
+        for i in 0..N * 2 {
+            let expect = (i % 2 == 0).then(|| i.to_be_bytes().to_vec());
+            assert_eq!(table.get(&key(i), u64::MAX).await.unwrap(), expect);
+        }
+        // Keys between two halves must be routed to the right page.
+        for i in (0..N).map(|i| i * 2 + 1) {
+            table.put(&key(i), i, &i.to_be_bytes()).await.unwrap();
+        }
+        let guard = table.pin();
+        let mut pages = guard.pages();
+        let mut i = 0u64;
+        while let Some(page) = pages.next().await.unwrap() {
+            for (k, v) in page {
+                assert_eq!(k, key(i));
+                assert_eq!(v, i.to_be_bytes());
+                i += 1;
+            }
+        }
+        assert_eq!(i, N * 2);
+        drop(guard);
+        table.close().await.unwrap();
+    }
+
+    #[photonio::test]
+    async fn random_crud() {
+        let path = tempdir().unwrap();
+        let table = Table::open(&path, OPTIONS).await.unwrap();
+        const N: u64 = 1 << 12;
+        for _ in 0..N {
+            let i = random();
+            must_put(&table, i, i).await;
+            must_get(&table, i, i, Some(i)).await;
+        }
+        table.close().await.unwrap();
+    }
+
+    #[photonio::test]
+    async fn concurrent_crud() {
+        let path = tempdir().unwrap();
+        let table = Table::open(&path, OPTIONS).await.unwrap();
+        let mut tasks = Vec::new();
+        for _ in 0..4 {
+            let table = table.clone();
+            let handle = photonio::task::spawn(async move {
+                const N: u64 = 1 << 10;
+                for _ in 0..N {
+                    let i = random();
+                    must_put(&table, i, i).await;
+                    must_get(&table, i, i, Some(i)).await;
+                }
+            });
+            tasks.push(handle);
+        }
+        for task in tasks {
+            task.await.unwrap();
+        }
+        table.close().await.unwrap();
+    }
+
+    #[photonio::test]
+    async fn int_keys() {
+        let path = tempdir().unwrap();
+        let table = Table::open(&path, OPTIONS).await.unwrap();
+        let mut keys = vec![0, 1, 0xFF, 0x100, u64::MAX];
+        keys.extend(0x7FFF_FFFF_FFFF_FFF0..0x8000_0000_0000_0010);
+        for (i, k) in keys.iter().rev().enumerate() {
+            table
+                .put_u64(*k, i as u64 + 1, &k.to_le_bytes())
+                .await
+                .unwrap();
+        }
+        let lsn = keys.len() as u64;
+        for k in &keys {
+            let value = table.get_u64(*k, lsn).await.unwrap();
+            assert_eq!(value, Some(k.to_le_bytes().to_vec()));
+        }
+        assert_eq!(table.get_u64(2, lsn).await.unwrap(), None);
+
+        let entries = table
+            .scan(Bound::Unbounded, Bound::Unbounded, lsn)
+            .await
+            .unwrap();
+        let scanned: Vec<u64> = entries
+            .iter()
+            .map(|(k, _)| IntKeyComparator::decode(k).unwrap())
+            .collect();
+        keys.sort_unstable();
+        assert_eq!(scanned, keys);
+        table.close().await.unwrap();
+    }
+
+    #[photonio::test]
+    async fn multi_get_snapshot() {
+        let path = tempdir().unwrap();
+        let table = Table::open(&path, OPTIONS).await.unwrap();
+        const N: u64 = 1 << 8;
+        let keys: [&[u8]; 2] = [b"a", b"b"];
+        table.put(keys[0], 1, &0u64.to_be_bytes()).await.unwrap();
+        table.put(keys[1], 2, &0u64.to_be_bytes()).await.unwrap();
+
+        let writer = {
+            let table = table.clone();
+            photonio::task::spawn(async move {
+                for i in 1..N {
+                    let value = i.to_be_bytes();
+                    table.put(b"a", i * 2 + 1, &value).await.unwrap();
+                    table.put(b"b", i * 2 + 2, &value).await.unwrap();
+                }
+            })
+        };
+        let reader = {
+            let table = table.clone();
+            photonio::task::spawn(async move {
+                let decode = |v: &Option<Vec<u8>>| {
+                    u64::from_be_bytes(v.as_deref().unwrap().try_into().unwrap())
+                };
+                for _ in 0..N {
+                    let snapshot = table.snapshot();
+                    let values = table
+                        .multi_get_with_snapshot(&keys, &snapshot)
+                        .await
+                        .unwrap();
+                    let lsn = snapshot.lsn();
+                    assert_eq!(decode(&values[0]), (lsn - 1) / 2);
+                    assert_eq!(decode(&values[1]), (lsn - 2) / 2);
+
+                    let values = table.multi_get(&keys).await.unwrap();
+                    let (a, b) = (decode(&values[0]), decode(&values[1]));
+                    assert!(a == b || a == b + 1, "torn view: a = {a}, b = {b}");
+                }
+            })
+        };
+        writer.await.unwrap();
+        reader.await.unwrap();
+
+        let snapshot = table.snapshot();
+        assert_eq!(snapshot.lsn(), N * 2);
+        let values = table.multi_get(&keys).await.unwrap();
+        let expect = Some((N - 1).to_be_bytes().to_vec());
+        assert_eq!(values, vec![expect.clone(), expect]);
+        table.close().await.unwrap();
+    }
+
+    #[photonio::test]
+    async fn shared_handles() {
+        fn assert_send_sync<T: Send + Sync + Clone>() {}
+        assert_send_sync::<Table>();
+
+        let path = tempdir().unwrap();
+        let table = Table::open(&path, OPTIONS).await.unwrap();
+        let other = table.clone();
+        const N: u64 = 1 << 8;
+        for i in 0..N {
+            must_put(&table, i, i).await;
+            must_get(&other, i, i, Some(i)).await;
+        }
+        for i in N..N * 2 {
+            must_put(&other, i, i).await;
+            must_get(&table, i, i, Some(i)).await;
+        }
+
+        // The remaining handle still flushes through the background jobs.
+        drop(table);
+        other.flush(&FlushOptions::default()).await;
+        assert!(other.stats().store.jobs.flush_write_bytes > 0);
+        other.close().await.unwrap();
+
+        let table = Table::open(&path, OPTIONS).await.unwrap();
+        for i in 0..N * 2 {
+            must_get(&table, i, i, Some(i)).await;
+        }
+        table.close().await.unwrap();
+    }
+
+    #[photonio::test]
+    async fn direct_io() {
+        let path = tempdir().unwrap();
+        let mut opts = OPTIONS;
+        opts.page_store.use_direct_io = true;
+        let table = Table::open(&path, opts.clone()).await.unwrap();
+        const N: u64 = 1 << 10;
+        for i in 0..N {
+            must_put(&table, i, i).await;
+        }
+        table.flush(&FlushOptions::default()).await;
+        table.close().await.unwrap();
+
+        let table = Table::open(&path, opts).await.unwrap();
+        for i in 0..N {
+            must_get(&table, i, i, Some(i)).await;
+        }
+        assert!(table.stats().store.read_source.from_disk > 0);
+        table.close().await.unwrap();
+    }
+
+    #[photonio::test]
+    async fn mmap_reads() {
+        let path = tempdir().unwrap();
+        let mut opts = OPTIONS;
+        opts.page_size = 1 << 10;
+        opts.page_store.file_base_size = 1;
+        opts.page_store.max_space_amplification_percent = 40;
+        opts.page_store.mmap_reads = true;
+        let table = Table::open(&path, opts.clone()).await.unwrap();
+        const N: u64 = 1 << 10;
+        for i in 0..N {
+            must_put(&table, i, 1).await;
+        }
+        table.flush(&FlushOptions::default()).await;
+        for i in 0..N {
+            must_get(&table, i, 1, Some(i)).await;
+        }
+
+        // Overwrite some entries so that mapped files are reclaimed.
+        for i in N / 2..N {
+            must_put(&table, i, 2).await;
+        }
+        table.flush(&FlushOptions::default()).await;
+        table.wait_for_reclaiming().await;
+        assert!(table.stats().store.jobs.reclaim_runs > 0);
+        for i in 0..N {
+            must_get(&table, i, 2, Some(i)).await;
+        }
+        table.close().await.unwrap();
+
+        let table = Table::open(&path, opts).await.unwrap();
+        for i in 0..N {
+            must_get(&table, i, 2, Some(i)).await;
+        }
+        assert!(table.stats().store.read_source.from_disk > 0);
+        table.close().await.unwrap();
+    }
+
+    #[photonio::test]
+    async fn put_if_lsn() {
+        let path = tempdir().unwrap();
+        let table = Table::open(&path, OPTIONS).await.unwrap();
+        assert!(table.put_if_lsn(b"a", 0, 1, b"1").await.unwrap());
+        // A newer write lands after the version with LSN 1 is read.
+        table.put(b"a", 3, b"3").await.unwrap();
+        assert!(!table.put_if_lsn(b"a", 1, 2, b"2").await.unwrap());
+        assert_eq!(
+            table.get(b"a", u64::MAX).await.unwrap(),
+            Some(b"3".to_vec())
+        );
+        assert!(table.put_if_lsn(b"a", 3, 4, b"4").await.unwrap());
+        // Deletes are versions too.
+        table.delete(b"a", 5).await.unwrap();
+        assert!(!table.put_if_lsn(b"a", 4, 6, b"6").await.unwrap());
+        assert_eq!(table.get(b"a", u64::MAX).await.unwrap(), None);
+        table.close().await.unwrap();
+    }
+
+    #[photonio::test]
+    async fn put_if_lsn_concurrently() {
+        const N: u64 = 64;
+        const T: u64 = 4;
+        let path = tempdir().unwrap();
+        let table = Table::open(&path, OPTIONS).await.unwrap();
+        let next_lsn = Arc::new(AtomicU64::new(1));
+        table.put(b"counter", 0, &0u64.to_be_bytes()).await.unwrap();
+
+        // Each task increments the counter with optimistic writes, retrying if
+        // another task wins the race.
+        let mut tasks = Vec::new();
+        for _ in 0..T {
+            let table = table.clone();
+            let next_lsn = next_lsn.clone();
+            tasks.push(photonio::task::spawn(async move {
+                for _ in 0..N {
+                    loop {
+                        let versions = table.get_versions(b"counter", u64::MAX).await.unwrap();
+                        let (lsn, value) = versions[0].clone();
+                        let count = u64::from_be_bytes(value.unwrap().try_into().unwrap());
+                        let new_lsn = next_lsn.fetch_add(1, Ordering::Relaxed);
+                        let value = (count + 1).to_be_bytes();
+                        if table
+                            .put_if_lsn(b"counter", lsn, new_lsn, &value)
+                            .await
+                            .unwrap()
+                        {
+                            break;
+                        }
+                    }
+                }
+            }));
+        }
+        for task in tasks {
+            task.await.unwrap();
+        }
+        let value = table.get(b"counter", u64::MAX).await.unwrap().unwrap();
+        assert_eq!(u64::from_be_bytes(value.try_into().unwrap()), N * T);
+        table.close().await.unwrap();
+    }
+
+    #[photonio::test]
+    async fn cursors_during_writes_and_reclaiming() {
+        const N: u64 = 1 << 9;
+        const CURSORS: usize = 100;
+        const ROUNDS: u64 = 16;
+        let path = tempdir().unwrap();
+        let mut opts = OPTIONS;
+        opts.page_size = 1 << 10;
+        opts.page_store.write_buffer_capacity = 1 << 16;
+        opts.page_store.file_base_size = 1 << 16;
+        let table = Table::open(&path, opts).await.unwrap();
+        for i in 0..N {
+            must_put(&table, i, 1).await;
+        }
+
+        // Each round overwrites all entries and flushes them, so that files
+        // are obsoleted and reclaimed while the cursors are open.
+        let rounds = Arc::new(AtomicU64::new(0));
+        let writer = {
+            let table = table.clone();
+            let rounds = rounds.clone();
+            photonio::task::spawn(async move {
+                for lsn in 2..ROUNDS + 2 {
+                    for i in 0..N {
+                        let key = i.to_be_bytes();
+                        table.put(&key, lsn, &lsn.to_be_bytes()).await.unwrap();
+                    }
+                    table.flush(&FlushOptions::default()).await;
+                    rounds.fetch_add(1, Ordering::Release);
+                }
+            })
+        };
+
+        // Cursors are opened at different times, so that they pin different
+        // versions, and all of them read the entries visible to LSN 1.
+        let mut cursors = Vec::with_capacity(CURSORS);
+        let mut steps = 0;
+        while rounds.load(Ordering::Acquire) < ROUNDS || steps < N {
+            if cursors.len() < CURSORS {
+                let mut cursor = table.cursor(1).unwrap();
+                let start = random::<u64>() % N;
+                cursor.seek(&start.to_be_bytes()).await.unwrap();
+                cursors.push(cursor);
+            }
+            for cursor in &mut cursors {
+                if !cursor.valid() {
+                    cursor.seek(&[]).await.unwrap();
+                }
+                assert_eq!(cursor.key(), cursor.value());
+                cursor.next().await.unwrap();
+            }
+            steps += 1;
+            photonio::task::yield_now().await;
+        }
+        writer.await.unwrap();
+        assert_eq!(cursors.len(), CURSORS);
+
+        // Reclaiming isn't blocked by the cursors either.
+        table.wait_for_reclaiming().await;
+        assert!(table.stats().store.jobs.files_reclaimed > 0);
+        for cursor in &mut cursors {
+            cursor.seek(&(N - 1).to_be_bytes()).await.unwrap();
+            assert_eq!(cursor.value(), &(N - 1).to_be_bytes());
+        }
+        drop(cursors);
+        for i in 0..N {
+            must_get(&table, i, u64::MAX, Some(ROUNDS + 1)).await;
+        }
+        table.close().await.unwrap();
+    }
+
+    #[photonio::test]
+    async fn put_and_get_previous() {
+        let path = tempdir().unwrap();
+        let table = Table::open(&path, OPTIONS).await.unwrap();
+        assert_eq!(
+            table.put_and_get_previous(b"a", 1, b"1").await.unwrap(),
+            None
+        );
+        assert_eq!(
+            table.put_and_get_previous(b"a", 2, b"2").await.unwrap(),
+            Some(b"1".to_vec())
+        );
+        // The previous value is the one visible to the LSN.
+        table.put(b"a", 4, b"4").await.unwrap();
+        assert_eq!(
+            table.put_and_get_previous(b"a", 3, b"3").await.unwrap(),
+            Some(b"2".to_vec())
+        );
+        table.delete(b"a", 5).await.unwrap();
+        assert_eq!(
+            table.put_and_get_previous(b"a", 6, b"6").await.unwrap(),
+            None
+        );
+        table.close().await.unwrap();
+    }
+
+    #[photonio::test]
+    async fn open_with_splits() {
+        const N: u64 = 1024;
+
+        async fn ingest(table: &Table) -> u64 {
+            let stats = table.stats().tree.success.split_page;
+            for i in 0..N {
+                must_put(table, i, 0).await;
+            }
+            for i in 0..N {
+                must_get(table, i, 0, Some(i)).await;
+            }
+            table.stats().tree.success.split_page - stats
+        }
+
+        let path = tempdir().unwrap();
+        let table = Table::open(&path, OPTIONS).await.unwrap();
+        let splits = ingest(&table).await;
+        table.close().await.unwrap();
+
+        let path = tempdir().unwrap();
+        let points: Vec<_> = (2..N).step_by(2).map(|i| i.to_be_bytes()).collect();
+        let points: Vec<_> = points.iter().map(|p| p.as_slice()).collect();
+        let table = Table::open_with_splits(&path, OPTIONS, &points)
+            .await
+            .unwrap();
+        let presplit_splits = ingest(&table).await;
+        assert!(
+            presplit_splits * 4 < splits,
+            "{presplit_splits} vs {splits}"
+        );
+        table.close().await.unwrap();
+
+        // Split points are ignored when the table exists.
+        let table = Table::open_with_splits(&path, OPTIONS, &[b"b"])
+            .await
+            .unwrap();
+        must_get(&table, 1, 0, Some(1)).await;
+        table.close().await.unwrap();
+
+        let path = tempdir().unwrap();
+        for points in [&[b"b".as_slice(), b"a"][..], &[b"a", b"a"], &[b""]] {
+            assert!(matches!(
+                Table::open_with_splits(&path, OPTIONS, points).await,
+                Err(Error::InvalidOptions(_))
+            ));
+        }
+    }
+
+    #[photonio::test]
+    async fn merge_pages() {
+        const N: u64 = 4096;
+
+        async fn count_pages(table: &Table) -> usize {
+            let guard = table.pin();
+            let mut pages = guard.pages();
+            let mut n = 0;
+            while pages.next().await.unwrap().is_some() {
+                n += 1;
+            }
+            n
+        }
+
+        let path = tempdir().unwrap();
+        let opts = TableOptions {
+            page_size: 1 << 10,
+            min_leaf_fill_ratio: 0.5,
+            ..OPTIONS
+        };
+        let table = Table::open(&path, opts).await.unwrap();
+        for i in 0..N {
+            must_put(&table, i, 0).await;
+        }
+        let num_pages = count_pages(&table).await;
+
+        // Delete most keys in the middle.
+        table.set_safe_lsn(u64::MAX);
+        let deleted = |i: u64| (N / 4..N * 3 / 4).contains(&i) && i % 16 != 0;
+        for i in (0..N).filter(|&i| deleted(i)) {
+            table.delete(&i.to_be_bytes(), 1).await.unwrap();
+        }
+        table.wait_for_merging().await;
+        assert!(table.stats().tree.success.merge_page > 0);
+        assert!(count_pages(&table).await < num_pages * 3 / 4);
+
+        let expect: Vec<_> = (0..N).filter(|&i| !deleted(i)).collect();
+        assert_eq!(
+            scan_keys(&table, Bound::Unbounded, Bound::Unbounded).await,
+            expect
+        );
+        for i in 0..N {
+            let value = (!deleted(i)).then_some(i);
+            must_get(&table, i, 1, value).await;
+        }
+        table.close().await.unwrap();
+    }
+
+    #[photonio::test]
+    async fn merge_pages_concurrently() {
+        const N: u64 = 4096;
+        const T: u64 = 4;
+        let path = tempdir().unwrap();
+        let opts = TableOptions {
+            page_size: 1 << 10,
+            min_leaf_fill_ratio: 0.5,
+            ..OPTIONS
+        };
+        let table = Table::open(&path, opts).await.unwrap();
+        table.set_safe_lsn(3);
+
+        // Each task fills and empties its own keys, so that pages are split and
+        // merged concurrently.
+        let kept = |i: u64| i % 64 == 0;
+        let mut tasks = Vec::new();
+        for t in 0..T {
+            let table = table.clone();
+            tasks.push(photonio::task::spawn(async move {
+                for round in 0..2 {
+                    for i in (t..N).step_by(T as usize) {
+                        must_put(&table, i, round * 2).await;
+                    }
+                    for i in (t..N).step_by(T as usize).filter(|&i| !kept(i)) {
+                        table.delete(&i.to_be_bytes(), round * 2 + 1).await.unwrap();
+                    }
+                }
+            }));
+        }
+        for task in tasks {
+            task.await.unwrap();
+        }
+        table.wait_for_merging().await;
+        assert!(table.stats().tree.success.merge_page > 0);
+
+        for i in 0..N {
+            must_get(&table, i, 3, kept(i).then_some(i)).await;
+        }
+        table.close().await.unwrap();
+    }
+
+    #[test]
+    fn write_one_key_concurrently() {
+        const N: u64 = 1 << 10;
+        const T: u64 = 8;
+        let path = tempdir().unwrap();
+        let opts = TableOptions {
+            cas_backoff: Some(CasBackoff::default()),
+            ..OPTIONS
+        };
+        let table = std::Table::open(&path, opts).unwrap();
+        // Drop old versions so that the page of the key stays small.
+        table.set_safe_lsn(N * T);
+
+        ::std::thread::scope(|s| {
+            for t in 0..T {
+                let table = table.clone();
+                s.spawn(move || {
+                    for i in 0..N {
+                        let lsn = i * T + t + 1;
+                        table.put(b"hot", lsn, &lsn.to_be_bytes()).unwrap();
+                    }
+                });
+            }
+        });
+        // Every writer makes progress despite the contention.
+        let stats = table.stats().tree;
+        assert_eq!(stats.success.write, N * T);
+        // Whether the writers contend depends on scheduling, but none of them
+        // gives up.
+        assert_eq!(stats.cas.busy, 0, "{stats}");
+        let value = table.get(b"hot", N * T).unwrap();
+        assert_eq!(value, Some((N * T).to_be_bytes().to_vec()));
+        table.close().unwrap();
+    }
+
+    #[photonio::test]
+    async fn log_stats_periodically() {
+        struct CapturedLogger(Mutex<Vec<String>>);
+
+        impl log::Log for CapturedLogger {
+            fn enabled(&self, metadata: &log::Metadata) -> bool {
+                metadata
+                    .target()
+                    .starts_with("photondb::page_store::jobs::stats_log")
+            }
+
+            fn log(&self, record: &log::Record) {
+                if self.enabled(record.metadata()) {
+                    let mut lines = self.0.lock().unwrap();
+                    lines.push(record.args().to_string());
+                }
+            }
+
+            fn flush(&self) {}
+        }
+
+        static LOGGER: CapturedLogger = CapturedLogger(Mutex::new(Vec::new()));
+        log::set_logger(&LOGGER).unwrap();
+        log::set_max_level(log::LevelFilter::Debug);
+
+        let path = tempdir().unwrap();
+        let opts = TableOptions {
+            page_store: PageStoreOptions {
+                stats_log_interval: Duration::from_millis(10),
+                ..OPTIONS.page_store
+            },
+            ..OPTIONS
+        };
+        let table = Table::open(&path, opts).await.unwrap();
+        must_put(&table, 1, 1).await;
+        let deadline = Instant::now() + Duration::from_secs(10);
+        while LOGGER.0.lock().unwrap().is_empty() && Instant::now() < deadline {
+            photonio::task::yield_now().await;
+        }
+        table.close().await.unwrap();
+
+        let lines = ::std::mem::take(&mut *LOGGER.0.lock().unwrap());
+        assert!(!lines.is_empty());
+        assert!(lines[0].contains("Page store stats"));
+    }
+
     #[photonio::test]
     async fn graceful_shutdown() {
         let path = tempdir().unwrap();
@@ -43,6 +43,16 @@ impl PagePtr {
         self.flags().kind()
     }
 
+    /// Returns the layout of items in the page.
+    pub(crate) fn layout(&self) -> PageLayout {
+        self.flags().layout()
+    }
+
+    /// Returns true if the page contains values stored in overflow pages.
+    pub(crate) fn has_overflow_values(&self) -> bool {
+        self.flags().has_overflow_values()
+    }
+
     /// Returns the page epoch.
     pub(crate) fn epoch(&self) -> u64 {
         unsafe {
@@ -321,11 +331,15 @@ impl From<u8> for PageTier {
 pub(crate) enum PageKind {
     Data = PAGE_KIND_DATA,
     Split = PAGE_KIND_SPLIT,
+    RangeDelete = PAGE_KIND_RANGE_DELETE,
+    Overflow = PAGE_KIND_OVERFLOW,
 }
 
 const PAGE_KIND_MASK: u8 = 0b0000_1110;
 const PAGE_KIND_DATA: u8 = 0b0000_0000;
 const PAGE_KIND_SPLIT: u8 = 0b0000_0010;
+const PAGE_KIND_RANGE_DELETE: u8 = 0b0000_0100;
+const PAGE_KIND_OVERFLOW: u8 = 0b0000_0110;
 
 impl PageKind {
     pub(crate) fn is_data(&self) -> bool {
@@ -335,6 +349,14 @@ impl PageKind {
     pub(crate) fn is_split(&self) -> bool {
         self == &Self::Split
     }
+
+    pub(crate) fn is_range_delete(&self) -> bool {
+        self == &Self::RangeDelete
+    }
+
+    pub(crate) fn is_overflow(&self) -> bool {
+        self == &Self::Overflow
+    }
 }
 
 impl From<u8> for PageKind {
@@ -342,16 +364,52 @@ impl From<u8> for PageKind {
         match value & PAGE_KIND_MASK {
             PAGE_KIND_DATA => Self::Data,
             PAGE_KIND_SPLIT => Self::Split,
+            PAGE_KIND_RANGE_DELETE => Self::RangeDelete,
+            PAGE_KIND_OVERFLOW => Self::Overflow,
             _ => unreachable!(),
         }
     }
 }
 
+/// The layout of items in a sorted page.
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
+#[repr(u8)]
+pub enum PageLayout {
+    /// Each key is followed by its value.
+    #[default]
+    Interleaved = PAGE_LAYOUT_INTERLEAVED,
+    /// All keys are stored contiguously, followed by all values.
+    ///
+    /// Binary searches only touch the keys, which improves cache locality
+    /// when values are large.
+    Separated = PAGE_LAYOUT_SEPARATED,
+}
+
+const PAGE_LAYOUT_MASK: u8 = 0b0001_0000;
+const PAGE_LAYOUT_INTERLEAVED: u8 = 0b0000_0000;
+const PAGE_LAYOUT_SEPARATED: u8 = 0b0001_0000;
+
+impl From<u8> for PageLayout {
+    fn from(value: u8) -> Self {
+        match value & PAGE_LAYOUT_MASK {
+            PAGE_LAYOUT_INTERLEAVED => Self::Interleaved,
+            PAGE_LAYOUT_SEPARATED => Self::Separated,
+            _ => unreachable!(),
+        }
+    }
+}
+
+const PAGE_OVERFLOW_VALUES: u8 = 0b0010_0000;
+
 struct PageFlags(u8);
 
 impl PageFlags {
-    fn new(tier: PageTier, kind: PageKind) -> Self {
-        Self(tier as u8 | kind as u8)
+    fn new(tier: PageTier, kind: PageKind, layout: PageLayout, overflow_values: bool) -> Self {
+        let mut flags = tier as u8 | kind as u8 | layout as u8;
+        if overflow_values {
+            flags |= PAGE_OVERFLOW_VALUES;
+        }
+        Self(flags)
     }
 
     fn tier(&self) -> PageTier {
@@ -361,17 +419,46 @@ impl PageFlags {
     fn kind(&self) -> PageKind {
         self.0.into()
     }
+
+    fn layout(&self) -> PageLayout {
+        self.0.into()
+    }
+
+    fn has_overflow_values(&self) -> bool {
+        self.0 & PAGE_OVERFLOW_VALUES != 0
+    }
 }
 
 /// Builds a page with basic information.
 pub(super) struct PageBuilder {
     tier: PageTier,
     kind: PageKind,
+    layout: PageLayout,
+    overflow_values: bool,
 }
 
 impl PageBuilder {
     pub(super) fn new(tier: PageTier, kind: PageKind) -> Self {
-        Self { tier, kind }
+        Self {
+            tier,
+            kind,
+            layout: PageLayout::default(),
+            overflow_values: false,
+        }
+    }
+
+    pub(super) fn with_layout(mut self, layout: PageLayout) -> Self {
+        self.layout = layout;
+        self
+    }
+
+    pub(super) fn layout(&self) -> PageLayout {
+        self.layout
+    }
+
+    pub(super) fn with_overflow_values(mut self, overflow_values: bool) -> Self {
+        self.overflow_values = overflow_values;
+        self
     }
 
     pub(super) fn size(&self, content_size: usize) -> usize {
@@ -379,7 +466,7 @@ impl PageBuilder {
     }
 
     pub(super) fn build(&self, page: &mut PageBuf<'_>) {
-        let flags = PageFlags::new(self.tier, self.kind);
+        let flags = PageFlags::new(self.tier, self.kind, self.layout, self.overflow_values);
         page.set_flags(flags);
         page.set_epoch(0);
         page.set_chain_len(1);
@@ -470,6 +557,35 @@ mod tests {
             assert!(page.tier().is_inner());
             assert!(page.kind().is_split());
         }
+        {
+            let builder = PageBuilder::new(PageTier::Leaf, PageKind::RangeDelete);
+            builder.build(&mut page);
+            assert!(page.tier().is_leaf());
+            assert!(page.kind().is_range_delete());
+            assert_eq!(page.layout(), PageLayout::Interleaved);
+        }
+        {
+            let builder = PageBuilder::new(PageTier::Inner, PageKind::Data)
+                .with_layout(PageLayout::Separated);
+            builder.build(&mut page);
+            assert!(page.tier().is_inner());
+            assert!(page.kind().is_data());
+            assert_eq!(page.layout(), PageLayout::Separated);
+            assert!(!page.has_overflow_values());
+        }
+        {
+            let builder =
+                PageBuilder::new(PageTier::Leaf, PageKind::Data).with_overflow_values(true);
+            builder.build(&mut page);
+            assert!(page.kind().is_data());
+            assert!(page.has_overflow_values());
+        }
+        {
+            let builder = PageBuilder::new(PageTier::Leaf, PageKind::Overflow);
+            builder.build(&mut page);
+            assert!(page.kind().is_overflow());
+            assert!(!page.has_overflow_values());
+        }
 
         assert_eq!(page.epoch(), 0);
         page.set_epoch(1);
@@ -53,6 +53,9 @@ impl<'a> Range<'a> {
 pub(crate) enum Value<'a> {
     Put(&'a [u8]),
     Delete,
+    /// A value that is too large to fit in a data page, which is stored in a
+    /// list of overflow pages instead.
+    Overflow(OverflowValue<'a>),
 }
 
 impl<'a> Value<'a> {
@@ -61,10 +64,64 @@ impl<'a> Value<'a> {
         match self {
             Value::Put(v) => v.len(),
             Value::Delete => 0,
+            Value::Overflow(v) => v.len as usize,
         }
     }
 }
 
+/// A reference to a value stored in overflow pages.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub(crate) struct OverflowValue<'a> {
+    /// The length of the whole value.
+    pub(crate) len: u64,
+    /// The ids and addresses of the overflow pages, encoded as pairs of
+    /// little-endian `u64`s.
+    pages: &'a [u8],
+}
+
+impl<'a> OverflowValue<'a> {
+    /// Creates an [`OverflowValue`] from the encoded pages.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the length of `pages` is not a multiple of 16.
+    pub(crate) fn new(len: u64, pages: &'a [u8]) -> Self {
+        assert_eq!(pages.len() % (core::mem::size_of::<u64>() * 2), 0);
+        Self { len, pages }
+    }
+
+    /// Encodes the ids and addresses of overflow pages.
+    pub(crate) fn encode_pages(pages: &[(u64, u64)]) -> Vec<u8> {
+        pages
+            .iter()
+            .flat_map(|(id, addr)| id.to_le_bytes().into_iter().chain(addr.to_le_bytes()))
+            .collect()
+    }
+
+    /// Returns the encoded pages.
+    pub(crate) fn raw_pages(&self) -> &'a [u8] {
+        self.pages
+    }
+
+    /// Returns an iterator over the ids and addresses of the overflow pages.
+    pub(crate) fn pages(&self) -> impl Iterator<Item = (u64, u64)> + 'a {
+        self.pages
+            .chunks_exact(core::mem::size_of::<u64>() * 2)
+            .map(|b| {
+                let (id, addr) = b.split_at(core::mem::size_of::<u64>());
+                (
+                    u64::from_le_bytes(id.try_into().unwrap()),
+                    u64::from_le_bytes(addr.try_into().unwrap()),
+                )
+            })
+    }
+
+    /// Returns an iterator over the addresses of the overflow pages.
+    pub(crate) fn addrs(&self) -> impl Iterator<Item = u64> + 'a {
+        self.pages().map(|(_, addr)| addr)
+    }
+}
+
 /// An index to a child page.
 #[derive(Copy, Clone, Debug, Eq, PartialEq)]
 pub(crate) struct Index {
@@ -97,4 +154,14 @@ mod tests {
         assert!(b > d);
         assert!(c > d);
     }
+
+    #[test]
+    fn overflow_value() {
+        let pages = OverflowValue::encode_pages(&[(4, 1), (5, 2), (6, 3)]);
+        let value = OverflowValue::new(100, &pages);
+        let expected = vec![(4, 1), (5, 2), (6, 3)];
+        assert_eq!(value.pages().collect::<Vec<_>>(), expected);
+        assert_eq!(value.addrs().collect::<Vec<_>>(), vec![1, 2, 3]);
+        assert_eq!(Value::Overflow(value).len(), 100);
+    }
 }
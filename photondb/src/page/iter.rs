@@ -335,6 +335,85 @@ where
     }
 }
 
+/// An iterator that merges duplicated keys from an ordered iterator.
+///
+/// The underlying iterator must yield items with equal keys next to each
+/// other, as [`MergingIter`] does. For each key, the item with the highest
+/// precedence is returned. If multiple items have the same precedence, the
+/// first one wins.
+pub(crate) struct MergeIter<I, F>
+where
+    I: Iterator,
+{
+    iter: I,
+    next: Option<I::Item>,
+    precedence: F,
+}
+
+impl<I, K, V, F> MergeIter<I, F>
+where
+    I: Iterator<Item = (K, V)>,
+    F: FnMut(&V, &V) -> Ordering,
+{
+    /// Creates a new [`MergeIter`] that compares values of the same key with
+    /// `precedence`.
+    pub(crate) fn new(iter: I, precedence: F) -> Self {
+        Self {
+            iter,
+            next: None,
+            precedence,
+        }
+    }
+}
+
+impl<I, K, V, F> Iterator for MergeIter<I, F>
+where
+    I: Iterator<Item = (K, V)>,
+    K: PartialEq,
+    F: FnMut(&V, &V) -> Ordering,
+{
+    type Item = (K, V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let mut item = self.next.take().or_else(|| self.iter.next())?;
+        for next in &mut self.iter {
+            if next.0 != item.0 {
+                self.next = Some(next);
+                break;
+            }
+            if (self.precedence)(&next.1, &item.1) == Ordering::Greater {
+                item = next;
+            }
+        }
+        Some(item)
+    }
+}
+
+impl<I, K, V, F> RewindableIterator for MergeIter<I, F>
+where
+    I: RewindableIterator<Item = (K, V)>,
+    K: PartialEq,
+    F: FnMut(&V, &V) -> Ordering,
+{
+    fn rewind(&mut self) {
+        self.iter.rewind();
+        self.next = None;
+    }
+}
+
+impl<I, K, V, F, T> SeekableIterator<T> for MergeIter<I, F>
+where
+    T: ?Sized,
+    I: SeekableIterator<T, Item = (K, V)>,
+    K: PartialEq,
+    F: FnMut(&V, &V) -> Ordering,
+{
+    fn seek(&mut self, target: &T) -> bool {
+        self.next = None;
+        self.iter.seek(target)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -416,4 +495,46 @@ mod tests {
         assert_eq!(iter.next(), Some((7, "d")));
         assert_eq!(iter.next(), Some((8, "c")));
     }
+
+    #[test]
+    fn merge_iter() {
+        // Values are (lsn, source) and the newest version of a key wins.
+        let input = [
+            vec![(1, (3, "a")), (3, (1, "a")), (5, (5, "a"))],
+            vec![(1, (7, "b")), (2, (2, "b")), (5, (4, "b"))],
+            vec![(2, (6, "c")), (3, (8, "c")), (4, (9, "c")), (5, (4, "c"))],
+        ];
+        let output = [
+            (1, (7, "b")),
+            (2, (6, "c")),
+            (3, (8, "c")),
+            (4, (9, "c")),
+            (5, (5, "a")),
+        ];
+
+        let mut builder = MergingIterBuilder::new();
+        for slice in input.iter() {
+            builder.add(SliceIter::new(slice));
+        }
+        let mut iter = MergeIter::new(builder.build(), |a: &(u64, &str), b| a.0.cmp(&b.0));
+
+        for _ in 0..2 {
+            for item in output {
+                assert_eq!(iter.next(), Some(item));
+            }
+            assert_eq!(iter.next(), None);
+            iter.rewind();
+        }
+
+        // Ties keep the item from the first iterator.
+        let mut builder = MergingIterBuilder::new();
+        for slice in input.iter().rev() {
+            builder.add(SliceIter::new(slice));
+        }
+        let mut iter = MergeIter::new(builder.build(), |_: &(u64, &str), _| Ordering::Equal);
+        assert_eq!(iter.next(), Some((1, (7, "b"))));
+        assert_eq!(iter.next(), Some((2, (6, "c"))));
+        assert_eq!(iter.nth(2), Some((5, (4, "c"))));
+        assert_eq!(iter.next(), None);
+    }
 }
@@ -1,24 +1,33 @@
 mod iter;
 pub(crate) use iter::{
-    ItemIter, MergingIter, MergingIterBuilder, RewindableIterator, SeekableIterator, SliceIter,
+    ItemIter, MergeIter, MergingIter, MergingIterBuilder, RewindableIterator, SeekableIterator,
+    SliceIter,
 };
 
 mod data;
-pub(crate) use data::{Index, Key, Range, Value};
+pub(crate) use data::{Index, Key, OverflowValue, Range, Value};
 
 mod codec;
 
 mod base_page;
 use base_page::PageBuilder;
+pub use base_page::PageLayout;
 pub(crate) use base_page::{PageBuf, PageInfo, PageKind, PageRef, PageTier};
 
 mod sorted_page;
 pub(crate) use sorted_page::{
-    SortedPageBuilder, SortedPageIter, SortedPageKey, SortedPageRef, SortedPageValue,
+    SortedPageBuilder, SortedPageIter, SortedPageKey, SortedPageRangeIter, SortedPageRef,
+    SortedPageValue,
 };
 
+mod overflow_page;
+pub(crate) use overflow_page::{OverflowPageBuilder, OverflowPageRef};
+
 pub(crate) type ValuePageRef<'a> = SortedPageRef<'a, Key<'a>, Value<'a>>;
 pub(crate) type IndexPageRef<'a> = SortedPageRef<'a, &'a [u8], Index>;
+/// A range delete page stores the start key and LSN of the range, with the
+/// end key as its value. An empty end key means that the range is unbounded.
+pub(crate) type RangeDeletePageRef<'a> = SortedPageRef<'a, Key<'a>, &'a [u8]>;
 
 #[cfg(test)]
 pub(crate) mod tests {
@@ -70,7 +79,16 @@ pub(crate) mod tests {
         where
             I: RewindableIterator<Item = (K, V)>,
         {
-            let builder = SortedPageBuilder::new(PageTier::Leaf, PageKind::Data).with_iter(iter);
+            Self::from_iter_with_layout(iter, PageLayout::default())
+        }
+
+        pub(crate) fn from_iter_with_layout<I>(iter: I, layout: PageLayout) -> Self
+        where
+            I: RewindableIterator<Item = (K, V)>,
+        {
+            let builder = SortedPageBuilder::new(PageTier::Leaf, PageKind::Data)
+                .with_layout(layout)
+                .with_iter(iter);
             let mut buf = alloc_page(builder.size());
             let mut page = PageBuf::new(buf.as_mut());
             builder.build(&mut page);
@@ -0,0 +1,79 @@
+use super::{PageBuf, PageBuilder, PageKind, PageRef, PageTier};
+
+/// Builds an overflow page that stores a chunk of a large value.
+///
+/// Overflow page format {
+///     data : the chunk of the value
+/// }
+pub(crate) struct OverflowPageBuilder<'a> {
+    base: PageBuilder,
+    data: &'a [u8],
+}
+
+impl<'a> OverflowPageBuilder<'a> {
+    /// Creates an [`OverflowPageBuilder`] that will build a page with the
+    /// given data.
+    pub(crate) fn new(data: &'a [u8]) -> Self {
+        Self {
+            base: PageBuilder::new(PageTier::Leaf, PageKind::Overflow),
+            data,
+        }
+    }
+
+    /// Returns the size of the page that will be built.
+    pub(crate) fn size(&self) -> usize {
+        self.base.size(self.data.len())
+    }
+
+    /// Builds the page with the given information.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the page size doesn't match the data.
+    pub(crate) fn build(self, page: &mut PageBuf<'_>) {
+        assert_eq!(page.size(), self.size());
+        self.base.build(page);
+        page.content_mut().copy_from_slice(self.data);
+    }
+}
+
+/// An immutable reference to an overflow page.
+pub(crate) struct OverflowPageRef<'a>(PageRef<'a>);
+
+impl<'a> OverflowPageRef<'a> {
+    /// Returns the chunk of the value stored in the page.
+    pub(crate) fn data(&self) -> &'a [u8] {
+        self.0.content()
+    }
+}
+
+impl<'a, T> From<T> for OverflowPageRef<'a>
+where
+    T: Into<PageRef<'a>>,
+{
+    fn from(page: T) -> Self {
+        let page = page.into();
+        debug_assert!(page.kind().is_overflow());
+        Self(page)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::page::tests::*;
+
+    #[test]
+    fn overflow_page() {
+        let data = [1u8, 2, 3, 4, 5];
+        let builder = OverflowPageBuilder::new(&data);
+        let mut buf = alloc_page(builder.size());
+        let mut page = PageBuf::new(buf.as_mut());
+        builder.build(&mut page);
+        assert!(page.tier().is_leaf());
+        assert!(page.kind().is_overflow());
+
+        let page = OverflowPageRef::from(page);
+        assert_eq!(page.data(), data);
+    }
+}
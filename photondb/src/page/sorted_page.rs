@@ -8,16 +8,30 @@ use std::{
 };
 
 use super::{
-    codec::*, data::*, ItemIter, PageBuf, PageBuilder, PageKind, PageRef, PageTier,
+    codec::*, data::*, ItemIter, PageBuf, PageBuilder, PageKind, PageLayout, PageRef, PageTier,
     RewindableIterator, SeekableIterator, SliceIter,
 };
 
 /// Builds a sorted page from an iterator.
+///
+/// Sorted page format {
+///     offsets : num_items * 4 bytes
+///     items   : key and value of each item
+/// }
+///
+/// With [`PageLayout::Separated`] {
+///     key_offsets   : num_items * 4 bytes
+///     value_offsets : num_items * 4 bytes
+///     keys          : key of each item
+///     values        : value of each item
+/// }
 pub(crate) struct SortedPageBuilder<I> {
     base: PageBuilder,
     iter: Option<I>,
     num_items: usize,
-    content_size: usize,
+    num_deletes: usize,
+    keys_size: usize,
+    values_size: usize,
 }
 
 impl<I, K, V> SortedPageBuilder<I>
@@ -33,28 +47,63 @@ where
             base: PageBuilder::new(tier, kind),
             iter: None,
             num_items: 0,
-            content_size: 0,
+            num_deletes: 0,
+            keys_size: 0,
+            values_size: 0,
         }
     }
 
+    /// Sets the layout of items in the page that will be built.
+    pub(crate) fn with_layout(mut self, layout: PageLayout) -> Self {
+        self.base = self.base.with_layout(layout);
+        self
+    }
+
     /// Creates a [`SortedPageBuilder`] that will build a page from the given
     /// iterator.
     pub(crate) fn with_iter(mut self, mut iter: I) -> Self {
+        let mut overflow_values = false;
         for (k, v) in &mut iter {
             self.num_items += 1;
-            self.content_size += k.encode_size() + v.encode_size();
+            if v.is_delete() {
+                self.num_deletes += 1;
+            }
+            self.keys_size += k.encode_size();
+            self.values_size += v.encode_size();
+            overflow_values |= v.as_overflow().is_some();
         }
-        self.content_size += self.num_items * mem::size_of::<u32>();
-        // We use `u32` to store item offsets, so the content size must not exceed
-        // `u32::MAX`.
-        assert!(self.content_size <= u32::MAX as usize);
+        self.base = self.base.with_overflow_values(overflow_values);
         self.iter = Some(iter);
         self
     }
 
     /// Returns the size of the page that will be built.
     pub(crate) fn size(&self) -> usize {
-        self.base.size(self.content_size)
+        self.base.size(self.content_size())
+    }
+
+    /// Returns the number of deletes in the page that will be built.
+    pub(crate) fn num_deletes(&self) -> usize {
+        self.num_deletes
+    }
+
+    /// Returns the iterator that the page will be built from.
+    ///
+    /// The iterator has gone through all items once.
+    pub(crate) fn iter(&self) -> Option<&I> {
+        self.iter.as_ref()
+    }
+
+    fn content_size(&self) -> usize {
+        let offsets_size = match self.base.layout() {
+            PageLayout::Interleaved => self.num_items * mem::size_of::<u32>(),
+            PageLayout::Separated => self.num_items * mem::size_of::<u32>() * 2,
+        };
+        let content_size = offsets_size + self.keys_size + self.values_size;
+        // We use `u32` to store item offsets, so the content size must not exceed
+        // `u32::MAX`.
+        assert!(content_size <= u32::MAX as usize);
+        content_size
     }
 
     /// Builds the page with the given information.
@@ -67,7 +116,12 @@ where
         self.base.build(page);
         if let Some(iter) = self.iter.as_mut() {
             unsafe {
-                let mut buf = SortedPageBuf::new(page.content_mut(), self.num_items);
+                let mut buf = SortedPageBuf::new(
+                    page.content_mut(),
+                    self.base.layout(),
+                    self.num_items,
+                    self.keys_size,
+                );
                 iter.rewind();
                 for (k, v) in iter {
                     buf.add(k, v);
@@ -104,6 +158,8 @@ where
 struct SortedPageBuf<K, V> {
     offsets: Encoder,
     payload: Encoder,
+    // The value offsets and values of the separated layout.
+    values: Option<(Encoder, Encoder)>,
     _marker: PhantomData<(K, V)>,
 }
 
@@ -112,21 +168,55 @@ where
     K: SortedPageKey,
     V: SortedPageValue,
 {
-    unsafe fn new(content: &mut [u8], num_items: usize) -> Self {
+    unsafe fn new(
+        content: &mut [u8],
+        layout: PageLayout,
+        num_items: usize,
+        keys_size: usize,
+    ) -> Self {
         let offsets_size = num_items * mem::size_of::<u32>();
-        let (offsets, payload) = content.split_at_mut(offsets_size);
-        Self {
-            offsets: Encoder::new(offsets),
-            payload: Encoder::new(payload),
-            _marker: PhantomData,
+        match layout {
+            PageLayout::Interleaved => {
+                let (offsets, payload) = content.split_at_mut(offsets_size);
+                Self {
+                    offsets: Encoder::new(offsets),
+                    payload: Encoder::new(payload),
+                    values: None,
+                    _marker: PhantomData,
+                }
+            }
+            PageLayout::Separated => {
+                let (offsets, content) = content.split_at_mut(offsets_size);
+                let (value_offsets, content) = content.split_at_mut(offsets_size);
+                let (keys, values) = content.split_at_mut(keys_size);
+                Self {
+                    offsets: Encoder::new(offsets),
+                    payload: Encoder::new(keys),
+                    values: Some((Encoder::new(value_offsets), Encoder::new(values))),
+                    _marker: PhantomData,
+                }
+            }
         }
     }
 
     unsafe fn add(&mut self, key: K, value: V) {
-        let offset = self.offsets.len() + self.payload.offset();
-        self.offsets.put_u32(offset as u32);
-        key.encode_to(&mut self.payload);
-        value.encode_to(&mut self.payload);
+        match self.values.as_mut() {
+            None => {
+                let offset = self.offsets.len() + self.payload.offset();
+                self.offsets.put_u32(offset as u32);
+                key.encode_to(&mut self.payload);
+                value.encode_to(&mut self.payload);
+            }
+            Some((value_offsets, values)) => {
+                let keys_offset = self.offsets.len() + value_offsets.len();
+                let offset = keys_offset + self.payload.offset();
+                self.offsets.put_u32(offset as u32);
+                key.encode_to(&mut self.payload);
+                let offset = keys_offset + self.payload.len() + values.offset();
+                value_offsets.put_u32(offset as u32);
+                value.encode_to(values);
+            }
+        }
     }
 }
 
@@ -136,6 +226,10 @@ pub(crate) struct SortedPageRef<'a, K, V> {
     page: PageRef<'a>,
     content: &'a [u8],
     offsets: &'a [u32],
+    // The value offsets of the separated layout.
+    value_offsets: Option<&'a [u32]>,
+    // The end of the last item, or the last key in the separated layout.
+    keys_end: usize,
     _marker: PhantomData<(K, V)>,
 }
 
@@ -146,20 +240,32 @@ where
 {
     pub(crate) fn new(page: PageRef<'a>) -> Self {
         let content = page.content();
-        let offsets = unsafe {
+        let num_offsets = match page.layout() {
+            PageLayout::Interleaved => 1,
+            PageLayout::Separated => 2,
+        };
+        let (offsets, value_offsets) = unsafe {
             let ptr = content.as_ptr() as *const u32;
             let len = if content.is_empty() {
                 0
             } else {
                 let size = u32::from_le(ptr.read());
-                size as usize / mem::size_of::<u32>()
+                size as usize / mem::size_of::<u32>() / num_offsets
             };
-            slice::from_raw_parts(ptr, len)
+            let offsets = slice::from_raw_parts(ptr, len);
+            let value_offsets =
+                (num_offsets == 2).then(|| slice::from_raw_parts(ptr.add(len), len));
+            (offsets, value_offsets)
         };
+        let keys_end = value_offsets
+            .and_then(|v| v.first())
+            .map_or(content.len(), |v| u32::from_le(*v) as usize);
         Self {
             page,
             content,
             offsets,
+            value_offsets,
+            keys_end,
             _marker: PhantomData,
         }
     }
@@ -175,7 +281,10 @@ where
             let mut dec = Decoder::new(item);
             unsafe {
                 let k = K::decode_from(&mut dec);
-                let v = V::decode_from(&mut dec);
+                let v = match self.value_item(index) {
+                    Some(value) => V::decode_from(&mut Decoder::new(value)),
+                    None => V::decode_from(&mut dec),
+                };
                 Some((k, v))
             }
         } else {
@@ -225,9 +334,26 @@ where
         K,
         SortedPageRangeIter<'a, K, V>,
         SortedPageRangeIter<'a, K, V>,
+    )> {
+        let mid = self.len() / 2;
+        self.into_split_iter_at(mid)
+    }
+
+    /// Finds a separator around the item at `mid` to split the page.
+    ///
+    /// This is the same as [`SortedPageRef::into_split_iter`], except that the
+    /// separator is derived from the item at `mid` instead of the middle one.
+    #[allow(clippy::type_complexity)]
+    pub(crate) fn into_split_iter_at(
+        self,
+        mid: usize,
+    ) -> Option<(
+        K,
+        SortedPageRangeIter<'a, K, V>,
+        SortedPageRangeIter<'a, K, V>,
     )> {
         let len = self.len();
-        if let Some((mid, _)) = self.get(len / 2) {
+        if let Some((mid, _)) = self.get(mid) {
             let sep = mid.as_split_separator();
             let index = match self.rank(&sep) {
                 Ok(i) => i,
@@ -242,9 +368,11 @@ where
         None
     }
 
+    // Returns the item at the given index, which only contains the key in the
+    // separated layout.
     fn item(&self, index: usize) -> Option<&[u8]> {
         if let Some(offset) = self.item_offset(index) {
-            let next_offset = self.item_offset(index + 1).unwrap_or(self.content.len());
+            let next_offset = self.item_offset(index + 1).unwrap_or(self.keys_end);
             Some(&self.content[offset..next_offset])
         } else {
             None
@@ -254,6 +382,18 @@ where
     fn item_offset(&self, index: usize) -> Option<usize> {
         self.offsets.get(index).map(|v| u32::from_le(*v) as usize)
     }
+
+    // Returns the value at the given index in the separated layout.
+    fn value_item(&self, index: usize) -> Option<&[u8]> {
+        let value_offsets = self.value_offsets?;
+        let offset = value_offsets
+            .get(index)
+            .map(|v| u32::from_le(*v) as usize)?;
+        let next_offset = value_offsets
+            .get(index + 1)
+            .map_or(self.content.len(), |v| u32::from_le(*v) as usize);
+        Some(&self.content[offset..next_offset])
+    }
 }
 
 impl<'a, K, V> Deref for SortedPageRef<'a, K, V> {
@@ -380,6 +520,20 @@ impl<'a, K, V> SortedPageRangeIter<'a, K, V> {
     }
 }
 
+impl<'a, K, V> SortedPageRangeIter<'a, K, V>
+where
+    K: SortedPageKey,
+    V: SortedPageValue,
+{
+    /// Returns the last item in the range without advancing the iterator.
+    pub(crate) fn last_item(&self) -> Option<(K, V)> {
+        if self.range.is_empty() {
+            return None;
+        }
+        self.page.get(self.range.end - 1)
+    }
+}
+
 impl<'a, K, V> Iterator for SortedPageRangeIter<'a, K, V>
 where
     K: SortedPageKey,
@@ -418,9 +572,34 @@ pub(crate) trait SortedPageKey: Codec + Clone + Ord {
 }
 
 /// Required methods for values in a sorted page.
-pub(crate) trait SortedPageValue: Codec + Clone {}
+pub(crate) trait SortedPageValue: Codec + Clone {
+    /// Returns the overflow value if the value is stored in overflow pages.
+    fn as_overflow(&self) -> Option<OverflowValue<'_>> {
+        None
+    }
+
+    /// Returns true if the value is a delete.
+    fn is_delete(&self) -> bool {
+        false
+    }
+}
+
+impl SortedPageValue for &[u8] {}
+
+impl SortedPageValue for Index {}
+
+impl SortedPageValue for Value<'_> {
+    fn as_overflow(&self) -> Option<OverflowValue<'_>> {
+        match self {
+            Value::Overflow(v) => Some(*v),
+            _ => None,
+        }
+    }
 
-impl<T> SortedPageValue for T where T: Codec + Clone {}
+    fn is_delete(&self) -> bool {
+        matches!(self, Value::Delete)
+    }
+}
 
 impl Codec for &[u8] {
     fn encode_size(&self) -> usize {
@@ -479,12 +658,14 @@ impl SortedPageKey for Key<'_> {
 /// These values are persisted to disk, don't change them.
 const VALUE_KIND_PUT: u8 = 0;
 const VALUE_KIND_DELETE: u8 = 1;
+const VALUE_KIND_OVERFLOW: u8 = 2;
 
 impl Codec for Value<'_> {
     fn encode_size(&self) -> usize {
         1 + match self {
             Self::Put(v) => v.len(),
             Self::Delete => 0,
+            Self::Overflow(v) => mem::size_of::<u64>() + v.raw_pages().len(),
         }
     }
 
@@ -495,6 +676,11 @@ impl Codec for Value<'_> {
                 enc.put_slice(v);
             }
             Value::Delete => enc.put_u8(VALUE_KIND_DELETE),
+            Value::Overflow(v) => {
+                enc.put_u8(VALUE_KIND_OVERFLOW);
+                enc.put_u64(v.len);
+                enc.put_slice(v.raw_pages());
+            }
         }
     }
 
@@ -503,6 +689,10 @@ impl Codec for Value<'_> {
         match kind {
             VALUE_KIND_PUT => Self::Put(dec.get_slice(dec.remaining())),
             VALUE_KIND_DELETE => Self::Delete,
+            VALUE_KIND_OVERFLOW => {
+                let len = dec.get_u64();
+                Self::Overflow(OverflowValue::new(len, dec.get_slice(dec.remaining())))
+            }
             _ => unreachable!(),
         }
     }
@@ -561,6 +751,92 @@ mod tests {
         }
     }
 
+    #[test]
+    fn sorted_page_separated() {
+        let data = [
+            (Key::new(b"a", 2), Value::Put(b"1")),
+            (Key::new(b"a", 1), Value::Delete),
+            (Key::new(b"bb", 3), Value::Put(b"")),
+            (Key::new(b"ccc", 1), Value::Put(b"333")),
+        ];
+        let interleaved = OwnedSortedPage::from_slice(&data);
+        let separated =
+            OwnedSortedPage::from_iter_with_layout(SliceIter::new(&data), PageLayout::Separated);
+        assert_eq!(interleaved.as_ref().layout(), PageLayout::Interleaved);
+        assert_eq!(separated.as_ref().layout(), PageLayout::Separated);
+
+        let page = separated.as_ref();
+        assert_eq!(page.len(), data.len());
+        for (i, item) in data.iter().enumerate() {
+            assert_eq!(page.get(i), Some(*item));
+            assert_eq!(page.rank(&item.0), Ok(i));
+        }
+        assert_eq!(page.get(data.len()), None);
+        assert_eq!(page.rank(&Key::new(b"b", 1)), Err(2));
+        assert_eq!(page.rank(&Key::new(b"d", 1)), Err(4));
+
+        let mut iter = separated.as_iter();
+        assert!(iter.seek(&Key::new(b"bb", 3)));
+        assert_eq!(iter.next(), Some(data[2]));
+        assert_eq!(iter.next(), Some(data[3]));
+        assert_eq!(iter.next(), None);
+
+        let empty = OwnedSortedPage::<Key, Value>::from_iter_with_layout(
+            SliceIter::new(&[]),
+            PageLayout::Separated,
+        );
+        assert_eq!(empty.as_ref().len(), 0);
+        assert_eq!(empty.as_ref().get(0), None);
+    }
+
+    #[test]
+    fn sorted_page_empty_key() {
+        let data = [
+            (Key::new(b"", 2), Value::Put(b"")),
+            (Key::new(b"", 1), Value::Delete),
+            (Key::new(b"a", 1), Value::Put(b"")),
+        ];
+        for layout in [PageLayout::Interleaved, PageLayout::Separated] {
+            let owned_page = OwnedSortedPage::from_iter_with_layout(SliceIter::new(&data), layout);
+            let page = owned_page.as_ref();
+            for (i, item) in data.iter().enumerate() {
+                assert_eq!(page.get(i), Some(*item));
+                assert_eq!(page.rank(&item.0), Ok(i));
+            }
+            assert_eq!(page.rank(&Key::new(b"", 3)), Err(0));
+            assert_eq!(page.rank(&Key::new(b"", 0)), Err(2));
+
+            let mut iter = owned_page.as_iter();
+            assert!(iter.seek(&Key::new(b"", u64::MAX)));
+            assert_eq!(iter.next(), Some(data[0]));
+        }
+    }
+
+    #[test]
+    fn sorted_page_overflow() {
+        let pages = OverflowValue::encode_pages(&[(3, 1), (4, 2)]);
+        let data = [
+            (Key::new(b"a", 2), Value::Put(b"1")),
+            (
+                Key::new(b"a", 1),
+                Value::Overflow(OverflowValue::new(10, &pages)),
+            ),
+            (Key::new(b"b", 1), Value::Delete),
+        ];
+        let owned_page = OwnedSortedPage::from_slice(&data);
+        let page = owned_page.as_ref();
+        assert!(page.has_overflow_values());
+        for (i, item) in data.iter().enumerate() {
+            assert_eq!(page.get(i), Some(*item));
+        }
+        let (_, value) = page.get(1).unwrap();
+        let value = value.as_overflow().unwrap();
+        assert_eq!(value.pages().collect::<Vec<_>>(), vec![(3, 1), (4, 2)]);
+
+        let owned_page = OwnedSortedPage::from_slice(&data[..1]);
+        assert!(!owned_page.as_ref().has_overflow_values());
+    }
+
     #[test]
     fn sorted_page_split() {
         // The middle key is ([3], 2), but it should split at ([3], 3).
@@ -584,6 +860,18 @@ mod tests {
         }
     }
 
+    #[test]
+    fn sorted_page_split_at() {
+        let data = raw_slice(&[[1], [2], [3], [4], [5]]);
+        let owned_page = OwnedSortedPage::from_slice(&data);
+        let (split_key, left_iter, right_iter) = owned_page.as_ref().into_split_iter_at(4).unwrap();
+        assert_eq!(split_key, &[5u8][..]);
+        assert_eq!(left_iter.count(), 4);
+        assert_eq!(right_iter.count(), 1);
+        assert!(owned_page.as_ref().into_split_iter_at(0).is_none());
+        assert!(owned_page.as_ref().into_split_iter_at(5).is_none());
+    }
+
     #[test]
     fn sorted_page_split_none() {
         {
@@ -1,6 +1,6 @@
 use std::{
     sync::{
-        atomic::{AtomicPtr, Ordering},
+        atomic::{AtomicBool, AtomicPtr, Ordering},
         Arc,
     },
     time::Instant,
@@ -12,7 +12,7 @@ use log::{debug, info};
 use super::{
     stats::{AtomicBufferSetStats, BufferSetStats},
     write_buffer::ReleaseState,
-    FlushOptions, Result, WriteBuffer,
+    Error, FlushOptions, MemoryBudget, Result, WriteBuffer,
 };
 use crate::util::notify::Notify;
 
@@ -24,6 +24,9 @@ pub(crate) struct BufferSet {
 
     flush_notify: Notify,
     write_buffer_permits: buffer_permits::WriteBufferPermits,
+    memory_budget: Option<MemoryBudget>,
+    /// Set while write buffers can't be flushed because the disk is full.
+    disk_full: AtomicBool,
 
     stats: AtomicBufferSetStats,
 }
@@ -72,10 +75,53 @@ impl BufferSet {
             current: AtomicPtr::new(raw),
             flush_notify: Notify::new(),
             write_buffer_permits,
+            memory_budget: None,
+            disk_full: AtomicBool::new(false),
             stats: AtomicBufferSetStats::default(),
         }
     }
 
+    /// Returns true if writes are rejected because the disk is full.
+    pub(crate) fn is_disk_full(&self) -> bool {
+        self.disk_full.load(Ordering::Acquire)
+    }
+
+    /// Rejects writes with [`Error::DiskFull`] until this is set to false.
+    pub(crate) fn set_disk_full(&self, disk_full: bool) {
+        self.disk_full.store(disk_full, Ordering::Release);
+    }
+
+    /// Shares the memory budget between write buffers and the page cache.
+    pub(crate) fn with_memory_budget(mut self, memory_budget: MemoryBudget) -> Self {
+        self.memory_budget = Some(memory_budget);
+        self.rebalance_memory();
+        self
+    }
+
+    /// Returns the memory used by write buffers.
+    pub(crate) fn memory_usage(&self) -> usize {
+        let buffers_range = self.current().buffers_range.clone();
+        buffers_range.len() * self.buffer_capacity as usize
+    }
+
+    /// Gives the memory not used by write buffers to the page cache.
+    fn rebalance_memory(&self) {
+        if let Some(memory_budget) = &self.memory_budget {
+            memory_budget.rebalance(|| self.memory_usage());
+        }
+    }
+
+    /// Takes the memory of one more write buffer from the page cache.
+    ///
+    /// This must be called before the buffer is allocated, so that write
+    /// buffers and the page cache stay within the memory budget while the
+    /// buffer is being installed.
+    pub(crate) fn reserve_buffer_memory(&self) {
+        if let Some(memory_budget) = &self.memory_budget {
+            memory_budget.rebalance(|| self.memory_usage() + self.buffer_capacity as usize);
+        }
+    }
+
     #[inline]
     pub(crate) fn stats(&self) -> BufferSetStats {
         self.stats.snapshot()
@@ -109,6 +155,8 @@ impl BufferSet {
     ///
     /// There are no concurrent requests here, because only the routine that
     /// seals the previous [`WriteBuffer`] can install the new [`WriteBuffer`].
+    /// [`BufferSet::reserve_buffer_memory`] should be called before the new
+    /// buffer is allocated.
     ///
     /// # Panic
     ///
@@ -142,6 +190,7 @@ impl BufferSet {
                 Err(v) => (guard, current) = v,
             };
         }
+        self.rebalance_memory();
     }
 
     pub(crate) fn release_until(&self, first_buffer_id: u32) {
@@ -179,6 +228,7 @@ impl BufferSet {
                 Err(v) => (guard, current) = v,
             }
         }
+        self.rebalance_memory();
     }
 
     /// Release a permit of write buffer, and wait new buffer to be installed.
@@ -289,6 +339,47 @@ impl BufferSet {
         None
     }
 
+    /// Like `acquire_active_buffer_id` but fails with [`Error::WriteStall`] if
+    /// the active buffer is not installed before the deadline.
+    pub(crate) async fn acquire_active_buffer_id_before(&self, deadline: Instant) -> Result<u32> {
+        if let Some(id) = self.acquire_active_buffer_id_fast() {
+            return Ok(id);
+        }
+        loop {
+            {
+                let buffer_set = self.current();
+                if !buffer_set.current_buffer.is_sealed() {
+                    return Ok(buffer_set.current_buffer.group_id());
+                }
+            }
+            if self.is_disk_full() {
+                return Err(Error::DiskFull);
+            }
+            if Instant::now() >= deadline {
+                return Err(Error::WriteStall);
+            }
+            photonio::task::yield_now().await;
+        }
+    }
+
+    /// Waits until a new buffer can be installed without write stalling, or
+    /// fails with [`Error::WriteStall`] after the deadline.
+    pub(crate) async fn wait_for_permits_before(&self, deadline: Instant) -> Result<()> {
+        while !self.write_buffer_permits.has_permits() {
+            if Instant::now() >= deadline {
+                return Err(Error::WriteStall);
+            }
+            photonio::task::yield_now().await;
+        }
+        Ok(())
+    }
+
+    /// Returns true if all buffers are in use, so that the next buffer can't
+    /// be installed until some buffers are flushed.
+    pub(crate) fn is_stalled(&self) -> bool {
+        !self.write_buffer_permits.has_permits()
+    }
+
     async fn acquire_active_buffer_id_slow(&self) -> u32 {
         loop {
             {
@@ -344,6 +435,7 @@ impl BufferSet {
                 .add(start_at.elapsed().as_millis() as u64);
         }
 
+        self.reserve_buffer_memory();
         let write_buffer = WriteBuffer::with_capacity(group_id + 1, self.buffer_capacity);
         self.install(Arc::new(write_buffer));
     }
@@ -426,6 +518,16 @@ impl BufferSetVersion {
         self.buffers_range.end
     }
 
+    /// Returns the last sealed buffer, which is flushed after all buffers
+    /// sealed before it.
+    pub(crate) fn last_sealed_buffer(&self) -> Option<&Arc<WriteBuffer>> {
+        if self.current_buffer.is_sealed() {
+            Some(&self.current_buffer)
+        } else {
+            self.sealed_buffers.last()
+        }
+    }
+
     fn snapshot(&self) -> Vec<Arc<WriteBuffer>> {
         let mut buffers = self.sealed_buffers.clone();
         buffers.push(self.current_buffer.clone());
@@ -501,6 +603,11 @@ mod buffer_permits {
             }
         }
 
+        /// Returns true if there are available permits.
+        pub(crate) fn has_permits(&self) -> bool {
+            self.permits.load(Ordering::Acquire) > 1
+        }
+
         /// Try acquire a permit, [`None`] is returned if no available permits.
         pub(crate) fn try_acquire(&self) -> Option<()> {
             self.acquire_fast(AcquireKind::None)
@@ -629,6 +736,7 @@ mod tests {
     use futures::{channel::mpsc, SinkExt, StreamExt};
 
     use super::*;
+    use crate::page_store::LRUCache;
 
     impl BufferSetVersion {
         #[inline]
@@ -678,6 +786,40 @@ mod tests {
         assert!(buffer_set.current().get(file_id + 1).is_some());
     }
 
+    #[test]
+    fn buffer_set_memory_budget() {
+        const CAPACITY: usize = 1 << 10;
+        const BUDGET: usize = 16 * CAPACITY;
+        let page_cache = Arc::new(LRUCache::new(BUDGET, -1, 0.5, 0.0));
+        let memory_budget = MemoryBudget::new(BUDGET, page_cache.clone());
+        let buffer_set = BufferSet::new(1, CAPACITY as u32, 8).with_memory_budget(memory_budget);
+        let first_id = buffer_set.current().last_writer_buffer().group_id();
+        assert_eq!(buffer_set.memory_usage(), CAPACITY);
+        assert_eq!(page_cache.capacity(), BUDGET - CAPACITY);
+
+        // Sealed buffers accumulate under heavy writes.
+        let mut last_cache_capacity = page_cache.capacity();
+        for id in first_id + 1..first_id + 8 {
+            buffer_set.current().last_writer_buffer().seal().unwrap();
+            // The cache shrinks before the new buffer is allocated.
+            buffer_set.reserve_buffer_memory();
+            assert!(buffer_set.memory_usage() + CAPACITY + page_cache.capacity() <= BUDGET);
+            let buf = WriteBuffer::with_capacity(id, CAPACITY as u32);
+            buffer_set.install(Arc::new(buf));
+            assert!(page_cache.capacity() < last_cache_capacity);
+            assert!(buffer_set.memory_usage() + page_cache.capacity() <= BUDGET);
+            last_cache_capacity = page_cache.capacity();
+        }
+        assert_eq!(buffer_set.memory_usage(), 8 * CAPACITY);
+
+        // Flushed buffers are released and the memory goes back to the cache.
+        for id in first_id + 1..=first_id + 4 {
+            buffer_set.release_until(id);
+        }
+        assert_eq!(buffer_set.memory_usage(), 4 * CAPACITY);
+        assert_eq!(page_cache.capacity(), BUDGET - 4 * CAPACITY);
+    }
+
     #[photonio::test]
     async fn buffer_set_concurrent_update() {
         let buffer_set = Arc::new(BufferSet::new(1, 32, 8));
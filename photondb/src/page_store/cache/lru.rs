@@ -1,27 +1,30 @@
 use std::{
     collections::hash_map::Entry,
-    ptr,
+    mem, ptr,
     sync::{
         atomic::{AtomicUsize, Ordering},
         Arc,
     },
 };
 
-use parking_lot::Mutex;
+use parking_lot::{Mutex, MutexGuard};
 use rustc_hash::FxHashMap;
 
 use super::{
-    AtomicCacheStats, Cache, CacheEntry, CacheToken, Handle, Key, LRUHandle, CACHE_AS_OLD,
-    CACHE_DISCARD,
+    AtomicCacheStats, Cache, CacheEntry, CacheToken, EvictCallback, EvictReason, Handle, Key,
+    LRUHandle, CACHE_AS_OLD, CACHE_DISCARD,
 };
 use crate::page_store::{
-    cache::CACHE_AS_RECENT, page_txn::CachePriority, stats::CacheStats, CacheOption, Result,
+    cache::CACHE_AS_RECENT, page_txn::CachePriority, stats::CacheStats, CacheOption, PageHasher,
+    Result,
 };
 
 pub(crate) struct LRUCache<T: Clone> {
     shards: Vec<Mutex<LRUCacheShard<T>>>,
     shard_mask: u32,
     stats: Vec<Arc<AtomicCacheStats>>,
+    hasher: PageHasher,
+    on_evict: Option<EvictCallback>,
 }
 
 struct LRUCacheShard<T: Clone> {
@@ -45,6 +48,10 @@ struct LRUCacheShard<T: Clone> {
     usage: Arc<AtomicUsize>,
 
     stats: Arc<AtomicCacheStats>,
+
+    /// The pages evicted under the lock, which are reported once the lock is
+    /// released. It is `None` if there is no evict callback.
+    evicted: Option<Vec<(u64, usize, EvictReason)>>,
 }
 
 struct LRUCacheHandleTable<T: Clone> {
@@ -108,7 +115,55 @@ impl<T: Clone> LRUCache<T> {
             shards,
             shard_mask,
             stats,
+            hasher: PageHasher::default(),
+            on_evict: None,
+        }
+    }
+
+    /// Uses the hasher to pick shards of keys.
+    pub(crate) fn with_hasher(mut self, hasher: PageHasher) -> Self {
+        self.hasher = hasher;
+        self
+    }
+
+    /// Calls the callback with the pages evicted from the cache.
+    pub(crate) fn with_evict_callback(mut self, on_evict: EvictCallback) -> Self {
+        for shard in &mut self.shards {
+            shard.get_mut().evicted = Some(Vec::new());
         }
+        self.on_evict = Some(on_evict);
+        self
+    }
+
+    /// Returns the total capacity of the cache.
+    pub(crate) fn capacity(&self) -> usize {
+        self.shards.iter().map(|shard| shard.lock().capacity).sum()
+    }
+
+    /// Returns the total charge of entries in the cache.
+    pub(crate) fn usage(&self) -> usize {
+        self.shards
+            .iter()
+            .map(|shard| shard.lock().usage.load(Ordering::Relaxed))
+            .sum()
+    }
+
+    /// Sets the total capacity of the cache, evicting entries if the cache
+    /// exceeds the new capacity.
+    pub(crate) fn set_capacity(&self, capacity: usize) {
+        let num_shards = self.shards.len();
+        let per_shard_cap = (capacity + (num_shards - 1)) / num_shards;
+        for shard in &self.shards {
+            let mut shard = shard.lock();
+            unsafe { shard.set_capacity(per_shard_cap) };
+            self.notify_evicted(shard);
+        }
+    }
+
+    /// Returns the statistics of each shard.
+    #[cfg(feature = "detailed-stats")]
+    pub(crate) fn shard_stats(&self) -> Vec<CacheStats> {
+        self.stats.iter().map(|s| s.snapshot()).collect()
     }
 
     #[inline]
@@ -117,24 +172,26 @@ impl<T: Clone> LRUCache<T> {
     }
 
     #[inline]
-    fn hash_key(key: u64) -> u32 {
-        // fnv32: https://github.com/golang/go/blob/master/src/hash/fnv/fnv.go#L99
-        const OFFSET32: u32 = 2166136261;
-        const PRIME32: u32 = 16777619;
+    fn hash_key(&self, key: u64) -> u32 {
+        self.hasher.hash(key)
+    }
 
-        let (mut file_id, mut offset) = ((key >> 32) as u32, key as u32);
-        let mut h = OFFSET32;
-        for _ in 0..4 {
-            h = h.wrapping_mul(PRIME32);
-            h ^= (file_id & 0xff) as u32;
-            file_id >>= 8;
-        }
-        for _ in 0..4 {
-            h = h.wrapping_mul(PRIME32);
-            h ^= (offset & 0xff) as u32;
-            offset >>= 8;
+    /// Reports the pages evicted from the shard after its lock is released.
+    fn notify_evicted(&self, mut shard: MutexGuard<'_, LRUCacheShard<T>>) {
+        let Some(on_evict) = &self.on_evict else {
+            return;
+        };
+        let evicted = shard.evicted.as_mut().map(mem::take).unwrap_or_default();
+        drop(shard);
+        for (key, charge, reason) in evicted {
+            on_evict.call(key, charge, reason);
         }
-        h
+    }
+
+    /// Returns the index of the shard that the key belongs to.
+    #[cfg(test)]
+    pub(crate) fn shard_of(&self, key: u64) -> u32 {
+        self.shard(self.hash_key(key))
     }
 }
 
@@ -146,11 +203,11 @@ impl<T: Clone> Cache<T> for LRUCache<T> {
         charge: usize,
         option: CacheOption,
     ) -> Result<Option<CacheEntry<T, Self>>> {
-        let hash = Self::hash_key(key);
+        let hash = self.hash_key(key);
         let idx = self.shard(hash);
         let shard = &self.shards[idx as usize];
         let mut shard = shard.lock();
-        unsafe { shard.insert(key, hash, value, charge, option) }.map(|ptr| {
+        let entry = unsafe { shard.insert(key, hash, value, charge, option) }.map(|ptr| {
             if ptr.is_null() {
                 None
             } else {
@@ -167,11 +224,13 @@ impl<T: Clone> Cache<T> for LRUCache<T> {
                     token,
                 })
             }
-        })
+        });
+        self.notify_evicted(shard);
+        entry
     }
 
     fn lookup(self: &std::sync::Arc<Self>, key: u64) -> Option<CacheEntry<T, Self>> {
-        let hash = Self::hash_key(key);
+        let hash = self.hash_key(key);
         let idx = self.shard(hash);
         let shard = &self.shards[idx as usize];
         let mut shard = shard.lock();
@@ -196,6 +255,7 @@ impl<T: Clone> Cache<T> for LRUCache<T> {
                 let idx = self.shard(hash);
                 let mut shard = self.shards[idx as usize].lock();
                 shard.release(lh, token);
+                self.notify_evicted(shard);
                 true
             } else {
                 unreachable!()
@@ -205,10 +265,11 @@ impl<T: Clone> Cache<T> for LRUCache<T> {
 
     fn erase(self: &std::sync::Arc<Self>, key: u64) {
         unsafe {
-            let hash = Self::hash_key(key);
+            let hash = self.hash_key(key);
             let idx = self.shard(hash);
             let mut shard = self.shards[idx as usize].lock();
             shard.erase(key.into());
+            self.notify_evicted(shard);
         }
     }
 
@@ -217,6 +278,7 @@ impl<T: Clone> Cache<T> for LRUCache<T> {
             for shard in &self.shards {
                 let mut shard = shard.lock();
                 shard.erase_file_pages(file_id);
+                self.notify_evicted(shard);
             }
         }
     }
@@ -254,6 +316,7 @@ impl<T: Clone> LRUCacheShard<T> {
             lru_bottom_pri: Box::new(LRUHandlePtr { ptr }),
             usage: Default::default(),
             stats: Default::default(),
+            evicted: None,
         }
     }
 
@@ -300,6 +363,14 @@ impl<T: Clone> LRUCacheShard<T> {
         Ok(lhd)
     }
 
+    unsafe fn set_capacity(&mut self, capacity: usize) {
+        self.capacity = capacity;
+        self.lru_high_capacity = ((capacity as f64) * self.high_pri_ratio) as usize;
+        self.lru_low_capacity = ((capacity as f64) * self.low_pri_ratio) as usize;
+        self.evict_lru(0, CacheOption::default());
+        self.maintain_priority_size();
+    }
+
     unsafe fn release(&mut self, h: *mut LRUHandle<T>, token: CacheToken) {
         debug_assert!(!h.is_null());
         if (*h).is_detached() {
@@ -328,6 +399,12 @@ impl<T: Clone> LRUCacheShard<T> {
 
             // Remove the handle from table.
             self.table.remove((*h).key);
+            let reason = if token.returning_behavior_match(CACHE_DISCARD) {
+                EvictReason::Active
+            } else {
+                EvictReason::Passive
+            };
+            self.record_evicted(h, reason);
         }
 
         self.clear_handle(h)
@@ -351,6 +428,7 @@ impl<T: Clone> LRUCacheShard<T> {
     unsafe fn erase(&mut self, key: Key) {
         let h = self.table.remove(key);
         if !h.is_null() {
+            self.record_evicted(h, EvictReason::Active);
             self.try_remove_cache_handle(h)
         }
         self.stats.active_evict.inc();
@@ -536,6 +614,7 @@ impl<T: Clone> LRUCacheShard<T> {
         {
             let old_ptr = (*self.lru_high_pri.ptr).page_link.next;
             self.table.remove((*old_ptr).key);
+            self.record_evicted(old_ptr, EvictReason::Passive);
             self.unlink_lru(old_ptr);
             self.unlink_file(old_ptr);
             self.clear_handle(old_ptr);
@@ -544,6 +623,12 @@ impl<T: Clone> LRUCacheShard<T> {
         true
     }
 
+    unsafe fn record_evicted(&mut self, h: *mut LRUHandle<T>, reason: EvictReason) {
+        if let Some(evicted) = &mut self.evicted {
+            evicted.push(((*h).key.into(), (*h).charge, reason));
+        }
+    }
+
     unsafe fn link_file(&mut self, e: *mut LRUHandle<T>) {
         debug_assert!(!e.is_null());
         let file_id = (*e).key.file_id();
@@ -40,6 +40,43 @@ pub(crate) trait Cache<T: Clone>: Sized {
     fn stats(self: &Arc<Self>) -> CacheStats;
 }
 
+/// The reason that a page is evicted from the page cache.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum EvictReason {
+    /// The page is erased explicitly, for example because its file is removed.
+    Active,
+    /// The page is evicted to make room for other pages.
+    Passive,
+}
+
+/// A callback invoked when a page is evicted from the page cache.
+///
+/// It is called with the address of the page, the charge of the page in bytes
+/// and the reason. It is never called while the cache is locked, so it may
+/// read pages through the cache.
+#[derive(Clone)]
+pub struct EvictCallback(Arc<dyn Fn(u64, usize, EvictReason) + Send + Sync>);
+
+impl EvictCallback {
+    /// Creates an evict callback that calls `f`.
+    pub fn new<F>(f: F) -> Self
+    where
+        F: Fn(u64, usize, EvictReason) + Send + Sync + 'static,
+    {
+        Self(Arc::new(f))
+    }
+
+    pub(crate) fn call(&self, addr: u64, charge: usize, reason: EvictReason) {
+        (self.0)(addr, charge, reason)
+    }
+}
+
+impl fmt::Debug for EvictCallback {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("EvictCallback").finish()
+    }
+}
+
 pub(crate) struct CacheEntry<T, C>
 where
     T: Clone,
@@ -482,6 +519,67 @@ mod tests {
         assert!(h.is_none());
     }
 
+    #[test]
+    fn test_lru_evict_callback() {
+        use ::std::sync::{Mutex, Weak};
+
+        use super::lru::*;
+
+        let evicted = Arc::new(Mutex::new(Vec::new()));
+        let cache = Arc::new(Mutex::new(Weak::<LRUCache<Vec<u8>>>::new()));
+        let on_evict = {
+            let evicted = evicted.clone();
+            let cache = cache.clone();
+            EvictCallback::new(move |addr, charge, reason| {
+                // The cache is not locked when the callback is called.
+                if let Some(c) = cache.lock().unwrap().upgrade() {
+                    c.usage();
+                }
+                evicted.lock().unwrap().push((addr, charge, reason));
+            })
+        };
+        let c = Arc::new(LRUCache::new(2, -1, 0.0, 0.0).with_evict_callback(on_evict));
+        *cache.lock().unwrap() = Arc::downgrade(&c);
+
+        for n in 1..=3 {
+            let h = c
+                .insert(n, Some(vec![n as u8]), 1, CacheOption::default())
+                .unwrap()
+                .unwrap();
+            drop(h);
+        }
+        assert_eq!(*evicted.lock().unwrap(), vec![(1, 1, EvictReason::Passive)]);
+
+        c.erase(3);
+        assert_eq!(
+            evicted.lock().unwrap().last(),
+            Some(&(3, 1, EvictReason::Active))
+        );
+    }
+
+    #[test]
+    fn test_lru_set_capacity() {
+        use super::lru::*;
+
+        let c = Arc::new(LRUCache::new(4, -1, 0.0, 0.0));
+        for n in 1..=4 {
+            let h = c
+                .insert(n, Some(vec![n as u8]), 1, CacheOption::default())
+                .unwrap()
+                .unwrap();
+            drop(h);
+        }
+        c.set_capacity(2);
+        assert_eq!(c.capacity(), 2);
+        assert!(c.lookup(1).is_none());
+        assert!(c.lookup(2).is_none());
+        assert!(c.lookup(3).is_some());
+        assert!(c.lookup(4).is_some());
+
+        c.set_capacity(8);
+        assert_eq!(c.capacity(), 8);
+    }
+
     #[test]
     fn test_lru_pri_op() {
         use super::lru::*;
@@ -588,4 +686,56 @@ mod tests {
         c.erase(4);
         assert!(c.lookup(4).is_none());
     }
+
+    #[cfg(feature = "detailed-stats")]
+    #[test]
+    fn test_lru_shard_stats() {
+        use super::lru::*;
+
+        let c = Arc::new(LRUCache::new(1 << 20, 3, 0.0, 0.0));
+        for key in 0..64 {
+            c.insert(key, Some(vec![0]), 1, CacheOption::default())
+                .unwrap();
+        }
+        const HOT_KEY: u64 = 7;
+        for _ in 0..1000 {
+            drop(c.lookup(HOT_KEY).unwrap());
+        }
+        for key in 0..64 {
+            drop(c.lookup(key).unwrap());
+        }
+
+        let shards = c.shard_stats();
+        assert_eq!(shards.len(), 8);
+        let lookups: Vec<u64> = shards
+            .iter()
+            .map(|s| s.lookup_hit + s.lookup_miss)
+            .collect();
+        assert_eq!(lookups.iter().sum::<u64>(), 1064);
+        let hot = *lookups.iter().max().unwrap();
+        assert!(hot >= 1000);
+        assert!(lookups.iter().filter(|&&n| n == hot).count() == 1);
+    }
+
+    #[test]
+    fn test_lru_seeded_hasher() {
+        use super::lru::*;
+        use crate::page_store::PageHasher;
+
+        let new_cache = |seed| {
+            let hasher = PageHasher::XxHash64 { seed };
+            LRUCache::<Vec<u8>>::new(1 << 20, 4, 0.0, 0.0).with_hasher(hasher)
+        };
+        let a = new_cache(42);
+        let b = new_cache(42);
+        let c = new_cache(43);
+        let keys = (0..256u64).map(|i| (i << 32) | (i * 4096));
+        let shards_a: Vec<_> = keys.clone().map(|k| a.shard_of(k)).collect();
+        let shards_b: Vec<_> = keys.clone().map(|k| b.shard_of(k)).collect();
+        let shards_c: Vec<_> = keys.map(|k| c.shard_of(k)).collect();
+        assert_eq!(shards_a, shards_b);
+        assert_ne!(shards_a, shards_c);
+        // Keys are spread over all shards.
+        assert!((0..16).all(|i| shards_a.contains(&i)));
+    }
 }
@@ -0,0 +1,80 @@
+use std::{io::ErrorKind, path::Path};
+
+use log::info;
+
+use super::{
+    page_file::constant::IO_BUFFER_SIZE, Manifest, NewFile, PageStore, Result, StreamEdit,
+    VersionEdit,
+};
+use crate::env::{Directory, Env, PositionalReaderExt, SequentialWriter, SequentialWriterExt};
+
+impl<E: Env> PageStore<E> {
+    /// Copies the page files of the current version to `dir`, with a manifest
+    /// that references them, so that `dir` can be opened as a page store.
+    ///
+    /// The directory must not exist or be empty. Data in write buffers is not
+    /// included, so the caller should flush write buffers first.
+    pub(crate) async fn checkpoint(&self, dir: &Path) -> Result<()> {
+        self.env.create_dir_all(dir).await?;
+        if self.env.read_dir(dir)?.next().is_some() {
+            return Err(std::io::Error::from(ErrorKind::AlreadyExists).into());
+        }
+
+        // The version doesn't change while the manifest is locked, and its
+        // files are kept on disk while they are pinned.
+        let (edit, files, manifest_version) = {
+            let manifest = self.manifest.lock().await;
+            let version = self.version();
+            let new_files = version.file_infos().values().map(NewFile::from).collect();
+            let dicts = self.page_files.compression_dicts().all();
+            let edit = VersionEdit {
+                file_stream: Some(StreamEdit {
+                    new_files,
+                    deleted_files: Vec::new(),
+                }),
+                compression_dicts: dicts.iter().map(|dict| dict.as_ref().into()).collect(),
+                table_id: None,
+                version: None,
+            };
+            (edit, self.pin_live_files(), manifest.version())
+        };
+
+        for file in files.files() {
+            let file_name = file.path.file_name().expect("page files have names");
+            copy_file(&self.env, &file.path, &dir.join(file_name), file.size).await?;
+        }
+        self.env.open_dir(dir).await?.sync_all().await?;
+
+        // The manifest is written last, so that a partial checkpoint can't be
+        // opened with missing files.
+        let mut manifest = Manifest::open(self.env.clone(), dir).await?;
+        // The checkpoint is a copy of this table, so it keeps the table id, and its
+        // versions follow the ones of this table.
+        manifest.set_table_id(self.table_id);
+        manifest.reset_version(manifest_version);
+        manifest
+            .record_version_edit(edit, VersionEdit::default)
+            .await?;
+        info!(
+            "Checkpoint {} page files to {}",
+            files.files().len(),
+            dir.display()
+        );
+        Ok(())
+    }
+}
+
+async fn copy_file<E: Env>(env: &E, from: &Path, to: &Path, size: u64) -> Result<()> {
+    let reader = env.open_positional_reader(from).await?;
+    let mut writer = env.open_sequential_writer(to).await?;
+    let mut buf = vec![0u8; IO_BUFFER_SIZE.min(size as usize)];
+    let mut offset = 0;
+    while offset < size {
+        let len = buf.len().min((size - offset) as usize);
+        reader.read_exact_at(&mut buf[..len], offset).await?;
+        writer.write_all(&buf[..len]).await?;
+        offset += len as u64;
+    }
+    writer.sync_all().await?;
+    Ok(())
+}
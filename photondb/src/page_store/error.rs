@@ -12,8 +12,32 @@ pub(crate) enum Error {
     MemoryLimit,
     #[error("TooLarge put size")]
     TooLargeSize,
+    #[error("Timeout")]
+    Timeout,
+    #[error("WriteStall")]
+    WriteStall,
+    #[error("Busy")]
+    Busy,
+    #[error("ReadOnly")]
+    ReadOnly,
+    #[error("Missing page file {file_id}")]
+    MissingFile { file_id: u32 },
+    #[error("Invalid manifest version {version}, the latest version is {latest}")]
+    InvalidManifestVersion { version: usize, latest: usize },
+    #[error("DiskFull")]
+    DiskFull,
     #[error("IO {0}")]
     Io(#[from] std::io::Error),
 }
 
+impl Error {
+    /// Converts an I/O error caused by a full disk to [`Error::DiskFull`].
+    pub(crate) fn map_disk_full(self) -> Self {
+        match self {
+            Error::Io(err) if err.raw_os_error() == Some(libc::ENOSPC) => Error::DiskFull,
+            err => err,
+        }
+    }
+}
+
 pub(crate) type Result<T, E = Error> = std::result::Result<T, E>;
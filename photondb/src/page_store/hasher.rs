@@ -0,0 +1,71 @@
+use xxhash_rust::xxh64::xxh64;
+
+/// A hash function for pages, which is used to pick the shards of the page
+/// cache.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum PageHasher {
+    /// Hashes page addresses with FNV-1, which is cheap but easy to collide on
+    /// purpose.
+    #[default]
+    Fnv,
+    /// Hashes page addresses with XXH64 and the seed, so that hashing can be
+    /// seeded deterministically or hardened against adversarial collisions.
+    XxHash64 {
+        /// The seed of the hash function.
+        seed: u64,
+    },
+}
+
+impl PageHasher {
+    /// Returns the hash of the page address.
+    #[inline]
+    pub(crate) fn hash(&self, addr: u64) -> u32 {
+        match *self {
+            PageHasher::Fnv => fnv32(addr),
+            PageHasher::XxHash64 { seed } => {
+                let h = xxh64(&addr.to_le_bytes(), seed);
+                (h ^ (h >> 32)) as u32
+            }
+        }
+    }
+}
+
+#[inline]
+fn fnv32(addr: u64) -> u32 {
+    // fnv32: https://github.com/golang/go/blob/master/src/hash/fnv/fnv.go#L99
+    const OFFSET32: u32 = 2166136261;
+    const PRIME32: u32 = 16777619;
+
+    let (mut file_id, mut offset) = ((addr >> 32) as u32, addr as u32);
+    let mut h = OFFSET32;
+    for _ in 0..4 {
+        h = h.wrapping_mul(PRIME32);
+        h ^= (file_id & 0xff) as u32;
+        file_id >>= 8;
+    }
+    for _ in 0..4 {
+        h = h.wrapping_mul(PRIME32);
+        h ^= (offset & 0xff) as u32;
+        offset >>= 8;
+    }
+    h
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn page_hasher() {
+        let a = PageHasher::XxHash64 { seed: 42 };
+        let b = PageHasher::XxHash64 { seed: 42 };
+        let c = PageHasher::XxHash64 { seed: 43 };
+        for addr in 0..64 {
+            assert_eq!(a.hash(addr), b.hash(addr));
+            let h = xxh64(&addr.to_le_bytes(), 42);
+            assert_eq!(a.hash(addr), (h ^ (h >> 32)) as u32);
+            assert_eq!(PageHasher::default().hash(addr), fnv32(addr));
+        }
+        assert!((0..64).any(|addr| a.hash(addr) != c.hash(addr)));
+    }
+}
@@ -0,0 +1,183 @@
+use std::fmt::Debug;
+
+use crate::{
+    page::{
+        IndexPageRef, PageRef, RangeDeletePageRef, SortedPageKey, SortedPageRef, SortedPageValue,
+        ValuePageRef,
+    },
+    page_store::NAN_ID,
+};
+
+/// Verifies the invariants of a page written by a background job, and panics
+/// with diagnostics on violation.
+///
+/// This is only used if `paranoid_checks` is enabled.
+pub(crate) fn check_page(job: &str, page_id: u64, page_addr: u64, page: PageRef<'_>) {
+    if let Err(err) = check_page_order(page) {
+        panic!(
+            "paranoid check failed after {job}: page {page_id} at address {page_addr} \
+             ({:?} {:?}): {err}",
+            page.tier(),
+            page.kind(),
+        );
+    }
+}
+
+/// Checks that the entries of a sorted page are in order, and that the
+/// separators of an index page lie within the range of the page.
+///
+/// Split and overflow pages have no ordered entries and are always valid.
+pub(crate) fn check_page_order(page: PageRef<'_>) -> Result<(), String> {
+    if page.kind().is_data() {
+        if page.tier().is_leaf() {
+            check_sorted(ValuePageRef::from(page))
+        } else {
+            check_separators(IndexPageRef::from(page))
+        }
+    } else if page.kind().is_range_delete() {
+        check_sorted(RangeDeletePageRef::from(page))
+    } else {
+        Ok(())
+    }
+}
+
+/// Checks that the separators of an index page are strictly increasing.
+///
+/// The first separator starts the range of the page. A placeholder child
+/// ends the range, so it can only be the last one, and no other separator
+/// lies beyond it.
+fn check_separators(page: IndexPageRef<'_>) -> Result<(), String> {
+    let mut prev: Option<&[u8]> = None;
+    for i in 0..page.len() {
+        let (key, index) = page
+            .get(i)
+            .ok_or_else(|| format!("separator {i} of {} is missing", page.len()))?;
+        if let Some(prev) = prev {
+            if prev >= key {
+                return Err(format!(
+                    "separator {i} {key:?} is not greater than the previous separator {prev:?}"
+                ));
+            }
+            if i + 1 < page.len() && index.id == NAN_ID {
+                return Err(format!(
+                    "separator {i} {key:?} ends the page range before the last separator"
+                ));
+            }
+        }
+        prev = Some(key);
+    }
+    Ok(())
+}
+
+fn check_sorted<K, V>(page: SortedPageRef<'_, K, V>) -> Result<(), String>
+where
+    K: SortedPageKey + Debug,
+    V: SortedPageValue,
+{
+    let mut prev: Option<K> = None;
+    for i in 0..page.len() {
+        let (key, _) = page
+            .get(i)
+            .ok_or_else(|| format!("entry {i} of {} is missing", page.len()))?;
+        if let Some(prev) = prev.as_ref() {
+            if *prev > key {
+                return Err(format!(
+                    "entry {i} {key:?} is less than the previous entry {prev:?}"
+                ));
+            }
+        }
+        prev = Some(key);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::page::{
+        tests::alloc_page, Index, Key, PageBuf, PageKind, PageTier, SortedPageBuilder, Value,
+    };
+
+    fn build_page(data: &[(Key<'_>, Value<'_>)]) -> Box<[u8]> {
+        let builder = SortedPageBuilder::new(PageTier::Leaf, PageKind::Data).with_slice(data);
+        let mut buf = alloc_page(builder.size());
+        let mut page = PageBuf::new(buf.as_mut());
+        builder.build(&mut page);
+        buf
+    }
+
+    fn build_index_page(data: &[(&[u8], Index)]) -> Box<[u8]> {
+        let builder = SortedPageBuilder::new(PageTier::Inner, PageKind::Data).with_slice(data);
+        let mut buf = alloc_page(builder.size());
+        let mut page = PageBuf::new(buf.as_mut());
+        builder.build(&mut page);
+        buf
+    }
+
+    #[test]
+    fn check_page_order_sorted() {
+        let page = build_page(&[
+            (Key::new(b"a", 2), Value::Put(b"1")),
+            (Key::new(b"a", 1), Value::Put(b"2")),
+            (Key::new(b"b", 1), Value::Delete),
+        ]);
+        assert!(check_page_order(PageRef::new(&page)).is_ok());
+    }
+
+    #[test]
+    fn check_page_order_unsorted() {
+        let page = build_page(&[
+            (Key::new(b"c", 1), Value::Put(b"1")),
+            (Key::new(b"b", 1), Value::Put(b"2")),
+        ]);
+        let err = check_page_order(PageRef::new(&page)).unwrap_err();
+        assert!(err.contains("entry 1"));
+    }
+
+    #[test]
+    fn check_page_order_separators() {
+        let page = build_index_page(&[
+            (b"".as_slice(), Index::new(1, 0)),
+            (b"b".as_slice(), Index::new(2, 0)),
+            (b"d".as_slice(), Index::new(NAN_ID, 0)),
+        ]);
+        assert!(check_page_order(PageRef::new(&page)).is_ok());
+
+        let page = build_index_page(&[
+            (b"".as_slice(), Index::new(1, 0)),
+            (b"d".as_slice(), Index::new(2, 0)),
+            (b"b".as_slice(), Index::new(3, 0)),
+        ]);
+        let err = check_page_order(PageRef::new(&page)).unwrap_err();
+        assert!(err.contains("separator 2"), "{err}");
+
+        // The separator after the placeholder lies beyond the page range.
+        let page = build_index_page(&[
+            (b"".as_slice(), Index::new(1, 0)),
+            (b"b".as_slice(), Index::new(NAN_ID, 0)),
+            (b"d".as_slice(), Index::new(2, 0)),
+        ]);
+        let err = check_page_order(PageRef::new(&page)).unwrap_err();
+        assert!(err.contains("separator 1"), "{err}");
+    }
+
+    #[test]
+    #[should_panic(expected = "paranoid check failed after reclaim")]
+    fn check_index_page_panics() {
+        let page = build_index_page(&[
+            (b"b".as_slice(), Index::new(1, 0)),
+            (b"a".as_slice(), Index::new(2, 0)),
+        ]);
+        check_page("reclaim", 1, 2, PageRef::new(&page));
+    }
+
+    #[test]
+    #[should_panic(expected = "paranoid check failed after flush")]
+    fn check_page_panics() {
+        let page = build_page(&[
+            (Key::new(b"a", 1), Value::Put(b"1")),
+            (Key::new(b"a", 2), Value::Put(b"2")),
+        ]);
+        check_page("flush", 1, 2, PageRef::new(&page));
+    }
+}
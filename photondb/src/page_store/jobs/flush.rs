@@ -1,14 +1,31 @@
-use std::{sync::Arc, time::Instant};
+use std::{
+    sync::{Arc, Mutex},
+    thread::{self, JoinHandle},
+    time::{Duration, Instant},
+};
 
-use log::info;
+use futures_timer::Delay;
+use log::{info, warn};
 use rustc_hash::{FxHashMap, FxHashSet};
 
 use crate::{
     env::Env,
-    page_store::{stats::AtomicJobStats, *},
+    page::{RangeDeletePageRef, Value, ValuePageRef},
+    page_store::{jobs::check::check_page, stats::AtomicJobStats, *},
     util::shutdown::{with_shutdown, Shutdown},
 };
 
+/// The maximum bytes of values sampled to train a compression dictionary.
+const MAX_DICT_SAMPLE_BYTES: usize = 1 << 20;
+/// The minimum number of values sampled to train a compression dictionary.
+const MIN_DICT_SAMPLES: usize = 128;
+/// The interval to retry a flush that failed because the disk is full.
+const DISK_FULL_RETRY_INTERVAL: Duration = Duration::from_secs(1);
+
+/// A compression dictionary being trained in the background, with the number
+/// of values it is trained from.
+type DictTraining = JoinHandle<Result<(CompressionDict, usize)>>;
+
 pub(crate) struct FlushCtx<E: Env> {
     options: Options,
     shutdown: Shutdown,
@@ -16,6 +33,7 @@ pub(crate) struct FlushCtx<E: Env> {
     version_owner: Arc<VersionOwner>,
     page_files: Arc<PageFiles<E>>,
     manifest: Arc<futures::lock::Mutex<Manifest<E>>>,
+    dict_training: Mutex<Option<DictTraining>>,
 }
 
 #[derive(Default)]
@@ -44,6 +62,7 @@ impl<E: Env> FlushCtx<E> {
             version_owner,
             page_files,
             manifest,
+            dict_training: Mutex::new(None),
         }
     }
 
@@ -65,7 +84,21 @@ impl<E: Env> FlushCtx<E> {
             }
 
             match self.flush(write_buffer.as_ref()).await {
-                Ok(()) => {}
+                Ok(()) => version.buffer_set.set_disk_full(false),
+                Err(Error::DiskFull) => {
+                    warn!(
+                        "Flush write buffer {} failed because the disk is full, \
+                            retry in {DISK_FULL_RETRY_INTERVAL:?}",
+                        write_buffer.group_id()
+                    );
+                    version.buffer_set.set_disk_full(true);
+                    if with_shutdown(&mut self.shutdown, Delay::new(DISK_FULL_RETRY_INTERVAL))
+                        .await
+                        .is_none()
+                    {
+                        break 'OUTER;
+                    }
+                }
                 Err(err) => {
                     todo!("flush write buffer: {err:?}");
                 }
@@ -99,9 +132,14 @@ impl<E: Env> FlushCtx<E> {
                 let _ = buffer.seal();
             }
             assert!(buffer.is_flushable());
-            self.flush_impl(&buffer, false)
-                .await
-                .expect("TODO: flush write buffer");
+            match self.flush_impl(&buffer, false).await {
+                Ok(()) => {}
+                Err(Error::DiskFull) => {
+                    warn!("Skip flushing write buffers during shutdown, the disk is full");
+                    break;
+                }
+                Err(err) => panic!("TODO: flush write buffer: {err:?}"),
+            }
         }
     }
 
@@ -113,7 +151,10 @@ impl<E: Env> FlushCtx<E> {
     async fn flush_impl(&self, write_buffer: &WriteBuffer, wait: bool) -> Result<()> {
         let start_at = Instant::now();
         let group_id = write_buffer.group_id();
-        let (dealloc_pages, page_group, file_info) = self.build_page_file(write_buffer).await?;
+        let (dealloc_pages, page_group, file_info) = self
+            .build_page_file(write_buffer)
+            .await
+            .map_err(Error::map_disk_full)?;
 
         let file_id = file_info.meta().file_id;
         let file_size = file_info.meta().file_size;
@@ -125,8 +166,17 @@ impl<E: Env> FlushCtx<E> {
             start_at.elapsed().as_micros()
         );
 
-        self.save_and_install_version(page_group, file_info, dealloc_pages, wait)
-            .await?;
+        if let Err(err) = self
+            .save_and_install_version(page_group, file_info, dealloc_pages, wait)
+            .await
+        {
+            // A failed version edit is not persisted, so the file is not referenced by
+            // any version.
+            self.page_files.remove_files(vec![file_id]).await;
+            return Err(err.map_disk_full());
+        }
+        let elapsed = start_at.elapsed().as_nanos() as u64;
+        self.job_stats.flush_nanos.add(elapsed);
 
         write_buffer.on_flushed();
 
@@ -153,11 +203,13 @@ impl<E: Env> FlushCtx<E> {
         if !page_group.is_empty() {
             page_groups.insert(group_id, page_group);
         }
+        let edit = make_flush_version_edit(&file_info, &obsoleted_files);
         file_infos.insert(file_id, file_info);
 
-        let edit = make_flush_version_edit(file_id, &obsoleted_files);
         manifest
-            .record_version_edit(edit, || version_snapshot(&version))
+            .record_version_edit(edit, || {
+                version_snapshot(&version, self.page_files.compression_dicts())
+            })
             .await?;
 
         // Release buffer permit and ensure the new buffer is installed, before install
@@ -191,10 +243,33 @@ impl<E: Env> FlushCtx<E> {
         let group_id = write_buffer.group_id();
         info!("Flush write buffer {group_id} to file, {flush_stats}");
 
+        self.train_compression_dict(write_buffer).await?;
+
         let file_id = {
             let mut lock = self.manifest.lock().await;
             lock.next_file_id()
         };
+        match self
+            .write_page_file(write_buffer, file_id, &dealloc_pages, &skip_pages)
+            .await
+        {
+            Ok((page_group, file_info)) => Ok((dealloc_pages, page_group, file_info)),
+            Err(err) => {
+                // Rolls back the partial file, so that it is not left on disk.
+                self.page_files.remove_files(vec![file_id]).await;
+                Err(err)
+            }
+        }
+    }
+
+    async fn write_page_file(
+        &self,
+        write_buffer: &WriteBuffer,
+        file_id: u32,
+        dealloc_pages: &[u64],
+        skip_pages: &FxHashSet<u32>,
+    ) -> Result<(PageGroup, FileInfo)> {
+        let group_id = write_buffer.group_id();
         let mut builder = self
             .page_files
             .new_file_builder(
@@ -212,6 +287,27 @@ impl<E: Env> FlushCtx<E> {
                     discard_bytes += header.page_size();
                     continue;
                 }
+                if page.tier().is_leaf() && page.kind().is_data() {
+                    let page = ValuePageRef::from(page);
+                    if let (Some((first, _)), Some((last, _))) =
+                        (page.get(0), page.get(page.len().saturating_sub(1)))
+                    {
+                        group_builder.add_key_range(first.raw, last.raw);
+                    }
+                    let lsns = (0..page.len())
+                        .filter_map(|i| page.get(i))
+                        .map(|(k, _)| k.lsn);
+                    if let (Some(min_lsn), Some(max_lsn)) = (lsns.clone().min(), lsns.max()) {
+                        group_builder.add_lsn_range(min_lsn, max_lsn);
+                    }
+                } else if page.tier().is_leaf() && page.kind().is_range_delete() {
+                    if let Some((start, _)) = RangeDeletePageRef::from(page).get(0) {
+                        group_builder.add_lsn_range(start.lsn, start.lsn);
+                    }
+                }
+                if self.options.paranoid_checks {
+                    check_page("flush", header.page_id(), page_addr, page);
+                }
                 let content = page.data();
                 group_builder
                     .add_page(header.page_id(), page_addr, page.info(), content)
@@ -220,7 +316,7 @@ impl<E: Env> FlushCtx<E> {
                 let _ = self.page_files.populate_cache(page_addr, content);
             }
         }
-        group_builder.add_dealloc_pages(&dealloc_pages);
+        group_builder.add_dealloc_pages(dealloc_pages);
         builder = group_builder.finish().await?;
         let (page_groups, file_info) = builder.finish(file_id).await?;
         let page_group = page_groups
@@ -231,7 +327,77 @@ impl<E: Env> FlushCtx<E> {
         self.job_stats.flush_write_bytes.add(write_bytes as u64);
         self.job_stats.flush_discard_bytes.add(discard_bytes as u64);
 
-        Ok((dealloc_pages, page_group, file_info))
+        Ok((page_group, file_info))
+    }
+
+    /// Trains a compression dictionary from the values of the write buffer,
+    /// if it is enabled and there is no dictionary yet.
+    ///
+    /// The training runs in a background thread to keep it off the flush
+    /// path, and the dictionary is installed by the first flush after it is
+    /// done, so only the later files are compressed with it. Pages can be
+    /// compressed without a dictionary, so it is skipped if there are not
+    /// enough values or the training fails.
+    async fn train_compression_dict(&self, write_buffer: &WriteBuffer) -> Result<()> {
+        let uses_zstd = self.options.compression_on_flush == Compression::ZSTD
+            || self.options.compression_on_cold_compact == Compression::ZSTD;
+        let dicts = self.page_files.compression_dicts();
+        if !self.options.enable_compression_dict || !uses_zstd || dicts.current().is_some() {
+            return Ok(());
+        }
+
+        let training = {
+            let mut training = self.dict_training.lock().expect("Poisoned");
+            match training.take() {
+                Some(handle) if !handle.is_finished() => {
+                    *training = Some(handle);
+                    return Ok(());
+                }
+                training => training,
+            }
+        };
+        let Some(training) = training else {
+            let (samples, sizes) = sample_values(write_buffer);
+            if sizes.len() >= MIN_DICT_SAMPLES {
+                let handle = thread::spawn(move || {
+                    let dict = train_dict(&samples, &sizes).and_then(CompressionDict::new)?;
+                    Ok((dict, sizes.len()))
+                });
+                *self.dict_training.lock().expect("Poisoned") = Some(handle);
+            }
+            return Ok(());
+        };
+        let (dict, num_samples) = match training
+            .join()
+            .expect("Compression dictionary training panicked")
+        {
+            Ok(trained) => trained,
+            Err(err) => {
+                info!("Skip training compression dictionary: {err}");
+                return Ok(());
+            }
+        };
+
+        // The dictionary must be recorded before the files compressed with it.
+        let mut manifest = self.manifest.lock().await;
+        let version = self.version_owner.current();
+        let edit = VersionEdit {
+            file_stream: None,
+            compression_dicts: vec![(&dict).into()],
+            table_id: None,
+            version: None,
+        };
+        manifest
+            .record_version_edit(edit, || version_snapshot(&version, dicts))
+            .await?;
+        let dict = dicts.add(dict);
+        info!(
+            "Train compression dictionary {} with {} bytes from {} values",
+            dict.id,
+            dict.data.len(),
+            num_samples
+        );
+        Ok(())
     }
 
     fn apply_dealloc_pages(
@@ -348,7 +514,36 @@ fn collect_dealloc_pages_and_stats(
     (dealloc_pages, skip_pages)
 }
 
-pub(super) fn version_snapshot(version: &Version) -> VersionEdit {
+/// Samples the values of the leaf data pages in the write buffer, returns the
+/// concatenated values and their sizes.
+fn sample_values(write_buffer: &WriteBuffer) -> (Vec<u8>, Vec<usize>) {
+    let mut samples = Vec::new();
+    let mut sizes = Vec::new();
+    for (_, header, record_ref) in write_buffer.iter() {
+        let RecordRef::Page(page) = record_ref else {
+            continue;
+        };
+        if header.is_tombstone() || !page.tier().is_leaf() || !page.kind().is_data() {
+            continue;
+        }
+        let page = ValuePageRef::from(page);
+        for (_, value) in (0..page.len()).filter_map(|i| page.get(i)) {
+            if let Value::Put(value) = value {
+                if value.is_empty() {
+                    continue;
+                }
+                if samples.len() + value.len() > MAX_DICT_SAMPLE_BYTES {
+                    return (samples, sizes);
+                }
+                samples.extend_from_slice(value);
+                sizes.push(value.len());
+            }
+        }
+    }
+    (samples, sizes)
+}
+
+pub(crate) fn version_snapshot(version: &Version, dicts: &CompressionDicts) -> VersionEdit {
     let new_files: Vec<NewFile> = version
         .file_infos()
         .values()
@@ -362,20 +557,32 @@ pub(super) fn version_snapshot(version: &Version) -> VersionEdit {
         new_files,
         deleted_files,
     };
+    let compression_dicts = dicts
+        .all()
+        .iter()
+        .map(|dict| dict.as_ref().into())
+        .collect();
+    // The table id is filled by the manifest.
     VersionEdit {
         file_stream: Some(stream),
+        compression_dicts,
+        table_id: None,
+        version: None,
     }
 }
 
-fn make_flush_version_edit(file_id: u32, obsoleted_files: &FxHashSet<u32>) -> VersionEdit {
+fn make_flush_version_edit(file_info: &FileInfo, obsoleted_files: &FxHashSet<u32>) -> VersionEdit {
     let deleted_files = obsoleted_files.iter().cloned().collect();
-    let new_files = vec![NewFile::from(file_id)];
+    let new_files = vec![NewFile::from(file_info)];
     let stream = StreamEdit {
         new_files,
         deleted_files,
     };
     VersionEdit {
         file_stream: Some(stream),
+        compression_dicts: Vec::new(),
+        table_id: None,
+        version: None,
     }
 }
 
@@ -386,6 +593,7 @@ mod tests {
     use super::FlushCtx;
     use crate::{
         env::Photon,
+        page::{Key, PageKind, PageTier, SortedPageBuilder, Value},
         page_store::{
             version::{DeltaVersion, Version, VersionOwner},
             Manifest, PageFiles, WriteBuffer,
@@ -447,4 +655,68 @@ mod tests {
             assert!(file_info.get_page_handle(addr).is_none());
         }
     }
+
+    #[photonio::test]
+    async fn flush_write_buffer_key_range() {
+        let base = tempdir::TempDir::new("flush_key_range").unwrap();
+        let ctx = new_flush_ctx(base.path()).await;
+        let wb = WriteBuffer::with_capacity(1, 1 << 16);
+        let data = [
+            (Key::new(b"b", 1), Value::Put(b"1")),
+            (Key::new(b"c", 1), Value::Put(b"2")),
+            (Key::new(b"e", 1), Value::Delete),
+        ];
+        let builder = SortedPageBuilder::new(PageTier::Leaf, PageKind::Data).with_slice(&data);
+        unsafe {
+            let (_, _, mut page) = wb.alloc_page(1, builder.size() as u32, false).unwrap();
+            builder.build(&mut page);
+            wb.seal().unwrap();
+        }
+        let (_, _, file_info) = ctx.build_page_file(&wb).await.unwrap();
+        let range = file_info.key_range().unwrap();
+        assert_eq!(range.min_key, b"b");
+        assert_eq!(range.max_key, b"e");
+    }
+
+    #[photonio::test]
+    async fn flush_write_buffer_lsn_range() {
+        let base = tempdir::TempDir::new("flush_lsn_range").unwrap();
+        let ctx = new_flush_ctx(base.path()).await;
+        let wb = WriteBuffer::with_capacity(1, 1 << 16);
+        let data = [
+            (Key::new(b"b", 3), Value::Put(b"1")),
+            (Key::new(b"c", 7), Value::Put(b"2")),
+            (Key::new(b"e", 5), Value::Delete),
+        ];
+        let builder = SortedPageBuilder::new(PageTier::Leaf, PageKind::Data).with_slice(&data);
+        unsafe {
+            let (_, _, mut page) = wb.alloc_page(1, builder.size() as u32, false).unwrap();
+            builder.build(&mut page);
+            wb.seal().unwrap();
+        }
+        let (_, _, file_info) = ctx.build_page_file(&wb).await.unwrap();
+        let range = file_info.lsn_range().unwrap();
+        assert_eq!(range.min_lsn, 3);
+        assert_eq!(range.max_lsn, 7);
+    }
+
+    #[photonio::test]
+    #[should_panic(expected = "paranoid check failed after flush")]
+    async fn flush_write_buffer_paranoid_checks() {
+        let base = tempdir::TempDir::new("flush_paranoid_checks").unwrap();
+        let mut ctx = new_flush_ctx(base.path()).await;
+        ctx.options.paranoid_checks = true;
+        let wb = WriteBuffer::with_capacity(1, 1 << 16);
+        let data = [
+            (Key::new(b"c", 1), Value::Put(b"1")),
+            (Key::new(b"b", 1), Value::Put(b"2")),
+        ];
+        let builder = SortedPageBuilder::new(PageTier::Leaf, PageKind::Data).with_slice(&data);
+        unsafe {
+            let (_, _, mut page) = wb.alloc_page(1, builder.size() as u32, false).unwrap();
+            builder.build(&mut page);
+            wb.seal().unwrap();
+        }
+        let _ = ctx.build_page_file(&wb).await;
+    }
 }
@@ -1,8 +1,10 @@
 //! A mod contains jobs used by `page_store`.
 
+pub(crate) mod check;
 pub(crate) mod cleanup;
 pub(crate) mod flush;
 pub(crate) mod reclaim;
+pub(crate) mod stats_log;
 
-use flush::version_snapshot;
-pub(crate) use reclaim::wait_for_reclaiming;
+pub(crate) use flush::version_snapshot;
+pub(crate) use reclaim::{reclaim_dry_run, wait_for_reclaiming, ReclaimPause};
@@ -1,5 +1,12 @@
-use std::{sync::Arc, time::Instant};
+use std::{
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+    time::Instant,
+};
 
+use futures::future::Either;
 use log::{debug, error, info, trace};
 use rustc_hash::{FxHashMap, FxHashSet};
 
@@ -7,14 +14,18 @@ use crate::{
     env::Env,
     page::PageRef,
     page_store::{
+        jobs::check::check_page,
         page_file::{FileBuilder, FileMetaHolder, FileReader, PageGroupBuilder},
         stats::AtomicJobStats,
-        strategy::ReclaimPickStrategy,
+        strategy::{FileSummary, ReclaimPickStrategy},
         version::{DeltaVersion, VersionOwner, VersionUpdateReason},
-        FileInfo, Manifest, NewFile, Options, PageFiles, PageGroup, Result, StrategyBuilder,
-        StreamEdit, Version, VersionEdit,
+        Error, FileInfo, Manifest, NewFile, Options, PageFiles, PageGroup, PickedFile, Result,
+        StrategyBuilder, StreamEdit, Version, VersionEdit,
+    },
+    util::{
+        notify::Notify,
+        shutdown::{with_shutdown, Shutdown},
     },
-    util::shutdown::{with_shutdown, Shutdown},
 };
 
 pub(crate) struct ReclaimCtx<E>
@@ -33,6 +44,19 @@ where
     cleaned_files: FxHashSet<u32>,
 
     job_stats: Arc<AtomicJobStats>,
+    pause: Arc<ReclaimPause>,
+    /// Held while files are compacted, so that a file is not compacted by
+    /// the reclaimer and [`ReclaimCtx::compact_file_on_demand`] at once.
+    running: Arc<futures::lock::Mutex<()>>,
+}
+
+/// A reentrant switch to pause space reclaiming.
+///
+/// Reclaiming is paused until it is resumed as many times as it is paused.
+#[derive(Default)]
+pub(crate) struct ReclaimPause {
+    count: AtomicUsize,
+    notify: Notify,
 }
 
 #[derive(Debug)]
@@ -68,10 +92,22 @@ enum ReclaimReason {
     LargeSpaceAmp,
 }
 
+/// A summary of a reclaim cycle, which is logged when the cycle ends.
+#[derive(Debug)]
+struct ReclaimCycle {
+    reason: ReclaimReason,
+    files_scored: usize,
+    picked_files: Vec<u32>,
+    bytes_freed: u64,
+    /// The score of the first picked file.
+    score: Option<f64>,
+}
+
 #[derive(Debug, Default)]
 struct CompactStats {
     num_active_pages: usize,
     num_dealloc_pages: usize,
+    num_dropped_pages: usize,
     input_size: usize,
     output_size: usize,
 }
@@ -90,6 +126,8 @@ where
         version_owner: Arc<VersionOwner>,
         manifest: Arc<futures::lock::Mutex<Manifest<E>>>,
         job_stats: Arc<AtomicJobStats>,
+        pause: Arc<ReclaimPause>,
+        running: Arc<futures::lock::Mutex<()>>,
     ) -> Self {
         ReclaimCtx {
             options,
@@ -100,12 +138,34 @@ where
             manifest,
             cleaned_files: FxHashSet::default(),
             job_stats,
+            pause,
+            running,
         }
     }
 
+    /// Compacts the file into a new file regardless of its score, and marks
+    /// it as obsoleted.
+    ///
+    /// Returns [`Error::MissingFile`] if the file is not in the current
+    /// version.
+    pub(crate) async fn compact_file_on_demand(mut self, file_id: u32) -> Result<()> {
+        let _running = self.running.lock().await;
+        let version = self.version_owner.current();
+        if !version.file_infos().contains_key(&file_id) {
+            return Err(Error::MissingFile { file_id });
+        }
+        let mut progress = ReclaimProgress::new(&self.options, &version, &FxHashSet::default());
+        let victims = [file_id].into_iter().collect();
+        self.reclaim_files(&mut progress, &version, victims).await
+    }
+
     pub(crate) async fn run(mut self, mut version: Arc<Version>) {
         loop {
             if !self.options.disable_space_reclaiming {
+                let resumed = self.pause.wait_until(|paused| !paused);
+                if with_shutdown(&mut self.shutdown, resumed).await.is_none() {
+                    break;
+                }
                 self.reclaim(&version).await;
                 version.reclaimed();
             }
@@ -125,17 +185,25 @@ where
             return;
         }
 
+        let start_at = Instant::now();
+        let mut cycle = ReclaimCycle::new(progress.reclaim_reason());
+        let bytes_freed = self.job_stats.reclaim_bytes_freed.get();
         if let Err(err) = self
-            .reclaim_files_by_strategy(&mut progress, version, &cleaned_files)
+            .reclaim_files_by_strategy(&mut progress, &mut cycle, version, &cleaned_files)
             .await
         {
             error!("Reclaim files: {err:?}");
         }
+        cycle.bytes_freed = self.job_stats.reclaim_bytes_freed.get() - bytes_freed;
+        let elapsed = start_at.elapsed().as_nanos() as u64;
+        self.job_stats.reclaim_nanos.add(elapsed);
+        cycle.log();
     }
 
     async fn reclaim_files_by_strategy(
         &mut self,
         progress: &mut ReclaimProgress,
+        cycle: &mut ReclaimCycle,
         version: &Arc<Version>,
         cleaned_files: &FxHashSet<u32>,
     ) -> Result<()> {
@@ -144,17 +212,39 @@ where
             lock.now()
         };
         let mut strategy = self.build_strategy(now, version, cleaned_files);
+        cycle.files_scored = version
+            .file_infos()
+            .keys()
+            .filter(|id| !cleaned_files.contains(id))
+            .count();
         let mut builder = ReclaimJobBuilder::new(self.options.file_base_size);
-        while let Some((file, active_size)) = strategy.apply() {
-            if let Some(job) = builder.add(file, active_size) {
+        while let Some(file) = strategy.apply() {
+            if cycle.score.is_none() {
+                cycle.score = strategy.last_score();
+            }
+            if let Some(job) = builder.add(file.file_id, file.active_size) {
                 match job {
                     ReclaimJob::Compact(victims) => {
-                        self.reclaim_files(progress, version, victims).await?;
+                        let running = self.running.clone();
+                        let _running = running.lock().await;
+                        // The victims may have been compacted on demand since
+                        // they were picked.
+                        let current = self.version_owner.current();
+                        if victims
+                            .iter()
+                            .any(|id| !current.file_infos().contains_key(id))
+                        {
+                            break;
+                        }
+                        cycle.picked_files.extend(victims.iter().copied());
+                        cycle.picked_files.sort_unstable();
+                        self.reclaim_files(progress, &current, victims).await?;
                     }
                 }
             }
 
             if self.shutdown.is_terminated()
+                || self.pause.is_paused()
                 || version.has_next_version()
                 || !progress.is_reclaimable()
             {
@@ -177,24 +267,36 @@ where
 
         let file_infos = version.file_infos();
         let page_groups = version.page_groups();
-        let (page_groups, file_info) = self
+        let outputs = self
             .compact_files(progress, file_id, file_infos, page_groups, &victims)
             .await?;
 
         // All input are obsoleted, since it doesn't relocate pages.
-        let edit = make_compact_version_edit(&file_info, &victims);
         let mut manifest = self.manifest.lock().await;
         let version = self.version_owner.current();
+        // Flushes may have installed versions since the victims were read, and
+        // even obsoleted some of them.
+        let victims = victims
+            .into_iter()
+            .filter(|id| version.file_infos().contains_key(id))
+            .collect::<FxHashSet<_>>();
+        let edit = make_compact_version_edit(outputs.iter().map(|(_, info)| info), &victims);
         manifest
-            .record_version_edit(edit, || super::version_snapshot(&version))
+            .record_version_edit(edit, || {
+                super::version_snapshot(&version, self.page_files.compression_dicts())
+            })
             .await?;
 
         let mut delta = DeltaVersion::from(version.as_ref());
         delta.reason = VersionUpdateReason::Compact;
         delta.file_infos.retain(|id, _| !victims.contains(id));
-        delta.file_infos.insert(file_id, file_info);
-        // FIXME: need remove empty infos if it is not contained in.
-        delta.page_groups.extend(page_groups.into_iter());
+        for (page_groups, file_info) in outputs {
+            delta.file_infos.insert(file_info.meta().file_id, file_info);
+            // FIXME: need remove empty infos if it is not contained in.
+            delta
+                .page_groups
+                .extend(reconcile_page_groups(page_groups, version.page_groups()));
+        }
         delta.obsoleted_files = victims.into_iter().collect();
         // Safety: the mutable reference of [`Manifest`] is hold.
         unsafe { self.version_owner.install(delta) };
@@ -216,13 +318,16 @@ where
                 continue;
             }
 
-            strategy.collect_file(page_groups, file);
+            strategy.collect_file(&FileSummary::from((page_groups, file)));
         }
         strategy
     }
 
-    /// Compact a set of files into a new file, and release mark the compacted
+    /// Compact a set of files into new files, and release mark the compacted
     /// files as obsoleted to reclaim space.
+    ///
+    /// The first new file is `new_file_id`. Another file is started once the
+    /// current one reaches [`Options::target_file_size`].
     async fn compact_files(
         &mut self,
         progress: &mut ReclaimProgress,
@@ -230,65 +335,104 @@ where
         file_infos: &FxHashMap<u32, FileInfo>,
         page_groups: &FxHashMap<u32, PageGroup>,
         victims: &FxHashSet<u32>,
-    ) -> Result<(FxHashMap<u32, PageGroup>, FileInfo)> {
+    ) -> Result<Vec<(FxHashMap<u32, PageGroup>, FileInfo)>> {
         let start_at = Instant::now();
-        let mut builder = self
-            .page_files
-            .new_file_builder(
-                new_file_id,
-                self.options.compression_on_cold_compact,
-                self.options.page_checksum_type,
-            )
-            .await?;
+        let mut builder = self.new_file_builder(new_file_id).await?;
         let mut victims = victims.iter().cloned().collect::<Vec<_>>();
         victims.sort_unstable();
+
+        // When we include the page in a new segment that contains re-written pages from
+        // other segments, the value for up2 for the new segment is the average up2 for
+        // all pages written to it.
+        let up2 = victims
+            .iter()
+            .map(|id| file_infos.get(id).expect("Victim must exists").up2())
+            .sum::<u32>()
+            / (victims.len() as u32);
         let mut stats = CompactStats::default();
-        let mut up2_sum = 0;
+        let mut outputs = Vec::new();
         for &id in &victims {
             let info = file_infos.get(&id).expect("Victim must exists");
-            up2_sum += info.up2();
+            add_file_ranges(&mut builder, info);
             builder = self
-                .compact_file(builder, &mut stats, info, page_groups)
+                .compact_file(builder, &mut stats, info, page_groups, up2, &mut outputs)
                 .await?;
-            self.cleaned_files.insert(id);
             progress.track_file(info, page_groups);
         }
+        outputs.push(builder.finish(up2).await?);
+        self.cleaned_files.extend(victims.iter().cloned());
+        let new_files = outputs
+            .iter()
+            .map(|(_, info)| info.meta().file_id)
+            .collect::<Vec<_>>();
 
-        // When we include the page in a new segment that contains re-written pages from
-        // other segments, the value for up2 for the new segment is the average up2 for
-        // all pages written to it.
-        let up2 = up2_sum / (victims.len() as u32);
-        let (page_groups, file_info) = builder.finish(up2).await?;
-
-        let elapsed = start_at.elapsed().as_micros();
+        let elapsed = start_at.elapsed();
+        self.job_stats.compact_nanos.add(elapsed.as_nanos() as u64);
+        let elapsed = elapsed.as_micros();
         let CompactStats {
             num_active_pages,
             num_dealloc_pages,
+            num_dropped_pages,
             input_size,
             output_size,
         } = stats;
         self.job_stats.compact_input_bytes.add(input_size as u64);
         self.job_stats.compact_write_bytes.add(output_size as u64);
         let free_size = input_size.saturating_sub(output_size);
+        self.job_stats.reclaim_runs.inc();
+        self.job_stats.files_reclaimed.add(victims.len() as u64);
+        self.job_stats.reclaim_bytes_freed.add(free_size as u64);
+        self.job_stats
+            .reclaim_pages_moved
+            .add(num_active_pages as u64);
+        self.job_stats
+            .reclaim_pages_dropped
+            .add(num_dropped_pages as u64);
         let free_ratio = (free_size as f64) / (input_size as f64);
         info!(
-            "Compact files {victims:?} into a new file {new_file_id} \
+            "Compact files {victims:?} into new files {new_files:?} \
                     with up2 {up2}, relocate {num_active_pages} pages, \
+                    drop {num_dropped_pages} pages, \
                     dealloc {num_dealloc_pages} pages, \
                     relocate {output_size} bytes, \
                     free {free_size} bytes, free ratio {free_ratio:.4}, \
                     latest {elapsed} microseconds"
         );
 
-        Ok((page_groups, file_info))
+        Ok(outputs)
     }
 
+    async fn new_file_builder(&self, file_id: u32) -> Result<FileBuilder<'_, E>> {
+        self.page_files
+            .new_file_builder(
+                file_id,
+                self.options.compression_on_cold_compact,
+                self.options.page_checksum_type,
+            )
+            .await
+    }
+
+    /// Returns true if the file being built should be finished before more
+    /// page groups are added.
+    ///
+    /// A file always takes at least one page group, however large it is.
+    fn reaches_target_file_size(&self, builder: &FileBuilder<'_, E>) -> bool {
+        let Some(target_file_size) = self.options.target_file_size else {
+            return false;
+        };
+        builder.num_page_groups() > 0 && builder.size() >= target_file_size
+    }
+
+    // FIXME: reduce number of arguments
+    #[allow(clippy::too_many_arguments)]
     async fn compact_file<'a>(
-        &self,
+        &'a self,
         mut builder: FileBuilder<'a, E>,
         stats: &mut CompactStats,
         file_info: &FileInfo,
         page_groups: &FxHashMap<u32, PageGroup>,
+        up2: u32,
+        outputs: &mut Vec<(FxHashMap<u32, PageGroup>, FileInfo)>,
     ) -> Result<FileBuilder<'a, E>> {
         let file_id = file_info.meta().file_id;
         let existed_groups = file_info
@@ -321,10 +465,20 @@ where
         for id in target_groups {
             let Some(page_group) = page_groups.get(&id) else {
                 debug!("Skip forwarding page group {id} of file {file_id}, because it is obsoleted");
+                stats.num_dropped_pages += file_meta.page_groups[&id].total_pages();
                 continue;
             };
 
             assert!(!page_group.is_empty());
+            if self.reaches_target_file_size(&builder) {
+                outputs.push(builder.finish(up2).await?);
+                let file_id = {
+                    let mut lock = self.manifest.lock().await;
+                    lock.next_file_id()
+                };
+                builder = self.new_file_builder(file_id).await?;
+                add_file_ranges(&mut builder, file_info);
+            }
             let mut group_builder = builder.add_page_group(id);
             self.compact_page_group(
                 &mut group_builder,
@@ -369,6 +523,9 @@ where
                 .await?;
             let page_id = *page_table.get(&page_addr).expect("Must exists");
             let page_ref = PageRef::new(page.as_slice());
+            if self.options.paranoid_checks {
+                check_page("reclaim", page_id, page_addr, page_ref);
+            }
             builder
                 .add_page(page_id, page_addr, page_ref.info(), &page)
                 .await?;
@@ -488,16 +645,93 @@ impl ReclaimProgress {
     }
 }
 
+impl ReclaimCycle {
+    fn new(reason: ReclaimReason) -> Self {
+        ReclaimCycle {
+            reason,
+            files_scored: 0,
+            picked_files: Vec::new(),
+            bytes_freed: 0,
+            score: None,
+        }
+    }
+
+    fn log(&self) {
+        #[cfg(feature = "tracing")]
+        tracing::info!(
+            target: "photondb::reclaim",
+            reason = ?self.reason,
+            files_scored = self.files_scored,
+            picked_files = ?self.picked_files,
+            bytes_freed = self.bytes_freed,
+            score = self.score,
+            "Reclaim cycle finished"
+        );
+        #[cfg(not(feature = "tracing"))]
+        debug!(
+            "Reclaim cycle finished: reason {:?}, scored {} files, picked files {:?}, freed {} bytes, score {:?}",
+            self.reason, self.files_scored, self.picked_files, self.bytes_freed, self.score
+        );
+    }
+}
+
+impl ReclaimPause {
+    /// Pauses reclaiming, the running reclaiming stops after the current file.
+    pub(crate) fn pause(&self) {
+        if self.count.fetch_add(1, Ordering::AcqRel) == 0 {
+            self.notify.notify_waiters();
+        }
+    }
+
+    /// Resumes reclaiming if it is not paused by others, does nothing if it
+    /// is not paused.
+    pub(crate) fn resume(&self) {
+        let resumed = self
+            .count
+            .fetch_update(Ordering::AcqRel, Ordering::Acquire, |count| {
+                count.checked_sub(1)
+            });
+        if resumed == Ok(1) {
+            self.notify.notify_waiters();
+        }
+    }
+
+    pub(crate) fn is_paused(&self) -> bool {
+        self.count.load(Ordering::Acquire) > 0
+    }
+
+    /// Waits until `cond` returns true for whether reclaiming is paused.
+    async fn wait_until(&self, cond: impl Fn(bool) -> bool) {
+        let notified = self.notify.notified();
+        futures::pin_mut!(notified);
+        loop {
+            // Make sure that no wake-up is lost.
+            notified.as_mut().enable();
+            if cond(self.is_paused()) {
+                return;
+            }
+            notified.as_mut().await;
+            notified.set(self.notify.notified());
+        }
+    }
+}
+
 impl CompactStats {
     fn collect(&mut self, page_group: &PageGroup) {
         self.num_active_pages += page_group.num_active_pages();
+        self.num_dropped_pages += page_group.meta().total_pages() - page_group.num_active_pages();
         self.input_size += page_group.meta().total_page_size();
         self.output_size += page_group.effective_size();
     }
 }
 
-/// Wait until the running reclaiming progress to finish.
-pub(crate) async fn wait_for_reclaiming(options: &Options, mut version: Arc<Version>) {
+/// Wait until the running reclaiming progress to finish, or reclaiming is
+/// paused.
+pub(crate) async fn wait_for_reclaiming(
+    options: &Options,
+    pause: &ReclaimPause,
+    mut version: Arc<Version>,
+) {
     if options.disable_space_reclaiming {
         return;
     }
@@ -506,7 +740,12 @@ pub(crate) async fn wait_for_reclaiming(options: &Options, mut version: Arc<Vers
         let progress = ReclaimProgress::new(options, &version, &FxHashSet::default());
         progress.trace_log();
         if progress.is_reclaimable() {
-            version.wait_for_reclaiming().await;
+            let reclaimed = version.wait_for_reclaiming();
+            let paused = pause.wait_until(|paused| paused);
+            futures::pin_mut!(reclaimed, paused);
+            if let Either::Right(_) = futures::future::select(reclaimed, paused).await {
+                return;
+            }
             if let Some(next_version) = version.try_next() {
                 version = next_version;
                 continue;
@@ -516,6 +755,30 @@ pub(crate) async fn wait_for_reclaiming(options: &Options, mut version: Arc<Vers
     }
 }
 
+/// Returns the files that the strategy picks for reclaiming in order, with the
+/// bytes freed by reclaiming each of them, without reclaiming any file.
+pub(crate) fn reclaim_dry_run(
+    strategy_builder: &dyn StrategyBuilder,
+    now: u32,
+    version: &Version,
+) -> Vec<(PickedFile, usize)> {
+    let mut strategy = strategy_builder.build(now);
+    let page_groups = version.page_groups();
+    let file_infos = version.file_infos();
+    for file in file_infos.values() {
+        strategy.collect_file(&FileSummary::from((page_groups, file)));
+    }
+    let mut picked = Vec::new();
+    while let Some(file) = strategy.apply() {
+        let file_size = file_infos
+            .get(&file.file_id)
+            .map_or(0, |info| info.meta().file_size);
+        let freed_size = file_size.saturating_sub(file.active_size);
+        picked.push((file, freed_size));
+    }
+    picked
+}
+
 fn compute_base_size(
     page_files: &FxHashMap<u32, PageGroup>,
     cleaned_files: &FxHashSet<u32>,
@@ -541,17 +804,59 @@ fn compute_used_space(
         .sum::<usize>() as u64
 }
 
-fn make_compact_version_edit(
-    file_info: &FileInfo,
+/// Applies the pages deallocated since the compaction started to the page
+/// groups of the new file, and drops the groups that have been released.
+///
+/// Readers switch from the victims to the new file when the version is
+/// installed, so the new groups must agree with the `current` ones.
+fn reconcile_page_groups(
+    compacted: FxHashMap<u32, PageGroup>,
+    current: &FxHashMap<u32, PageGroup>,
+) -> FxHashMap<u32, PageGroup> {
+    compacted
+        .into_iter()
+        .filter_map(|(group_id, mut page_group)| {
+            let current = current.get(&group_id)?;
+            for page_addr in page_group.iter() {
+                if current.get_page_handle(page_addr).is_none() {
+                    page_group.deactivate_page(page_addr);
+                }
+            }
+            (!page_group.is_empty()).then_some((group_id, page_group))
+        })
+        .collect()
+}
+
+/// Extends the key range and LSN range of the file being built to cover the
+/// file compacted into it.
+///
+/// If either range of the compacted file is unknown, so is that of the file
+/// being built.
+fn add_file_ranges<E: Env>(builder: &mut FileBuilder<'_, E>, info: &FileInfo) {
+    match info.key_range() {
+        Some(range) => builder.add_key_range(&range.min_key, &range.max_key),
+        None => builder.add_unknown_key_range(),
+    }
+    match info.lsn_range() {
+        Some(range) => builder.add_lsn_range(range.min_lsn, range.max_lsn),
+        None => builder.add_unknown_lsn_range(),
+    }
+}
+
+fn make_compact_version_edit<'a>(
+    new_files: impl Iterator<Item = &'a FileInfo>,
     obsoleted_files: &FxHashSet<u32>,
 ) -> VersionEdit {
     let deleted_files = obsoleted_files.iter().cloned().collect::<Vec<_>>();
-    let new_files = vec![NewFile::from(file_info)];
+    let new_files = new_files.map(NewFile::from).collect();
     VersionEdit {
         file_stream: Some(StreamEdit {
             new_files,
             deleted_files,
         }),
+        compression_dicts: Vec::new(),
+        table_id: None,
+        version: None,
     }
 }
 
@@ -567,7 +872,7 @@ mod tests {
         page::PageInfo,
         page_store::{
             page_file::Compression, version::DeltaVersion, ChecksumType,
-            MinDeclineRateStrategyBuilder,
+            MaxFreeSpaceStrategyBuilder, MinDeclineRateStrategyBuilder, SwappableStrategyBuilder,
         },
         util::shutdown::ShutdownNotifier,
     };
@@ -575,7 +880,7 @@ mod tests {
     async fn build_reclaim_ctx(dir: &Path) -> ReclaimCtx<Photon> {
         let notifier = ShutdownNotifier::new();
         let shutdown = notifier.subscribe();
-        let strategy_builder = Box::new(MinDeclineRateStrategyBuilder);
+        let strategy_builder = Box::new(MinDeclineRateStrategyBuilder::new(1, None));
         let options = Options {
             cache_capacity: 2 << 10,
             ..Default::default()
@@ -599,6 +904,8 @@ mod tests {
             version_owner,
             cleaned_files: HashSet::default(),
             job_stats: Arc::default(),
+            pause: Arc::default(),
+            running: Arc::default(),
         }
     }
 
@@ -629,6 +936,35 @@ mod tests {
         builder.finish(file_id).await.unwrap()
     }
 
+    #[photonio::test]
+    async fn add_file_ranges_with_unknown_ranges() {
+        let root = TempDir::new("add_file_ranges").unwrap();
+        let ctx = build_reclaim_ctx(root.path()).await;
+        let (_, known) = {
+            let mut builder = ctx.new_file_builder(1).await.unwrap();
+            builder.add_key_range(b"a", b"c");
+            builder.add_lsn_range(1, 2);
+            builder.finish(1).await.unwrap()
+        };
+        let (_, unknown) = build_file(&ctx.page_files, 2, FxHashMap::default()).await;
+        assert!(unknown.key_range().is_none());
+        assert!(unknown.lsn_range().is_none());
+
+        let mut builder = ctx.new_file_builder(3).await.unwrap();
+        add_file_ranges(&mut builder, &known);
+        let (_, info) = builder.finish(1).await.unwrap();
+        assert_eq!(info.key_range(), known.key_range());
+        assert_eq!(info.lsn_range(), known.lsn_range());
+
+        // The file takes data beyond any known range from the second one.
+        let mut builder = ctx.new_file_builder(4).await.unwrap();
+        add_file_ranges(&mut builder, &known);
+        add_file_ranges(&mut builder, &unknown);
+        let (_, info) = builder.finish(1).await.unwrap();
+        assert!(info.key_range().is_none());
+        assert!(info.lsn_range().is_none());
+    }
+
     #[photonio::test]
     async fn files_compacting() {
         let root = TempDir::new("compact_files").unwrap();
@@ -656,10 +992,12 @@ mod tests {
         let victims = HashSet::from_iter(vec![m1, m2].into_iter());
         let version = ctx.version_owner.current();
         let mut progress = ReclaimProgress::new(&ctx.options, &version, &HashSet::default());
-        let (virtual_infos, m3_info) = ctx
+        let mut outputs = ctx
             .compact_files(&mut progress, m3, &map_files, &page_files, &victims)
             .await
             .unwrap();
+        assert_eq!(outputs.len(), 1);
+        let (virtual_infos, m3_info) = outputs.pop().unwrap();
 
         assert!(virtual_infos.contains_key(&f1));
         assert!(virtual_infos.contains_key(&f2));
@@ -686,6 +1024,351 @@ mod tests {
         assert!(base_size < used_size);
     }
 
+    #[photonio::test]
+    async fn files_compacting_with_target_file_size() {
+        let root = TempDir::new("compact_files_with_target_file_size").unwrap();
+        let root = root.into_path();
+
+        let mut ctx = build_reclaim_ctx(&root).await;
+
+        let mut page_groups = FxHashMap::default();
+        let mut file_infos = FxHashMap::default();
+        for id in 1..=8u32 {
+            let mut pages = FxHashMap::default();
+            let addrs = (1..=64)
+                .map(|i| (i as u64, pa(id, i * 32)))
+                .collect::<Vec<_>>();
+            pages.insert(id, addrs);
+            let (virtual_infos, info) = build_file(&ctx.page_files, id, pages).await;
+            page_groups.extend(virtual_infos.into_iter());
+            file_infos.insert(id, info);
+        }
+        let group_size = file_infos[&1].meta().file_size;
+        let target_file_size = group_size * 2;
+        ctx.options.target_file_size = Some(target_file_size);
+        {
+            let mut lock = ctx.manifest.lock().await;
+            lock.reset_next_file_id(10);
+        }
+
+        let victims = file_infos.keys().cloned().collect::<HashSet<_>>();
+        let version = ctx.version_owner.current();
+        let mut progress = ReclaimProgress::new(&ctx.options, &version, &HashSet::default());
+        let outputs = ctx
+            .compact_files(&mut progress, 9, &file_infos, &page_groups, &victims)
+            .await
+            .unwrap();
+
+        assert!(outputs.len() > 1);
+        let mut groups = HashSet::new();
+        for (virtual_infos, info) in &outputs {
+            // Each file is finished once it reaches the target, so it exceeds the
+            // target by at most one page group.
+            assert!(info.meta().file_size < target_file_size + 2 * group_size);
+            groups.extend(virtual_infos.keys().cloned());
+        }
+        for (_, info) in &outputs[..outputs.len() - 1] {
+            assert!(info.meta().file_size >= target_file_size);
+        }
+        assert_eq!(groups, victims);
+        let file_ids = outputs
+            .iter()
+            .map(|(_, info)| info.meta().file_id)
+            .collect::<HashSet<_>>();
+        assert_eq!(file_ids.len(), outputs.len());
+    }
+
+    #[photonio::test]
+    async fn files_compacting_with_large_page_group() {
+        let root = TempDir::new("compact_files_with_large_page_group").unwrap();
+        let root = root.into_path();
+
+        let mut ctx = build_reclaim_ctx(&root).await;
+        ctx.options.target_file_size = Some(1);
+
+        let (f1, m1, m2) = (1, 1, 2);
+        let mut pages = FxHashMap::default();
+        let addrs = (1..=64)
+            .map(|i| (i as u64, pa(f1, i * 32)))
+            .collect::<Vec<_>>();
+        pages.insert(f1, addrs);
+        let (page_groups, m1_info) = build_file(&ctx.page_files, m1, pages).await;
+
+        let mut file_infos = FxHashMap::default();
+        file_infos.insert(m1, m1_info);
+        let victims = HashSet::from_iter(vec![m1].into_iter());
+        let version = ctx.version_owner.current();
+        let mut progress = ReclaimProgress::new(&ctx.options, &version, &HashSet::default());
+        let outputs = ctx
+            .compact_files(&mut progress, m2, &file_infos, &page_groups, &victims)
+            .await
+            .unwrap();
+
+        // A page group larger than the target is not split.
+        assert_eq!(outputs.len(), 1);
+        let (virtual_infos, info) = &outputs[0];
+        assert_eq!(info.meta().file_id, m2);
+        assert!(virtual_infos.contains_key(&f1));
+    }
+
+    #[photonio::test]
+    async fn files_compacting_stats() {
+        let root = TempDir::new("compact_files_stats").unwrap();
+        let root = root.into_path();
+
+        let mut ctx = build_reclaim_ctx(&root).await;
+
+        let (f1, f2) = (1, 2);
+        let (m1, m2, m3) = (1, 2, 3);
+        let mut pages = FxHashMap::default();
+        pages.insert(f1, vec![(1, pa(f1, 16)), (2, pa(f1, 32)), (3, pa(f1, 64))]);
+        let (virtual_infos, m1_info) = build_file(&ctx.page_files, m1, pages).await;
+        let mut page_groups = virtual_infos;
+
+        let mut pages = FxHashMap::default();
+        pages.insert(f2, vec![(4, pa(f2, 16)), (5, pa(f2, 32)), (6, pa(f2, 64))]);
+        let (virtual_infos, m2_info) = build_file(&ctx.page_files, m2, pages).await;
+        page_groups.extend(virtual_infos.into_iter());
+
+        // Make some space of both files free.
+        assert!(page_groups
+            .get_mut(&f1)
+            .unwrap()
+            .deactivate_page(pa(f1, 16)));
+        assert!(page_groups
+            .get_mut(&f2)
+            .unwrap()
+            .deactivate_page(pa(f2, 32)));
+        assert!(page_groups
+            .get_mut(&f2)
+            .unwrap()
+            .deactivate_page(pa(f2, 64)));
+        let free_size = page_groups
+            .values()
+            .map(|g| g.meta().total_page_size() - g.effective_size())
+            .sum::<usize>() as u64;
+        assert!(free_size > 0);
+
+        let mut file_infos = FxHashMap::default();
+        file_infos.insert(m1, m1_info);
+        file_infos.insert(m2, m2_info);
+        let victims = HashSet::from_iter(vec![m1, m2].into_iter());
+        let version = ctx.version_owner.current();
+        let mut progress = ReclaimProgress::new(&ctx.options, &version, &HashSet::default());
+        ctx.compact_files(&mut progress, m3, &file_infos, &page_groups, &victims)
+            .await
+            .unwrap();
+
+        let stats = ctx.job_stats.snapshot();
+        assert_eq!(stats.reclaim_runs, 1);
+        assert_eq!(stats.files_reclaimed, 2);
+        assert_eq!(stats.reclaim_bytes_freed, free_size);
+        assert_eq!(stats.reclaim_pages_moved, 3);
+        assert_eq!(stats.reclaim_pages_dropped, 3);
+        assert!((stats.reclaim_efficiency() - 0.5).abs() < f64::EPSILON);
+    }
+
+    #[photonio::test]
+    async fn swap_reclaim_strategy() {
+        let root = TempDir::new("swap_reclaim_strategy").unwrap();
+        let root = root.into_path();
+
+        let mut ctx = build_reclaim_ctx(&root).await;
+        let strategy_builder =
+            SwappableStrategyBuilder::new(Arc::new(MinDeclineRateStrategyBuilder::new(1, None)));
+        ctx.strategy_builder = Box::new(strategy_builder.clone());
+
+        let (f1, f2) = (1, 2);
+        let (m1, m2) = (1, 2);
+        let mut pages = FxHashMap::default();
+        pages.insert(f1, vec![(1, pa(f1, 16)), (2, pa(f1, 32)), (3, pa(f1, 64))]);
+        let (virtual_infos, m1_info) = build_file(&ctx.page_files, m1, pages).await;
+        let mut page_groups = virtual_infos;
+
+        let mut pages = FxHashMap::default();
+        pages.insert(f2, vec![(4, pa(f2, 16)), (5, pa(f2, 32)), (6, pa(f2, 64))]);
+        let (virtual_infos, m2_info) = build_file(&ctx.page_files, m2, pages).await;
+        page_groups.extend(virtual_infos.into_iter());
+
+        // The second file has more free space.
+        let f1_group = page_groups.get_mut(&f1).unwrap();
+        assert!(f1_group.deactivate_page(pa(f1, 16)));
+        let f2_group = page_groups.get_mut(&f2).unwrap();
+        assert!(f2_group.deactivate_page(pa(f2, 16)));
+        assert!(f2_group.deactivate_page(pa(f2, 32)));
+
+        let mut file_infos = FxHashMap::default();
+        file_infos.insert(m1, m1_info);
+        file_infos.insert(m2, m2_info);
+        let delta = DeltaVersion {
+            reason: VersionUpdateReason::Flush,
+            page_groups,
+            file_infos,
+            ..Default::default()
+        };
+        // No concurrent operations.
+        unsafe { ctx.version_owner.install(delta) };
+        let version = ctx.version_owner.current();
+
+        strategy_builder.set(Arc::new(MaxFreeSpaceStrategyBuilder::new(1)));
+        let mut strategy = ctx.build_strategy(10, &version, &HashSet::default());
+        assert_eq!(strategy.apply().map(|f| f.file_id), Some(m2));
+        assert!(strategy.apply().is_none());
+    }
+
+    #[photonio::test]
+    async fn reclaim_dry_run_order() {
+        let root = TempDir::new("reclaim_dry_run_order").unwrap();
+        let root = root.into_path();
+
+        let mut ctx = build_reclaim_ctx(&root).await;
+        let mut page_groups = FxHashMap::default();
+        let mut file_infos = FxHashMap::default();
+        for id in 1..=4u32 {
+            let mut pages = FxHashMap::default();
+            let addrs = (1..=4)
+                .map(|i| (i as u64, pa(id, i * 16)))
+                .collect::<Vec<_>>();
+            pages.insert(id, addrs);
+            let (virtual_infos, info) = build_file(&ctx.page_files, id, pages).await;
+            page_groups.extend(virtual_infos.into_iter());
+            file_infos.insert(id, info);
+            // Make some space of the file free, the more the larger id.
+            let group = page_groups.get_mut(&id).unwrap();
+            for i in 1..id {
+                assert!(group.deactivate_page(pa(id, i * 16)));
+            }
+        }
+        let delta = DeltaVersion {
+            reason: VersionUpdateReason::Flush,
+            page_groups,
+            file_infos,
+            ..Default::default()
+        };
+        // No concurrent operations.
+        unsafe { ctx.version_owner.install(delta) };
+        let version = ctx.version_owner.current();
+
+        let picked = reclaim_dry_run(ctx.strategy_builder.as_ref(), 10, &version);
+        assert_eq!(picked.len(), 3);
+        let mut strategy = ctx.build_strategy(10, &version, &HashSet::default());
+        for (file, freed_size) in picked {
+            assert_eq!(strategy.apply(), Some(file));
+            let info = version.file_infos().get(&file.file_id).unwrap();
+            assert_eq!(freed_size, info.meta().file_size - file.active_size);
+        }
+        assert!(strategy.apply().is_none());
+    }
+
+    #[cfg(feature = "tracing")]
+    #[photonio::test]
+    async fn log_reclaim_cycle() {
+        use std::{fmt, sync::Mutex};
+
+        use tracing::{
+            field::{Field, Visit},
+            span, Event, Metadata, Subscriber,
+        };
+
+        #[derive(Default)]
+        struct Fields(FxHashMap<&'static str, String>);
+
+        impl Visit for Fields {
+            fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+                self.0.insert(field.name(), format!("{value:?}"));
+            }
+        }
+
+        /// Captures the fields of reclaim cycle events.
+        #[derive(Clone, Default)]
+        struct Capture(Arc<Mutex<Vec<Fields>>>);
+
+        impl Subscriber for Capture {
+            fn enabled(&self, _: &Metadata<'_>) -> bool {
+                true
+            }
+
+            fn new_span(&self, _: &span::Attributes<'_>) -> span::Id {
+                span::Id::from_u64(1)
+            }
+
+            fn record(&self, _: &span::Id, _: &span::Record<'_>) {}
+
+            fn record_follows_from(&self, _: &span::Id, _: &span::Id) {}
+
+            fn event(&self, event: &Event<'_>) {
+                if event.metadata().target() == "photondb::reclaim" {
+                    let mut fields = Fields::default();
+                    event.record(&mut fields);
+                    self.0.lock().unwrap().push(fields);
+                }
+            }
+
+            fn enter(&self, _: &span::Id) {}
+
+            fn exit(&self, _: &span::Id) {}
+        }
+
+        let capture = Capture::default();
+        tracing::subscriber::set_global_default(capture.clone()).unwrap();
+
+        let root = TempDir::new("log_reclaim_cycle").unwrap();
+        let root = root.into_path();
+
+        let mut ctx = build_reclaim_ctx(&root).await;
+        ctx.strategy_builder = Box::new(MaxFreeSpaceStrategyBuilder::new(1));
+        ctx.options.file_base_size = 1;
+        ctx.options.max_space_amplification_percent = 1;
+
+        let (f1, f2) = (1, 2);
+        let (m1, m2, m3) = (1, 2, 3);
+        {
+            let mut lock = ctx.manifest.lock().await;
+            lock.reset_next_file_id(m3);
+        }
+        let mut pages = FxHashMap::default();
+        pages.insert(f1, vec![(1, pa(f1, 16)), (2, pa(f1, 32)), (3, pa(f1, 64))]);
+        let (virtual_infos, m1_info) = build_file(&ctx.page_files, m1, pages).await;
+        let mut page_groups = virtual_infos;
+
+        let mut pages = FxHashMap::default();
+        pages.insert(f2, vec![(4, pa(f2, 16)), (5, pa(f2, 32)), (6, pa(f2, 64))]);
+        let (virtual_infos, m2_info) = build_file(&ctx.page_files, m2, pages).await;
+        page_groups.extend(virtual_infos.into_iter());
+
+        // The second file has more free space, so it is picked.
+        let f1_group = page_groups.get_mut(&f1).unwrap();
+        assert!(f1_group.deactivate_page(pa(f1, 16)));
+        let f2_group = page_groups.get_mut(&f2).unwrap();
+        assert!(f2_group.deactivate_page(pa(f2, 16)));
+        assert!(f2_group.deactivate_page(pa(f2, 32)));
+        let free_size = f2_group.meta().total_page_size() - f2_group.effective_size();
+
+        let mut file_infos = FxHashMap::default();
+        file_infos.insert(m1, m1_info);
+        file_infos.insert(m2, m2_info);
+        let delta = DeltaVersion {
+            reason: VersionUpdateReason::Flush,
+            page_groups,
+            file_infos,
+            ..Default::default()
+        };
+        // No concurrent operations.
+        unsafe { ctx.version_owner.install(delta) };
+        let version = ctx.version_owner.current();
+        ctx.reclaim(&version).await;
+
+        // Other tests may log reclaim cycles as well.
+        let cycles = capture.0.lock().unwrap();
+        let fields = cycles
+            .iter()
+            .map(|fields| &fields.0)
+            .find(|fields| fields["files_scored"] == "2" && fields["picked_files"] == "[2]")
+            .unwrap();
+        assert_eq!(fields["bytes_freed"], free_size.to_string());
+        assert_eq!(fields["score"], format!("{:?}", free_size as f64));
+    }
+
     #[photonio::test]
     async fn files_reclaiming() {
         let root = TempDir::new("map_files_reclaiming").unwrap();
@@ -0,0 +1,81 @@
+use std::{
+    sync::{Arc, Condvar, Mutex},
+    thread,
+    time::Duration,
+};
+
+use log::debug;
+
+use crate::page_store::StoreStats;
+
+/// A thread that logs the statistics of a page store periodically.
+///
+/// The thread is stopped when the logger is stopped or dropped.
+pub(crate) struct StatsLogger {
+    stopped: Arc<(Mutex<bool>, Condvar)>,
+    handle: Option<thread::JoinHandle<()>>,
+}
+
+impl StatsLogger {
+    /// Spawns a thread that logs the stats returned by `collect` every
+    /// `interval`.
+    pub(crate) fn spawn<F>(interval: Duration, collect: F) -> Self
+    where
+        F: Fn() -> StoreStats + Send + 'static,
+    {
+        let stopped = Arc::new((Mutex::new(false), Condvar::new()));
+        let handle = {
+            let stopped = stopped.clone();
+            thread::Builder::new()
+                .name("photondb-stats".into())
+                .spawn(move || run(interval, collect, &stopped))
+                .expect("Failed to spawn the stats logger")
+        };
+        StatsLogger {
+            stopped,
+            handle: Some(handle),
+        }
+    }
+
+    /// Stops the thread and waits for it to exit.
+    pub(crate) fn stop(&mut self) {
+        let (lock, cvar) = &*self.stopped;
+        *lock.lock().expect("Poisoned") = true;
+        cvar.notify_all();
+        if let Some(handle) = self.handle.take() {
+            handle.join().expect("The stats logger panicked");
+        }
+    }
+}
+
+impl Drop for StatsLogger {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
+
+fn run<F>(interval: Duration, collect: F, stopped: &(Mutex<bool>, Condvar))
+where
+    F: Fn() -> StoreStats,
+{
+    let (lock, cvar) = stopped;
+    let mut last = collect();
+    loop {
+        let (stopped, _) = cvar
+            .wait_timeout_while(lock.lock().expect("Poisoned"), interval, |stopped| {
+                !*stopped
+            })
+            .expect("Poisoned");
+        if *stopped {
+            break;
+        }
+        drop(stopped);
+
+        let stats = collect();
+        debug!(
+            "Page store stats:\n{stats}Changes in the last {interval:?}: {}",
+            stats.delta(&last)
+        );
+        last = stats;
+    }
+}
@@ -0,0 +1,133 @@
+use std::{
+    path::PathBuf,
+    sync::{Arc, Mutex},
+};
+
+use rustc_hash::FxHashMap;
+
+/// A page file that is referenced by the current version of a table.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct LiveFile {
+    /// The id of the file.
+    pub file_id: u32,
+    /// The path of the file.
+    pub path: PathBuf,
+    /// The size of the file in bytes.
+    pub size: u64,
+    /// The smallest LSN of entries in the file.
+    ///
+    /// It is 0 if the file has no entries, or was written before LSNs of
+    /// files were recorded.
+    pub min_lsn: u64,
+    /// The largest LSN of entries in the file.
+    ///
+    /// It is 0 if the file has no entries, or was written before LSNs of
+    /// files were recorded.
+    pub max_lsn: u64,
+}
+
+/// A guard that keeps a set of live files on disk.
+///
+/// Files that become obsolete while they are pinned are not removed until the
+/// guard is dropped. They are removed at the next version cleanup after that,
+/// or when the table is reopened.
+pub struct LiveFilesGuard {
+    pins: Arc<FilePins>,
+    files: Vec<LiveFile>,
+}
+
+impl LiveFilesGuard {
+    pub(crate) fn new(pins: Arc<FilePins>, files: Vec<LiveFile>) -> Self {
+        LiveFilesGuard { pins, files }
+    }
+
+    /// Returns the pinned files.
+    pub fn files(&self) -> &[LiveFile] {
+        &self.files
+    }
+}
+
+impl Drop for LiveFilesGuard {
+    fn drop(&mut self) {
+        let file_ids = self.files.iter().map(|f| f.file_id).collect::<Vec<_>>();
+        self.pins.unpin(&file_ids);
+    }
+}
+
+impl std::fmt::Debug for LiveFilesGuard {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("LiveFilesGuard")
+            .field("files", &self.files)
+            .finish()
+    }
+}
+
+/// Records the files that must not be removed.
+#[derive(Default)]
+pub(crate) struct FilePins {
+    inner: Mutex<FilePinsInner>,
+}
+
+#[derive(Default)]
+struct FilePinsInner {
+    /// The number of pins of each pinned file.
+    pins: FxHashMap<u32, usize>,
+    /// The obsoleted files whose removal is deferred until they are unpinned.
+    deferred: Vec<u32>,
+}
+
+impl FilePins {
+    pub(crate) fn pin(&self, file_ids: &[u32]) {
+        let mut inner = self.inner.lock().expect("Poisoned");
+        for &id in file_ids {
+            *inner.pins.entry(id).or_default() += 1;
+        }
+    }
+
+    pub(crate) fn unpin(&self, file_ids: &[u32]) {
+        let mut inner = self.inner.lock().expect("Poisoned");
+        for id in file_ids {
+            if let Some(count) = inner.pins.get_mut(id) {
+                *count -= 1;
+                if *count == 0 {
+                    inner.pins.remove(id);
+                }
+            }
+        }
+    }
+
+    /// Returns the files that can be removed now, among the obsoleted files
+    /// and the files deferred before.
+    ///
+    /// Pinned files are deferred until they are unpinned.
+    pub(crate) fn take_removable(&self, obsoleted_files: Vec<u32>) -> Vec<u32> {
+        let mut inner = self.inner.lock().expect("Poisoned");
+        let inner = &mut *inner;
+        inner.deferred.extend(obsoleted_files);
+        let (deferred, removable): (Vec<_>, Vec<_>) = inner
+            .deferred
+            .drain(..)
+            .partition(|id| inner.pins.contains_key(id));
+        inner.deferred = deferred;
+        removable
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn file_pins_defer_removal() {
+        let pins = FilePins::default();
+        pins.pin(&[1, 2]);
+        pins.pin(&[2]);
+        assert_eq!(pins.take_removable(vec![1, 2, 3]), vec![3]);
+
+        pins.unpin(&[1, 2]);
+        assert_eq!(pins.take_removable(vec![]), vec![1]);
+        pins.unpin(&[2]);
+        assert_eq!(pins.take_removable(vec![4]), vec![2, 4]);
+        assert!(pins.take_removable(vec![]).is_empty());
+    }
+}
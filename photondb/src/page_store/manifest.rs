@@ -1,6 +1,7 @@
 use std::{io::ErrorKind, path::PathBuf};
 
 use prost::Message;
+use uuid::Uuid;
 
 use super::{meta::VersionEdit, Error};
 use crate::{env::*, page_store::Result};
@@ -19,6 +20,14 @@ pub(crate) struct Manifest<E: Env> {
 
     next_file_id: u32,
 
+    /// The UUID of the table, recorded in the first edit of every manifest
+    /// file.
+    table_id: Option<Uuid>,
+
+    /// The version of the last edit, which is increased by every edit and
+    /// kept across manifest files.
+    version: usize,
+
     current_file_num: Option<u32>,
     current_writer: Option<ManifestWriter<E::SequentialWriter>>,
 }
@@ -32,23 +41,38 @@ impl<E: Env> Manifest<E> {
     // Open manifest in specified folder.
     // it will reopen manifest by find CURRENT and do some cleanup.
     pub(crate) async fn open(env: E, base: impl Into<PathBuf>) -> Result<Self> {
-        let base = base.into();
+        let mut manifest = Self::new(env, base.into());
+        manifest.create_base_dir_if_not_exist().await?;
+        manifest.current_file_num = manifest.load_current().await?;
+        manifest.open_base_dir().await?;
+        manifest.cleanup_obsolete_files().await?;
+
+        Ok(manifest)
+    }
+
+    /// Opens the manifest in the folder without modifying the folder.
+    ///
+    /// Unlike [`Manifest::open`], the folder is not created and obsolete files
+    /// are not removed. No version edit can be recorded to the returned
+    /// manifest.
+    pub(crate) async fn open_read_only(env: E, base: impl Into<PathBuf>) -> Result<Self> {
+        let mut manifest = Self::new(env, base.into());
+        manifest.current_file_num = manifest.load_current().await?;
+        Ok(manifest)
+    }
 
-        let mut manifest = Self {
+    fn new(env: E, base: PathBuf) -> Self {
+        Self {
             env,
             base,
             base_dir: None,
             max_file_size: MAX_MANIFEST_SIZE,
             next_file_id: 0,
+            table_id: None,
+            version: 0,
             current_file_num: Default::default(),
             current_writer: None,
-        };
-        manifest.create_base_dir_if_not_exist().await?;
-        manifest.current_file_num = manifest.load_current().await?;
-        manifest.open_base_dir().await?;
-        manifest.cleanup_obsolete_files().await?;
-
-        Ok(manifest)
+        }
     }
 
     async fn create_base_dir_if_not_exist(&self) -> Result<()> {
@@ -74,6 +98,26 @@ impl<E: Env> Manifest<E> {
         self.next_file_id = next_id;
     }
 
+    pub(crate) fn table_id(&self) -> Option<Uuid> {
+        self.table_id
+    }
+
+    pub(crate) fn set_table_id(&mut self, table_id: Uuid) {
+        self.table_id = Some(table_id);
+    }
+
+    pub(super) fn reset_version(&mut self, version: usize) {
+        self.version = version;
+    }
+
+    /// Returns the version of the last edit.
+    ///
+    /// Every edit advances the version, including the snapshot recorded as the
+    /// first edit of a manifest file when the file is rolled.
+    pub(crate) fn version(&self) -> usize {
+        self.version
+    }
+
     pub(crate) fn next_file_id(&mut self) -> u32 {
         let id = self.next_file_id;
         self.next_file_id += 1;
@@ -88,6 +132,10 @@ impl<E: Env> Manifest<E> {
     // it will rolling file when the file size over `max_file_size`.
     // so it need pass-in a `version_snapshot` to get current snapshot when it
     // rolling.
+    //
+    // If it returns an error, the edit is not persisted. The writer is dropped,
+    // so the next edit rolls a new file instead of appending after a torn
+    // record, and a torn record at the end of a file is ignored on recovery.
     pub(crate) async fn record_version_edit(
         &mut self,
         ve: VersionEdit,
@@ -114,19 +162,23 @@ impl<E: Env> Manifest<E> {
         };
 
         let mut current = current.unwrap();
-        let written = if rolled_path.is_some() {
-            // TODO: remove new created file when write fail.
-            let base_snapshot = version_snapshot();
-            let base_written = VersionEditEncoder(base_snapshot)
-                .encode(&mut current.current_writer)
-                .await?;
-            match VersionEditEncoder(ve)
-                .encode(&mut current.current_writer)
-                .await
-            {
-                Ok(record_written) => base_written + record_written,
+        let written = if let Some(rolled_path) = &rolled_path {
+            let mut base_snapshot = version_snapshot();
+            base_snapshot.table_id = self.table_id.map(|id| id.as_bytes().to_vec());
+            base_snapshot.version = Some(self.version as u64 + 1);
+            let written = async {
+                let base_written = VersionEditEncoder(base_snapshot)
+                    .encode(&mut current.current_writer)
+                    .await?;
+                let record_written = VersionEditEncoder(ve)
+                    .encode(&mut current.current_writer)
+                    .await?;
+                Ok::<_, Error>(base_written + record_written)
+            };
+            match written.await {
+                Ok(written) => written,
                 Err(err) => {
-                    let _ = self.env.remove_file(rolled_path.as_ref().unwrap()).await;
+                    let _ = self.env.remove_file(rolled_path).await;
                     return Err(err);
                 }
             }
@@ -142,7 +194,10 @@ impl<E: Env> Manifest<E> {
                 .sync_all()
                 .await
                 .expect("sync new manifest file fail");
-            self.set_current(file_num).await?;
+            if let Err(err) = self.set_current(file_num).await {
+                let _ = self.env.remove_file(rolled_path.as_ref().unwrap()).await;
+                return Err(err);
+            }
             // TODO: notify cleaner previous manifest + size, so it can be delete when need.
             self.current_file_num = Some(file_num);
         } else {
@@ -154,6 +209,11 @@ impl<E: Env> Manifest<E> {
         }
 
         current.current_file_size += written;
+        if rolled_path.is_some() {
+            self.version += 2;
+        } else {
+            self.version += 1;
+        }
 
         self.current_writer = Some(current);
 
@@ -179,6 +239,18 @@ impl<E: Env> Manifest<E> {
         })
     }
 
+    /// Returns the version of the first edit in the versions listed by
+    /// [`Manifest::list_versions`].
+    ///
+    /// Manifest files written before versions were recorded number their edits
+    /// from 1.
+    pub(crate) fn first_version(versions: &[VersionEdit]) -> usize {
+        versions
+            .first()
+            .and_then(|edit| edit.version)
+            .map_or(1, |version| version as usize)
+    }
+
     async fn load_current(&self) -> Result<Option<u32 /* file_num */>> {
         let curr_file_reader = match self
             .env
@@ -330,9 +402,16 @@ impl<R: PositionalReader> VersionEditDecoder<R> {
         offset += core::mem::size_of::<u64>() as u64;
         let ve = {
             let mut ve_bytes = vec![0u8; len as usize];
-            self.reader
+            match self
+                .reader
                 .read_exact_at(&mut ve_bytes, offset as u64)
-                .await?;
+                .await
+            {
+                Ok(_) => {}
+                // A torn record at the end of the file is not persisted.
+                Err(err) if err.kind() == ErrorKind::UnexpectedEof => return Ok(None),
+                e @ Err(_) => e?,
+            };
             VersionEdit::decode(ve_bytes.as_slice()).map_err(|_| Error::Corrupted)?
         };
         self.offset = offset + len;
@@ -368,6 +447,7 @@ mod tests {
                             new_files: new_files(vec![2, 3]),
                             deleted_files: vec![1],
                         }),
+                        ..Default::default()
                     },
                     version_snapshot,
                 )
@@ -381,6 +461,7 @@ mod tests {
                             new_files: new_files(vec![2, 3]),
                             deleted_files: vec![1],
                         }),
+                        ..Default::default()
                     },
                     version_snapshot,
                 )
@@ -393,6 +474,7 @@ mod tests {
                             new_files: new_files(vec![2, 3]),
                             deleted_files: vec![1],
                         }),
+                        ..Default::default()
                     },
                     version_snapshot,
                 )
@@ -411,6 +493,19 @@ mod tests {
                 .count();
             assert_eq!(files, 5); // 3 data + 1 current + 1 tmp
         }
+        {
+            let manifest = Manifest::open_read_only(env.clone(), base.as_ref())
+                .await
+                .unwrap();
+            assert_eq!(manifest.list_versions().await.unwrap().len(), 2);
+
+            let files = env
+                .read_dir(&base)
+                .expect("open base dir fail")
+                .into_iter()
+                .count();
+            assert_eq!(files, 5);
+        }
         {
             let _ = Manifest::open(env.clone(), base.as_ref()).await.unwrap();
 
@@ -434,6 +529,7 @@ mod tests {
                 new_files: vec![],
                 deleted_files: vec![],
             }),
+            ..Default::default()
         }));
 
         let ve_snapshot = || {
@@ -466,6 +562,7 @@ mod tests {
                     new_files: new_files(vec![0]),
                     deleted_files: vec![],
                 }),
+                ..Default::default()
             };
             manifest
                 .record_version_edit(ve.to_owned(), ve_snapshot)
@@ -481,6 +578,7 @@ mod tests {
                         new_files: new_files(vec![i]),
                         deleted_files: vec![r],
                     }),
+                    ..Default::default()
                 };
                 manifest
                     .record_version_edit(ve.to_owned(), ve_snapshot)
@@ -498,6 +596,7 @@ mod tests {
 
             let mut recover_ver = VersionEdit {
                 file_stream: Some(StreamEdit::default()),
+                ..Default::default()
             };
             for ve in versions {
                 let recover_ver = recover_ver.file_stream.as_mut().unwrap();
@@ -518,6 +617,7 @@ mod tests {
                     new_files: new_files(vec![1]),
                     deleted_files: vec![],
                 }),
+                ..Default::default()
             };
             manifest2
                 .record_version_edit(ve.to_owned(), ve_snapshot)
@@ -544,6 +644,7 @@ mod tests {
                             new_files: new_files(vec![2, 3]),
                             deleted_files: vec![1],
                         }),
+                        ..Default::default()
                     },
                     version_snapshot,
                 )
@@ -556,6 +657,7 @@ mod tests {
                             new_files: new_files(vec![4]),
                             deleted_files: vec![],
                         }),
+                        ..Default::default()
                     },
                     version_snapshot,
                 )
@@ -568,6 +670,7 @@ mod tests {
                             new_files: new_files(vec![5]),
                             deleted_files: vec![],
                         }),
+                        ..Default::default()
                     },
                     version_snapshot,
                 )
@@ -581,4 +684,77 @@ mod tests {
             assert_eq!(versions.len(), 4);
         }
     }
+
+    #[photonio::test]
+    fn test_version_across_files() {
+        let env = crate::env::Photon;
+
+        let base = tempdir::TempDir::new("curr_test_version").unwrap();
+
+        {
+            let mut manifest = Manifest::open(env.to_owned(), base.as_ref()).await.unwrap();
+            for _ in 0..2 {
+                manifest
+                    .record_version_edit(VersionEdit::default(), VersionEdit::default)
+                    .await
+                    .unwrap();
+            }
+            // The snapshot of the new file is version 1.
+            assert_eq!(manifest.version(), 3);
+        }
+
+        {
+            let mut manifest = Manifest::open(env, base.as_ref()).await.unwrap();
+            let versions = manifest.list_versions().await.unwrap();
+            assert_eq!(Manifest::<crate::env::Photon>::first_version(&versions), 1);
+            assert_eq!(versions.len(), 3);
+
+            // The versions of a rolled file follow the ones of the previous file.
+            manifest.reset_version(3);
+            manifest
+                .record_version_edit(VersionEdit::default(), VersionEdit::default)
+                .await
+                .unwrap();
+            assert_eq!(manifest.version(), 5);
+            let versions = manifest.list_versions().await.unwrap();
+            assert_eq!(Manifest::<crate::env::Photon>::first_version(&versions), 4);
+            assert_eq!(versions.len(), 2);
+        }
+    }
+
+    #[photonio::test]
+    fn test_ignore_torn_record() {
+        use std::io::Write;
+
+        let env = crate::env::Photon;
+
+        let base = tempdir::TempDir::new("curr_test_torn").unwrap();
+
+        {
+            let mut manifest = Manifest::open(env.to_owned(), base.as_ref()).await.unwrap();
+            manifest
+                .record_version_edit(
+                    VersionEdit {
+                        file_stream: Some(StreamEdit {
+                            new_files: new_files(vec![1]),
+                            deleted_files: vec![],
+                        }),
+                        ..Default::default()
+                    },
+                    VersionEdit::default,
+                )
+                .await
+                .unwrap();
+        }
+
+        // A record whose length is written but whose content is not.
+        let path = base.as_ref().join(format!("{}_{}", MANIFEST_FILE_NAME, 1));
+        let mut file = std::fs::OpenOptions::new().append(true).open(path).unwrap();
+        file.write_all(&64usize.to_le_bytes()).unwrap();
+        file.write_all(&[1, 2, 3]).unwrap();
+
+        let manifest = Manifest::open(env, base.as_ref()).await.unwrap();
+        let versions = manifest.list_versions().await.unwrap();
+        assert_eq!(versions.len(), 2);
+    }
 }
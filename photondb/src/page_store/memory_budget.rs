@@ -0,0 +1,37 @@
+use std::sync::{Arc, Mutex};
+
+use super::LRUCache;
+
+/// A memory budget shared by write buffers and the page cache.
+///
+/// The page cache is given whatever the write buffers don't use, so the sum of
+/// them never exceeds the budget.
+pub(crate) struct MemoryBudget {
+    total: usize,
+    page_cache: Arc<LRUCache<Vec<u8>>>,
+    // Serializes rebalances so that the latest write buffer usage wins.
+    lock: Mutex<()>,
+}
+
+impl MemoryBudget {
+    pub(crate) fn new(total: usize, page_cache: Arc<LRUCache<Vec<u8>>>) -> Self {
+        MemoryBudget {
+            total,
+            page_cache,
+            lock: Mutex::default(),
+        }
+    }
+
+    /// Gives the memory not used by write buffers to the page cache.
+    ///
+    /// `writebuf_usage` is called with the lock held to get the current usage
+    /// of write buffers.
+    pub(crate) fn rebalance<F>(&self, writebuf_usage: F)
+    where
+        F: FnOnce() -> usize,
+    {
+        let _guard = self.lock.lock().expect("Poisoned");
+        let cache_capacity = self.total.saturating_sub(writebuf_usage());
+        self.page_cache.set_capacity(cache_capacity);
+    }
+}
@@ -10,6 +10,18 @@ pub(crate) struct NewFile {
     pub up1: u32,
     #[prost(uint32, tag = "3")]
     pub up2: u32,
+    /// The smallest user key in the file, if known.
+    #[prost(bytes = "vec", optional, tag = "4")]
+    pub min_key: Option<Vec<u8>>,
+    /// The largest user key in the file, if known.
+    #[prost(bytes = "vec", optional, tag = "5")]
+    pub max_key: Option<Vec<u8>>,
+    /// The smallest LSN in the file, if known.
+    #[prost(uint64, optional, tag = "6")]
+    pub min_lsn: Option<u64>,
+    /// The largest LSN in the file, if known.
+    #[prost(uint64, optional, tag = "7")]
+    pub max_lsn: Option<u64>,
 }
 
 /// A sequence of ordered files forms a stream.
@@ -22,17 +34,60 @@ pub(crate) struct StreamEdit {
     pub deleted_files: Vec<u32>,
 }
 
+/// A dictionary used to compress pages.
+#[allow(unreachable_pub)]
+#[derive(Clone, PartialEq, Eq, Message)]
+pub(crate) struct CompressionDictEdit {
+    #[prost(uint32, tag = "1")]
+    pub id: u32,
+    #[prost(bytes = "vec", tag = "2")]
+    pub data: Vec<u8>,
+}
+
 #[allow(unreachable_pub)]
 #[derive(Clone, PartialEq, Message)]
 pub(crate) struct VersionEdit {
     /// A set of map files.
     #[prost(message, tag = "1")]
     pub file_stream: Option<StreamEdit>,
+    /// The compression dictionaries added, in the order they are added.
+    #[prost(message, repeated, tag = "2")]
+    pub compression_dicts: Vec<CompressionDictEdit>,
+    /// The UUID of the table.
+    ///
+    /// This is only recorded in the first edit of a manifest file.
+    #[prost(bytes = "vec", optional, tag = "3")]
+    pub table_id: Option<Vec<u8>>,
+    /// The manifest version of the edit.
+    ///
+    /// This is only recorded in the first edit of a manifest file, later
+    /// edits of the file follow it.
+    #[prost(uint64, optional, tag = "4")]
+    pub version: Option<u64>,
 }
 
 mod convert {
     use super::*;
-    use crate::page_store::FileInfo;
+    use crate::page_store::{CompressionDict, FileInfo, KeyRange, LsnRange};
+
+    impl NewFile {
+        pub(crate) fn key_range(&self) -> Option<KeyRange> {
+            match (&self.min_key, &self.max_key) {
+                (Some(min_key), Some(max_key)) => Some(KeyRange {
+                    min_key: min_key.clone(),
+                    max_key: max_key.clone(),
+                }),
+                _ => None,
+            }
+        }
+
+        pub(crate) fn lsn_range(&self) -> Option<LsnRange> {
+            match (self.min_lsn, self.max_lsn) {
+                (Some(min_lsn), Some(max_lsn)) => Some(LsnRange { min_lsn, max_lsn }),
+                _ => None,
+            }
+        }
+    }
 
     impl From<u32> for NewFile {
         fn from(file_id: u32) -> Self {
@@ -40,16 +95,35 @@ mod convert {
                 id: file_id,
                 up1: file_id,
                 up2: file_id,
+                min_key: None,
+                max_key: None,
+                min_lsn: None,
+                max_lsn: None,
             }
         }
     }
 
     impl From<&FileInfo> for NewFile {
         fn from(info: &FileInfo) -> Self {
+            let range = info.key_range();
+            let lsn_range = info.lsn_range();
             NewFile {
                 id: info.meta().file_id,
                 up1: info.up1(),
                 up2: info.up2(),
+                min_key: range.map(|r| r.min_key.clone()),
+                max_key: range.map(|r| r.max_key.clone()),
+                min_lsn: lsn_range.map(|r| r.min_lsn),
+                max_lsn: lsn_range.map(|r| r.max_lsn),
+            }
+        }
+    }
+
+    impl From<&CompressionDict> for CompressionDictEdit {
+        fn from(dict: &CompressionDict) -> Self {
+            CompressionDictEdit {
+                id: dict.id,
+                data: dict.data.clone(),
             }
         }
     }
@@ -67,6 +141,12 @@ mod tests {
                 new_files,
                 deleted_files: vec![1, 2, 3],
             }),
+            compression_dicts: vec![CompressionDictEdit {
+                id: 1,
+                data: vec![1, 2, 3],
+            }],
+            table_id: None,
+            version: Some(1),
         };
 
         let payload = edit.encode_to_vec();
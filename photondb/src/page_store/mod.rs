@@ -1,6 +1,9 @@
-use std::{fmt, mem, path::Path, sync::Arc};
+use std::{fmt, future::Future, mem, ops::Range, path::Path, sync::Arc, time::Duration};
 
-use crate::{env::Env, util::shutdown::ShutdownNotifier};
+use crate::{
+    env::Env,
+    util::shutdown::{Shutdown, ShutdownNotifier},
+};
 
 mod error;
 pub(crate) use error::{Error, Result};
@@ -8,6 +11,7 @@ pub(crate) use error::{Error, Result};
 mod page_txn;
 use futures::lock::Mutex;
 pub(crate) use page_txn::{CacheOption, Guard};
+use uuid::Uuid;
 
 mod page_table;
 use page_table::PageTable;
@@ -20,34 +24,64 @@ mod version;
 use version::{DeltaVersion, Version, VersionOwner, VersionUpdateReason};
 
 mod jobs;
-use jobs::{cleanup::CleanupCtx, flush::FlushCtx, reclaim::ReclaimCtx};
+use jobs::{cleanup::CleanupCtx, flush::FlushCtx, reclaim::ReclaimCtx, stats_log::StatsLogger};
 
 mod write_buffer;
 pub(crate) use write_buffer::{RecordRef, WriteBuffer};
 
 mod buffer_set;
+use buffer_set::BufferSet;
+
+mod memory_budget;
+use memory_budget::MemoryBudget;
 
 mod manifest;
 pub(crate) use manifest::Manifest;
 
 mod page_file;
-pub(crate) use page_file::{FileInfo, PageFiles, PageGroup};
+pub(crate) use page_file::{
+    train_dict, CompressionDict, CompressionDicts, FileInfo, KeyRange, LsnRange, PageFiles,
+    PageGroup, SharedCaches, StoreCaches, EMPTY_PAGES_RATE_WITHOUT_PAGES,
+};
+
+mod live_files;
+use live_files::FilePins;
+pub use live_files::{LiveFile, LiveFilesGuard};
 
 mod recover;
+
+mod checkpoint;
+mod page_heat;
+use page_heat::PageHeat;
+
 mod strategy;
-pub(crate) use strategy::{MinDeclineRateStrategyBuilder, StrategyBuilder};
+pub(crate) use strategy::{
+    AdaptiveStrategyBuilder, MaxFreeSpaceStrategyBuilder, SwappableStrategyBuilder,
+};
+pub use strategy::{
+    FileSummary, MinDeclineRateStrategyBuilder, PickedFile, ReclaimPickStrategy, ReclaimStrategy,
+    StrategyBuilder,
+};
 
 mod cache;
 #[allow(unused_imports)]
 pub(crate) use cache::{clock::ClockCache, lru::LRUCache, Cache, CacheEntry, CacheToken};
+pub use cache::{EvictCallback, EvictReason};
+
+mod hasher;
+pub use hasher::PageHasher;
 
 mod stats;
 pub use page_file::{ChecksumType, Compression};
-pub use stats::StoreStats;
+pub(crate) use stats::CacheStats;
+pub use stats::{DeltaChainHistogram, ReadSourceStats, SpaceReport, StatsDelta, StoreStats};
 
 use self::{
-    jobs::wait_for_reclaiming,
-    stats::{AtomicJobStats, AtomicWritebufStats},
+    jobs::{reclaim_dry_run, wait_for_reclaiming, ReclaimPause},
+    stats::{
+        AtomicDeltaChainHistogram, AtomicJobStats, AtomicReadSourceStats, AtomicWritebufStats,
+        FileStats, MemoryStats,
+    },
 };
 
 /// Options to configure a page store.
@@ -61,17 +95,81 @@ pub struct Options {
 
     /// The maxmum number of write buffers.
     ///
-    /// If there exists too many write buffers, writing will be stalled until at
-    /// leaset one write buffer is flushed.
+    /// When the active write buffer is full, it is sealed and flushed in the
+    /// background, while writes continue into a new buffer. Reads are served
+    /// from sealed buffers until they are flushed. If there exists too many
+    /// write buffers, writing will be stalled until at leaset one write buffer
+    /// is flushed.
+    ///
+    /// Write buffers take up to `max_write_buffers * write_buffer_capacity`
+    /// bytes of memory, which is counted in [`Options::memory_budget`].
     ///
     /// Default: 8
     pub max_write_buffers: usize,
 
+    /// The maximum number of sealed write buffers waiting to be flushed into
+    /// page files.
+    ///
+    /// Once the active write buffer is full and there are this many unflushed
+    /// buffers, writes stall until a flush finishes. This bounds the memory of
+    /// unflushed data below [`Options::max_write_buffers`] if flushes can't
+    /// keep up. If this is `None`, up to `max_write_buffers - 1` buffers can
+    /// wait for flush.
+    ///
+    /// Default: None
+    pub max_unflushed_files: Option<usize>,
+
+    /// If true, each write flushes the write buffer before it returns.
+    ///
+    /// Writes still go through the write buffer, but it is flushed to a page
+    /// file right away, so reads are served from page files. This doesn't
+    /// change the ordering or durability of writes, and is mainly useful to
+    /// measure the cost of flushes.
+    ///
+    /// Default: false
+    pub writebuf_disabled: bool,
+
     /// If true, use O_DIRECT to read/write page files.
     ///
+    /// This avoids caching pages in both the page cache and the OS page cache.
+    /// Reads and writes are aligned to the block size of page files.
+    ///
+    /// O_DIRECT is only supported on Linux. On other platforms, or if the file
+    /// system rejects O_DIRECT, page files fall back to buffered I/O.
+    ///
     /// Default: false
     pub use_direct_io: bool,
 
+    /// If true, page reads are served from memory mappings of page files
+    /// instead of the file readers.
+    ///
+    /// This saves a syscall per page read, and takes precedence over
+    /// [`Options::use_direct_io`] for page reads. It is only supported on
+    /// Unix.
+    ///
+    /// Default: false
+    pub mmap_reads: bool,
+
+    /// The number of times a failed read of page files is retried before the
+    /// error is returned.
+    ///
+    /// Retries back off exponentially from 1ms to 100ms. Errors that won't go
+    /// away, such as reading past the end of a file, are not retried, and
+    /// neither are corrupted pages. Reads from memory mappings are never
+    /// retried.
+    ///
+    /// Default: 0
+    pub read_retries: u32,
+
+    /// If true, pages written by flush and reclaim jobs are verified before
+    /// they are persisted, and the job panics with diagnostics if a page is
+    /// malformed (e.g. its entries are out of order).
+    ///
+    /// This is intended for development and fuzzing.
+    ///
+    /// Default: false
+    pub paranoid_checks: bool,
+
     /// If true, no space reclamation.
     ///
     /// Default: false
@@ -91,16 +189,74 @@ pub struct Options {
     /// Default: u64::MAX
     pub space_used_high: u64,
 
+    /// The minimum number of files retained during space reclaiming.
+    ///
+    /// Files are picked for reclaiming only if there are more files than this.
+    ///
+    /// Default: 1
+    pub min_files_retained: usize,
+
+    /// The range of user keys that is updated more frequently than others.
+    ///
+    /// If set, files overlapping this range are preferred during space
+    /// reclaiming.
+    ///
+    /// Default: None
+    pub hot_range: Option<Range<Vec<u8>>>,
+
+    /// A custom strategy to pick files for space reclaiming.
+    ///
+    /// If this is `None`, files are picked with
+    /// [`ReclaimStrategy::MinDeclineRate`]. Either way, the strategy can be
+    /// replaced at runtime with a [`ReclaimStrategy`].
+    ///
+    /// Default: None
+    pub reclaim_strategy: Option<Arc<dyn StrategyBuilder>>,
+
     /// Target file size for compaction.
     ///
     /// Default: 64MB
     pub file_base_size: usize,
 
+    /// The size at which compaction finishes an output file and starts a new
+    /// one.
+    ///
+    /// Unlike [`Options::file_base_size`], which decides how many files are
+    /// compacted together, this bounds the size of each file written. A
+    /// single page group is never split, so a file may exceed this size when
+    /// it holds one large page group. If this is `None`, all victims of a
+    /// compaction are written into one file.
+    ///
+    /// Default: None
+    pub target_file_size: Option<usize>,
+
     /// The capacity of the page read cache in bytes.
     ///
     /// Default: 8 Mib
     pub cache_capacity: usize,
 
+    /// The total memory shared by write buffers and the page read cache.
+    ///
+    /// If set, [`Options::cache_capacity`] is ignored and the page cache is
+    /// given whatever the write buffers don't use. So the page cache shrinks
+    /// as sealed write buffers accumulate under write-heavy load, and grows
+    /// back once they are flushed and released. The budget must be larger
+    /// than `max_write_buffers * write_buffer_capacity`.
+    ///
+    /// Default: None
+    pub memory_budget: Option<usize>,
+
+    /// The capacity of a separate cache for index pages in bytes.
+    ///
+    /// If set, inner pages of the tree are cached apart from leaf pages, so
+    /// that large scans over data pages can't evict the pages to navigate the
+    /// tree. The capacity is taken out of [`Options::cache_capacity`], or out
+    /// of [`Options::memory_budget`] if it is set, and the rest is left to data
+    /// pages. It is ignored if the table uses the caches shared by an engine.
+    ///
+    /// Default: None
+    pub index_cache_capacity: Option<usize>,
+
     /// The estimated average `charge` associated with cache entries.
     ///
     /// Default: 8 Kib
@@ -122,11 +278,36 @@ pub struct Options {
     ///   by not utilizing the full capacity.
     pub cache_estimated_entry_charge: usize,
 
+    /// The hash function to pick the shards of the page cache.
+    ///
+    /// It is ignored if the table uses the caches shared by an engine.
+    ///
+    /// Default: [`PageHasher::Fnv`]
+    pub hasher: PageHasher,
+
+    /// A callback invoked when a page is evicted from the page cache.
+    ///
+    /// This helps to observe cache churn, for example by logging or sampling
+    /// evictions. It is ignored if the table uses the caches shared by an
+    /// engine.
+    ///
+    /// Default: None
+    pub on_evict: Option<EvictCallback>,
+
     /// The capacity of file_reader cache.
     ///
     /// Default: 5000 file_readers.
     pub cache_file_reader_capacity: u64,
 
+    /// The maximum number of page files opened for reads at the same time.
+    ///
+    /// If more files are needed, the least recently used file reader is
+    /// closed before a new one is opened. This also caps
+    /// [`Options::cache_file_reader_capacity`]. A value of -1 means unlimited.
+    ///
+    /// Default: -1
+    pub max_open_files: i64,
+
     /// Whether report error when there is no enough memory for the page cache.
     ///
     /// Default: false
@@ -148,17 +329,72 @@ pub struct Options {
     /// Default: Zstd(Level3).
     pub compression_on_cold_compact: Compression,
 
+    /// Compress pages with a zstd dictionary if true.
+    ///
+    /// If there is no dictionary yet, one is trained in the background from
+    /// the values sampled on flush, and used to compress the later files with
+    /// [`Compression::ZSTD`]. Dictionaries are kept in the manifest, so files
+    /// compressed with any of them or without a dictionary stay readable.
+    ///
+    /// Default: false
+    pub enable_compression_dict: bool,
+
     /// ChecksumType for each page.
     ///
     /// Default: NONE.
     pub page_checksum_type: ChecksumType,
 
+    /// Verify pages served from the page cache if true.
+    ///
+    /// Pages read from page files are always verified with
+    /// [`Options::page_checksum_type`]. If this is enabled, a checksum is also
+    /// kept with each page in the page cache and verified on every cache hit,
+    /// which detects corruption of the in-memory copy at some CPU cost.
+    ///
+    /// Default: false
+    pub verify_checksums_on_cache_hit: bool,
+
     /// PhotonDB will flush all write buffers on DB close, if there are
     /// unpersisted data. The flush can be skip to speed up DB close, but
     /// unpersisted data WILL BE LOST.
     ///
     /// Default: false
     pub avoid_flush_during_shutdown: bool,
+
+    /// The interval to log [`StoreStats`] at the debug level.
+    ///
+    /// Each record contains the current stats and the changes since the
+    /// previous record. If this is zero, stats are not logged.
+    ///
+    /// Default: 0
+    pub stats_log_interval: Duration,
+}
+
+impl Options {
+    /// Returns the maximum number of write buffers, including the active one
+    /// and the unflushed ones.
+    pub(crate) fn max_sealed_buffers(&self) -> usize {
+        match self.max_unflushed_files {
+            Some(files) => self.max_write_buffers.min(files + 1),
+            None => self.max_write_buffers,
+        }
+    }
+
+    /// Returns the capacity of the cache for data pages, which is the page
+    /// cache capacity without [`Options::index_cache_capacity`].
+    pub fn data_cache_capacity(&self) -> usize {
+        let capacity = self.memory_budget.unwrap_or(self.cache_capacity);
+        capacity.saturating_sub(self.index_cache_capacity.unwrap_or(0))
+    }
+
+    /// Returns the capacity of the file reader cache, which is bounded by
+    /// [`Options::max_open_files`].
+    pub(crate) fn file_reader_capacity(&self) -> u64 {
+        match u64::try_from(self.max_open_files) {
+            Ok(max_open_files) => self.cache_file_reader_capacity.min(max_open_files),
+            Err(_) => self.cache_file_reader_capacity,
+        }
+    }
 }
 
 impl Default for Options {
@@ -166,20 +402,37 @@ impl Default for Options {
         Self {
             write_buffer_capacity: 128 << 20,
             max_write_buffers: 8,
+            max_unflushed_files: None,
+            writebuf_disabled: false,
             use_direct_io: false,
+            mmap_reads: false,
+            read_retries: 0,
+            paranoid_checks: false,
             disable_space_reclaiming: false,
             max_space_amplification_percent: 100,
             space_used_high: u64::MAX,
+            min_files_retained: 1,
+            hot_range: None,
+            reclaim_strategy: None,
             file_base_size: 64 << 20,
+            target_file_size: None,
             cache_capacity: 8 << 20,
+            memory_budget: None,
+            index_cache_capacity: None,
             cache_estimated_entry_charge: 8 << 10,
+            hasher: PageHasher::Fnv,
+            on_evict: None,
             cache_file_reader_capacity: 5000,
+            max_open_files: -1,
             cache_strict_capacity_limit: false,
             prepopulate_cache_on_flush: true,
             compression_on_flush: Compression::SNAPPY,
             compression_on_cold_compact: Compression::ZSTD,
+            enable_compression_dict: false,
             page_checksum_type: ChecksumType::NONE,
+            verify_checksums_on_cache_hit: false,
             avoid_flush_during_shutdown: false,
+            stats_log_interval: Duration::ZERO,
         }
     }
 }
@@ -210,7 +463,6 @@ impl Default for FlushOptions {
 
 pub(crate) struct PageStore<E: Env> {
     options: Options,
-    #[allow(unused)]
     env: E,
     table: PageTable,
 
@@ -220,9 +472,19 @@ pub(crate) struct PageStore<E: Env> {
 
     job_stats: Arc<AtomicJobStats>,
     writebuf_stats: Arc<AtomicWritebufStats>,
+    read_source_stats: Arc<AtomicReadSourceStats>,
+    delta_chain_hist: Arc<AtomicDeltaChainHistogram>,
+
+    strategy_builder: SwappableStrategyBuilder,
+    reclaim_pause: Arc<ReclaimPause>,
+    reclaim_running: Arc<Mutex<()>>,
+    page_heat: Arc<PageHeat>,
 
     jobs: Vec<E::JoinHandle<()>>,
     shutdown: ShutdownNotifier,
+    stats_logger: Option<StatsLogger>,
+    read_only: bool,
+    table_id: Uuid,
 }
 
 impl<E: Env> PageStore<E> {
@@ -230,15 +492,85 @@ impl<E: Env> PageStore<E> {
     where
         P: AsRef<Path>,
     {
-        let (next_page_file_id, manifest, table, page_files, delta) =
-            Self::recover(env.to_owned(), path, &options).await?;
+        Self::open_with_caches(env, path, options, None).await
+    }
 
-        let version = Version::new(
-            options.write_buffer_capacity,
+    /// Opens a page store that uses the caches shared with other page stores,
+    /// or its own caches if `caches` is `None`.
+    pub(crate) async fn open_with_caches<P>(
+        env: E,
+        path: P,
+        options: Options,
+        caches: Option<StoreCaches<E>>,
+    ) -> Result<Self>
+    where
+        P: AsRef<Path>,
+    {
+        Self::open_impl(env, path, options, caches, None).await
+    }
+
+    /// Opens a read-only page store as it was at the manifest version.
+    ///
+    /// Neither the manifest nor page files are modified, and no background
+    /// jobs are spawned.
+    /// Returns [`Error::MissingFile`] if a page file of the version has been
+    /// removed.
+    pub(crate) async fn open_at_version<P>(
+        env: E,
+        path: P,
+        options: Options,
+        version: usize,
+    ) -> Result<Self>
+    where
+        P: AsRef<Path>,
+    {
+        Self::open_impl(env, path, options, None, Some(version)).await
+    }
+
+    async fn open_impl<P>(
+        env: E,
+        path: P,
+        options: Options,
+        caches: Option<StoreCaches<E>>,
+        version: Option<usize>,
+    ) -> Result<Self>
+    where
+        P: AsRef<Path>,
+    {
+        let read_only = version.is_some();
+        let (next_page_file_id, mut manifest, table, page_files, delta) =
+            Self::recover(env.to_owned(), path, &options, caches, version).await?;
+
+        let mut buffer_set = BufferSet::new(
             next_page_file_id,
-            options.max_write_buffers,
-            delta,
+            options.write_buffer_capacity,
+            options.max_sealed_buffers(),
         );
+        if let Some(budget) = options.memory_budget {
+            let budget = budget.saturating_sub(options.index_cache_capacity.unwrap_or(0));
+            let memory_budget = MemoryBudget::new(budget, page_files.page_cache().clone());
+            buffer_set = buffer_set.with_memory_budget(memory_budget);
+        }
+        let version = Version::with_buffer_set(next_page_file_id, Arc::new(buffer_set), delta);
+
+        let table_id = match manifest.table_id() {
+            Some(table_id) => table_id,
+            None => {
+                // The table is new, or created before tables have ids.
+                let table_id = Uuid::new_v4();
+                manifest.set_table_id(table_id);
+                if !read_only {
+                    // The manifest is rolled on the first edit after opening, which
+                    // records the table id.
+                    manifest
+                        .record_version_edit(VersionEdit::default(), || {
+                            jobs::version_snapshot(&version, page_files.compression_dicts())
+                        })
+                        .await?;
+                }
+                table_id
+            }
+        };
 
         let version_owner = Arc::new(VersionOwner::new(version));
         let manifest = Arc::new(futures::lock::Mutex::new(manifest));
@@ -246,6 +578,15 @@ impl<E: Env> PageStore<E> {
         let shutdown = ShutdownNotifier::new();
         let job_stats = Arc::default();
         let writebuf_stats = Arc::default();
+        let read_source_stats = Arc::new(AtomicReadSourceStats {
+            read_retries: page_files.read_retry_count(),
+            ..Default::default()
+        });
+        let delta_chain_hist = Arc::default();
+        let strategy_builder = SwappableStrategyBuilder::new(match &options.reclaim_strategy {
+            Some(builder) => builder.clone(),
+            None => new_strategy_builder(&options, ReclaimStrategy::MinDeclineRate, &job_stats),
+        });
 
         let mut store = PageStore {
             options,
@@ -256,43 +597,66 @@ impl<E: Env> PageStore<E> {
             manifest,
             job_stats,
             writebuf_stats,
+            read_source_stats,
+            delta_chain_hist,
+            strategy_builder,
+            reclaim_pause: Arc::default(),
+            reclaim_running: Arc::default(),
+            page_heat: Arc::default(),
             jobs: Vec::new(),
             shutdown,
+            stats_logger: None,
+            read_only,
+            table_id,
         };
 
         // Spawn background jobs.
-        store.spawn_flush_job();
-        store.spawn_cleanup_job();
-        store.spawn_reclaim_job();
+        if !store.read_only {
+            store.spawn_flush_job();
+            store.spawn_cleanup_job();
+            store.spawn_reclaim_job();
+        }
+        store.spawn_stats_logger();
 
         Ok(store)
     }
 
     #[inline]
     pub(crate) fn guard(&self) -> Guard<E> {
-        Guard::new(
+        let mut guard = Guard::new(
             self.version(),
             self.table.clone(),
             self.page_files.clone(),
             self.writebuf_stats.clone(),
-        )
+            self.read_source_stats.clone(),
+            self.delta_chain_hist.clone(),
+        );
+        guard.set_page_heat(self.page_heat.clone());
+        if self.read_only {
+            guard.set_read_only();
+        }
+        guard
+    }
+
+    /// Returns the UUID of the table, which is kept across reopens and
+    /// checkpoints.
+    pub(crate) fn table_id(&self) -> Uuid {
+        self.table_id
+    }
+
+    /// Returns the version of the last manifest edit.
+    pub(crate) async fn manifest_version(&self) -> usize {
+        self.manifest.lock().await.version()
     }
 
     pub(crate) fn stats(&self) -> StoreStats {
-        let (page_cache, file_reader_cache) = self.page_files.stats();
-        let writebuf = self.writebuf_stats.snapshot();
-        let jobs = self.job_stats.snapshot();
-        let buffer_set = self.version().buffer_set.stats();
-        StoreStats {
-            page_cache,
-            file_reader_cache,
-            writebuf,
-            jobs,
-            buffer_set,
-        }
+        self.stats_collector().collect()
     }
 
     pub(crate) async fn close(mut self) {
+        if let Some(mut logger) = self.stats_logger.take() {
+            logger.stop();
+        }
         self.shutdown.terminate();
         let jobs = mem::take(&mut self.jobs);
         for job in jobs {
@@ -300,16 +664,180 @@ impl<E: Env> PageStore<E> {
         }
     }
 
+    /// Returns the number of page files and map files in the current version.
+    pub(crate) fn file_count(&self) -> (usize, usize) {
+        let files = file_stats(&self.version());
+        (files.page_files, files.map_files)
+    }
+
+    /// Returns the space used by files in the current version.
+    pub(crate) fn space_report(&self) -> SpaceReport {
+        space_report(&self.version())
+    }
+
+    /// Returns true if writes stall once the active write buffer is full.
+    pub(crate) fn is_write_stalled(&self) -> bool {
+        self.version().buffer_set.is_stalled()
+    }
+
+    /// Returns the heat of files in the current version, in ascending order
+    /// of file ids.
+    pub(crate) fn page_heat(&self) -> Vec<(u32, f64)> {
+        let mut file_ids = self
+            .version()
+            .file_infos()
+            .keys()
+            .cloned()
+            .collect::<Vec<_>>();
+        file_ids.sort_unstable();
+        self.page_heat.heat(&file_ids)
+    }
+
+    /// Returns the files in the current version.
+    pub(crate) fn live_files(&self) -> Vec<LiveFile> {
+        self.collect_live_files(&self.version())
+    }
+
+    /// Returns the files in the current version, and pins them until the
+    /// returned guard is dropped.
+    pub(crate) fn pin_live_files(&self) -> LiveFilesGuard {
+        // The files of a version are not removed before the version is released,
+        // so they are pinned while the version is held.
+        let version = self.version();
+        let files = self.collect_live_files(&version);
+        let file_ids = files.iter().map(|f| f.file_id).collect::<Vec<_>>();
+        let pins = self.page_files.file_pins().clone();
+        pins.pin(&file_ids);
+        LiveFilesGuard::new(pins, files)
+    }
+
+    fn collect_live_files(&self, version: &Version) -> Vec<LiveFile> {
+        let mut files = version
+            .file_infos()
+            .values()
+            .map(|info| {
+                let meta = info.meta();
+                let lsn_range = info.lsn_range();
+                LiveFile {
+                    file_id: meta.file_id,
+                    path: self.page_files.file_path(meta.file_id),
+                    size: meta.file_size as u64,
+                    min_lsn: lsn_range.map_or(0, |r| r.min_lsn),
+                    max_lsn: lsn_range.map_or(0, |r| r.max_lsn),
+                }
+            })
+            .collect::<Vec<_>>();
+        files.sort_unstable_by_key(|f| f.file_id);
+        files
+    }
+
+    /// Replaces the strategy to pick files for future reclaim cycles.
+    ///
+    /// A reclaim cycle in progress keeps using the former strategy.
+    pub(crate) fn set_reclaim_strategy(&self, strategy: ReclaimStrategy) {
+        let builder = new_strategy_builder(&self.options, strategy, &self.job_stats);
+        self.strategy_builder.set(builder);
+    }
+
+    /// Returns the files that the current strategy would pick for reclaiming
+    /// in order, with the bytes freed by reclaiming each of them.
+    ///
+    /// No file is reclaimed.
+    pub(crate) async fn reclaim_dry_run(&self) -> Vec<(PickedFile, usize)> {
+        let now = {
+            let lock = self.manifest.lock().await;
+            lock.now()
+        };
+        reclaim_dry_run(&self.strategy_builder, now, &self.version())
+    }
+
+    /// Compacts the file into a new file regardless of its reclaim score.
+    ///
+    /// This waits for the file being compacted by space reclaiming, if any.
+    /// Returns [`Error::MissingFile`] if the file is not in the current
+    /// version.
+    pub(crate) async fn compact_file(&self, file_id: u32) -> Result<()> {
+        if self.read_only {
+            return Err(Error::ReadOnly);
+        }
+        let ctx = ReclaimCtx::new(
+            self.options.clone(),
+            self.shutdown.subscribe(),
+            Box::new(self.strategy_builder.clone()),
+            self.page_files.clone(),
+            self.version_owner.clone(),
+            self.manifest.clone(),
+            self.job_stats.clone(),
+            self.reclaim_pause.clone(),
+            self.reclaim_running.clone(),
+        );
+        ctx.compact_file_on_demand(file_id).await
+    }
+
+    /// Pauses space reclaiming until it is resumed as many times as it is
+    /// paused. Flush is not paused.
+    pub(crate) fn pause_reclaiming(&self) {
+        self.reclaim_pause.pause();
+    }
+
+    /// Resumes space reclaiming paused by [`PageStore::pause_reclaiming`].
+    pub(crate) fn resume_reclaiming(&self) {
+        self.reclaim_pause.resume();
+    }
+
+    /// Returns true if writes should flush the write buffer right away.
+    #[inline]
+    pub(crate) fn writebuf_disabled(&self) -> bool {
+        self.options.writebuf_disabled
+    }
+
     /// Flush the active write buffer if it is not empty.
+    ///
+    /// Read-only stores have nothing to flush.
     #[inline]
     pub(crate) async fn flush(&self, opts: &FlushOptions) {
+        if self.read_only {
+            return;
+        }
         self.version().buffer_set.flush_active_buffer(opts).await
     }
 
-    /// Wait all pending reclaiming to finish.
+    /// Wait all pending reclaiming to finish, returns early if reclaiming is
+    /// paused.
     #[inline]
     pub(crate) async fn wait_for_reclaiming(&self) {
-        wait_for_reclaiming(&self.options, self.version()).await;
+        wait_for_reclaiming(&self.options, &self.reclaim_pause, self.version()).await;
+    }
+
+    /// Waits until no flush or reclaim job is running or queued.
+    ///
+    /// Sealed write buffers are waited to be flushed, the active one is not.
+    /// Like [`PageStore::wait_for_reclaiming`], this returns early if
+    /// reclaiming is paused, and flushes are not waited if the disk is full.
+    pub(crate) async fn wait_for_background_idle(&self) {
+        if self.read_only {
+            return;
+        }
+        loop {
+            let sealed = self.last_sealed_buffer();
+            if let Some(buffer) = &sealed {
+                if !self.version().buffer_set.is_disk_full() {
+                    buffer.wait_flushed().await;
+                }
+            }
+            self.wait_for_reclaiming().await;
+            // More buffers may have been sealed in the meantime.
+            let group_id = |buffer: Option<Arc<WriteBuffer>>| buffer.map(|b| b.group_id());
+            if group_id(self.last_sealed_buffer()) == group_id(sealed) {
+                break;
+            }
+        }
+    }
+
+    fn last_sealed_buffer(&self) -> Option<Arc<WriteBuffer>> {
+        let version = self.version();
+        let current = version.buffer_set.current();
+        current.last_sealed_buffer().cloned()
     }
 
     #[inline]
@@ -337,7 +865,7 @@ impl<E: Env> PageStore<E> {
     }
 
     fn spawn_reclaim_job(&mut self) {
-        let strategy_builder = Box::new(MinDeclineRateStrategyBuilder);
+        let strategy_builder = Box::new(self.strategy_builder.clone());
         let job = ReclaimCtx::new(
             self.options.clone(),
             self.shutdown.subscribe(),
@@ -346,10 +874,201 @@ impl<E: Env> PageStore<E> {
             self.version_owner.clone(),
             self.manifest.clone(),
             self.job_stats.clone(),
+            self.reclaim_pause.clone(),
+            self.reclaim_running.clone(),
         );
         let handle = self.env.spawn_background(job.run(self.version()));
         self.jobs.push(handle);
     }
+
+    /// Spawns a background job that is stopped and waited for when the store
+    /// is closed.
+    ///
+    /// The job is given a [`GuardFactory`] to access the store, since it
+    /// can't borrow the store. Does nothing if the store is read-only.
+    pub(crate) fn spawn_job<F, Fut>(&mut self, job: F)
+    where
+        F: FnOnce(GuardFactory<E>, Shutdown) -> Fut,
+        Fut: Future<Output = ()> + Send + 'static,
+    {
+        if self.read_only {
+            return;
+        }
+        let guards = GuardFactory {
+            version_owner: self.version_owner.clone(),
+            table: self.table.clone(),
+            page_files: self.page_files.clone(),
+            writebuf_stats: self.writebuf_stats.clone(),
+            read_source_stats: self.read_source_stats.clone(),
+            delta_chain_hist: self.delta_chain_hist.clone(),
+            page_heat: self.page_heat.clone(),
+        };
+        let handle = self
+            .env
+            .spawn_background(job(guards, self.shutdown.subscribe()));
+        self.jobs.push(handle);
+    }
+
+    fn spawn_stats_logger(&mut self) {
+        let interval = self.options.stats_log_interval;
+        if interval.is_zero() {
+            return;
+        }
+        let collector = self.stats_collector();
+        self.stats_logger = Some(StatsLogger::spawn(interval, move || collector.collect()));
+    }
+
+    fn stats_collector(&self) -> StatsCollector<E> {
+        StatsCollector {
+            memory_budget: self.options.memory_budget.unwrap_or_default(),
+            version_owner: self.version_owner.clone(),
+            page_files: self.page_files.clone(),
+            job_stats: self.job_stats.clone(),
+            writebuf_stats: self.writebuf_stats.clone(),
+            read_source_stats: self.read_source_stats.clone(),
+            delta_chain_hist: self.delta_chain_hist.clone(),
+        }
+    }
+}
+
+/// Collects [`StoreStats`] without referencing the page store, so that stats
+/// can be collected by the stats logger.
+struct StatsCollector<E: Env> {
+    memory_budget: usize,
+    version_owner: Arc<VersionOwner>,
+    page_files: Arc<PageFiles<E>>,
+    job_stats: Arc<AtomicJobStats>,
+    writebuf_stats: Arc<AtomicWritebufStats>,
+    read_source_stats: Arc<AtomicReadSourceStats>,
+    delta_chain_hist: Arc<AtomicDeltaChainHistogram>,
+}
+
+impl<E: Env> StatsCollector<E> {
+    fn collect(&self) -> StoreStats {
+        let (page_cache, file_reader_cache) = self.page_files.stats();
+        let index_cache = self
+            .page_files
+            .index_cache()
+            .map(|cache| cache.stats())
+            .unwrap_or_default();
+        let writebuf = self.writebuf_stats.snapshot();
+        let read_source = self.read_source_stats.snapshot();
+        let delta_chain_hist = self.delta_chain_hist.snapshot();
+        let jobs = self.job_stats.snapshot();
+        let version = self.version_owner.current();
+        let buffer_set = version.buffer_set.stats();
+        let memory = MemoryStats {
+            budget: self.memory_budget,
+            writebuf: version.buffer_set.memory_usage(),
+            page_cache: self.page_files.page_cache().capacity(),
+            index_cache: self
+                .page_files
+                .index_cache()
+                .map_or(0, |cache| cache.capacity()),
+        };
+        let files = file_stats(&version);
+        StoreStats {
+            page_cache,
+            index_cache,
+            #[cfg(feature = "detailed-stats")]
+            page_cache_shards: self.page_files.page_cache().shard_stats(),
+            file_reader_cache,
+            writebuf,
+            read_source,
+            delta_chain_hist,
+            jobs,
+            buffer_set,
+            memory,
+            files,
+        }
+    }
+}
+
+fn file_stats(version: &Version) -> FileStats {
+    let (leaf_base_pages, leaf_base_page_size) = version
+        .page_groups()
+        .values()
+        .map(PageGroup::leaf_base_pages)
+        .fold((0, 0), |(n, size), (m, s)| (n + m, size + s));
+    FileStats {
+        page_files: version.page_groups().len(),
+        map_files: version.file_infos().len(),
+        leaf_base_pages,
+        leaf_base_page_size,
+        page_fill_factor: 0.0,
+    }
+}
+
+fn space_report(version: &Version) -> SpaceReport {
+    let page_groups = version.page_groups();
+    let mut report = SpaceReport::default();
+    for info in version.file_infos().values() {
+        let meta = info.meta();
+        report.total_bytes += meta.file_size as u64;
+        for (group_id, group_meta) in &meta.page_groups {
+            // Page groups not in the version are obsoleted entirely.
+            let live_size = page_groups
+                .get(group_id)
+                .map_or(0, PageGroup::effective_size);
+            let dead_size = group_meta.total_page_size().saturating_sub(live_size);
+            report.live_bytes += live_size as u64;
+            report.reclaimable_bytes += dead_size as u64;
+        }
+    }
+    if report.live_bytes > 0 {
+        report.amplification = report.total_bytes as f64 / report.live_bytes as f64;
+    }
+    report
+}
+
+fn new_strategy_builder(
+    options: &Options,
+    strategy: ReclaimStrategy,
+    job_stats: &Arc<AtomicJobStats>,
+) -> Arc<dyn StrategyBuilder> {
+    match strategy {
+        ReclaimStrategy::MinDeclineRate => Arc::new(MinDeclineRateStrategyBuilder::new(
+            options.min_files_retained,
+            options.hot_range.clone(),
+        )),
+        ReclaimStrategy::MaxFreeSpace => {
+            Arc::new(MaxFreeSpaceStrategyBuilder::new(options.min_files_retained))
+        }
+        ReclaimStrategy::Adaptive { alpha_percent } => Arc::new(AdaptiveStrategyBuilder::new(
+            options.min_files_retained,
+            options.hot_range.clone(),
+            f64::from(alpha_percent.min(100)) / 100.0,
+            job_stats.clone(),
+        )),
+    }
+}
+
+/// Creates guards of a [`PageStore`] for a job spawned by
+/// [`PageStore::spawn_job`].
+pub(crate) struct GuardFactory<E: Env> {
+    version_owner: Arc<VersionOwner>,
+    table: PageTable,
+    page_files: Arc<PageFiles<E>>,
+    writebuf_stats: Arc<AtomicWritebufStats>,
+    read_source_stats: Arc<AtomicReadSourceStats>,
+    delta_chain_hist: Arc<AtomicDeltaChainHistogram>,
+    page_heat: Arc<PageHeat>,
+}
+
+impl<E: Env> GuardFactory<E> {
+    /// Returns a guard of the current version, like [`PageStore::guard`].
+    pub(crate) fn guard(&self) -> Guard<E> {
+        let mut guard = Guard::new(
+            self.version_owner.current(),
+            self.table.clone(),
+            self.page_files.clone(),
+            self.writebuf_stats.clone(),
+            self.read_source_stats.clone(),
+            self.delta_chain_hist.clone(),
+        );
+        guard.set_page_heat(self.page_heat.clone());
+        guard
+    }
 }
 
 impl<E: Env> Drop for PageStore<E> {
@@ -8,26 +8,40 @@ use crate::{
     page_store::{cache::Cache, stats::CacheStats, CacheOption, Error, LRUCache, Result},
 };
 
+pub(super) type ReaderCache<E> = LRUCache<Arc<FileReader<<E as Env>::PositionalReader>>>;
+
 pub(super) struct FileReaderCache<E: Env> {
-    cache: Arc<LRUCache<Arc<FileReader<E::PositionalReader>>>>,
+    cache: Arc<ReaderCache<E>>,
+    // Distinguishes the files of page stores sharing the cache.
+    namespace: u64,
     _marker: PhantomData<E>,
 }
 
 impl<E: Env> FileReaderCache<E> {
     pub(super) fn new(max_size: u64) -> Self {
         let cache = Arc::new(LRUCache::new(max_size as usize, -1, 0.0, 0.0));
+        Self::shared(cache, 0)
+    }
+
+    pub(super) fn shared(cache: Arc<ReaderCache<E>>, namespace: u32) -> Self {
         Self {
             cache,
+            namespace: namespace as u64,
             _marker: PhantomData,
         }
     }
 
+    #[inline]
+    fn key(&self, file_id: u32) -> u64 {
+        self.namespace << 32 | file_id as u64
+    }
+
     pub(super) async fn get_with(
         &self,
         file_id: u32,
         init: impl Future<Output = Result<Arc<FileReader<E::PositionalReader>>>>,
     ) -> Result<Arc<FileReader<E::PositionalReader>>> {
-        let key = file_id as u64;
+        let key = self.key(file_id);
         if let Some(cached) = self.cache.lookup(key) {
             return Ok(cached.value().clone());
         }
@@ -43,7 +57,7 @@ impl<E: Env> FileReaderCache<E> {
     }
 
     pub(super) fn invalidate(&self, file_id: u32) {
-        self.cache.erase(file_id as u64);
+        self.cache.erase(self.key(file_id));
     }
 
     pub(super) fn stats(&self) -> CacheStats {
@@ -9,8 +9,7 @@ pub struct ChecksumType: u8 {
     const NONE = 0;
     /// Checksum with CRC32.
     const CRC32 = 1;
-    /// Checksum with XXHASH.
-    #[allow(dead_code)]
+    /// Checksum with XXH32.
     const XXHASH = 2;
 }
 }
@@ -19,7 +18,7 @@ pub(crate) fn checksum(typ: ChecksumType, content: &[u8]) -> Option<u32> {
     match typ {
         ChecksumType::NONE => None,
         ChecksumType::CRC32 => Some(crc32fast::hash(content)),
-        ChecksumType::XXHASH => todo!(),
+        ChecksumType::XXHASH => Some(xxhash_rust::xxh32::xxh32(content, 0)),
         _ => unreachable!(),
     }
 }
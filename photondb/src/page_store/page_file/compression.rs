@@ -1,7 +1,16 @@
+use std::sync::Arc;
+
 use bitflags::bitflags;
+use parking_lot::RwLock;
+use zstd::dict::{DecoderDictionary, EncoderDictionary};
 
 use crate::page_store::{Error, Result};
 
+const ZSTD_LEVEL: i32 = 3;
+
+/// The maximum size of a trained dictionary.
+const MAX_DICT_SIZE: usize = 16 << 10;
+
 bitflags! {
     /// Compression method.
     pub struct Compression: u8 {
@@ -14,10 +23,76 @@ bitflags! {
     }
 }
 
+/// A zstd dictionary to compress pages.
+pub(crate) struct CompressionDict {
+    pub(crate) id: u32,
+    pub(crate) data: Vec<u8>,
+    encoder: EncoderDictionary<'static>,
+    decoder: DecoderDictionary<'static>,
+}
+
+/// The dictionaries used by the pages of a page store.
+///
+/// Dictionaries are never removed, since the pages compressed with an old
+/// dictionary might still be read. The last added one is used to compress new
+/// pages.
+#[derive(Default)]
+pub(crate) struct CompressionDicts {
+    dicts: RwLock<Vec<Arc<CompressionDict>>>,
+}
+
+impl CompressionDict {
+    pub(crate) fn new(data: Vec<u8>) -> Result<Self> {
+        let id = zstd_safe::get_dict_id_from_dict(&data)
+            .ok_or(Error::Corrupted)?
+            .get();
+        Ok(CompressionDict {
+            id,
+            encoder: EncoderDictionary::copy(&data, ZSTD_LEVEL),
+            decoder: DecoderDictionary::copy(&data),
+            data,
+        })
+    }
+}
+
+impl CompressionDicts {
+    /// Adds a dictionary and makes it current, if it is not added before.
+    pub(crate) fn add(&self, dict: CompressionDict) -> Arc<CompressionDict> {
+        let mut dicts = self.dicts.write();
+        if let Some(dict) = dicts.iter().find(|d| d.id == dict.id) {
+            return dict.clone();
+        }
+        let dict = Arc::new(dict);
+        dicts.push(dict.clone());
+        dict
+    }
+
+    /// Returns the dictionary to compress new pages.
+    pub(crate) fn current(&self) -> Option<Arc<CompressionDict>> {
+        self.dicts.read().last().cloned()
+    }
+
+    /// Returns all dictionaries in the order they were added.
+    pub(crate) fn all(&self) -> Vec<Arc<CompressionDict>> {
+        self.dicts.read().clone()
+    }
+
+    fn get(&self, id: u32) -> Option<Arc<CompressionDict>> {
+        self.dicts.read().iter().find(|dict| dict.id == id).cloned()
+    }
+}
+
+/// Trains a dictionary from the concatenated `samples`, which are split by
+/// `sizes`.
+pub(crate) fn train_dict(samples: &[u8], sizes: &[usize]) -> Result<Vec<u8>> {
+    Ok(zstd::dict::from_continuous(samples, sizes, MAX_DICT_SIZE)?)
+}
+
 pub(crate) fn decompress_into(
     compression: Compression,
     input: &[u8],
     target: &mut Vec<u8>,
+    dicts: &CompressionDicts,
 ) -> Result<()> {
     match compression {
         Compression::NONE => Ok(()),
@@ -29,7 +104,15 @@ pub(crate) fn decompress_into(
             Ok(())
         }
         Compression::ZSTD => {
-            let mut decompressor = zstd::bulk::Decompressor::new().unwrap();
+            // The frame records the id of the dictionary it is compressed with.
+            let mut decompressor = match zstd_safe::get_dict_id_from_frame(input) {
+                Some(id) => {
+                    let dict = dicts.get(id.get()).ok_or(Error::Corrupted)?;
+                    zstd::bulk::Decompressor::with_prepared_dictionary(&dict.decoder)
+                        .map_err(|_| Error::Corrupted)?
+                }
+                None => zstd::bulk::Decompressor::new().unwrap(),
+            };
             let len = decompressor
                 .decompress_to_buffer(input, target)
                 .map_err(|_| Error::Corrupted)?;
@@ -66,6 +149,7 @@ pub(crate) fn compress_page<'a>(
     compression: Compression,
     raw: &'a [u8],
     compress_buf: &'a mut [u8],
+    dict: Option<&CompressionDict>,
 ) -> Result<&'a [u8]> {
     match compression {
         Compression::NONE => Ok(raw),
@@ -79,7 +163,12 @@ pub(crate) fn compress_page<'a>(
             let raw_len = raw.len() as u64;
             compress_buf[0..std::mem::size_of::<u64>()].copy_from_slice(&raw_len.to_le_bytes());
             let dat_size = {
-                let mut compressor = zstd::bulk::Compressor::new(3).unwrap();
+                let mut compressor = match dict {
+                    Some(dict) => {
+                        zstd::bulk::Compressor::with_prepared_dictionary(&dict.encoder).unwrap()
+                    }
+                    None => zstd::bulk::Compressor::new(ZSTD_LEVEL).unwrap(),
+                };
                 compressor
                     .compress_to_buffer(raw, &mut compress_buf[std::mem::size_of::<u64>()..])
                     .unwrap()
@@ -98,3 +187,69 @@ pub(crate) fn compress_max_len(compression: Compression, input: &[u8]) -> usize
         _ => unreachable!(),
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample_values() -> Vec<Vec<u8>> {
+        (0..2000u32)
+            .map(|i| {
+                format!(
+                    r#"{{"id":{i},"name":"user-{}","email":"user{}@example.com","status":"active","roles":["reader","writer"]}}"#,
+                    i * 7,
+                    i * 13
+                )
+                .into_bytes()
+            })
+            .collect()
+    }
+
+    fn compress(value: &[u8], dict: Option<&CompressionDict>) -> Vec<u8> {
+        let mut buf = vec![0u8; compress_max_len(Compression::ZSTD, value)];
+        compress_page(Compression::ZSTD, value, &mut buf, dict)
+            .unwrap()
+            .to_vec()
+    }
+
+    fn decompress(page: &[u8], dicts: &CompressionDicts) -> Result<Vec<u8>> {
+        let (len, skip) = decompress_len(Compression::ZSTD, page)?;
+        let mut buf = vec![0u8; len];
+        decompress_into(Compression::ZSTD, &page[skip..], &mut buf, dicts)?;
+        Ok(buf)
+    }
+
+    #[test]
+    fn compression_dict_ratio() {
+        let values = sample_values();
+        let sizes = values.iter().map(Vec::len).collect::<Vec<_>>();
+        let dicts = CompressionDicts::default();
+        let dict = train_dict(&values.concat(), &sizes).unwrap();
+        let dict = dicts.add(CompressionDict::new(dict).unwrap());
+        assert_eq!(dicts.current().unwrap().id, dict.id);
+
+        let plain_size = values
+            .iter()
+            .map(|v| compress(v, None).len())
+            .sum::<usize>();
+        let dict_size = values
+            .iter()
+            .map(|v| compress(v, Some(&dict)).len())
+            .sum::<usize>();
+        assert!(dict_size < plain_size);
+
+        for value in &values[..10] {
+            let page = compress(value, Some(&dict));
+            assert_eq!(&decompress(&page, &dicts).unwrap(), value);
+            let page = compress(value, None);
+            assert_eq!(&decompress(&page, &dicts).unwrap(), value);
+        }
+
+        // The dictionary of a page must be known to decompress it.
+        let page = compress(&values[0], Some(&dict));
+        assert!(matches!(
+            decompress(&page, &CompressionDicts::default()),
+            Err(Error::Corrupted)
+        ));
+    }
+}
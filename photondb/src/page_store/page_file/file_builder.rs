@@ -2,7 +2,7 @@ use std::{alloc::Layout, collections::BTreeMap, marker::PhantomData, sync::Arc};
 
 use super::{
     checksum,
-    compression::{compress_max_len, compress_page, Compression},
+    compression::{compress_max_len, compress_page, Compression, CompressionDict},
     ChecksumType, PageGroupMeta,
 };
 use crate::{
@@ -15,17 +15,24 @@ pub(crate) struct CommonFileBuilder {
     group_id: u32,
     compression: Compression,
     checksum: ChecksumType,
+    compression_dict: Option<Arc<CompressionDict>>,
 
     index: IndexBlockBuilder,
     page_table: PageTable,
 }
 
 impl CommonFileBuilder {
-    pub(super) fn new(group_id: u32, compression: Compression, checksum: ChecksumType) -> Self {
+    pub(super) fn new(
+        group_id: u32,
+        compression: Compression,
+        checksum: ChecksumType,
+        compression_dict: Option<Arc<CompressionDict>>,
+    ) -> Self {
         CommonFileBuilder {
             group_id,
             compression,
             checksum,
+            compression_dict,
             index: IndexBlockBuilder::default(),
             page_table: PageTable::default(),
         }
@@ -41,7 +48,12 @@ impl CommonFileBuilder {
         page_content: &[u8],
     ) -> Result<()> {
         let mut tmp_buf = vec![0u8; compress_max_len(self.compression, page_content)]; // TODO: pool this.
-        let page_content = compress_page(self.compression, page_content, &mut tmp_buf)?;
+        let page_content = compress_page(
+            self.compression,
+            page_content,
+            &mut tmp_buf,
+            self.compression_dict.as_deref(),
+        )?;
         let checksum = checksum::checksum(self.checksum, page_content);
         let file_offset = writer.write_with_checksum(page_content, checksum).await?;
         self.index.add_data_block(page_addr, file_offset, page_info);
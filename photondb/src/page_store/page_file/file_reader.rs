@@ -1,16 +1,28 @@
+use std::{io::ErrorKind, sync::Arc, time::Duration};
+
+use futures_timer::Delay;
+
 use super::file_builder::*;
 use crate::{
     env::{PositionalReader, PositionalReaderExt},
-    page_store::Result,
+    page_store::{Error, Result},
     util::atomic::Counter,
 };
 
+/// The backoff before the first retry of a failed read, which is doubled for
+/// each following retry.
+const READ_RETRY_BACKOFF: Duration = Duration::from_millis(1);
+/// The maximum backoff before a retry of a failed read.
+const MAX_READ_RETRY_BACKOFF: Duration = Duration::from_millis(100);
+
 pub(crate) struct FileReader<R: PositionalReader> {
     reader: R,
     use_direct: bool,
     pub(super) align_size: usize,
     pub(super) file_size: usize,
     read_bytes: Counter,
+    read_retries: u32,
+    read_retry_count: Arc<Counter>,
 }
 
 impl<R: PositionalReader> FileReader<R> {
@@ -22,11 +34,38 @@ impl<R: PositionalReader> FileReader<R> {
             align_size,
             file_size,
             read_bytes: Counter::new(0),
+            read_retries: 0,
+            read_retry_count: Arc::default(),
         }
     }
 
+    /// Retries failed reads up to `retries` times, and counts the retries in
+    /// `retry_count`.
+    pub(super) fn with_read_retries(mut self, retries: u32, retry_count: Arc<Counter>) -> Self {
+        self.read_retries = retries;
+        self.read_retry_count = retry_count;
+        self
+    }
+
     /// Reads the exact number of bytes from the page specified by `offset`.
+    ///
+    /// Reads failed with transient I/O errors are retried with backoff.
     pub(crate) async fn read_exact_at(&self, buf: &mut [u8], req_offset: u64) -> Result<()> {
+        let mut retries = 0;
+        loop {
+            match self.read_exact_at_once(buf, req_offset).await {
+                Err(Error::Io(err)) if retries < self.read_retries && is_transient(&err) => {
+                    let backoff = READ_RETRY_BACKOFF * 2u32.saturating_pow(retries);
+                    retries += 1;
+                    self.read_retry_count.inc();
+                    Delay::new(backoff.min(MAX_READ_RETRY_BACKOFF)).await;
+                }
+                res => return res,
+            }
+        }
+    }
+
+    async fn read_exact_at_once(&self, buf: &mut [u8], req_offset: u64) -> Result<()> {
         if buf.is_empty() {
             return Ok(());
         }
@@ -90,3 +129,15 @@ impl<R: PositionalReader> FileReader<R> {
         self.read_bytes.get()
     }
 }
+
+/// Returns false if the error will happen again when the read is retried.
+fn is_transient(err: &std::io::Error) -> bool {
+    !matches!(
+        err.kind(),
+        ErrorKind::UnexpectedEof
+            | ErrorKind::NotFound
+            | ErrorKind::PermissionDenied
+            | ErrorKind::InvalidInput
+            | ErrorKind::Unsupported
+    )
+}
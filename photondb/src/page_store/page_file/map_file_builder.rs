@@ -7,10 +7,10 @@ use std::{
 use rustc_hash::{FxHashMap, FxHashSet};
 
 use super::{
-    compression::Compression,
+    compression::{Compression, CompressionDict},
     constant::*,
     file_builder::CommonFileBuilder,
-    types::{split_page_addr, FileMeta},
+    types::{split_page_addr, FileMeta, KeyRange, LsnRange},
     BlockHandle, BufferedWriter, ChecksumType, FileInfo, PageGroup,
 };
 use crate::{
@@ -44,6 +44,15 @@ pub(crate) struct FileBuilder<'a, E: Env> {
     file_offset: usize,
     compression: Compression,
     checksum: ChecksumType,
+    compression_dict: Option<Arc<CompressionDict>>,
+    key_range: Option<KeyRange>,
+    /// Set if some data of the file has no known key range, so the file has
+    /// none either.
+    key_range_unknown: bool,
+    lsn_range: Option<LsnRange>,
+    /// Set if some data of the file has no known LSN range, so the file has
+    /// none either.
+    lsn_range_unknown: bool,
 }
 
 /// A builder for page group.
@@ -86,6 +95,7 @@ impl<'a, E: Env> FileBuilder<'a, E> {
         block_size: usize,
         compression: Compression,
         checksum: ChecksumType,
+        compression_dict: Option<Arc<CompressionDict>>,
     ) -> Self {
         let writer = BufferedWriter::new(file, IO_BUFFER_SIZE, use_direct, block_size, base_dir);
         Self {
@@ -98,18 +108,24 @@ impl<'a, E: Env> FileBuilder<'a, E> {
             block_size,
             compression,
             checksum,
+            compression_dict,
+            key_range: None,
+            key_range_unknown: false,
+            lsn_range: None,
+            lsn_range_unknown: false,
         }
     }
 
     pub(crate) fn add_page_group(self, group_id: u32) -> PageGroupBuilder<'a, E> {
         let compression = self.compression;
         let checksum_type = self.checksum;
+        let compression_dict = self.compression_dict.clone();
         let base_offset = self.writer.next_offset();
         PageGroupBuilder {
             group_id,
             base_offset,
             builder: self,
-            inner: CommonFileBuilder::new(group_id, compression, checksum_type),
+            inner: CommonFileBuilder::new(group_id, compression, checksum_type, compression_dict),
         }
     }
 
@@ -117,6 +133,49 @@ impl<'a, E: Env> FileBuilder<'a, E> {
         self.dealloc_pages.extend(dealloc_pages);
     }
 
+    /// Extends the key range of the file to cover `[min_key, max_key]`.
+    pub(crate) fn add_key_range(&mut self, min_key: &[u8], max_key: &[u8]) {
+        match &mut self.key_range {
+            Some(range) => range.extend(min_key, max_key),
+            None => {
+                self.key_range = Some(KeyRange {
+                    min_key: min_key.to_owned(),
+                    max_key: max_key.to_owned(),
+                })
+            }
+        }
+    }
+
+    /// Marks the key range of the file as unknown, because it holds data
+    /// without a known key range.
+    pub(crate) fn add_unknown_key_range(&mut self) {
+        self.key_range_unknown = true;
+    }
+
+    /// Extends the LSN range of the file to cover `[min_lsn, max_lsn]`.
+    pub(crate) fn add_lsn_range(&mut self, min_lsn: u64, max_lsn: u64) {
+        match &mut self.lsn_range {
+            Some(range) => range.extend(min_lsn, max_lsn),
+            None => self.lsn_range = Some(LsnRange { min_lsn, max_lsn }),
+        }
+    }
+
+    /// Marks the LSN range of the file as unknown, because it holds data
+    /// without a known LSN range.
+    pub(crate) fn add_unknown_lsn_range(&mut self) {
+        self.lsn_range_unknown = true;
+    }
+
+    /// Returns the number of bytes written so far.
+    pub(crate) fn size(&self) -> usize {
+        self.writer.next_offset() as usize
+    }
+
+    /// Returns the number of page groups added so far.
+    pub(crate) fn num_page_groups(&self) -> usize {
+        self.page_groups.len()
+    }
+
     pub(crate) async fn finish(
         mut self,
         up2: u32,
@@ -128,15 +187,19 @@ impl<'a, E: Env> FileBuilder<'a, E> {
             .iter()
             .map(|(&id, info)| (id, info.meta().clone()))
             .collect::<FxHashMap<_, _>>();
-        let file_meta = Arc::new(FileMeta::new(
-            self.file_id,
-            file_size,
-            DEFAULT_BLOCK_SIZE,
-            self.checksum,
-            self.compression,
-            self.get_referenced_groups(),
-            page_groups,
-        ));
+        let file_meta = Arc::new(
+            FileMeta::new(
+                self.file_id,
+                file_size,
+                DEFAULT_BLOCK_SIZE,
+                self.checksum,
+                self.compression,
+                self.get_referenced_groups(),
+                page_groups,
+            )
+            .with_key_range(self.key_range.take().filter(|_| !self.key_range_unknown))
+            .with_lsn_range(self.lsn_range.take().filter(|_| !self.lsn_range_unknown)),
+        );
         let file_info = FileInfo::new(up2, up2, file_meta);
         Ok((self.page_groups, file_info))
     }
@@ -209,6 +272,16 @@ impl<'a, E: Env> PageGroupBuilder<'a, E> {
         self.builder.dealloc_pages.extend(dealloc_pages);
     }
 
+    /// Extends the key range of the file to cover `[min_key, max_key]`.
+    pub(crate) fn add_key_range(&mut self, min_key: &[u8], max_key: &[u8]) {
+        self.builder.add_key_range(min_key, max_key);
+    }
+
+    /// Extends the LSN range of the file to cover `[min_lsn, max_lsn]`.
+    pub(crate) fn add_lsn_range(&mut self, min_lsn: u64, max_lsn: u64) {
+        self.builder.add_lsn_range(min_lsn, max_lsn);
+    }
+
     pub(crate) async fn finish(mut self) -> Result<FileBuilder<'a, E>> {
         self.inner
             .finish_meta_block(&mut self.builder.writer)
@@ -0,0 +1,54 @@
+use std::{
+    collections::HashMap,
+    path::Path,
+    sync::{Arc, Mutex},
+};
+
+use crate::{
+    env::{Env, Mmap, PositionalReader},
+    page_store::Result,
+};
+
+/// Memory mappings of page files, indexed by file id.
+///
+/// Page files are immutable once they are visible to readers, so a mapping
+/// can be shared until the file is removed.
+#[derive(Default)]
+pub(super) struct MmapFiles {
+    files: Mutex<HashMap<u32, Arc<Mmap>>>,
+}
+
+impl MmapFiles {
+    /// Returns the mapping of the file, mapping it with `env` if necessary.
+    ///
+    /// The file is opened and mapped without the lock, so that reads of other
+    /// files are not blocked meanwhile.
+    pub(super) async fn get_or_map<E: Env>(
+        &self,
+        env: &E,
+        file_id: u32,
+        path: &Path,
+    ) -> Result<Arc<Mmap>> {
+        let mapped = self.files.lock().expect("Poisoned").get(&file_id).cloned();
+        if let Some(file) = mapped {
+            return Ok(file);
+        }
+        let reader = env.open_positional_reader(path).await?;
+        let file = Arc::new(reader.mmap()?);
+        let mut files = self.files.lock().expect("Poisoned");
+        Ok(files.entry(file_id).or_insert(file).clone())
+    }
+
+    /// Drops the mapping of the file.
+    ///
+    /// This must be called before the file is removed. Ongoing reads keep
+    /// their own reference, and the file is unmapped once they finish.
+    pub(super) fn unmap(&self, file_id: u32) {
+        self.files.lock().expect("Poisoned").remove(&file_id);
+    }
+
+    #[cfg(test)]
+    pub(super) fn len(&self) -> usize {
+        self.files.lock().expect("Poisoned").len()
+    }
+}
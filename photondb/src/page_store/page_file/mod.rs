@@ -6,9 +6,14 @@ pub(self) use file_builder::{BlockHandle, BufferedWriter};
 mod file_reader;
 pub(crate) use file_reader::FileReader;
 
+mod mmap;
+
 mod types;
-pub(crate) use facade::PageFiles;
-pub(crate) use types::{FileInfo, PageGroup, PageGroupMeta};
+pub(crate) use facade::{PageFiles, SharedCaches, StoreCaches};
+pub(crate) use types::{
+    FileInfo, FileMeta, KeyRange, LsnRange, PageGroup, PageGroupMeta,
+    EMPTY_PAGES_RATE_WITHOUT_PAGES,
+};
 
 mod map_file_builder;
 pub(crate) use map_file_builder::{FileBuilder, PageGroupBuilder};
@@ -18,6 +23,7 @@ pub(crate) use read_meta::FileMetaHolder;
 
 mod compression;
 pub use compression::Compression;
+pub(crate) use compression::{train_dict, CompressionDict, CompressionDicts};
 
 mod checksum;
 pub use checksum::ChecksumType;
@@ -34,12 +40,21 @@ pub(crate) mod constant {
 }
 
 pub(crate) mod facade {
-    use std::{path::PathBuf, sync::Arc};
+    use std::{
+        path::PathBuf,
+        sync::{
+            atomic::{AtomicU32, Ordering},
+            Arc,
+        },
+    };
+
+    use log::error;
 
     use super::{
-        cache::FileReaderCache,
+        cache::{FileReaderCache, ReaderCache},
         constant::DEFAULT_BLOCK_SIZE,
         file_reader::FileReader,
+        mmap::MmapFiles,
         types::{FileMeta, PageHandle},
         *,
     };
@@ -49,13 +64,76 @@ pub(crate) mod facade {
         page_store::{
             page_txn::{CacheOption, CachePriority},
             stats::CacheStats,
-            Cache, CacheEntry, Error, LRUCache, Result,
+            Cache, CacheEntry, Error, FilePins, LRUCache, Result,
         },
+        util::atomic::Counter,
         PageStoreOptions,
     };
 
     pub(crate) const FILE_PREFIX: &str = "map";
 
+    /// The number of high bits of page addresses that namespace the pages of
+    /// page stores sharing a page cache.
+    const CACHE_NAMESPACE_BITS: u32 = 8;
+    const MAX_CACHE_NAMESPACE: u32 = (1 << CACHE_NAMESPACE_BITS) - 1;
+
+    /// The page cache and file reader cache shared by multiple page stores.
+    ///
+    /// Each page store opened with the caches gets a distinct namespace, which
+    /// is put in the high bits of the file ids in cache keys, so that pages of
+    /// different stores never collide.
+    pub(crate) struct SharedCaches<E: Env> {
+        page_cache: Arc<LRUCache<Vec<u8>>>,
+        reader_cache: Arc<ReaderCache<E>>,
+        next_namespace: AtomicU32,
+    }
+
+    /// The shared caches with the namespace allocated to a page store.
+    pub(crate) struct StoreCaches<E: Env> {
+        page_cache: Arc<LRUCache<Vec<u8>>>,
+        reader_cache: Arc<ReaderCache<E>>,
+        namespace: u32,
+    }
+
+    impl<E: Env> SharedCaches<E> {
+        pub(crate) fn new(page_cache_capacity: usize, reader_cache_capacity: u64) -> Self {
+            let page_cache = Arc::new(LRUCache::new(page_cache_capacity, -1, 0.5, 0.0));
+            let reader_cache =
+                Arc::new(LRUCache::new(reader_cache_capacity as usize, -1, 0.0, 0.0));
+            Self {
+                page_cache,
+                reader_cache,
+                // Namespace 0 is reserved for page stores with their own caches.
+                next_namespace: AtomicU32::new(1),
+            }
+        }
+
+        /// Allocates a namespace of the caches for a new page store.
+        ///
+        /// Returns `None` if all namespaces are in use.
+        pub(crate) fn alloc(&self) -> Option<StoreCaches<E>> {
+            let namespace = self
+                .next_namespace
+                .fetch_update(Ordering::AcqRel, Ordering::Acquire, |ns| {
+                    (ns <= MAX_CACHE_NAMESPACE).then_some(ns + 1)
+                })
+                .ok()?;
+            Some(StoreCaches {
+                page_cache: self.page_cache.clone(),
+                reader_cache: self.reader_cache.clone(),
+                namespace,
+            })
+        }
+
+        pub(crate) fn page_cache(&self) -> &Arc<LRUCache<Vec<u8>>> {
+            &self.page_cache
+        }
+
+        pub(crate) fn stats(&self) -> (CacheStats, CacheStats) {
+            (self.page_cache.stats(), self.reader_cache.stats())
+        }
+    }
+
     /// The facade for page_file module.
     /// it hides the detail about disk location for caller(after it be created).
     pub(crate) struct PageFiles<E: Env> {
@@ -65,9 +143,19 @@ pub(crate) mod facade {
 
         use_direct: bool,
         prepopulate_cache_on_flush: bool,
+        verify_checksums_on_cache_hit: bool,
+        read_retries: u32,
+        read_retry_count: Arc<Counter>,
 
         reader_cache: cache::FileReaderCache<E>,
         page_cache: Arc<LRUCache<Vec<u8>>>,
+        index_cache: Option<Arc<LRUCache<Vec<u8>>>>,
+        cache_namespace: u32,
+        mmap_files: Option<MmapFiles>,
+        file_pins: Arc<FilePins>,
+
+        enable_compression_dict: bool,
+        compression_dicts: CompressionDicts,
     }
 
     impl<E: Env> PageFiles<E> {
@@ -77,21 +165,66 @@ pub(crate) mod facade {
             env: E,
             base: impl Into<PathBuf>,
             options: &PageStoreOptions,
+        ) -> Result<Self> {
+            Self::open(env, base, options, None).await
+        }
+
+        /// Create page file facade with the caches shared with other page
+        /// stores, or with its own caches if `caches` is `None`.
+        pub(crate) async fn open(
+            env: E,
+            base: impl Into<PathBuf>,
+            options: &PageStoreOptions,
+            caches: Option<StoreCaches<E>>,
         ) -> Result<Self> {
             let base = base.into();
             let base_dir = env.open_dir(&base).await?;
-            let reader_cache = FileReaderCache::new(options.cache_file_reader_capacity);
-            let page_cache = Arc::new(LRUCache::new(options.cache_capacity, -1, 0.5, 0.0));
+            let (reader_cache, page_cache, index_cache, cache_namespace) = match caches {
+                Some(caches) => {
+                    let ns = caches.namespace;
+                    let reader_cache = FileReaderCache::shared(caches.reader_cache, ns);
+                    (reader_cache, caches.page_cache, None, ns)
+                }
+                None => {
+                    let reader_cache = FileReaderCache::new(options.file_reader_capacity());
+                    let new_cache = |capacity, high_pri_ratio| {
+                        let cache = LRUCache::new(capacity, -1, high_pri_ratio, 0.0)
+                            .with_hasher(options.hasher);
+                        match options.on_evict.clone() {
+                            Some(on_evict) => Arc::new(cache.with_evict_callback(on_evict)),
+                            None => Arc::new(cache),
+                        }
+                    };
+                    let page_cache = new_cache(options.data_cache_capacity(), 0.5);
+                    let index_cache = options
+                        .index_cache_capacity
+                        .map(|capacity| new_cache(capacity, 0.0));
+                    (reader_cache, page_cache, index_cache, 0)
+                }
+            };
             let use_direct = options.use_direct_io;
             let prepopulate_cache_on_flush = options.prepopulate_cache_on_flush;
+            let verify_checksums_on_cache_hit = options.verify_checksums_on_cache_hit;
+            let read_retries = options.read_retries;
+            let mmap_files = options.mmap_reads.then(MmapFiles::default);
+            let enable_compression_dict = options.enable_compression_dict;
             Ok(Self {
                 env,
                 base,
                 base_dir,
                 use_direct,
                 prepopulate_cache_on_flush,
+                verify_checksums_on_cache_hit,
+                read_retries,
+                read_retry_count: Arc::default(),
                 reader_cache,
                 page_cache,
+                index_cache,
+                cache_namespace,
+                mmap_files,
+                file_pins: Arc::default(),
+                enable_compression_dict,
+                compression_dicts: CompressionDicts::default(),
             })
         }
 
@@ -103,9 +236,15 @@ pub(crate) mod facade {
             checksum: ChecksumType,
         ) -> Result<FileBuilder<E>> {
             // TODO: switch to env in suitable time.
-            let path = self.base.join(format!("{}_{file_id}", FILE_PREFIX));
+            let path = self.file_path(file_id);
             let writer = self.env.open_sequential_writer(path.to_owned()).await?;
             let use_direct = self.use_direct && writer.direct_io_ify().is_ok();
+            let compression_dict =
+                if self.enable_compression_dict && compression == Compression::ZSTD {
+                    self.compression_dicts.current()
+                } else {
+                    None
+                };
             Ok(FileBuilder::new(
                 file_id,
                 &self.base_dir,
@@ -114,36 +253,53 @@ pub(crate) mod facade {
                 DEFAULT_BLOCK_SIZE,
                 compression,
                 checksum,
+                compression_dict,
             ))
         }
 
+        /// Returns the dictionaries to compress and decompress pages.
+        pub(crate) fn compression_dicts(&self) -> &CompressionDicts {
+            &self.compression_dicts
+        }
+
+        /// Reads a page through the page cache, or through the index cache if
+        /// the page `is_inner` and there is a separate index cache.
         pub(crate) async fn read_page(
             &self,
             file_id: u32,
             file_meta: &FileMeta,
             addr: u64,
             handle: PageHandle,
+            is_inner: bool,
             mut hint: CacheOption,
         ) -> Result<(CacheEntry<Vec<u8>, LRUCache<Vec<u8>>>, /* hit */ bool)> {
-            if let Some(cache_entry) = self.page_cache.lookup(addr) {
+            let key = self.cache_key(addr);
+            let cache = self.cache_of(is_inner);
+            if let Some(cache_entry) = cache.lookup(key) {
+                if self.verify_checksums_on_cache_hit && !verify_cached_page(cache_entry.value()) {
+                    cache.erase(key);
+                    error!(
+                        "Corrupted cached page of file {file_id} at offset {}",
+                        handle.offset
+                    );
+                    return Err(Error::Corrupted);
+                }
                 return Ok((cache_entry, true));
             }
 
-            let buf = self.read_file_page(file_id, file_meta, handle).await?;
+            let mut buf = self.read_file_page(file_id, file_meta, handle).await?;
 
-            let is_inner = {
-                let page =
-                    PageRef::new(unsafe { std::slice::from_raw_parts(buf.as_ptr(), buf.len()) });
-                page.tier().is_inner()
-            };
             hint = hint.set_priority(if is_inner {
                 CachePriority::High
             } else {
                 CachePriority::Low
             });
 
+            if self.verify_checksums_on_cache_hit {
+                append_cached_checksum(&mut buf);
+            }
             let charge = buf.len();
-            let cache_entry = self.page_cache.insert(addr, Some(buf), charge, hint)?;
+            let cache_entry = cache.insert(key, Some(buf), charge, hint)?;
             Ok((cache_entry.unwrap(), false))
         }
 
@@ -153,13 +309,24 @@ pub(crate) mod facade {
             file_meta: &FileMeta,
             handle: PageHandle,
         ) -> Result<Vec<u8>> {
-            const CHECKSUM_LEN: usize = std::mem::size_of::<u32>();
-
-            let reader = self.open_page_reader(file_id, file_meta.block_size).await?;
-
             let mut buf = vec![0u8; handle.size as usize]; // TODO: aligned buffer pool
-            self.read_file_page_from_reader(&reader, file_meta, handle, &mut buf)
-                .await?;
+            let res = if let Some(mmap_files) = &self.mmap_files {
+                self.read_mapped_page(mmap_files, file_id, file_meta, handle, &mut buf)
+                    .await
+            } else {
+                let reader = self.open_page_reader(file_id, file_meta.block_size).await?;
+                self.read_file_page_from_reader(&reader, file_meta, handle, &mut buf)
+                    .await
+            };
+            res.map_err(|err| {
+                if matches!(err, Error::Corrupted) {
+                    error!(
+                        "Corrupted page of file {file_id} at offset {}",
+                        handle.offset
+                    );
+                }
+                err
+            })?;
             Ok(buf)
         }
 
@@ -170,9 +337,32 @@ pub(crate) mod facade {
             handle: PageHandle,
             output: &mut Vec<u8>,
         ) -> Result<()> {
-            const CHECKSUM_LEN: usize = std::mem::size_of::<u32>();
-
             reader.read_exact_at(output, handle.offset as u64).await?;
+            self.decode_page(file_meta, output)
+        }
+
+        async fn read_mapped_page(
+            &self,
+            mmap_files: &MmapFiles,
+            file_id: u32,
+            file_meta: &FileMeta,
+            handle: PageHandle,
+            output: &mut Vec<u8>,
+        ) -> Result<()> {
+            let path = self.file_path(file_id);
+            let file = mmap_files.get_or_map(&self.env, file_id, &path).await?;
+            let start = handle.offset as usize;
+            let page = file
+                .as_slice()
+                .get(start..start + handle.size as usize)
+                .ok_or(Error::Corrupted)?;
+            output.copy_from_slice(page);
+            self.decode_page(file_meta, output)
+        }
+
+        // Verifies and decompresses a page read from a page file.
+        fn decode_page(&self, file_meta: &FileMeta, output: &mut Vec<u8>) -> Result<()> {
+            const CHECKSUM_LEN: usize = std::mem::size_of::<u32>();
 
             if file_meta.checksum_type != ChecksumType::NONE {
                 let checksum = u32::from_le_bytes(
@@ -188,7 +378,12 @@ pub(crate) mod facade {
             if compression != Compression::NONE {
                 let (decompress_len, skip) = compression::decompress_len(compression, output)?;
                 let mut dec_buf = vec![0u8; decompress_len];
-                compression::decompress_into(compression, &output[skip..], &mut dec_buf)?;
+                compression::decompress_into(
+                    compression,
+                    &output[skip..],
+                    &mut dec_buf,
+                    &self.compression_dicts,
+                )?;
                 if output.len() < dec_buf.len() {
                     output.resize(dec_buf.len(), 0u8);
                 }
@@ -211,24 +406,19 @@ pub(crate) mod facade {
                     let (prefix, id) = (FILE_PREFIX, file_id);
                     let (file, file_size) = self.open_positional_reader(prefix, id).await?;
                     let use_direct = self.use_direct && file.direct_io_ify().is_ok();
-                    Ok(Arc::new(FileReader::from(
-                        file,
-                        use_direct,
-                        block_size,
-                        file_size as usize,
-                    )))
+                    let reader = FileReader::from(file, use_direct, block_size, file_size as usize)
+                        .with_read_retries(self.read_retries, self.read_retry_count.clone());
+                    Ok(Arc::new(reader))
                 })
                 .await
         }
 
         pub(crate) async fn read_file_meta(&self, file_id: u32) -> Result<FileMetaHolder> {
             let (file, file_size) = self.open_positional_reader(FILE_PREFIX, file_id).await?;
-            let page_file_reader = Arc::new(FileReader::from(
-                file,
-                true,
-                DEFAULT_BLOCK_SIZE,
-                file_size as usize,
-            ));
+            let page_file_reader = Arc::new(
+                FileReader::from(file, true, DEFAULT_BLOCK_SIZE, file_size as usize)
+                    .with_read_retries(self.read_retries, self.read_retry_count.clone()),
+            );
             FileMetaHolder::read(file_id, page_file_reader).await
         }
 
@@ -243,43 +433,98 @@ pub(crate) mod facade {
             Ok((file, file_size))
         }
 
+        /// Removes the files, and the files deferred before because they were
+        /// pinned.
+        ///
+        /// Pinned files are not removed until they are unpinned.
         pub(crate) async fn remove_files(&self, files: Vec<u32>) {
-            for file_id in files {
+            for file_id in self.file_pins.take_removable(files) {
+                // Mappings must be dropped before the file is removed.
+                if let Some(mmap_files) = &self.mmap_files {
+                    mmap_files.unmap(file_id);
+                }
                 self.remove_file(file_id).await;
                 self.reader_cache.invalidate(file_id);
             }
         }
 
         async fn remove_file(&self, file_id: u32) {
-            let path = self.base.join(format!("{}_{file_id}", FILE_PREFIX));
+            let path = self.file_path(file_id);
             let _ = self.env.remove_file(&path).await;
         }
 
+        /// Returns the path of the page file.
+        pub(crate) fn file_path(&self, file_id: u32) -> PathBuf {
+            self.base.join(format!("{}_{file_id}", FILE_PREFIX))
+        }
+
+        #[inline]
+        pub(crate) fn file_pins(&self) -> &Arc<FilePins> {
+            &self.file_pins
+        }
+
         pub(crate) fn populate_cache(&self, page_addr: u64, page_content: &[u8]) -> Result<()> {
             if !self.prepopulate_cache_on_flush {
                 return Ok(());
             }
-            let val = page_content.to_owned(); // TODO: aligned buffer pool
-            let guard = match self.page_cache.insert(
-                page_addr,
-                Some(val),
-                page_content.len(),
-                CacheOption::default(),
-            ) {
-                Ok(guard) => guard,
-                Err(Error::MemoryLimit) => return Ok(()),
-                Err(err) => return Err(err),
-            };
+            let mut val = page_content.to_owned(); // TODO: aligned buffer pool
+            if self.verify_checksums_on_cache_hit {
+                append_cached_checksum(&mut val);
+            }
+            let charge = val.len();
+            let key = self.cache_key(page_addr);
+            let is_inner =
+                self.index_cache.is_some() && PageRef::new(page_content).tier().is_inner();
+            let guard =
+                match self
+                    .cache_of(is_inner)
+                    .insert(key, Some(val), charge, CacheOption::default())
+                {
+                    Ok(guard) => guard,
+                    Err(Error::MemoryLimit) => return Ok(()),
+                    Err(err) => return Err(err),
+                };
             drop(guard);
             Ok(())
         }
 
+        /// Returns the page content of a value in the page cache.
+        pub(crate) fn cached_page<'a>(&self, value: &'a [u8]) -> &'a [u8] {
+            if self.verify_checksums_on_cache_hit {
+                &value[..value.len() - CACHED_CHECKSUM_LEN]
+            } else {
+                value
+            }
+        }
+
         pub(crate) fn evict_cached_pages(&self, files: &[u32]) {
             for file_id in files {
-                self.page_cache.erase_file_pages(*file_id);
+                let file_id = file_id | self.cache_namespace << (32 - CACHE_NAMESPACE_BITS);
+                self.page_cache.erase_file_pages(file_id);
+                if let Some(index_cache) = &self.index_cache {
+                    index_cache.erase_file_pages(file_id);
+                }
             }
         }
 
+        /// Returns the cache for inner pages if `is_inner`, or the cache for
+        /// leaf pages otherwise.
+        #[inline]
+        fn cache_of(&self, is_inner: bool) -> &Arc<LRUCache<Vec<u8>>> {
+            match &self.index_cache {
+                Some(index_cache) if is_inner => index_cache,
+                _ => &self.page_cache,
+            }
+        }
+
+        /// Returns the key of a page in the page cache.
+        #[inline]
+        fn cache_key(&self, addr: u64) -> u64 {
+            let shift = 64 - CACHE_NAMESPACE_BITS;
+            debug_assert!(self.cache_namespace == 0 || addr >> shift == 0);
+            addr | (self.cache_namespace as u64) << shift
+        }
+
         pub(crate) fn list_files(&self) -> Result<Vec<u32>> {
             let prefix = format!("{}_", FILE_PREFIX).into_bytes();
             self.list_files_with_prefix(&prefix)
@@ -305,11 +550,39 @@ pub(crate) mod facade {
             Ok(files)
         }
 
+        pub(crate) fn page_cache(&self) -> &Arc<LRUCache<Vec<u8>>> {
+            &self.page_cache
+        }
+
+        /// Returns the separate cache for inner pages, if any.
+        pub(crate) fn index_cache(&self) -> Option<&Arc<LRUCache<Vec<u8>>>> {
+            self.index_cache.as_ref()
+        }
+
         pub(crate) fn stats(&self) -> (CacheStats, CacheStats) {
             let page_cache = self.page_cache.stats();
             let table_cache = self.reader_cache.stats();
             (page_cache, table_cache)
         }
+
+        /// Returns the counter of reads of page files retried so far.
+        pub(crate) fn read_retry_count(&self) -> Arc<Counter> {
+            self.read_retry_count.clone()
+        }
+    }
+
+    const CACHED_CHECKSUM_LEN: usize = std::mem::size_of::<u32>();
+
+    // Appends the checksum of a page to be verified on cache hits.
+    fn append_cached_checksum(buf: &mut Vec<u8>) {
+        let checksum = crc32fast::hash(buf);
+        buf.extend_from_slice(&checksum.to_le_bytes());
+    }
+
+    // Verifies a page in the page cache with its appended checksum.
+    fn verify_cached_page(value: &[u8]) -> bool {
+        let (page, checksum) = value.split_at(value.len() - CACHED_CHECKSUM_LEN);
+        crc32fast::hash(page).to_le_bytes() == checksum
     }
 
     #[cfg(test)]
@@ -398,6 +671,212 @@ pub(crate) mod facade {
             }
         }
 
+        #[photonio::test]
+        fn test_max_open_files() {
+            let env = crate::env::Photon;
+            let base = TempDir::new("test_max_open_files").unwrap();
+            let mut opt = test_option();
+            opt.max_open_files = 2;
+            let files = PageFiles::new(env, base.path(), &opt).await.unwrap();
+            for file_id in 1..=3 {
+                let b = files
+                    .new_file_builder(file_id, Compression::NONE, ChecksumType::NONE)
+                    .await
+                    .unwrap();
+                let mut b = b.add_page_group(file_id);
+                b.add_page(1, page_addr(file_id, 0), empty_page_info(), &[1].repeat(64))
+                    .await
+                    .unwrap();
+                let builder = b.finish().await.unwrap();
+                builder.finish(1).await.unwrap();
+            }
+
+            for file_id in 1..=3 {
+                files
+                    .open_page_reader(file_id, DEFAULT_BLOCK_SIZE)
+                    .await
+                    .unwrap();
+            }
+            let (_, stats) = files.stats();
+            assert_eq!(stats.insert, 3);
+            assert_eq!(stats.passive_evict, 1);
+
+            // The least recently used reader is closed.
+            for file_id in [3, 2, 1] {
+                files
+                    .open_page_reader(file_id, DEFAULT_BLOCK_SIZE)
+                    .await
+                    .unwrap();
+            }
+            let (_, stats) = files.stats();
+            assert_eq!(stats.lookup_hit, 2);
+            assert_eq!(stats.passive_evict, 2);
+        }
+
+        #[photonio::test]
+        fn test_read_mapped_page() {
+            let env = crate::env::Photon;
+            let base = TempDir::new("test_mmap").unwrap();
+            let mut opt = test_option();
+            opt.mmap_reads = true;
+            let files = PageFiles::new(env, base.path(), &opt).await.unwrap();
+            let file_id = 4;
+            let (group, info) = {
+                let b = files
+                    .new_file_builder(file_id, Compression::SNAPPY, ChecksumType::CRC32)
+                    .await
+                    .unwrap();
+                let mut b = b.add_page_group(1);
+                b.add_page(1, page_addr(4, 0), empty_page_info(), &[7].repeat(4097))
+                    .await
+                    .unwrap();
+                b.add_page(2, page_addr(4, 1), empty_page_info(), &[8].repeat(123))
+                    .await
+                    .unwrap();
+                let builder = b.finish().await.unwrap();
+                let (groups, info) = builder.finish(1).await.unwrap();
+                (groups.get(&1).unwrap().clone(), info)
+            };
+
+            let hd = group.get_page_handle(page_addr(4, 0)).unwrap();
+            let page = files
+                .read_file_page(file_id, info.meta(), hd)
+                .await
+                .unwrap();
+            assert_eq!(page, [7].repeat(4097));
+            let hd = group.get_page_handle(page_addr(4, 1)).unwrap();
+            let page = files
+                .read_file_page(file_id, info.meta(), hd)
+                .await
+                .unwrap();
+            assert_eq!(page, [8].repeat(123));
+
+            let mmap_files = files.mmap_files.as_ref().unwrap();
+            assert_eq!(mmap_files.len(), 1);
+            files.remove_files(vec![file_id]).await;
+            assert_eq!(mmap_files.len(), 0);
+            assert!(files.list_files().unwrap().is_empty());
+        }
+
+        #[photonio::test]
+        fn test_read_corrupted_page() {
+            use std::{
+                fs::OpenOptions,
+                io::{Seek, SeekFrom, Write},
+            };
+
+            let env = crate::env::Photon;
+            let base = TempDir::new("test_corrupted_page").unwrap();
+            let files = PageFiles::new(env, base.path(), &test_option())
+                .await
+                .unwrap();
+            let file_id = 3;
+            let (group, info) = {
+                let b = files
+                    .new_file_builder(file_id, Compression::NONE, ChecksumType::CRC32)
+                    .await
+                    .unwrap();
+                let mut b = b.add_page_group(1);
+                b.add_page(1, page_addr(3, 0), empty_page_info(), &[7].repeat(512))
+                    .await
+                    .unwrap();
+                let builder = b.finish().await.unwrap();
+                let (groups, info) = builder.finish(1).await.unwrap();
+                (groups.get(&1).unwrap().clone(), info)
+            };
+
+            let hd = group.get_page_handle(page_addr(3, 0)).unwrap();
+            let mut file = OpenOptions::new()
+                .write(true)
+                .open(base.path().join(format!("{FILE_PREFIX}_{file_id}")))
+                .unwrap();
+            file.seek(SeekFrom::Start(hd.offset as u64 + 1)).unwrap();
+            file.write_all(&[0]).unwrap();
+            file.sync_all().unwrap();
+
+            let err = files
+                .read_file_page(file_id, info.meta(), hd)
+                .await
+                .unwrap_err();
+            assert!(matches!(crate::Error::from(err), crate::Error::Corrupted));
+        }
+
+        #[photonio::test]
+        fn test_verify_checksums_on_cache_hit() {
+            let env = crate::env::Photon;
+            let base = TempDir::new("test_verify_cache_hit").unwrap();
+            let mut opt = test_option();
+            opt.verify_checksums_on_cache_hit = true;
+            let files = PageFiles::new(env, base.path(), &opt).await.unwrap();
+            let file_id = 5;
+            let addr = page_addr(5, 0);
+            let (group, info) = {
+                let b = files
+                    .new_file_builder(file_id, Compression::NONE, ChecksumType::CRC32)
+                    .await
+                    .unwrap();
+                let mut b = b.add_page_group(1);
+                b.add_page(1, addr, empty_page_info(), &[7].repeat(512))
+                    .await
+                    .unwrap();
+                let builder = b.finish().await.unwrap();
+                let (groups, info) = builder.finish(1).await.unwrap();
+                (groups.get(&1).unwrap().clone(), info)
+            };
+
+            let hd = group.get_page_handle(addr).unwrap();
+            for expect_hit in [false, true] {
+                let (entry, hit) = files
+                    .read_page(
+                        file_id,
+                        info.meta(),
+                        addr,
+                        hd,
+                        false,
+                        CacheOption::default(),
+                    )
+                    .await
+                    .unwrap();
+                assert_eq!(hit, expect_hit);
+                assert_eq!(files.cached_page(entry.value()), [7].repeat(512));
+            }
+
+            // Corrupt the cached copy.
+            let mut bad = [7].repeat(512);
+            bad.extend_from_slice(&[0; CACHED_CHECKSUM_LEN]);
+            drop(
+                files
+                    .page_cache
+                    .insert(addr, Some(bad), 516, CacheOption::default())
+                    .unwrap(),
+            );
+            let res = files
+                .read_page(
+                    file_id,
+                    info.meta(),
+                    addr,
+                    hd,
+                    false,
+                    CacheOption::default(),
+                )
+                .await;
+            assert!(matches!(res, Err(Error::Corrupted)));
+
+            // The corrupted copy is evicted, so the next read goes to the file.
+            let (_, hit) = files
+                .read_page(
+                    file_id,
+                    info.meta(),
+                    addr,
+                    hd,
+                    false,
+                    CacheOption::default(),
+                )
+                .await
+                .unwrap();
+            assert!(!hit);
+        }
+
         #[photonio::test]
         fn test_simple_write_reader() {
             let env = crate::env::Photon;
@@ -1,10 +1,14 @@
-use std::{collections::BTreeMap, sync::Arc};
+use std::{collections::BTreeMap, ops::Range, sync::Arc};
 
 use rustc_hash::{FxHashMap, FxHashSet};
 
 use super::{compression::Compression, BlockHandle, ChecksumType};
 use crate::{page::PageInfo, util::bitmap::FixedBitmap};
 
+/// The empty pages rate of a file or page group without any page, all of it
+/// is free.
+pub(crate) const EMPTY_PAGES_RATE_WITHOUT_PAGES: f64 = 1.0;
+
 #[derive(Debug, Copy, Clone)]
 pub(crate) struct PageHandle {
     pub(crate) offset: u32,
@@ -25,6 +29,11 @@ pub(crate) struct PageGroup {
 
     active_size: usize,
 
+    /// The number and the total size of active leaf data pages that are the
+    /// base of their delta chains.
+    leaf_base_pages: usize,
+    leaf_base_page_size: usize,
+
     meta: Arc<PageGroupMeta>,
 }
 
@@ -63,6 +72,22 @@ pub(crate) struct FileInfo {
     meta: Arc<FileMeta>,
 }
 
+/// The range of user keys stored in a file.
+///
+/// Keys are ordered bytewise, which is the only order of tables.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) struct KeyRange {
+    pub(crate) min_key: Vec<u8>,
+    pub(crate) max_key: Vec<u8>,
+}
+
+/// The range of LSNs of entries stored in a file.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) struct LsnRange {
+    pub(crate) min_lsn: u64,
+    pub(crate) max_lsn: u64,
+}
+
 /// The meta of files.
 pub(crate) struct FileMeta {
     pub(crate) file_id: u32,
@@ -75,15 +100,29 @@ pub(crate) struct FileMeta {
     pub(crate) checksum_type: ChecksumType,
     pub(crate) compression: Compression,
     pub(crate) page_groups: FxHashMap<u32, Arc<PageGroupMeta>>,
+
+    /// The range of user keys in this file, if known.
+    pub(crate) key_range: Option<KeyRange>,
+
+    /// The range of LSNs in this file, if known.
+    pub(crate) lsn_range: Option<LsnRange>,
 }
 
 impl PageGroup {
     pub(crate) fn new(meta: Arc<PageGroupMeta>) -> Self {
         let dealloc_pages = FixedBitmap::new(meta.page_meta_map.len() as u32);
         let active_size = meta.total_page_size();
+        let (leaf_base_pages, leaf_base_page_size) = meta
+            .page_meta_map
+            .values()
+            .map(|page| &page.info)
+            .filter(|info| is_leaf_base_page(info))
+            .fold((0, 0), |(n, size), info| (n + 1, size + info.size()));
         PageGroup {
             dealloc_pages,
             active_size,
+            leaf_base_pages,
+            leaf_base_page_size,
             meta,
         }
     }
@@ -94,12 +133,16 @@ impl PageGroup {
     }
 
     pub(crate) fn deactivate_page(&mut self, page_addr: u64) -> bool {
-        let Some((index, handle)) = self.meta.get_page_handle(page_addr) else {
+        let Some(page) = self.meta.page_meta_map.get(&(page_addr as u32)) else {
             return false;
         };
 
-        if self.dealloc_pages.set(index as u32) {
-            self.active_size -= handle.size as usize;
+        if self.dealloc_pages.set(page.index) {
+            self.active_size -= page.handle.size as usize;
+            if is_leaf_base_page(&page.info) {
+                self.leaf_base_pages -= 1;
+                self.leaf_base_page_size -= page.info.size();
+            }
             return true;
         }
 
@@ -144,10 +187,13 @@ impl PageGroup {
 
     #[inline]
     pub(crate) fn empty_pages_rate(&self) -> f64 {
-        let active_pages = self.dealloc_pages.free() as f64;
-        let total_pages = self.meta.total_pages() as f64 + 0.1;
+        let total_pages = self.meta.total_pages();
+        if total_pages == 0 {
+            return EMPTY_PAGES_RATE_WITHOUT_PAGES;
+        }
+        let active_pages = self.dealloc_pages.free() as usize;
         debug_assert!(active_pages <= total_pages);
-        1.0 - (active_pages / total_pages)
+        1.0 - (active_pages as f64 / total_pages as f64)
     }
 
     #[inline]
@@ -155,12 +201,23 @@ impl PageGroup {
         self.active_size as usize
     }
 
+    /// Returns the number and the total size of active leaf data pages that
+    /// are the base of their delta chains.
+    #[inline]
+    pub(crate) fn leaf_base_pages(&self) -> (usize, usize) {
+        (self.leaf_base_pages, self.leaf_base_page_size)
+    }
+
     #[inline]
     pub(crate) fn iter(&self) -> PageGroupIterator {
         PageGroupIterator::new(self)
     }
 }
 
+fn is_leaf_base_page(info: &PageInfo) -> bool {
+    info.tier().is_leaf() && info.kind().is_data() && info.chain_len() == 1
+}
+
 impl PageGroupMeta {
     pub(crate) fn new(
         group_id: u32,
@@ -298,6 +355,16 @@ impl FileInfo {
     pub(crate) fn up2(&self) -> u32 {
         self.up2
     }
+
+    #[inline]
+    pub(crate) fn key_range(&self) -> Option<&KeyRange> {
+        self.meta.key_range.as_ref()
+    }
+
+    #[inline]
+    pub(crate) fn lsn_range(&self) -> Option<LsnRange> {
+        self.meta.lsn_range
+    }
 }
 
 impl FileMeta {
@@ -318,7 +385,44 @@ impl FileMeta {
             compression,
             referenced_groups,
             page_groups,
+            key_range: None,
+            lsn_range: None,
+        }
+    }
+
+    pub(crate) fn with_key_range(mut self, key_range: Option<KeyRange>) -> Self {
+        self.key_range = key_range;
+        self
+    }
+
+    pub(crate) fn with_lsn_range(mut self, lsn_range: Option<LsnRange>) -> Self {
+        self.lsn_range = lsn_range;
+        self
+    }
+}
+
+impl KeyRange {
+    /// Returns true if this range overlaps with the half-open `range`.
+    pub(crate) fn overlaps(&self, range: &Range<Vec<u8>>) -> bool {
+        self.min_key < range.end && range.start <= self.max_key
+    }
+
+    /// Extends this range to cover `[min_key, max_key]`.
+    pub(crate) fn extend(&mut self, min_key: &[u8], max_key: &[u8]) {
+        if min_key < self.min_key.as_slice() {
+            self.min_key = min_key.to_owned();
         }
+        if max_key > self.max_key.as_slice() {
+            self.max_key = max_key.to_owned();
+        }
+    }
+}
+
+impl LsnRange {
+    /// Extends this range to cover `[min_lsn, max_lsn]`.
+    pub(crate) fn extend(&mut self, min_lsn: u64, max_lsn: u64) {
+        self.min_lsn = self.min_lsn.min(min_lsn);
+        self.max_lsn = self.max_lsn.max(max_lsn);
     }
 }
 
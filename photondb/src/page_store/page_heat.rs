@@ -0,0 +1,140 @@
+use std::{
+    cell::Cell,
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+use rustc_hash::FxHashMap;
+
+/// One in this many page reads is sampled on average.
+const SAMPLE_RATE: u32 = 8;
+
+/// The time for the heat of a file to halve if it is not read.
+const HALF_LIFE: Duration = Duration::from_secs(60);
+
+thread_local! {
+    // The number of reads to skip before the next sample, and the state of
+    // the xorshift generator of gaps.
+    static SAMPLER: Cell<(u32, u32)> = Cell::new((0, 0x9E37_79B9));
+}
+
+/// Tracks how frequently the pages of each file are read.
+///
+/// Reads are sampled with random gaps, so most reads only cost a thread-local
+/// counter. The heat of a file is an exponentially weighted moving average of
+/// its reads: each sampled read adds [`SAMPLE_RATE`] to it, and it halves
+/// every [`HALF_LIFE`].
+#[derive(Default)]
+pub(crate) struct PageHeat {
+    files: Mutex<FxHashMap<u32, FileHeat>>,
+}
+
+#[derive(Clone, Copy, Debug)]
+struct FileHeat {
+    heat: f64,
+    updated_at: Instant,
+}
+
+impl PageHeat {
+    /// Records a read of a page in the file, if the read is sampled.
+    #[inline]
+    pub(crate) fn record(&self, file_id: u32) {
+        if sample() {
+            self.record_at(file_id, Instant::now());
+        }
+    }
+
+    /// Returns the heat of the files in order, files not read are zero.
+    ///
+    /// Files not in `file_ids` are forgotten, since they have been removed.
+    pub(crate) fn heat(&self, file_ids: &[u32]) -> Vec<(u32, f64)> {
+        self.heat_at(file_ids, Instant::now())
+    }
+
+    fn record_at(&self, file_id: u32, now: Instant) {
+        let mut files = self.files.lock().expect("Poisoned");
+        let file = files.entry(file_id).or_insert(FileHeat {
+            heat: 0.0,
+            updated_at: now,
+        });
+        file.heat = file.decayed(now) + SAMPLE_RATE as f64;
+        file.updated_at = now;
+    }
+
+    fn heat_at(&self, file_ids: &[u32], now: Instant) -> Vec<(u32, f64)> {
+        let mut files = self.files.lock().expect("Poisoned");
+        files.retain(|id, _| file_ids.contains(id));
+        file_ids
+            .iter()
+            .map(|id| (*id, files.get(id).map_or(0.0, |file| file.decayed(now))))
+            .collect()
+    }
+}
+
+impl FileHeat {
+    fn decayed(&self, now: Instant) -> f64 {
+        let elapsed = now.saturating_duration_since(self.updated_at);
+        self.heat * 0.5f64.powf(elapsed.as_secs_f64() / HALF_LIFE.as_secs_f64())
+    }
+}
+
+/// Returns true if the current read should be sampled.
+fn sample() -> bool {
+    SAMPLER.with(|sampler| {
+        let (skip, state) = sampler.get();
+        if skip > 0 {
+            sampler.set((skip - 1, state));
+            return false;
+        }
+        let mut x = state;
+        x ^= x << 13;
+        x ^= x >> 17;
+        x ^= x << 5;
+        // Skip `[0, 2 * SAMPLE_RATE - 2]` reads uniformly, so one in
+        // `SAMPLE_RATE` reads is sampled on average, without aliasing with
+        // periodic access patterns.
+        sampler.set((x % (2 * SAMPLE_RATE - 1), x));
+        true
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn page_heat_decay() {
+        let heat = PageHeat::default();
+        let now = Instant::now();
+        heat.record_at(1, now);
+        heat.record_at(1, now);
+        heat.record_at(2, now);
+        assert_eq!(
+            heat.heat_at(&[1, 2, 3], now),
+            vec![(1, 16.0), (2, 8.0), (3, 0.0)]
+        );
+
+        let later = now + HALF_LIFE;
+        let files = heat.heat_at(&[1, 2], later);
+        assert!((files[0].1 - 8.0).abs() < 1e-9);
+        assert!((files[1].1 - 4.0).abs() < 1e-9);
+        heat.record_at(2, later);
+        let files = heat.heat_at(&[1, 2], later);
+        assert!((files[1].1 - 12.0).abs() < 1e-9);
+
+        // Removed files are forgotten.
+        assert_eq!(heat.heat_at(&[2], later).len(), 1);
+        assert_eq!(heat.heat_at(&[1], later), vec![(1, 0.0)]);
+    }
+
+    #[test]
+    fn page_heat_sample_rate() {
+        const N: usize = 1 << 16;
+        let sampled = (0..N).filter(|_| sample()).count();
+        let expect = N / SAMPLE_RATE as usize;
+        assert!(
+            sampled > expect * 9 / 10 && sampled < expect * 11 / 10,
+            "{sampled}"
+        );
+    }
+}
@@ -1,16 +1,19 @@
 use std::{
     collections::HashMap,
     sync::{Arc, Mutex},
+    time::Instant,
 };
 
 use bitflags::bitflags;
 
 use super::{
     cache::CacheToken,
-    stats::AtomicWritebufStats,
+    page_heat::PageHeat,
+    stats::{AtomicDeltaChainHistogram, AtomicReadSourceStats, AtomicWritebufStats},
     version::Version,
     write_buffer::{RecordHeader, ReleaseState},
-    CacheEntry, Error, LRUCache, PageFiles, PageTable, Result, WriteBuffer, NAN_ID,
+    CacheEntry, Error, FileSummary, LRUCache, PageFiles, PageTable, PickedFile, Result,
+    WriteBuffer, NAN_ID,
 };
 use crate::{
     env::Env,
@@ -30,6 +33,9 @@ pub struct CacheOption: u8 {
     const LOW_PRI = 0b00000010;
 
     const BOTTOM_PRI = 0b00000100;
+
+    /// Warmup: the read is issued to warm up the page cache.
+    const WARMUP = 0b00001000;
 }
 }
 
@@ -94,7 +100,13 @@ where
     page_table: PageTable,
     page_files: Arc<PageFiles<E>>,
     cache_guards: Mutex<Vec<CacheEntryGuard>>,
+    owned_values: Mutex<Vec<Box<[u8]>>>,
     writebuf_stats: Arc<AtomicWritebufStats>,
+    read_source_stats: Arc<AtomicReadSourceStats>,
+    delta_chain_hist: Arc<AtomicDeltaChainHistogram>,
+    page_heat: Option<Arc<PageHeat>>,
+    deadline: Option<Instant>,
+    read_only: bool,
 }
 
 impl<E: Env> Guard<E> {
@@ -103,25 +115,93 @@ impl<E: Env> Guard<E> {
         page_table: PageTable,
         page_files: Arc<PageFiles<E>>,
         writebuf_stats: Arc<AtomicWritebufStats>,
+        read_source_stats: Arc<AtomicReadSourceStats>,
+        delta_chain_hist: Arc<AtomicDeltaChainHistogram>,
     ) -> Self {
         Guard {
             version,
             page_table,
             page_files,
             cache_guards: Mutex::default(),
+            owned_values: Mutex::default(),
             writebuf_stats,
+            read_source_stats,
+            delta_chain_hist,
+            page_heat: None,
+            deadline: None,
+            read_only: false,
         }
     }
 
-    pub(crate) async fn begin(&self) -> PageTxn<E> {
-        let buffer_id = self.version.buffer_set.acquire_active_buffer_id().await;
-        PageTxn {
+    /// Samples the page reads of this guard into the heat of files.
+    pub(crate) fn set_page_heat(&mut self, page_heat: Arc<PageHeat>) {
+        self.page_heat = Some(page_heat);
+    }
+
+    /// Makes transactions started by this guard fail with
+    /// [`Error::ReadOnly`].
+    pub(crate) fn set_read_only(&mut self) {
+        self.read_only = true;
+    }
+
+    /// Sets a deadline for transactions started by this guard.
+    ///
+    /// Waiting for write buffers fails with [`Error::WriteStall`] after the
+    /// deadline.
+    pub(crate) fn set_deadline(&mut self, deadline: Instant) {
+        self.deadline = Some(deadline);
+    }
+
+    /// Returns [`Error::ReadOnly`] or [`Error::DiskFull`] if transactions
+    /// can't be started.
+    pub(crate) fn check_writable(&self) -> Result<()> {
+        if self.read_only {
+            return Err(Error::ReadOnly);
+        }
+        if self.version.buffer_set.is_disk_full() {
+            return Err(Error::DiskFull);
+        }
+        Ok(())
+    }
+
+    /// Returns [`Error::Timeout`] if the deadline has passed.
+    pub(crate) fn check_deadline(&self) -> Result<()> {
+        match self.deadline {
+            Some(deadline) if Instant::now() >= deadline => Err(Error::Timeout),
+            _ => Ok(()),
+        }
+    }
+
+    pub(crate) async fn begin(&self) -> Result<PageTxn<E>> {
+        self.begin_impl(self.deadline).await
+    }
+
+    /// Begins a transaction that ignores the deadline of this guard.
+    ///
+    /// This is used by operations that must finish once they have started,
+    /// which must check that the guard is writable before starting.
+    pub(crate) async fn begin_without_deadline(&self) -> PageTxn<E> {
+        self.begin_impl(None)
+            .await
+            .expect("transactions without deadlines never time out")
+    }
+
+    async fn begin_impl(&self, deadline: Option<Instant>) -> Result<PageTxn<E>> {
+        self.check_writable()?;
+        let buffer_set = &self.version.buffer_set;
+        let buffer_id = match deadline {
+            Some(deadline) => buffer_set.acquire_active_buffer_id_before(deadline).await?,
+            None => buffer_set.acquire_active_buffer_id().await,
+        };
+        Ok(PageTxn {
             guard: self,
+            deadline,
             buffer_id,
             hold_write_guard: false,
             records: HashMap::default(),
             page_ids: Vec::default(),
-        }
+            dealloc_page_ids: Vec::default(),
+        })
     }
 
     /// Returns the address of the corresponding page.
@@ -138,6 +218,68 @@ impl<E: Env> Guard<E> {
         self.page_table.get(id)
     }
 
+    /// Records the delta-chain length of a page that is read.
+    #[inline]
+    pub(crate) fn record_delta_chain(&self, chain_len: u8) {
+        self.delta_chain_hist.record(chain_len);
+    }
+
+    /// Returns true if the page cache has no space for more pages.
+    pub(crate) fn page_cache_is_full(&self) -> bool {
+        let cache = self.page_files.page_cache();
+        cache.usage() >= cache.capacity()
+    }
+
+    /// Returns the ids of files in the current version.
+    pub(crate) fn file_ids(&self) -> Vec<u32> {
+        let mut file_ids = self
+            .version
+            .file_infos()
+            .keys()
+            .cloned()
+            .collect::<Vec<_>>();
+        file_ids.sort_unstable();
+        file_ids
+    }
+
+    /// Returns the addresses of active pages stored in the file.
+    ///
+    /// Returns an empty list if the file is not exists.
+    pub(crate) fn file_pages(&self, file_id: u32) -> Vec<u64> {
+        let Some(file_info) = self.version.file_infos().get(&file_id) else {
+            return Vec::new();
+        };
+        let page_groups = self.version.page_groups();
+        let mut pages = file_info
+            .meta()
+            .page_groups
+            .keys()
+            .filter_map(|group_id| page_groups.get(group_id))
+            .filter(|group| group.meta().file_id == file_id)
+            .flat_map(|group| group.iter())
+            .collect::<Vec<_>>();
+        pages.sort_unstable();
+        pages
+    }
+
+    /// Returns the file that stores the page.
+    ///
+    /// Returns `None` if the page is in a write buffer.
+    pub(crate) fn locate_page(&self, addr: u64) -> Option<PickedFile> {
+        let logical_id = (addr >> 32) as u32;
+        if self.version.get(logical_id).is_some() {
+            return None;
+        }
+        let page_groups = self.version.page_groups();
+        let file_id = page_groups.get(&logical_id)?.meta().file_id;
+        let file_info = self.version.file_infos().get(&file_id)?;
+        let summary = FileSummary::from((page_groups, file_info));
+        Some(PickedFile {
+            file_id,
+            active_size: summary.active_size(),
+        })
+    }
+
     pub(crate) fn read_page_info(&self, addr: u64) -> Result<PageInfo> {
         let logical_id = (addr >> 32) as u32;
         if let Some(buf) = self.version.get(logical_id) {
@@ -165,6 +307,7 @@ impl<E: Env> Guard<E> {
         let logical_id = (addr >> 32) as u32;
         if let Some(buf) = self.version.get(logical_id) {
             self.writebuf_stats.read_in_buf.inc();
+            self.read_source_stats.from_writebuf.inc();
             // Safety: all mutable references are released.
             return Ok((unsafe { buf.page(addr) }, None));
         }
@@ -181,19 +324,33 @@ impl<E: Env> Guard<E> {
         let Some(handle) = page_group.get_page_handle(addr) else {
             panic!("The addr {addr} is not belongs to the target file {physical_id:?}");
         };
+        let is_inner = page_group
+            .get_page_info(addr)
+            .map_or(false, |info| info.tier().is_inner());
+        if let Some(page_heat) = &self.page_heat {
+            if !hint.contains(CacheOption::WARMUP) {
+                page_heat.record(physical_id);
+            }
+        }
 
         let (entry, hit) = self
             .page_files
-            .read_page(physical_id, file_info.meta(), addr, handle, hint)
+            .read_page(physical_id, file_info.meta(), addr, handle, is_inner, hint)
             .await?;
 
         let mut owned_pages = self.cache_guards.lock().expect("Poisoned");
         owned_pages.push(entry);
 
         let last_guard = owned_pages.last().unwrap();
-        let page = last_guard.value();
-        if !hit {
+        let page = self.page_files.cached_page(last_guard.value());
+        if hit {
+            self.read_source_stats.from_page_cache.inc();
+        } else {
+            self.read_source_stats.from_disk.inc();
             self.writebuf_stats.read_file_bytes.add(page.len() as u64);
+            if hint.contains(CacheOption::WARMUP) {
+                self.read_source_stats.warmup_bytes.add(page.len() as u64);
+            }
         }
         let cache_token = last_guard.cache_token();
 
@@ -208,6 +365,20 @@ impl<E: Env> Guard<E> {
 
         Ok((page, Some(cache_token)))
     }
+
+    /// Keeps the value alive until the guard is dropped, like the pages read
+    /// by the guard.
+    ///
+    /// This is used to return values assembled from multiple pages.
+    pub(crate) fn keep_value(&self, value: Vec<u8>) -> &[u8] {
+        let value = value.into_boxed_slice();
+        let (ptr, len) = (value.as_ptr(), value.len());
+        self.owned_values.lock().expect("Poisoned").push(value);
+        unsafe {
+            // Safety: the boxed value is not moved or dropped until the guard is dropped.
+            std::slice::from_raw_parts(ptr, len)
+        }
+    }
 }
 
 /// A transaction to manipulate pages in a page store.
@@ -224,6 +395,7 @@ where
     Self: Send,
 {
     guard: &'a Guard<E>,
+    deadline: Option<Instant>,
 
     buffer_id: u32,
     // We may allocate multiple page buffers inside one PageTxn, for example when we split a tree
@@ -233,6 +405,7 @@ where
     hold_write_guard: bool,
     records: HashMap<u64 /* page addr */, &'a mut RecordHeader>,
     page_ids: Vec<u64>,
+    dealloc_page_ids: Vec<u64>,
 }
 
 impl<'a, E: Env> PageTxn<'a, E> {
@@ -335,6 +508,24 @@ impl<'a, E: Env> PageTxn<'a, E> {
         Ok(())
     }
 
+    /// Deallocates the pages and commits the transaction.
+    ///
+    /// This is used to release pages that are not referenced by any page
+    /// chain, like overflow pages of a removed value.
+    pub(crate) async fn dealloc_pages(mut self, page_addrs: &[u64]) -> Result<()> {
+        self.dealloc_pages_impl(page_addrs).await?;
+        self.commit();
+        Ok(())
+    }
+
+    /// Deallocates the page ids when the transaction commits.
+    ///
+    /// The ids must not be used to look up pages anymore, like the ids of
+    /// overflow pages, which are referenced by their addresses.
+    pub(crate) fn dealloc_page_ids(&mut self, page_ids: impl IntoIterator<Item = u64>) {
+        self.dealloc_page_ids.extend(page_ids);
+    }
+
     #[inline]
     async fn alloc_page_impl(
         &mut self,
@@ -377,11 +568,13 @@ impl<'a, E: Env> PageTxn<'a, E> {
             }
             Err(Error::TooLargeSize) => Err(Error::TooLargeSize),
             Err(Error::Again) => {
-                self.guard
-                    .version
-                    .buffer_set
-                    .switch_buffer(self.buffer_id)
-                    .await;
+                let buffer_set = &self.guard.version.buffer_set;
+                if let Some(deadline) = self.deadline {
+                    // Installing the next buffer can't be abandoned once the buffer is sealed,
+                    // so make sure that it won't stall before sealing.
+                    buffer_set.wait_for_permits_before(deadline).await?;
+                }
+                buffer_set.switch_buffer(self.buffer_id).await;
                 Err(Error::Again)
             }
             _ => unreachable!(),
@@ -391,6 +584,10 @@ impl<'a, E: Env> PageTxn<'a, E> {
     /// Commits the transaction.
     pub(crate) fn commit(mut self) {
         self.page_ids.clear();
+        for id in self.dealloc_page_ids.drain(..) {
+            // TODO: safety conditions.
+            unsafe { self.guard.page_table.dealloc(id) };
+        }
         if self.hold_write_guard {
             self.records.clear();
             self.drop_writer_guard();
@@ -457,8 +654,15 @@ mod tests {
         );
         let version = new_version(512);
         let page_table = PageTable::default();
-        let guard = Guard::new(version.clone(), page_table, files, Default::default());
-        let mut page_txn = guard.begin().await;
+        let guard = Guard::new(
+            version.clone(),
+            page_table,
+            files,
+            Default::default(),
+            Default::default(),
+            Default::default(),
+        );
+        let mut page_txn = guard.begin().await.unwrap();
         let (addr, _) = page_txn.alloc_page(123).await.unwrap();
         let id = page_txn.insert_page(addr);
         let (new, _) = page_txn.alloc_page(123).await.unwrap();
@@ -479,17 +683,24 @@ mod tests {
 
         let version = new_version(1 << 10);
         let page_table = PageTable::default();
-        let guard = Guard::new(version.clone(), page_table, files, Default::default());
+        let guard = Guard::new(
+            version.clone(),
+            page_table,
+            files,
+            Default::default(),
+            Default::default(),
+            Default::default(),
+        );
 
         // insert old page.
-        let mut page_txn = guard.begin().await;
+        let mut page_txn = guard.begin().await.unwrap();
         let (addr, _) = page_txn.alloc_page(123).await.unwrap();
         let id = page_txn.insert_page(addr);
         let (new, _) = page_txn.alloc_page(123).await.unwrap();
         assert!(page_txn.update_page(id, addr, new).is_ok());
 
         // operate is failed.
-        let mut page_txn = guard.begin().await;
+        let mut page_txn = guard.begin().await.unwrap();
         let (addr, _) = page_txn.alloc_page(123).await.unwrap();
         assert!(page_txn.update_page(id, 1, addr).is_err());
 
@@ -508,8 +719,15 @@ mod tests {
 
         let version = new_version(512);
         let page_table = PageTable::default();
-        let guard = Guard::new(version, page_table, files, Default::default());
-        let page_txn = guard.begin().await;
+        let guard = Guard::new(
+            version,
+            page_table,
+            files,
+            Default::default(),
+            Default::default(),
+            Default::default(),
+        );
+        let page_txn = guard.begin().await.unwrap();
         assert!(matches!(page_txn.update_page(1, 3, 2), Err(None)));
     }
 
@@ -525,8 +743,15 @@ mod tests {
 
         let version = new_version(1 << 10);
         let page_table = PageTable::default();
-        let guard = Guard::new(version.clone(), page_table, files, Default::default());
-        let mut page_txn = guard.begin().await;
+        let guard = Guard::new(
+            version.clone(),
+            page_table,
+            files,
+            Default::default(),
+            Default::default(),
+            Default::default(),
+        );
+        let mut page_txn = guard.begin().await.unwrap();
         let (addr, _) = page_txn.alloc_page(123).await.unwrap();
         let id = page_txn.insert_page(addr);
         let (new, _) = page_txn.alloc_page(123).await.unwrap();
@@ -538,6 +763,43 @@ mod tests {
         assert_current_buffer_is_flushable(version);
     }
 
+    #[photonio::test]
+    async fn page_txn_dealloc_page_ids() {
+        let env = crate::env::Photon;
+        let base = tempdir::TempDir::new("test_page_txn_dealloc_page_ids").unwrap();
+        let files = Arc::new(
+            PageFiles::new(env, base.path(), &test_option())
+                .await
+                .unwrap(),
+        );
+
+        let version = new_version(1 << 10);
+        let page_table = PageTable::default();
+        let guard = Guard::new(
+            version,
+            page_table.clone(),
+            files,
+            Default::default(),
+            Default::default(),
+            Default::default(),
+        );
+        let mut page_txn = guard.begin().await.unwrap();
+        let (addr, _) = page_txn.alloc_page(123).await.unwrap();
+        let id = page_txn.insert_page(addr);
+        page_txn.commit();
+
+        // The ids are kept if the transaction aborts.
+        let mut page_txn = guard.begin().await.unwrap();
+        page_txn.dealloc_page_ids([id]);
+        drop(page_txn);
+        assert_ne!(unsafe { page_table.alloc() }, Some(id));
+
+        let mut page_txn = guard.begin().await.unwrap();
+        page_txn.dealloc_page_ids([id]);
+        page_txn.dealloc_pages(&[addr]).await.unwrap();
+        assert_eq!(unsafe { page_table.alloc() }, Some(id));
+    }
+
     impl<'a, E: Env> PageTxn<'a, E> {
         async fn seal_write_buffer(&mut self) {
             self.guard
@@ -560,8 +822,15 @@ mod tests {
 
         let version = new_version(512);
         let page_table = PageTable::default();
-        let guard = Guard::new(version, page_table, files, Default::default());
-        let mut page_txn = guard.begin().await;
+        let guard = Guard::new(
+            version,
+            page_table,
+            files,
+            Default::default(),
+            Default::default(),
+            Default::default(),
+        );
+        let mut page_txn = guard.begin().await.unwrap();
         page_txn.seal_write_buffer().await;
     }
 
@@ -577,9 +846,16 @@ mod tests {
 
         let version = new_version(512);
         let page_table = PageTable::default();
-        let guard = Guard::new(version, page_table, files, Default::default());
-        let mut page_txn_1 = guard.begin().await;
-        let mut page_txn_2 = guard.begin().await;
+        let guard = Guard::new(
+            version,
+            page_table,
+            files,
+            Default::default(),
+            Default::default(),
+            Default::default(),
+        );
+        let mut page_txn_1 = guard.begin().await.unwrap();
+        let mut page_txn_2 = guard.begin().await.unwrap();
         page_txn_1.seal_write_buffer().await;
         page_txn_2.seal_write_buffer().await;
     }
@@ -601,8 +877,10 @@ mod tests {
             page_table.clone(),
             files,
             Default::default(),
+            Default::default(),
+            Default::default(),
         );
-        let mut page_txn = guard.begin().await;
+        let mut page_txn = guard.begin().await.unwrap();
         let (addr, _) = page_txn.alloc_page(123).await.unwrap();
         let id = page_txn.insert_page(addr);
         page_txn.commit();
@@ -1,15 +1,18 @@
 use std::{
     collections::{HashMap, HashSet},
     path::Path,
+    sync::Arc,
 };
 
-use log::debug;
+use log::{debug, warn};
 use rustc_hash::FxHashMap;
+use uuid::Uuid;
 
 use super::{
     page_table::{PageTable, PageTableBuilder},
     version::DeltaVersion,
-    FileInfo, NewFile, PageFiles, PageGroup, PageStore, Result, VersionEdit,
+    CompressionDict, Error, FileInfo, NewFile, PageFiles, PageGroup, PageStore, Result,
+    StoreCaches, StreamEdit, VersionEdit,
 };
 use crate::{env::Env, page_store::Manifest};
 
@@ -32,13 +35,23 @@ struct FileInfoBuilder<'a, E: Env> {
 struct FilesSummary {
     active_files: HashMap<u32, NewFile>,
     obsoleted_files: HashSet<u32>,
+    /// The compression dictionaries in the order they are added.
+    compression_dicts: Vec<Vec<u8>>,
+    /// The table id recorded in the manifest, if any.
+    table_id: Option<Vec<u8>>,
 }
 
 impl<E: Env> PageStore<E> {
+    /// Recovers the page store from the manifest.
+    ///
+    /// If `version` is given, only the edits of the manifest up to the version
+    /// are applied, and neither the manifest nor the page files are modified.
     pub(super) async fn recover<P: AsRef<Path>>(
         env: E,
         path: P,
         options: &crate::PageStoreOptions,
+        caches: Option<StoreCaches<E>>,
+        version: Option<usize>,
     ) -> Result<(
         u32, /* next page file id */
         Manifest<E>,
@@ -46,18 +59,63 @@ impl<E: Env> PageStore<E> {
         PageFiles<E>,
         DeltaVersion,
     )> {
-        let mut manifest = Manifest::open(env.to_owned(), path.as_ref()).await?;
-        let versions = manifest.list_versions().await?;
+        let mut manifest = match version {
+            Some(_) => Manifest::open_read_only(env.to_owned(), path.as_ref()).await?,
+            None => Manifest::open(env.to_owned(), path.as_ref()).await?,
+        };
+        let mut versions = manifest.list_versions().await?;
+        let first = Manifest::<E>::first_version(&versions);
+        let latest = (first + versions.len()).saturating_sub(1);
+        if let Some(version) = version {
+            if version < first || version > latest {
+                return Err(Error::InvalidManifestVersion { version, latest });
+            }
+            versions.truncate(version - first + 1);
+        }
+        manifest.reset_version(latest);
         let summary = Self::apply_version_edits(versions);
         debug!("Recover with file summary {summary:?}");
+        if let Some(table_id) = &summary.table_id {
+            manifest.set_table_id(decode_table_id(table_id)?);
+        }
 
-        let page_files = PageFiles::new(env, path.as_ref(), options).await?;
+        let page_files = PageFiles::open(env, path.as_ref(), options, caches).await?;
+        // A committed file that is missing leaves a gap in the recovered data, which
+        // would expose an inconsistent view. Files of an older version may also have
+        // been reclaimed already.
+        let exist_files = page_files.list_files()?.into_iter().collect::<HashSet<_>>();
+        let mut file_ids = summary.active_files.keys().copied().collect::<Vec<_>>();
+        file_ids.sort_unstable();
+        if let Some(&file_id) = file_ids.iter().find(|id| !exist_files.contains(id)) {
+            warn!("Page file {file_id} of the manifest is missing");
+            return Err(Error::MissingFile { file_id });
+        }
+        if version.is_none() {
+            let mut orphaned_files = exist_files
+                .iter()
+                .copied()
+                .filter(|&id| id >= summary.next_file_id())
+                .collect::<Vec<_>>();
+            if !orphaned_files.is_empty() {
+                orphaned_files.sort_unstable();
+                warn!(
+                    "Discard page files {orphaned_files:?} written after the last committed version"
+                );
+            }
+        }
+        for data in &summary.compression_dicts {
+            let dict = CompressionDict::new(data.clone())?;
+            page_files.compression_dicts().add(dict);
+        }
 
         let mut builder = FileInfoBuilder::new(&page_files);
         Self::recover_page_groups(&mut builder, &summary.active_files).await?;
         let (page_groups, file_infos, page_table) = builder.build();
 
-        Self::delete_unreferenced_page_files(&page_files, &summary).await?;
+        // Files of later versions must be kept when opening an older version.
+        if version.is_none() {
+            Self::delete_unreferenced_page_files(&page_files, &summary).await?;
+        }
 
         let next_file_id = summary.next_file_id();
         manifest.reset_next_file_id(summary.next_file_id());
@@ -69,10 +127,88 @@ impl<E: Env> PageStore<E> {
         Ok((next_file_id, manifest, page_table, page_files, delta))
     }
 
+    /// Rebuilds the manifest from the page files in the path, so that the page
+    /// table is recovered from all of them on the next open.
+    ///
+    /// Every page file whose metadata is readable is recorded as a live file,
+    /// and the ids of the others are returned. Compression dictionaries are
+    /// kept if the old manifest is readable.
+    pub(crate) async fn rebuild_manifest<P: AsRef<Path>>(
+        env: E,
+        path: P,
+        options: &crate::PageStoreOptions,
+    ) -> Result<Vec<u32>> {
+        let mut manifest = Manifest::open(env.to_owned(), path.as_ref()).await?;
+        let compression_dicts = match manifest.list_versions().await {
+            Ok(versions) => {
+                // The versions of the new manifest follow the old ones.
+                let first = Manifest::<E>::first_version(&versions);
+                manifest.reset_version((first + versions.len()).saturating_sub(1));
+                // The table keeps its id if it is readable.
+                let table_id = versions
+                    .iter()
+                    .rev()
+                    .find_map(|edit| edit.table_id.as_deref());
+                if let Some(Ok(table_id)) = table_id.map(decode_table_id) {
+                    manifest.set_table_id(table_id);
+                }
+                versions
+                    .into_iter()
+                    .flat_map(|edit| edit.compression_dicts)
+                    .collect()
+            }
+            Err(err) => {
+                warn!("Rebuild manifest without compression dictionaries: {err:?}");
+                Vec::new()
+            }
+        };
+
+        let page_files = PageFiles::open(env, path.as_ref(), options, None).await?;
+        let mut file_ids = page_files.list_files()?;
+        file_ids.sort_unstable();
+        let mut new_files = Vec::with_capacity(file_ids.len());
+        let mut skipped_files = Vec::new();
+        for file_id in file_ids {
+            if let Err(err) = page_files.read_file_meta(file_id).await {
+                warn!("Skip page file {file_id} with unreadable metadata: {err:?}");
+                skipped_files.push(file_id);
+                continue;
+            }
+            // The key and LSN ranges are only kept in the manifest.
+            new_files.push(NewFile {
+                id: file_id,
+                up1: file_id,
+                up2: file_id,
+                ..Default::default()
+            });
+        }
+
+        let edit = VersionEdit {
+            file_stream: Some(StreamEdit {
+                new_files,
+                deleted_files: Vec::new(),
+            }),
+            compression_dicts,
+            table_id: None,
+            version: None,
+        };
+        // The manifest is rolled on the first edit after opening, so the edit
+        // replaces all the previous ones.
+        manifest
+            .record_version_edit(edit, VersionEdit::default)
+            .await?;
+        Ok(skipped_files)
+    }
+
     fn apply_version_edits(versions: Vec<VersionEdit>) -> FilesSummary {
         let mut active_files = HashMap::new();
         let mut obsoleted_files = HashSet::new();
+        let mut compression_dicts = Vec::new();
+        let mut table_id = None;
         for edit in versions {
+            if edit.table_id.is_some() {
+                table_id = edit.table_id;
+            }
             if let Some(edit) = edit.file_stream {
                 for file in edit.new_files {
                     active_files.insert(file.id, file);
@@ -82,11 +218,14 @@ impl<E: Env> PageStore<E> {
                     obsoleted_files.insert(file);
                 }
             }
+            compression_dicts.extend(edit.compression_dicts.into_iter().map(|dict| dict.data));
         }
 
         FilesSummary {
             active_files,
             obsoleted_files,
+            compression_dicts,
+            table_id,
         }
     }
 
@@ -163,7 +302,13 @@ impl<'a, E: Env> FileInfoBuilder<'a, E> {
                 .insert(file.id, meta_reader.dealloc_pages);
         }
 
-        let file_meta = meta_reader.file_meta;
+        let mut file_meta = meta_reader.file_meta;
+        {
+            let file_meta =
+                Arc::get_mut(&mut file_meta).expect("The file meta is not shared during recovery");
+            file_meta.key_range = file.key_range();
+            file_meta.lsn_range = file.lsn_range();
+        }
         self.file_infos
             .insert(file.id, FileInfo::new(file.up1, file.up2, file_meta));
 
@@ -223,11 +368,16 @@ impl FilesSummary {
     }
 }
 
+fn decode_table_id(bytes: &[u8]) -> Result<Uuid> {
+    Uuid::from_slice(bytes).map_err(|_| Error::Corrupted)
+}
+
 impl std::fmt::Debug for FilesSummary {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         f.debug_struct("FilesSummary")
             .field("files", &self.active_files.keys())
             .field("obsoleted_files", &self.obsoleted_files)
+            .field("compression_dicts", &self.compression_dicts.len())
             .finish()
     }
 }
@@ -1,4 +1,4 @@
-use std::fmt::Display;
+use std::{fmt::Display, sync::Arc};
 
 use crate::util::atomic::Counter;
 
@@ -6,15 +6,33 @@ use crate::util::atomic::Counter;
 #[derive(Clone, Default)]
 pub struct StoreStats {
     /// Statistics of page cache.
+    ///
+    /// It only caches data pages if there is a separate index cache.
     pub page_cache: CacheStats,
+    /// Statistics of the separate cache for index pages, which are zero if
+    /// [`PageStoreOptions::index_cache_capacity`] is not set.
+    ///
+    /// [`PageStoreOptions::index_cache_capacity`]: crate::PageStoreOptions::index_cache_capacity
+    pub index_cache: CacheStats,
+    /// Statistics of each page cache shard.
+    #[cfg(feature = "detailed-stats")]
+    pub page_cache_shards: Vec<CacheStats>,
     /// Statistics of file reader cache.
     pub file_reader_cache: CacheStats,
     /// Statistics of writebuf.
     pub writebuf: WritebufStats,
+    /// Statistics of where page reads are served from.
+    pub read_source: ReadSourceStats,
+    /// Histogram of delta-chain lengths sampled during reads.
+    pub delta_chain_hist: DeltaChainHistogram,
     /// Statistics of jobs.
     pub jobs: JobStats,
     /// Statistics of buffer set.
     pub buffer_set: BufferSetStats,
+    /// Statistics of memory usage.
+    pub memory: MemoryStats,
+    /// Statistics of files.
+    pub files: FileStats,
 }
 
 impl StoreStats {
@@ -22,17 +40,36 @@ impl StoreStats {
     pub fn sub(&self, o: &StoreStats) -> StoreStats {
         StoreStats {
             page_cache: self.page_cache.sub(&o.page_cache),
+            index_cache: self.index_cache.sub(&o.index_cache),
+            #[cfg(feature = "detailed-stats")]
+            page_cache_shards: self
+                .page_cache_shards
+                .iter()
+                .zip(&o.page_cache_shards)
+                .map(|(a, b)| a.sub(b))
+                .collect(),
             file_reader_cache: self.file_reader_cache.sub(&o.file_reader_cache),
             writebuf: self.writebuf.sub(&o.writebuf),
+            read_source: self.read_source.sub(&o.read_source),
+            delta_chain_hist: self.delta_chain_hist.sub(&o.delta_chain_hist),
             jobs: self.jobs.sub(&o.jobs),
             buffer_set: self.buffer_set.sub(&o.buffer_set),
+            memory: self.memory.clone(),
+            files: self.files.clone(),
         }
     }
+
+    /// Sub other stats to produce a [`StatsDelta`].
+    pub fn delta(&self, o: &StoreStats) -> StatsDelta {
+        StatsDelta(self.sub(o))
+    }
 }
 
 impl Display for StoreStats {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         self.writebuf.fmt(f)?;
+        self.read_source.fmt(f)?;
+        self.delta_chain_hist.fmt(f)?;
         writeln!(
             f,
             "PageCacheStats: lookup_hit: {}, lookup_miss: {}, hit_rate: {:.2}%, insert: {}, active_evict: {}, passive_evict: {}, recommendation: {:?}",
@@ -45,6 +82,23 @@ impl Display for StoreStats {
             self.page_cache.passive_evict,
             self.page_cache.recommendation,
         )?;
+        writeln!(
+            f,
+            "IndexCacheStats: lookup_hit: {}, lookup_miss: {}, insert: {}, active_evict: {}, passive_evict: {}",
+            self.index_cache.lookup_hit,
+            self.index_cache.lookup_miss,
+            self.index_cache.insert,
+            self.index_cache.active_evict,
+            self.index_cache.passive_evict,
+        )?;
+        #[cfg(feature = "detailed-stats")]
+        for (i, shard) in self.page_cache_shards.iter().enumerate() {
+            writeln!(
+                f,
+                "PageCacheShardStats: shard: {}, lookup_hit: {}, lookup_miss: {}, insert: {}",
+                i, shard.lookup_hit, shard.lookup_miss, shard.insert,
+            )?;
+        }
         writeln!(
             f,
             "FileReaderCacheStats: lookup_hit: {}, lookup_miss: {}, hit_rate: {}%, insert: {}, active_evict: {}, passive_evict: {}, recommendation: {:?}",
@@ -58,10 +112,155 @@ impl Display for StoreStats {
             self.file_reader_cache.recommendation,
         )?;
         self.buffer_set.fmt(f)?;
+        self.memory.fmt(f)?;
+        self.files.fmt(f)?;
         self.jobs.fmt(f)
     }
 }
 
+/// The difference between two [`StoreStats`].
+///
+/// This is mainly used to assert the side effects of operations in tests.
+#[derive(Clone, Default)]
+pub struct StatsDelta(StoreStats);
+
+impl StatsDelta {
+    /// Returns the underlying stats.
+    pub fn stats(&self) -> &StoreStats {
+        &self.0
+    }
+
+    /// Returns the number of page cache hits.
+    pub fn page_cache_hits(&self) -> u64 {
+        self.0.page_cache.lookup_hit
+    }
+
+    /// Returns the number of page cache misses.
+    pub fn page_cache_misses(&self) -> u64 {
+        self.0.page_cache.lookup_miss
+    }
+
+    /// Returns the number of pages inserted into the page cache.
+    pub fn page_cache_inserts(&self) -> u64 {
+        self.0.page_cache.insert
+    }
+
+    /// Returns the number of pages read from write buffers.
+    pub fn reads_from_writebuf(&self) -> u64 {
+        self.0.read_source.from_writebuf
+    }
+
+    /// Returns the number of pages read from the page cache.
+    pub fn reads_from_page_cache(&self) -> u64 {
+        self.0.read_source.from_page_cache
+    }
+
+    /// Returns the number of pages read from page files.
+    pub fn reads_from_disk(&self) -> u64 {
+        self.0.read_source.from_disk
+    }
+
+    /// Returns the number of stalling writes.
+    pub fn stall_writes(&self) -> u64 {
+        self.0.buffer_set.stall_writes
+    }
+
+    /// Returns all counters by name.
+    pub fn counters(&self) -> Vec<(&'static str, u64)> {
+        let s = &self.0;
+        vec![
+            ("page_cache.lookup_hit", s.page_cache.lookup_hit),
+            ("page_cache.lookup_miss", s.page_cache.lookup_miss),
+            ("page_cache.insert", s.page_cache.insert),
+            ("page_cache.active_evict", s.page_cache.active_evict),
+            ("page_cache.passive_evict", s.page_cache.passive_evict),
+            (
+                "file_reader_cache.lookup_hit",
+                s.file_reader_cache.lookup_hit,
+            ),
+            (
+                "file_reader_cache.lookup_miss",
+                s.file_reader_cache.lookup_miss,
+            ),
+            ("file_reader_cache.insert", s.file_reader_cache.insert),
+            (
+                "file_reader_cache.active_evict",
+                s.file_reader_cache.active_evict,
+            ),
+            (
+                "file_reader_cache.passive_evict",
+                s.file_reader_cache.passive_evict,
+            ),
+            ("writebuf.read_in_buf", s.writebuf.read_in_buf),
+            ("writebuf.read_in_file", s.writebuf.read_in_file),
+            ("writebuf.read_file_bytes", s.writebuf.read_file_bytes),
+            ("writebuf.miss_inner", s.writebuf.miss_inner),
+            ("read_source.from_writebuf", s.read_source.from_writebuf),
+            ("read_source.from_page_cache", s.read_source.from_page_cache),
+            ("read_source.from_disk", s.read_source.from_disk),
+            ("read_source.warmup_bytes", s.read_source.warmup_bytes),
+            ("delta_chain_hist.len_1", s.delta_chain_hist.len_1),
+            ("delta_chain_hist.len_2_4", s.delta_chain_hist.len_2_4),
+            ("delta_chain_hist.len_5_8", s.delta_chain_hist.len_5_8),
+            ("delta_chain_hist.len_9_16", s.delta_chain_hist.len_9_16),
+            (
+                "delta_chain_hist.len_over_16",
+                s.delta_chain_hist.len_over_16,
+            ),
+            ("jobs.flush_write_bytes", s.jobs.flush_write_bytes),
+            ("jobs.flush_discard_bytes", s.jobs.flush_discard_bytes),
+            ("jobs.compact_write_bytes", s.jobs.compact_write_bytes),
+            ("jobs.compact_input_bytes", s.jobs.compact_input_bytes),
+            ("jobs.read_file_bytes", s.jobs.read_file_bytes),
+            ("jobs.reclaim_runs", s.jobs.reclaim_runs),
+            ("jobs.files_reclaimed", s.jobs.files_reclaimed),
+            ("jobs.reclaim_bytes_freed", s.jobs.reclaim_bytes_freed),
+            ("buffer_set.stall_writes", s.buffer_set.stall_writes),
+            (
+                "buffer_set.stall_intervals_ms",
+                s.buffer_set.stall_intervals_ms,
+            ),
+        ]
+    }
+
+    /// Returns true if no counter has changed.
+    pub fn is_empty(&self) -> bool {
+        self.counters().iter().all(|(_, v)| *v == 0)
+    }
+
+    /// Asserts that no page was read from page files.
+    #[track_caller]
+    pub fn assert_no_disk_reads(&self) {
+        assert_eq!(self.reads_from_disk(), 0, "unexpected disk reads: {self}");
+    }
+
+    /// Asserts the number of page cache hits and misses.
+    #[track_caller]
+    pub fn assert_page_cache(&self, hits: u64, misses: u64) {
+        assert_eq!(
+            (self.page_cache_hits(), self.page_cache_misses()),
+            (hits, misses),
+            "unexpected page cache (hits, misses): {self}"
+        );
+    }
+}
+
+impl Display for StatsDelta {
+    /// Only shows the counters that have changed.
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "StatsDelta {{")?;
+        let mut empty = true;
+        for (name, value) in self.counters() {
+            if value != 0 {
+                let sep = if empty { " " } else { ", " };
+                write!(f, "{sep}{name}: +{value}")?;
+                empty = false;
+            }
+        }
+        write!(f, " }}")
+    }
+}
+
 /// Statistics of cache.
 #[derive(Default, Clone, Debug)]
 pub struct CacheStats {
@@ -151,6 +350,139 @@ impl AtomicWritebufStats {
     }
 }
 
+/// Statistics of where page reads are served from.
+#[derive(Default, Clone, Debug, Copy)]
+pub struct ReadSourceStats {
+    /// The number of pages read from write buffers.
+    pub from_writebuf: u64,
+    /// The number of pages read from the page cache.
+    pub from_page_cache: u64,
+    /// The number of pages read from page files.
+    pub from_disk: u64,
+    /// The bytes of pages read from page files to warm up the page cache.
+    pub warmup_bytes: u64,
+    /// The number of reads of page files retried after transient I/O errors.
+    pub read_retries: u64,
+}
+
+impl ReadSourceStats {
+    /// Sub other stats to produce an new stats.
+    pub fn sub(&self, o: &ReadSourceStats) -> ReadSourceStats {
+        ReadSourceStats {
+            from_writebuf: self.from_writebuf.wrapping_sub(o.from_writebuf),
+            from_page_cache: self.from_page_cache.wrapping_sub(o.from_page_cache),
+            from_disk: self.from_disk.wrapping_sub(o.from_disk),
+            warmup_bytes: self.warmup_bytes.wrapping_sub(o.warmup_bytes),
+            read_retries: self.read_retries.wrapping_sub(o.read_retries),
+        }
+    }
+}
+
+impl Display for ReadSourceStats {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(
+            f,
+            "ReadSourceStats: from_writebuf: {}, from_page_cache: {}, from_disk: {}, \
+                warmup_bytes: {}, read_retries: {}",
+            self.from_writebuf,
+            self.from_page_cache,
+            self.from_disk,
+            self.warmup_bytes,
+            self.read_retries,
+        )
+    }
+}
+
+#[derive(Default)]
+pub(crate) struct AtomicReadSourceStats {
+    pub(super) from_writebuf: Counter,
+    pub(super) from_page_cache: Counter,
+    pub(super) from_disk: Counter,
+    pub(super) warmup_bytes: Counter,
+    /// Shared with the file readers, which retry the reads.
+    pub(super) read_retries: Arc<Counter>,
+}
+
+impl AtomicReadSourceStats {
+    pub(super) fn snapshot(&self) -> ReadSourceStats {
+        ReadSourceStats {
+            from_writebuf: self.from_writebuf.get(),
+            from_page_cache: self.from_page_cache.get(),
+            from_disk: self.from_disk.get(),
+            warmup_bytes: self.warmup_bytes.get(),
+            read_retries: self.read_retries.get(),
+        }
+    }
+}
+
+/// Histogram of delta-chain lengths of the pages sampled during reads.
+///
+/// A chain of length 1 is a consolidated page without deltas.
+#[derive(Default, Clone, Debug, Copy)]
+pub struct DeltaChainHistogram {
+    /// The number of chains of length 1.
+    pub len_1: u64,
+    /// The number of chains of length 2 to 4.
+    pub len_2_4: u64,
+    /// The number of chains of length 5 to 8.
+    pub len_5_8: u64,
+    /// The number of chains of length 9 to 16.
+    pub len_9_16: u64,
+    /// The number of chains longer than 16.
+    pub len_over_16: u64,
+}
+
+impl DeltaChainHistogram {
+    /// Sub other stats to produce an new stats.
+    pub fn sub(&self, o: &DeltaChainHistogram) -> DeltaChainHistogram {
+        DeltaChainHistogram {
+            len_1: self.len_1.wrapping_sub(o.len_1),
+            len_2_4: self.len_2_4.wrapping_sub(o.len_2_4),
+            len_5_8: self.len_5_8.wrapping_sub(o.len_5_8),
+            len_9_16: self.len_9_16.wrapping_sub(o.len_9_16),
+            len_over_16: self.len_over_16.wrapping_sub(o.len_over_16),
+        }
+    }
+}
+
+impl Display for DeltaChainHistogram {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(
+            f,
+            "DeltaChainHistogram: 1: {}, 2-4: {}, 5-8: {}, 9-16: {}, >16: {}",
+            self.len_1, self.len_2_4, self.len_5_8, self.len_9_16, self.len_over_16,
+        )
+    }
+}
+
+#[derive(Default)]
+pub(crate) struct AtomicDeltaChainHistogram {
+    buckets: [Counter; 5],
+}
+
+impl AtomicDeltaChainHistogram {
+    pub(super) fn record(&self, chain_len: u8) {
+        let bucket = match chain_len {
+            0..=1 => 0,
+            2..=4 => 1,
+            5..=8 => 2,
+            9..=16 => 3,
+            _ => 4,
+        };
+        self.buckets[bucket].inc();
+    }
+
+    pub(super) fn snapshot(&self) -> DeltaChainHistogram {
+        DeltaChainHistogram {
+            len_1: self.buckets[0].get(),
+            len_2_4: self.buckets[1].get(),
+            len_5_8: self.buckets[2].get(),
+            len_9_16: self.buckets[3].get(),
+            len_over_16: self.buckets[4].get(),
+        }
+    }
+}
+
 #[derive(Debug, Copy, Clone, Default)]
 pub struct JobStats {
     /// The total write bytes during flush.
@@ -163,6 +495,24 @@ pub struct JobStats {
     pub compact_input_bytes: u64,
     /// The total bytes read during compaction.
     pub read_file_bytes: u64,
+    /// The total number of reclaiming runs which rewrite files.
+    pub reclaim_runs: u64,
+    /// The total number of files reclaimed.
+    pub files_reclaimed: u64,
+    /// The total bytes freed by reclaiming.
+    pub reclaim_bytes_freed: u64,
+    /// The total number of active pages moved to new files by reclaiming.
+    pub reclaim_pages_moved: u64,
+    /// The total number of dead pages dropped by reclaiming instead of being
+    /// moved.
+    pub reclaim_pages_dropped: u64,
+    /// The total time spent in flushing write buffers in nanoseconds.
+    pub flush_nanos: u64,
+    /// The total time spent in compacting files in nanoseconds.
+    pub compact_nanos: u64,
+    /// The total time spent in reclaiming runs in nanoseconds, which
+    /// includes the time of compaction.
+    pub reclaim_nanos: u64,
 }
 
 #[derive(Default, Debug)]
@@ -172,6 +522,14 @@ pub(crate) struct AtomicJobStats {
     pub(super) compact_write_bytes: Counter,
     pub(super) compact_input_bytes: Counter,
     pub(super) read_file_bytes: Counter,
+    pub(super) reclaim_runs: Counter,
+    pub(super) files_reclaimed: Counter,
+    pub(super) reclaim_bytes_freed: Counter,
+    pub(super) reclaim_pages_moved: Counter,
+    pub(super) reclaim_pages_dropped: Counter,
+    pub(super) flush_nanos: Counter,
+    pub(super) compact_nanos: Counter,
+    pub(super) reclaim_nanos: Counter,
 }
 
 impl JobStats {
@@ -182,10 +540,50 @@ impl JobStats {
             compact_write_bytes: self.compact_write_bytes.wrapping_sub(o.compact_write_bytes),
             compact_input_bytes: self.compact_input_bytes.wrapping_sub(o.compact_input_bytes),
             read_file_bytes: self.read_file_bytes.wrapping_sub(o.read_file_bytes),
+            reclaim_runs: self.reclaim_runs.wrapping_sub(o.reclaim_runs),
+            files_reclaimed: self.files_reclaimed.wrapping_sub(o.files_reclaimed),
+            reclaim_bytes_freed: self.reclaim_bytes_freed.wrapping_sub(o.reclaim_bytes_freed),
+            reclaim_pages_moved: self.reclaim_pages_moved.wrapping_sub(o.reclaim_pages_moved),
+            reclaim_pages_dropped: self
+                .reclaim_pages_dropped
+                .wrapping_sub(o.reclaim_pages_dropped),
+            flush_nanos: self.flush_nanos.wrapping_sub(o.flush_nanos),
+            compact_nanos: self.compact_nanos.wrapping_sub(o.compact_nanos),
+            reclaim_nanos: self.reclaim_nanos.wrapping_sub(o.reclaim_nanos),
+        }
+    }
+
+    /// Returns the bytes written by flush per second of flushing.
+    pub fn flush_throughput(&self) -> f64 {
+        throughput(self.flush_write_bytes, self.flush_nanos)
+    }
+
+    /// Returns the bytes written by compaction per second of compaction.
+    pub fn compact_throughput(&self) -> f64 {
+        throughput(self.compact_write_bytes, self.compact_nanos)
+    }
+
+    /// Returns the ratio of dropped pages to all pages visited by
+    /// reclaiming, or zero if no page is visited.
+    pub fn reclaim_efficiency(&self) -> f64 {
+        let total = self.reclaim_pages_moved + self.reclaim_pages_dropped;
+        if total == 0 {
+            0.0
+        } else {
+            self.reclaim_pages_dropped as f64 / total as f64
         }
     }
 }
 
+// Returns the bytes per second, or zero if no time is spent.
+fn throughput(bytes: u64, nanos: u64) -> f64 {
+    if nanos == 0 {
+        0.0
+    } else {
+        bytes as f64 * 1e9 / nanos as f64
+    }
+}
+
 impl Display for JobStats {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         let input_bytes = self.compact_input_bytes;
@@ -202,13 +600,35 @@ impl Display for JobStats {
             compact_input_bytes: {}, \
             compact_write_bytes: {}, \
             read_file_bytes: {}, \
-            write_amp: {:.2}",
+            write_amp: {:.2}, \
+            reclaim_runs: {}, \
+            files_reclaimed: {}, \
+            reclaim_bytes_freed: {}, \
+            reclaim_pages_moved: {}, \
+            reclaim_pages_dropped: {}, \
+            reclaim_efficiency: {:.2}, \
+            flush_nanos: {}, \
+            compact_nanos: {}, \
+            reclaim_nanos: {}, \
+            flush_throughput: {:.2}, \
+            compact_throughput: {:.2}",
             self.flush_write_bytes,
             self.flush_discard_bytes,
             self.compact_input_bytes,
             self.compact_write_bytes,
             self.read_file_bytes,
-            write_amp
+            write_amp,
+            self.reclaim_runs,
+            self.files_reclaimed,
+            self.reclaim_bytes_freed,
+            self.reclaim_pages_moved,
+            self.reclaim_pages_dropped,
+            self.reclaim_efficiency(),
+            self.flush_nanos,
+            self.compact_nanos,
+            self.reclaim_nanos,
+            self.flush_throughput(),
+            self.compact_throughput(),
         )
     }
 }
@@ -221,6 +641,14 @@ impl AtomicJobStats {
             compact_write_bytes: self.compact_write_bytes.get(),
             compact_input_bytes: self.compact_input_bytes.get(),
             read_file_bytes: self.read_file_bytes.get(),
+            reclaim_runs: self.reclaim_runs.get(),
+            files_reclaimed: self.files_reclaimed.get(),
+            reclaim_bytes_freed: self.reclaim_bytes_freed.get(),
+            reclaim_pages_moved: self.reclaim_pages_moved.get(),
+            reclaim_pages_dropped: self.reclaim_pages_dropped.get(),
+            flush_nanos: self.flush_nanos.get(),
+            compact_nanos: self.compact_nanos.get(),
+            reclaim_nanos: self.reclaim_nanos.get(),
         }
     }
 }
@@ -252,6 +680,83 @@ impl Display for BufferSetStats {
     }
 }
 
+/// Statistics of memory usage.
+///
+/// Unlike other statistics, these are the current values rather than
+/// cumulative counters.
+#[derive(Default, Clone, Debug)]
+pub struct MemoryStats {
+    /// The total memory budget, or 0 if there is no budget.
+    pub budget: usize,
+    /// The memory used by write buffers.
+    pub writebuf: usize,
+    /// The capacity of the page cache.
+    pub page_cache: usize,
+    /// The capacity of the separate index cache, or 0 if there is none.
+    pub index_cache: usize,
+}
+
+impl Display for MemoryStats {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(
+            f,
+            "Memory: budget: {} writebuf: {} page_cache: {} index_cache: {}",
+            self.budget, self.writebuf, self.page_cache, self.index_cache,
+        )
+    }
+}
+
+/// Statistics of files in the current version.
+///
+/// Like [`MemoryStats`], these are the current values.
+#[derive(Default, Clone, Debug)]
+pub struct FileStats {
+    /// The number of page files, each is a group of pages written by a flush.
+    pub page_files: usize,
+    /// The number of map files, each holds one or more page files.
+    pub map_files: usize,
+    /// The number of live leaf pages in page files that are the base of their
+    /// delta chains.
+    pub leaf_base_pages: usize,
+    /// The total size of live leaf base pages in page files.
+    pub leaf_base_page_size: usize,
+    /// The average size of live leaf base pages in page files relative to
+    /// [`TableOptions::page_size`], or 0 if there is no such page.
+    ///
+    /// A low fill factor suggests that pages are split too often, or that
+    /// many entries have been deleted. It is only known to tables, and is 0
+    /// in the stats logged by page stores.
+    ///
+    /// [`TableOptions::page_size`]: crate::TableOptions::page_size
+    pub page_fill_factor: f64,
+}
+
+impl FileStats {
+    /// Sets [`FileStats::page_fill_factor`] relative to `page_size`.
+    pub(crate) fn set_page_fill_factor(&mut self, page_size: usize) {
+        self.page_fill_factor = if self.leaf_base_pages == 0 || page_size == 0 {
+            0.0
+        } else {
+            self.leaf_base_page_size as f64 / (self.leaf_base_pages * page_size) as f64
+        };
+    }
+}
+
+impl Display for FileStats {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(
+            f,
+            "Files: page_files: {} map_files: {} leaf_base_pages: {} leaf_base_page_size: {} \
+                page_fill_factor: {:.2}",
+            self.page_files,
+            self.map_files,
+            self.leaf_base_pages,
+            self.leaf_base_page_size,
+            self.page_fill_factor,
+        )
+    }
+}
+
 #[derive(Default, Debug)]
 pub(crate) struct AtomicBufferSetStats {
     pub(crate) stall_writes: Counter,
@@ -266,3 +771,47 @@ impl AtomicBufferSetStats {
         }
     }
 }
+
+/// An overview of the space used by the page files of a table.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct SpaceReport {
+    /// The total size of page files in bytes.
+    pub total_bytes: u64,
+    /// The size of active pages in bytes, which are kept when files are
+    /// reclaimed.
+    pub live_bytes: u64,
+    /// The size of inactive pages in bytes, which are freed when files are
+    /// reclaimed.
+    ///
+    /// The rest of `total_bytes` is taken by the metadata of files.
+    pub reclaimable_bytes: u64,
+    /// The space amplification, which is `total_bytes / live_bytes`, or zero
+    /// if there are no live bytes.
+    pub amplification: f64,
+}
+
+impl SpaceReport {
+    /// Returns the percentage of `total_bytes` that is reclaimable.
+    pub fn reclaimable_percent(&self) -> f64 {
+        if self.total_bytes == 0 {
+            0.0
+        } else {
+            self.reclaimable_bytes as f64 * 100.0 / self.total_bytes as f64
+        }
+    }
+}
+
+impl Display for SpaceReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(
+            f,
+            "SpaceReport: total_bytes: {}, live_bytes: {}, reclaimable_bytes: {}, \
+            reclaimable_percent: {:.2}, amplification: {:.2}",
+            self.total_bytes,
+            self.live_bytes,
+            self.reclaimable_bytes,
+            self.reclaimable_percent(),
+            self.amplification,
+        )
+    }
+}
@@ -1,30 +1,184 @@
-use std::collections::HashMap;
+use std::{
+    collections::HashMap,
+    fmt,
+    ops::Range,
+    sync::{Arc, Mutex},
+};
 
 use rustc_hash::FxHashMap;
 
-use super::{FileInfo, PageGroup};
+use super::{
+    stats::{AtomicJobStats, JobStats},
+    FileInfo, KeyRange, PageGroup, EMPTY_PAGES_RATE_WITHOUT_PAGES,
+};
 
-pub(crate) trait StrategyBuilder: Send + Sync {
+/// The multiplier to boost the score of files overlapping the hot range.
+const HOT_RANGE_SCORE_MULTIPLIER: f64 = 2.0;
+
+/// The amount that [`AdaptiveStrategyBuilder`] adjusts alpha by each time.
+const ADAPTIVE_ALPHA_STEP: f64 = 0.1;
+
+/// The effective rate of a file without any page size, nothing in it needs
+/// to be rewritten.
+const EFFECTIVE_RATE_WITHOUT_PAGES: f64 = 0.0;
+
+/// The strategy to pick files for space reclaiming.
+#[non_exhaustive]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ReclaimStrategy {
+    /// Picks the files whose free space declines the slowest first.
+    ///
+    /// This is the default strategy. It balances the free space of files and
+    /// how frequently they are updated.
+    MinDeclineRate,
+    /// Picks the files with the most free space first.
+    MaxFreeSpace,
+    /// Blends the ranks of files under [`ReclaimStrategy::MinDeclineRate`]
+    /// and [`ReclaimStrategy::MaxFreeSpace`].
+    ///
+    /// The decline-rate rank is weighted by alpha and the free-space rank by
+    /// `1 - alpha`. Alpha starts at `alpha_percent` and is adjusted
+    /// gradually towards the lower write amplification observed.
+    Adaptive {
+        /// The initial alpha in percent, from 0 to 100.
+        alpha_percent: u8,
+    },
+}
+
+/// A file picked by the reclaim strategy.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct PickedFile {
+    /// The id of the file.
+    pub file_id: u32,
+    /// The size of active pages in the file, which are rewritten when the
+    /// file is reclaimed.
+    pub active_size: usize,
+}
+
+/// Builds a [`ReclaimPickStrategy`] for each reclaim cycle.
+///
+/// Custom strategies can be plugged in with
+/// [`Options::reclaim_strategy`](crate::PageStoreOptions::reclaim_strategy).
+pub trait StrategyBuilder: Send + Sync {
+    /// Builds a strategy to pick files at the logical time `now`, which is
+    /// comparable with [`FileSummary::up2`].
     fn build(&self, now: u32) -> Box<dyn ReclaimPickStrategy>;
 }
 
+impl fmt::Debug for dyn StrategyBuilder {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("StrategyBuilder").finish_non_exhaustive()
+    }
+}
+
 /// An abstraction describes the strategy of page files reclaiming.
-pub(crate) trait ReclaimPickStrategy: Send + Sync {
-    /// Collect map file info and compute reclamation score.
-    fn collect_file(&mut self, page_groups: &FxHashMap<u32, PageGroup>, file_info: &FileInfo);
+///
+/// All files of the store are collected before the first
+/// [`ReclaimPickStrategy::apply`].
+pub trait ReclaimPickStrategy: Send + Sync {
+    /// Collect the summary of a file and compute its reclamation score.
+    fn collect_file(&mut self, summary: &FileSummary);
+
+    /// Return the most suitable file for reclaiming under the strategy, or
+    /// `None` if no more file should be reclaimed.
+    fn apply(&mut self) -> Option<PickedFile>;
 
-    /// Return the most suitable files for reclaiming under the strategy.
-    fn apply(&mut self) -> Option<(u32, usize /* active size */)>;
+    /// Returns the score of the file returned by the last
+    /// [`ReclaimPickStrategy::apply`], the higher the better to reclaim.
+    ///
+    /// The score is only used for reporting.
+    fn last_score(&self) -> Option<f64> {
+        None
+    }
 }
 
 pub(crate) struct MinDeclineRateStrategy {
     now: u32,
+    min_files_retained: usize,
+    hot_range: Option<Range<Vec<u8>>>,
+    write_amplify_term: bool,
+    age_term: bool,
+    min_free_ratio: f64,
 
     sorted: bool,
     scores: Vec<FileScore>,
+    // The number of files skipped for having too little free space.
+    num_skipped: usize,
+    last_score: Option<f64>,
+}
+
+/// A [`StrategyBuilder`] for [`ReclaimStrategy::MinDeclineRate`].
+///
+/// The terms of the score can be disabled individually to compare their
+/// effect on the picked files. A disabled term contributes neutrally, as if
+/// it was the same for all files.
+pub struct MinDeclineRateStrategyBuilder {
+    /// The minimum number of files retained, the strategy will not pick any
+    /// file if there are not more files than it.
+    min_files_retained: usize,
+    /// Files overlapping this range are preferred for reclaiming.
+    hot_range: Option<Range<Vec<u8>>>,
+    /// Whether files with the same score are ordered by write amplification.
+    write_amplify_term: bool,
+    /// Whether the decline rate accounts for the time since a file was last
+    /// updated.
+    age_term: bool,
+    /// Files whose empty pages rate is below this are never picked.
+    min_free_ratio: f64,
 }
 
-pub(crate) struct MinDeclineRateStrategyBuilder;
+pub(crate) struct MaxFreeSpaceStrategy {
+    min_files_retained: usize,
+
+    sorted: bool,
+    // Pairs of free size, file id and active size.
+    files: Vec<(usize, u32, usize)>,
+    last_score: Option<f64>,
+}
+
+pub(crate) struct MaxFreeSpaceStrategyBuilder {
+    /// The minimum number of files retained, the strategy will not pick any
+    /// file if there are not more files than it.
+    min_files_retained: usize,
+}
+
+pub(crate) struct AdaptiveStrategy {
+    alpha: f64,
+    min_files_retained: usize,
+    decline_rate: MinDeclineRateStrategy,
+    free_space: MaxFreeSpaceStrategy,
+
+    sorted: bool,
+    // Pairs of blended score, file id and active size.
+    files: Vec<(f64, u32, usize)>,
+    last_score: Option<f64>,
+}
+
+pub(crate) struct AdaptiveStrategyBuilder {
+    /// The minimum number of files retained, the strategy will not pick any
+    /// file if there are not more files than it.
+    min_files_retained: usize,
+    /// Files overlapping this range are preferred by the decline-rate score.
+    hot_range: Option<Range<Vec<u8>>>,
+    job_stats: Arc<AtomicJobStats>,
+    state: Mutex<AdaptiveState>,
+}
+
+struct AdaptiveState {
+    alpha: f64,
+    // The adjustment applied to alpha last time.
+    step: f64,
+    last_stats: JobStats,
+    last_write_amp: Option<f64>,
+}
+
+/// A [`StrategyBuilder`] that can be replaced at runtime.
+///
+/// The replacement only affects strategies built after it.
+#[derive(Clone)]
+pub(crate) struct SwappableStrategyBuilder {
+    inner: Arc<Mutex<Arc<dyn StrategyBuilder>>>,
+}
 
 #[derive(PartialEq, PartialOrd, Debug, Clone)]
 struct FileScore {
@@ -35,29 +189,51 @@ struct FileScore {
     file_id: u32,
 }
 
-#[derive(Debug)]
-struct FileSummary {
+/// The summary of a file collected by a [`ReclaimPickStrategy`].
+#[derive(Clone, Debug)]
+pub struct FileSummary {
+    file_id: u32,
     num_active_pages: usize,
     total_page_size: usize,
     effective_size: usize,
     effective_rate: f64,
     empty_pages_rate: f64,
     up2: u32,
+    key_range: Option<KeyRange>,
 }
 
 impl MinDeclineRateStrategy {
-    fn new(now: u32) -> Self {
+    fn new(now: u32, min_files_retained: usize, hot_range: Option<Range<Vec<u8>>>) -> Self {
         MinDeclineRateStrategy {
             now,
+            min_files_retained,
+            hot_range,
+            write_amplify_term: true,
+            age_term: true,
+            min_free_ratio: 0.0,
             sorted: false,
             scores: Vec::default(),
+            num_skipped: 0,
+            last_score: None,
         }
     }
 
     fn collect(&mut self, file_id: u32, summary: &FileSummary) {
-        let score = decline_rate(summary, self.now);
+        if summary.empty_pages_rate < self.min_free_ratio {
+            self.num_skipped += 1;
+            return;
+        }
+        let mut score = decline_rate(summary, self.now, self.age_term);
+        if score != f64::MIN && self.is_hot(summary) {
+            // The score is not positive, scale it towards zero to raise it.
+            score /= HOT_RANGE_SCORE_MULTIPLIER;
+        }
         let effective_rate = summary.effective_rate;
-        let write_amplify = write_amplification(summary.empty_pages_rate);
+        let write_amplify = if self.write_amplify_term {
+            write_amplification(summary.empty_pages_rate)
+        } else {
+            0.0
+        };
         assert!(!score.is_nan());
         assert!(!effective_rate.is_nan());
         assert!(!effective_rate.is_infinite());
@@ -69,16 +245,10 @@ impl MinDeclineRateStrategy {
             score,
         });
     }
-}
-
-impl ReclaimPickStrategy for MinDeclineRateStrategy {
-    fn collect_file(&mut self, page_groups: &FxHashMap<u32, PageGroup>, file_info: &FileInfo) {
-        let file_id = file_info.meta().file_id;
-        let summary = FileSummary::from((page_groups, file_info));
-        self.collect(file_id, &summary);
-    }
 
-    fn apply(&mut self) -> Option<(u32, usize)> {
+    /// Sorts the files in ascending order of scores, so the best one is the
+    /// last.
+    fn sort(&mut self) {
         if !self.sorted {
             self.sorted = true;
             self.scores.sort_unstable_by(|a, b| {
@@ -86,19 +256,351 @@ impl ReclaimPickStrategy for MinDeclineRateStrategy {
                     .unwrap_or_else(|| a.file_id.cmp(&b.file_id))
             });
         }
+    }
 
-        if self.scores.len() < 2 {
+    fn is_hot(&self, summary: &FileSummary) -> bool {
+        match (&self.hot_range, &summary.key_range) {
+            (Some(hot_range), Some(key_range)) => key_range.overlaps(hot_range),
+            _ => false,
+        }
+    }
+}
+
+impl ReclaimPickStrategy for MinDeclineRateStrategy {
+    fn collect_file(&mut self, summary: &FileSummary) {
+        self.collect(summary.file_id, summary);
+    }
+
+    fn apply(&mut self) -> Option<PickedFile> {
+        self.sort();
+        // Skipped files are retained as well.
+        if self.scores.len() + self.num_skipped <= self.min_files_retained {
             return None;
         }
 
-        self.scores.pop().map(|f| (f.file_id, f.active_size))
+        let file = self.scores.pop()?;
+        self.last_score = Some(file.score);
+        Some(PickedFile {
+            file_id: file.file_id,
+            active_size: file.active_size,
+        })
+    }
+
+    fn last_score(&self) -> Option<f64> {
+        self.last_score
+    }
+}
+
+impl MinDeclineRateStrategyBuilder {
+    /// Creates a builder with all terms of the score enabled.
+    pub fn new(min_files_retained: usize, hot_range: Option<Range<Vec<u8>>>) -> Self {
+        MinDeclineRateStrategyBuilder {
+            min_files_retained,
+            hot_range,
+            write_amplify_term: true,
+            age_term: true,
+            min_free_ratio: 0.0,
+        }
+    }
+
+    /// Sets whether files with the same decline rate are ordered by their
+    /// write amplification.
+    pub fn write_amplify_term(mut self, enabled: bool) -> Self {
+        self.write_amplify_term = enabled;
+        self
+    }
+
+    /// Sets whether the decline rate accounts for the time since a file was
+    /// last updated. If disabled, files of different ages are scored alike.
+    pub fn age_term(mut self, enabled: bool) -> Self {
+        self.age_term = enabled;
+        self
+    }
+
+    /// Sets the minimum ratio of empty pages for a file to be picked, from 0
+    /// to 1. Files with less free space are not worth rewriting.
+    ///
+    /// Default: 0, all files may be picked.
+    pub fn min_free_ratio(mut self, ratio: f64) -> Self {
+        self.min_free_ratio = ratio;
+        self
     }
 }
 
 impl StrategyBuilder for MinDeclineRateStrategyBuilder {
     #[inline]
     fn build(&self, now: u32) -> Box<dyn ReclaimPickStrategy> {
-        Box::new(MinDeclineRateStrategy::new(now))
+        let mut strategy =
+            MinDeclineRateStrategy::new(now, self.min_files_retained, self.hot_range.clone());
+        strategy.write_amplify_term = self.write_amplify_term;
+        strategy.age_term = self.age_term;
+        strategy.min_free_ratio = self.min_free_ratio;
+        Box::new(strategy)
+    }
+}
+
+impl MaxFreeSpaceStrategy {
+    fn new(min_files_retained: usize) -> Self {
+        MaxFreeSpaceStrategy {
+            min_files_retained,
+            sorted: false,
+            files: Vec::default(),
+            last_score: None,
+        }
+    }
+
+    fn collect(&mut self, file_id: u32, summary: &FileSummary) {
+        let free_size = summary
+            .total_page_size
+            .saturating_sub(summary.effective_size);
+        self.files
+            .push((free_size, file_id, summary.effective_size));
+    }
+
+    /// Sorts the files in ascending order of free space, so the best one is
+    /// the last.
+    fn sort(&mut self) {
+        if !self.sorted {
+            self.sorted = true;
+            self.files.sort_unstable();
+        }
+    }
+}
+
+impl ReclaimPickStrategy for MaxFreeSpaceStrategy {
+    fn collect_file(&mut self, summary: &FileSummary) {
+        self.collect(summary.file_id, summary);
+    }
+
+    fn apply(&mut self) -> Option<PickedFile> {
+        self.sort();
+        if self.files.len() <= self.min_files_retained {
+            return None;
+        }
+
+        let (free_size, file_id, active_size) = self.files.pop()?;
+        self.last_score = Some(free_size as f64);
+        Some(PickedFile {
+            file_id,
+            active_size,
+        })
+    }
+
+    fn last_score(&self) -> Option<f64> {
+        self.last_score
+    }
+}
+
+impl MaxFreeSpaceStrategyBuilder {
+    pub(crate) fn new(min_files_retained: usize) -> Self {
+        MaxFreeSpaceStrategyBuilder { min_files_retained }
+    }
+}
+
+impl StrategyBuilder for MaxFreeSpaceStrategyBuilder {
+    #[inline]
+    fn build(&self, _now: u32) -> Box<dyn ReclaimPickStrategy> {
+        Box::new(MaxFreeSpaceStrategy::new(self.min_files_retained))
+    }
+}
+
+impl AdaptiveStrategy {
+    fn new(
+        now: u32,
+        min_files_retained: usize,
+        hot_range: Option<Range<Vec<u8>>>,
+        alpha: f64,
+    ) -> Self {
+        AdaptiveStrategy {
+            alpha,
+            min_files_retained,
+            decline_rate: MinDeclineRateStrategy::new(now, 0, hot_range),
+            free_space: MaxFreeSpaceStrategy::new(0),
+            sorted: false,
+            files: Vec::default(),
+            last_score: None,
+        }
+    }
+
+    fn collect(&mut self, file_id: u32, summary: &FileSummary) {
+        self.decline_rate.collect(file_id, summary);
+        self.free_space.collect(file_id, summary);
+    }
+
+    fn sort(&mut self) {
+        if self.sorted {
+            return;
+        }
+        self.sorted = true;
+        self.decline_rate.sort();
+        self.free_space.sort();
+
+        // Normalizes the rank of a file to [0, 1], the best file is ranked 1.
+        let num_files = self.decline_rate.scores.len();
+        let rank = |i: usize| {
+            if num_files > 1 {
+                i as f64 / (num_files - 1) as f64
+            } else {
+                1.0
+            }
+        };
+        let mut blended = FxHashMap::default();
+        for (i, file) in self.decline_rate.scores.iter().enumerate() {
+            blended.insert(file.file_id, (self.alpha * rank(i), file.active_size));
+        }
+        for (i, &(_, file_id, _)) in self.free_space.files.iter().enumerate() {
+            if let Some((score, _)) = blended.get_mut(&file_id) {
+                *score += (1.0 - self.alpha) * rank(i);
+            }
+        }
+        self.files = blended
+            .into_iter()
+            .map(|(file_id, (score, active_size))| (score, file_id, active_size))
+            .collect();
+        self.files
+            .sort_unstable_by(|a, b| a.0.total_cmp(&b.0).then(a.1.cmp(&b.1)));
+    }
+}
+
+impl ReclaimPickStrategy for AdaptiveStrategy {
+    fn collect_file(&mut self, summary: &FileSummary) {
+        self.collect(summary.file_id, summary);
+    }
+
+    fn apply(&mut self) -> Option<PickedFile> {
+        self.sort();
+        if self.files.len() <= self.min_files_retained {
+            return None;
+        }
+
+        let (score, file_id, active_size) = self.files.pop()?;
+        self.last_score = Some(score);
+        Some(PickedFile {
+            file_id,
+            active_size,
+        })
+    }
+
+    fn last_score(&self) -> Option<f64> {
+        self.last_score
+    }
+}
+
+impl AdaptiveStrategyBuilder {
+    pub(crate) fn new(
+        min_files_retained: usize,
+        hot_range: Option<Range<Vec<u8>>>,
+        alpha: f64,
+        job_stats: Arc<AtomicJobStats>,
+    ) -> Self {
+        let state = AdaptiveState {
+            alpha: alpha.clamp(0.0, 1.0),
+            step: ADAPTIVE_ALPHA_STEP,
+            last_stats: job_stats.snapshot(),
+            last_write_amp: None,
+        };
+        AdaptiveStrategyBuilder {
+            min_files_retained,
+            hot_range,
+            job_stats,
+            state: Mutex::new(state),
+        }
+    }
+
+    /// Adjusts alpha by the write amplification since the last adjustment,
+    /// and returns the adjusted alpha.
+    fn adjust_alpha(&self, stats: JobStats) -> f64 {
+        let mut state = self.state.lock().expect("Poisoned");
+        let delta = stats.sub(&state.last_stats);
+        if delta.flush_write_bytes == 0 {
+            return state.alpha;
+        }
+        let write_amp = (delta.flush_write_bytes + delta.compact_write_bytes) as f64
+            / delta.flush_write_bytes as f64;
+        if let Some(last_write_amp) = state.last_write_amp {
+            // Keep moving in the same direction while write amplification
+            // doesn't rise, otherwise turn back.
+            if write_amp > last_write_amp {
+                state.step = -state.step;
+            }
+            state.alpha = (state.alpha + state.step).clamp(0.0, 1.0);
+        }
+        state.last_stats = stats;
+        state.last_write_amp = Some(write_amp);
+        state.alpha
+    }
+}
+
+impl StrategyBuilder for AdaptiveStrategyBuilder {
+    fn build(&self, now: u32) -> Box<dyn ReclaimPickStrategy> {
+        let alpha = self.adjust_alpha(self.job_stats.snapshot());
+        Box::new(AdaptiveStrategy::new(
+            now,
+            self.min_files_retained,
+            self.hot_range.clone(),
+            alpha,
+        ))
+    }
+}
+
+impl SwappableStrategyBuilder {
+    pub(crate) fn new(builder: Arc<dyn StrategyBuilder>) -> Self {
+        SwappableStrategyBuilder {
+            inner: Arc::new(Mutex::new(builder)),
+        }
+    }
+
+    /// Replaces the builder used for strategies built after this.
+    pub(crate) fn set(&self, builder: Arc<dyn StrategyBuilder>) {
+        *self.inner.lock().expect("Poisoned") = builder;
+    }
+}
+
+impl StrategyBuilder for SwappableStrategyBuilder {
+    fn build(&self, now: u32) -> Box<dyn ReclaimPickStrategy> {
+        let builder = self.inner.lock().expect("Poisoned").clone();
+        builder.build(now)
+    }
+}
+
+impl FileSummary {
+    /// Returns the id of the file.
+    pub fn file_id(&self) -> u32 {
+        self.file_id
+    }
+
+    /// Returns the number of active pages in the file.
+    pub fn num_active_pages(&self) -> usize {
+        self.num_active_pages
+    }
+
+    /// Returns the size of all pages in the file, including inactive ones.
+    pub fn total_page_size(&self) -> usize {
+        self.total_page_size
+    }
+
+    /// Returns the size of active pages in the file, which are rewritten
+    /// when the file is reclaimed.
+    pub fn active_size(&self) -> usize {
+        self.effective_size
+    }
+
+    /// Returns the ratio of inactive pages to all pages in the file.
+    pub fn empty_pages_rate(&self) -> f64 {
+        self.empty_pages_rate
+    }
+
+    /// Returns the logical time when the file was last updated, see
+    /// [`StrategyBuilder::build`].
+    pub fn up2(&self) -> u32 {
+        self.up2
+    }
+
+    /// Returns the minimum and maximum user keys in the file, if known.
+    pub fn key_range(&self) -> Option<(&[u8], &[u8])> {
+        self.key_range
+            .as_ref()
+            .map(|range| (range.min_key.as_slice(), range.max_key.as_slice()))
     }
 }
 
@@ -119,24 +621,32 @@ impl From<(&FxHashMap<u32, PageGroup>, &FileInfo)> for FileSummary {
             total_pages += meta.total_pages();
             total_page_size += meta.total_page_size();
         }
-        let effective_rate = effective_size as f64 / (total_page_size as f64);
+        let effective_rate = if total_page_size > 0 {
+            effective_size as f64 / total_page_size as f64
+        } else {
+            EFFECTIVE_RATE_WITHOUT_PAGES
+        };
         let empty_pages_rate = if total_pages > 0 {
             1.0 - (num_active_pages as f64 / total_pages as f64)
         } else {
-            0.0
+            EMPTY_PAGES_RATE_WITHOUT_PAGES
         };
         FileSummary {
+            file_id: meta.file_id,
             num_active_pages,
             effective_size,
             effective_rate,
             empty_pages_rate,
             total_page_size,
             up2,
+            key_range: info.key_range().cloned(),
         }
     }
 }
 
-fn decline_rate(summary: &FileSummary, now: u32) -> f64 {
+/// Returns the decline rate of the file. If `age_term` is false, the file is
+/// treated as if it was updated a unit of time ago.
+fn decline_rate(summary: &FileSummary, now: u32, age_term: bool) -> f64 {
     let num_active_pages = summary.num_active_pages;
     if num_active_pages == 0 {
         return 0.0;
@@ -145,19 +655,22 @@ fn decline_rate(summary: &FileSummary, now: u32) -> f64 {
     let total_page_size = summary.total_page_size;
     let effective_size = summary.effective_size;
     let free_size = total_page_size.saturating_sub(effective_size);
-    if free_size == 0 || summary.up2 == now {
+    if free_size == 0 || (age_term && summary.up2 == now) {
         return f64::MIN;
     }
 
     let num_active_pages = num_active_pages as f64;
     let effective_size = effective_size as f64;
     let free_size = free_size as f64;
-    let up2 = summary.up2 as f64;
-    let now = now as f64;
+    let age = if age_term {
+        now as f64 - summary.up2 as f64
+    } else {
+        1.0
+    };
 
     // See "Efficiently Reclaiming Space in a Log Structured Store" section 5.1.3
     // "Transformed Declining Cost Equation" for details.
-    -(effective_size / free_size).powi(2) / (num_active_pages * (now - up2))
+    -(effective_size / free_size).powi(2) / (num_active_pages * age)
 }
 
 #[allow(unused)]
@@ -176,3 +689,284 @@ pub(crate) fn write_amplification(empty_rate: f64) -> f64 {
     // "The Cost of Cleaning" for details.
     (1.0 / empty_rate) * (1.0 - empty_rate)
 }
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use rustc_hash::FxHashSet;
+
+    use super::*;
+    use crate::page_store::{
+        page_file::{FileMeta, PageGroupMeta},
+        ChecksumType, Compression,
+    };
+
+    fn summary(up2: u32) -> FileSummary {
+        FileSummary {
+            file_id: 0,
+            num_active_pages: 4,
+            total_page_size: 1 << 10,
+            effective_size: 1 << 9,
+            effective_rate: 0.5,
+            empty_pages_rate: 0.5,
+            up2,
+            key_range: None,
+        }
+    }
+
+    fn key_range(min_key: &[u8], max_key: &[u8]) -> Option<KeyRange> {
+        Some(KeyRange {
+            min_key: min_key.to_vec(),
+            max_key: max_key.to_vec(),
+        })
+    }
+
+    fn map_file(file_size: usize, page_groups: Vec<Arc<PageGroupMeta>>) -> FileInfo {
+        let page_groups = page_groups
+            .into_iter()
+            .map(|meta| (meta.group_id, meta))
+            .collect();
+        let meta = FileMeta::new(
+            1,
+            file_size,
+            4096,
+            ChecksumType::NONE,
+            Compression::NONE,
+            FxHashSet::default(),
+            page_groups,
+        );
+        FileInfo::new(1, 1, Arc::new(meta))
+    }
+
+    #[test]
+    fn file_summary_zero_size_map_file() {
+        let info = map_file(0, vec![]);
+        let summary = FileSummary::from((&FxHashMap::default(), &info));
+        assert_eq!(summary.total_page_size(), 0);
+        assert_eq!(summary.effective_rate, EFFECTIVE_RATE_WITHOUT_PAGES);
+        assert_eq!(summary.empty_pages_rate(), EMPTY_PAGES_RATE_WITHOUT_PAGES);
+
+        let mut strategy = MinDeclineRateStrategy::new(10, 0, None);
+        strategy.collect_file(&summary);
+        assert_eq!(strategy.apply().map(|f| f.file_id), Some(1));
+    }
+
+    #[test]
+    fn file_summary_zero_pages_map_file() {
+        let group = Arc::new(PageGroupMeta::new(2, 1, 0, vec![0, 0], BTreeMap::default()));
+        let page_groups: FxHashMap<_, _> =
+            [(2, PageGroup::new(group.clone()))].into_iter().collect();
+        let info = map_file(64, vec![group]);
+        let summary = FileSummary::from((&page_groups, &info));
+        assert_eq!(summary.num_active_pages(), 0);
+        assert_eq!(summary.empty_pages_rate(), EMPTY_PAGES_RATE_WITHOUT_PAGES);
+        // A page group without pages counts as fully active over its minimal
+        // page size of one byte.
+        assert_eq!(summary.effective_rate, 1.0);
+        assert_eq!(
+            page_groups[&2].empty_pages_rate(),
+            EMPTY_PAGES_RATE_WITHOUT_PAGES
+        );
+    }
+
+    #[test]
+    fn min_decline_rate_strategy_min_files_retained() {
+        let mut strategy = MinDeclineRateStrategy::new(10, 3, None);
+        for file_id in 1..=5 {
+            strategy.collect(file_id, &summary(file_id));
+        }
+        assert!(strategy.apply().is_some());
+        assert!(strategy.apply().is_some());
+        // Only 3 files are left.
+        assert!(strategy.apply().is_none());
+
+        // There are fewer files than the retained number.
+        let mut strategy = MinDeclineRateStrategy::new(10, 3, None);
+        for file_id in 1..=2 {
+            strategy.collect(file_id, &summary(file_id));
+        }
+        assert!(strategy.apply().is_none());
+    }
+
+    #[test]
+    fn min_decline_rate_strategy_hot_range() {
+        let hot_range = b"b".to_vec()..b"d".to_vec();
+        let mut strategy = MinDeclineRateStrategy::new(10, 0, Some(hot_range));
+        let cold = FileSummary {
+            key_range: key_range(b"d", b"f"),
+            ..summary(1)
+        };
+        let hot = FileSummary {
+            key_range: key_range(b"a", b"b"),
+            ..summary(1)
+        };
+        strategy.collect(1, &cold);
+        strategy.collect(2, &hot);
+        strategy.collect(3, &summary(1));
+        assert_eq!(strategy.apply().map(|f| f.file_id), Some(2));
+    }
+
+    #[test]
+    fn min_decline_rate_strategy_disable_age_term() {
+        let score = |builder: &MinDeclineRateStrategyBuilder, up2| {
+            let mut strategy = builder.build(10);
+            strategy.collect_file(&summary(up2));
+            strategy.apply().unwrap();
+            strategy.last_score().unwrap()
+        };
+
+        let builder = MinDeclineRateStrategyBuilder::new(0, None);
+        assert_ne!(score(&builder, 1), score(&builder, 8));
+
+        // Both files have the same free space.
+        let builder = builder.age_term(false);
+        assert_eq!(score(&builder, 1), score(&builder, 8));
+        assert_eq!(score(&builder, 1), score(&builder, 10));
+    }
+
+    #[test]
+    fn min_decline_rate_strategy_disable_write_amplify_term() {
+        let builder = MinDeclineRateStrategyBuilder::new(0, None).write_amplify_term(false);
+        let mut strategy = builder.build(10);
+        // Files with more empty pages have lower write amplification.
+        for (file_id, empty_pages_rate) in [(1, 0.5), (2, 0.25), (3, 0.75)] {
+            let summary = FileSummary {
+                file_id,
+                empty_pages_rate,
+                ..summary(1)
+            };
+            strategy.collect_file(&summary);
+        }
+        // Files are ordered by id only.
+        let picked = std::iter::from_fn(|| strategy.apply().map(|f| f.file_id)).collect::<Vec<_>>();
+        assert_eq!(picked, vec![3, 2, 1]);
+    }
+
+    #[test]
+    fn min_decline_rate_strategy_min_free_ratio() {
+        let builder = MinDeclineRateStrategyBuilder::new(0, None).min_free_ratio(0.1);
+        let mut strategy = builder.build(10);
+        for (file_id, empty_pages_rate) in [(1, 0.05), (2, 0.1), (3, 0.5)] {
+            let summary = FileSummary {
+                file_id,
+                empty_pages_rate,
+                ..summary(1)
+            };
+            strategy.collect_file(&summary);
+        }
+        // The file with 5% free space is never picked.
+        let mut picked =
+            std::iter::from_fn(|| strategy.apply().map(|f| f.file_id)).collect::<Vec<_>>();
+        picked.sort_unstable();
+        assert_eq!(picked, vec![2, 3]);
+
+        // Skipped files count towards the retained files, so the skipped file
+        // is retained and the other one can be picked.
+        let builder = MinDeclineRateStrategyBuilder::new(1, None).min_free_ratio(0.1);
+        let mut strategy = builder.build(10);
+        for (file_id, empty_pages_rate) in [(1, 0.05), (2, 0.5)] {
+            let summary = FileSummary {
+                file_id,
+                empty_pages_rate,
+                ..summary(1)
+            };
+            strategy.collect_file(&summary);
+        }
+        assert_eq!(strategy.apply().map(|f| f.file_id), Some(2));
+        assert!(strategy.apply().is_none());
+    }
+
+    #[test]
+    fn key_range_overlaps() {
+        let query = b"f".to_vec()..b"m".to_vec();
+        let overlaps =
+            |min_key: &[u8], max_key: &[u8]| key_range(min_key, max_key).unwrap().overlaps(&query);
+        assert!(overlaps(b"a", b"f"));
+        assert!(overlaps(b"h", b"k"));
+        assert!(overlaps(b"l", b"z"));
+        assert!(overlaps(b"a", b"z"));
+        // The end of the query is excluded.
+        assert!(!overlaps(b"m", b"z"));
+        assert!(!overlaps(b"a", b"e"));
+    }
+
+    #[test]
+    fn max_free_space_strategy() {
+        let mut strategy = MaxFreeSpaceStrategy::new(1);
+        for (file_id, effective_size) in [(1, 512), (2, 128), (3, 768)] {
+            let summary = FileSummary {
+                effective_size,
+                ..summary(file_id)
+            };
+            strategy.collect(file_id, &summary);
+        }
+        let picked = |file_id, active_size| PickedFile {
+            file_id,
+            active_size,
+        };
+        assert_eq!(strategy.apply(), Some(picked(2, 128)));
+        assert_eq!(strategy.apply(), Some(picked(1, 512)));
+        assert_eq!(strategy.apply(), None);
+    }
+
+    #[test]
+    fn adaptive_strategy_alpha() {
+        // The orders of decline rates and free space are different.
+        let files = [(1, 512, 1), (2, 128, 9), (3, 768, 1), (4, 256, 2)].map(
+            |(file_id, effective_size, up2)| {
+                let summary = FileSummary {
+                    effective_size,
+                    ..summary(up2)
+                };
+                (file_id, summary)
+            },
+        );
+        fn pick_all(strategy: &mut dyn ReclaimPickStrategy) -> Vec<u32> {
+            std::iter::from_fn(|| strategy.apply().map(|f| f.file_id)).collect()
+        }
+
+        let mut decline_rate = MinDeclineRateStrategy::new(10, 0, None);
+        let mut free_space = MaxFreeSpaceStrategy::new(0);
+        let mut adaptive_1 = AdaptiveStrategy::new(10, 0, None, 1.0);
+        let mut adaptive_0 = AdaptiveStrategy::new(10, 0, None, 0.0);
+        for (file_id, summary) in &files {
+            decline_rate.collect(*file_id, summary);
+            free_space.collect(*file_id, summary);
+            adaptive_1.collect(*file_id, summary);
+            adaptive_0.collect(*file_id, summary);
+        }
+        let decline_rate = pick_all(&mut decline_rate);
+        let free_space = pick_all(&mut free_space);
+        assert_ne!(decline_rate, free_space);
+        assert_eq!(pick_all(&mut adaptive_1), decline_rate);
+        assert_eq!(pick_all(&mut adaptive_0), free_space);
+
+        let mut strategy = AdaptiveStrategy::new(10, 2, None, 0.5);
+        for (file_id, summary) in &files {
+            strategy.collect(*file_id, summary);
+        }
+        assert_eq!(pick_all(&mut strategy).len(), 2);
+    }
+
+    #[test]
+    fn adaptive_strategy_builder_adjust_alpha() {
+        let job_stats = Arc::new(AtomicJobStats::default());
+        let builder = AdaptiveStrategyBuilder::new(0, None, 0.5, job_stats);
+        let stats = |flush_write_bytes, compact_write_bytes| JobStats {
+            flush_write_bytes,
+            compact_write_bytes,
+            ..Default::default()
+        };
+        // Nothing is flushed.
+        assert_eq!(builder.adjust_alpha(stats(0, 0)), 0.5);
+        // The first observation.
+        assert_eq!(builder.adjust_alpha(stats(100, 100)), 0.5);
+        // Write amplification declines, move on.
+        let alpha = builder.adjust_alpha(stats(200, 150));
+        assert!((alpha - 0.6).abs() < 1e-9);
+        // Write amplification rises, turn back.
+        let alpha = builder.adjust_alpha(stats(300, 300));
+        assert!((alpha - 0.5).abs() < 1e-9);
+    }
+}
@@ -159,6 +159,7 @@ impl Drop for VersionOwner {
 }
 
 impl Version {
+    #[cfg(test)]
     pub(crate) fn new(
         buffer_capacity: u32,
         next_group_id: u32,
@@ -5,7 +5,10 @@
 
 use std::{ops::Deref, path::Path};
 
-pub use crate::raw::TableStats;
+pub use crate::raw::{
+    EngineOptions, EngineStats, FileEntryIter, KeyBounds, KeyIter, ResumeToken, ScanCacheStats,
+    Snapshot, TableStats,
+};
 use crate::{env::Photon, raw, Result, TableOptions};
 
 /// A reference to a latch-free, log-structured table that stores sorted
@@ -25,6 +28,44 @@ impl Table {
         Ok(Self(table))
     }
 
+    /// Opens a table in the path with the given options, and pre-splits it at
+    /// the split points if it is newly created.
+    ///
+    /// This is the same as [`raw::Table::open_with_splits`] with the
+    /// [`Photon`] environment.
+    pub async fn open_with_splits<P: AsRef<Path>>(
+        path: P,
+        options: TableOptions,
+        split_points: &[&[u8]],
+    ) -> Result<Self> {
+        let table = raw::Table::open_with_splits(Photon, path, options, split_points).await?;
+        Ok(Self(table))
+    }
+
+    /// Opens a table in the path read-only, as it was at the manifest version.
+    ///
+    /// This is the same as [`raw::Table::open_at_manifest_version`] with the
+    /// [`Photon`] environment.
+    pub async fn open_at_manifest_version<P: AsRef<Path>>(
+        path: P,
+        options: TableOptions,
+        version: usize,
+    ) -> Result<Self> {
+        let table = raw::Table::open_at_manifest_version(Photon, path, options, version).await?;
+        Ok(Self(table))
+    }
+
+    /// Rebuilds the mapping of the table in the path from its page files.
+    ///
+    /// This is the same as [`raw::Table::rebuild_mapping`] with the [`Photon`]
+    /// environment.
+    pub async fn rebuild_mapping<P: AsRef<Path>>(
+        path: P,
+        options: TableOptions,
+    ) -> Result<Vec<u32>> {
+        raw::Table::rebuild_mapping(Photon, path, options).await
+    }
+
     /// Closes the table if this is the only reference to it.
     ///
     /// This is the same as [`raw::Table::close`] with the [`Photon`]
@@ -42,8 +83,48 @@ impl Deref for Table {
     }
 }
 
+/// An engine that opens multiple tables sharing the same caches.
+///
+/// This is the same as [`raw::Engine`] with the [`Photon`] environment.
+#[derive(Clone, Debug)]
+pub struct Engine(raw::Engine<Photon>);
+
+impl Engine {
+    /// Creates an engine with the given options.
+    ///
+    /// This is the same as [`raw::Engine::new`] with the [`Photon`]
+    /// environment.
+    pub fn new(options: EngineOptions) -> Self {
+        Self(raw::Engine::new(Photon, options))
+    }
+
+    /// Opens a table in the path with the given options.
+    ///
+    /// This is the same as [`raw::Engine::open_table`] with the [`Photon`]
+    /// environment.
+    pub async fn open_table<P: AsRef<Path>>(
+        &self,
+        path: P,
+        options: TableOptions,
+    ) -> Result<Table> {
+        let table = self.0.open_table(path, options).await?;
+        Ok(Table(table))
+    }
+}
+
+impl Deref for Engine {
+    type Target = raw::Engine<Photon>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
 /// A handle that holds some resources of a table for user operations.
 pub type Guard<'a> = raw::Guard<'a, Photon>;
 
 /// An iterator over pages in a table.
 pub type Pages<'a, 't> = raw::Pages<'a, 't, Photon>;
+
+/// A cursor over the entries of a table that can step forward and backward.
+pub type TableCursor<'a> = raw::TableCursor<'a, Photon>;
@@ -0,0 +1,139 @@
+use std::{fmt, path::Path, sync::Arc};
+
+use super::Table;
+use crate::{
+    env::Env,
+    page_store::{CacheStats, SharedCaches},
+    Error, Result, TableOptions,
+};
+
+/// Options to create an [`Engine`].
+#[derive(Clone, Debug)]
+#[non_exhaustive]
+pub struct EngineOptions {
+    /// The capacity of the page read cache shared by all tables in bytes.
+    ///
+    /// Default: 8 Mib
+    pub cache_capacity: usize,
+
+    /// The capacity of the file_reader cache shared by all tables.
+    ///
+    /// Default: 5000 file_readers.
+    pub cache_file_reader_capacity: u64,
+}
+
+impl Default for EngineOptions {
+    fn default() -> Self {
+        Self {
+            cache_capacity: 8 << 20,
+            cache_file_reader_capacity: 5000,
+        }
+    }
+}
+
+/// An engine that opens multiple tables sharing the same caches and
+/// environment.
+///
+/// Tables opened from an engine use the page cache and file reader cache of
+/// the engine instead of their own, so the cache options of the tables are
+/// ignored. Their background jobs are all spawned on the environment of the
+/// engine.
+///
+/// The engine is cheap to clone, and all clones share the same caches. An
+/// engine can open up to 255 tables in its lifetime, reopening a table counts
+/// as a new one.
+#[derive(Clone)]
+pub struct Engine<E: Env> {
+    env: E,
+    caches: Arc<SharedCaches<E>>,
+}
+
+impl<E: Env> Engine<E> {
+    /// Creates an engine with the given options.
+    pub fn new(env: E, options: EngineOptions) -> Self {
+        let caches = SharedCaches::new(options.cache_capacity, options.cache_file_reader_capacity);
+        Self {
+            env,
+            caches: Arc::new(caches),
+        }
+    }
+
+    /// Opens a table in the path with the given options.
+    ///
+    /// Returns [`Error::InvalidOptions`] if the options are inconsistent, if
+    /// [`PageStoreOptions::memory_budget`] is set, since the page cache is not
+    /// owned by the table, or if the engine can't open more tables.
+    ///
+    /// [`PageStoreOptions::memory_budget`]: crate::PageStoreOptions::memory_budget
+    pub async fn open_table<P: AsRef<Path>>(
+        &self,
+        path: P,
+        options: TableOptions,
+    ) -> Result<Table<E>> {
+        if options.page_store.memory_budget.is_some() {
+            return Err(Error::InvalidOptions(
+                "memory_budget is not supported by tables sharing caches".to_owned(),
+            ));
+        }
+        let Some(caches) = self.caches.alloc() else {
+            return Err(Error::InvalidOptions(
+                "too many tables opened from the engine".to_owned(),
+            ));
+        };
+        Table::open_impl(self.env.clone(), path, options, &[], Some(caches)).await
+    }
+
+    /// Returns the statistics of the caches shared by all tables.
+    ///
+    /// The statistics of each table, which are returned by
+    /// [`Table::stats`], also report the shared caches.
+    pub fn stats(&self) -> EngineStats {
+        let (page_cache, file_reader_cache) = self.caches.stats();
+        EngineStats {
+            page_cache_capacity: self.caches.page_cache().capacity(),
+            page_cache,
+            file_reader_cache,
+        }
+    }
+}
+
+impl<E: Env> fmt::Debug for Engine<E> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Engine").finish_non_exhaustive()
+    }
+}
+
+/// Statistics of an [`Engine`], aggregated over all its tables.
+#[derive(Clone, Debug, Default)]
+pub struct EngineStats {
+    /// The capacity of the shared page cache in bytes.
+    pub page_cache_capacity: usize,
+    /// The stats of the shared page cache.
+    pub page_cache: CacheStats,
+    /// The stats of the shared file reader cache.
+    pub file_reader_cache: CacheStats,
+}
+
+impl fmt::Display for EngineStats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "PageCache: capacity: {}, lookup_hit: {}, lookup_miss: {}, insert: {}, active_evict: {}, passive_evict: {}",
+            self.page_cache_capacity,
+            self.page_cache.lookup_hit,
+            self.page_cache.lookup_miss,
+            self.page_cache.insert,
+            self.page_cache.active_evict,
+            self.page_cache.passive_evict,
+        )?;
+        writeln!(
+            f,
+            "FileReaderCache: lookup_hit: {}, lookup_miss: {}, insert: {}, active_evict: {}, passive_evict: {}",
+            self.file_reader_cache.lookup_hit,
+            self.file_reader_cache.lookup_miss,
+            self.file_reader_cache.insert,
+            self.file_reader_cache.active_evict,
+            self.file_reader_cache.passive_evict,
+        )
+    }
+}
@@ -1,7 +1,16 @@
 //! Raw PhotonDB APIs that can can run with different environments.
 
+mod engine;
+pub use engine::{Engine, EngineOptions, EngineStats};
+
 mod table;
-pub use table::{Guard, Pages, Table, TableStats};
+pub use table::{
+    FileEntryIter, Guard, KeyBounds, KeyIter, Pages, ResumeToken, Snapshot, Table, TableCursor,
+    TableStats,
+};
+
+mod scan_cache;
+pub use scan_cache::ScanCacheStats;
 
 #[cfg(test)]
 mod tree_test {
@@ -0,0 +1,173 @@
+use std::{
+    collections::HashMap,
+    ops::Bound,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Mutex,
+    },
+};
+
+use crate::util::atomic::Counter;
+
+/// Materialized entries of a scan.
+pub(super) type ScanEntries = Vec<(Vec<u8>, Vec<u8>)>;
+
+#[derive(Clone, PartialEq, Eq, Hash)]
+struct ScanKey {
+    start: Bound<Vec<u8>>,
+    end: Bound<Vec<u8>>,
+    lsn: u64,
+}
+
+struct CachedScan {
+    entries: ScanEntries,
+    last_used: u64,
+}
+
+/// A bounded LRU cache of materialized scan results.
+///
+/// A cached scan at `lsn` stays valid until an entry with a LSN at or below
+/// `lsn` is written. Writes with larger LSNs are invisible to the scan and
+/// don't invalidate it.
+pub(super) struct ScanCache {
+    capacity: usize,
+    scans: Mutex<HashMap<ScanKey, CachedScan>>,
+    // The largest LSN of scans that have started.
+    max_lsn: AtomicU64,
+    // Bumped whenever a write may invalidate a scan, so that scans that raced
+    // with the write are not cached.
+    generation: AtomicU64,
+    tick: AtomicU64,
+    stats: AtomicScanCacheStats,
+}
+
+impl std::fmt::Debug for ScanCache {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ScanCache")
+            .field("capacity", &self.capacity)
+            .finish()
+    }
+}
+
+/// A token returned by a cache miss to insert the scan result later.
+pub(super) struct ScanToken {
+    key: ScanKey,
+    generation: u64,
+}
+
+impl ScanCache {
+    pub(super) fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            scans: Mutex::default(),
+            max_lsn: AtomicU64::new(0),
+            generation: AtomicU64::new(0),
+            tick: AtomicU64::new(0),
+            stats: AtomicScanCacheStats::default(),
+        }
+    }
+
+    /// Looks up the scan between `start` and `end` at `lsn`.
+    ///
+    /// Returns the cached entries on hit, or a token to insert the entries
+    /// after the scan on miss.
+    pub(super) fn lookup(
+        &self,
+        start: Bound<&[u8]>,
+        end: Bound<&[u8]>,
+        lsn: u64,
+    ) -> Result<ScanEntries, ScanToken> {
+        self.max_lsn.fetch_max(lsn, Ordering::SeqCst);
+        let generation = self.generation.load(Ordering::SeqCst);
+        let key = ScanKey {
+            start: to_owned_bound(start),
+            end: to_owned_bound(end),
+            lsn,
+        };
+        let mut scans = self.scans.lock().expect("Poisoned");
+        if let Some(scan) = scans.get_mut(&key) {
+            scan.last_used = self.tick.fetch_add(1, Ordering::Relaxed);
+            self.stats.hit.inc();
+            return Ok(scan.entries.clone());
+        }
+        self.stats.miss.inc();
+        Err(ScanToken { key, generation })
+    }
+
+    /// Inserts the entries of a missed scan, unless a write may have changed
+    /// them since the lookup.
+    pub(super) fn insert(&self, token: ScanToken, entries: ScanEntries) {
+        let mut scans = self.scans.lock().expect("Poisoned");
+        if self.generation.load(Ordering::SeqCst) != token.generation {
+            return;
+        }
+        if scans.len() >= self.capacity {
+            let lru = scans
+                .iter()
+                .min_by_key(|(_, scan)| scan.last_used)
+                .map(|(key, _)| key.clone());
+            if let Some(key) = lru {
+                scans.remove(&key);
+            }
+        }
+        let last_used = self.tick.fetch_add(1, Ordering::Relaxed);
+        scans.insert(token.key, CachedScan { entries, last_used });
+    }
+
+    /// Invalidates scans that may see an entry written at `lsn`.
+    ///
+    /// This must be called after the entry is written.
+    pub(super) fn invalidate(&self, lsn: u64) {
+        if lsn > self.max_lsn.load(Ordering::SeqCst) {
+            return;
+        }
+        let mut scans = self.scans.lock().expect("Poisoned");
+        self.generation.fetch_add(1, Ordering::SeqCst);
+        scans.retain(|key, _| key.lsn < lsn);
+    }
+
+    pub(super) fn stats(&self) -> ScanCacheStats {
+        ScanCacheStats {
+            hit: self.stats.hit.get(),
+            miss: self.stats.miss.get(),
+        }
+    }
+}
+
+fn to_owned_bound(bound: Bound<&[u8]>) -> Bound<Vec<u8>> {
+    match bound {
+        Bound::Included(key) => Bound::Included(key.to_vec()),
+        Bound::Excluded(key) => Bound::Excluded(key.to_vec()),
+        Bound::Unbounded => Bound::Unbounded,
+    }
+}
+
+/// Statistics of cached scans.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct ScanCacheStats {
+    /// The number of scans served from the cache.
+    pub hit: u64,
+    /// The number of scans that missed the cache.
+    pub miss: u64,
+}
+
+impl ScanCacheStats {
+    pub(super) fn sub(&self, o: &Self) -> Self {
+        Self {
+            hit: self.hit.wrapping_sub(o.hit),
+            miss: self.miss.wrapping_sub(o.miss),
+        }
+    }
+}
+
+impl std::fmt::Display for ScanCacheStats {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "ScanCacheStats: hit: {}, miss: {}", self.hit, self.miss)
+    }
+}
+
+#[derive(Default)]
+struct AtomicScanCacheStats {
+    hit: Counter,
+    miss: Counter,
+}
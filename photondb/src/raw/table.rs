@@ -1,36 +1,198 @@
-use std::{path::Path, sync::Arc};
+use std::{
+    cmp::Ordering,
+    io::{Read, Write},
+    ops::Bound,
+    path::Path,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use uuid::Uuid;
 
+use super::scan_cache::{ScanCache, ScanCacheStats, ScanEntries};
 use crate::{
     env::Env,
     page::{Key, Value},
-    page_store::{FlushOptions, PageStore, StoreStats},
+    page_store::{
+        FlushOptions, LiveFile, LiveFilesGuard, PageStore, PickedFile, ReclaimStrategy,
+        SpaceReport, StoreCaches, StoreStats,
+    },
     tree::*,
-    Result,
+    Error, Result,
 };
 
+/// The number of entries scanned or ingested at a time by [`Table::export`]
+/// and [`Table::import`].
+const EXPORT_BATCH_SIZE: usize = 1024;
+
 /// A reference to a latch-free, log-structured table that stores sorted
 /// key-value entries.
 ///
-/// The reference is thread-safe and cheap to clone.
+/// The reference is thread-safe and cheap to clone. All clones share the same
+/// page store, cache and background jobs. Dropping the last reference signals
+/// the background jobs to stop, use [`Table::close`] to wait for them.
+///
+/// Keys are ordered bytewise. The empty key is a valid key, which is the
+/// smallest one. The empty value is a valid value as well, which is distinct
+/// from a deleted or absent entry: reading it returns `Some` empty value
+/// instead of `None`.
 #[derive(Clone, Debug)]
 pub struct Table<E: Env> {
     tree: Arc<Tree>,
     store: Arc<PageStore<E>>,
+    scan_cache: Option<Arc<ScanCache>>,
 }
 
 impl<E: Env> Table<E> {
     /// Opens a table in the path with the given options.
+    ///
+    /// Page files written after the last committed version, for example by a
+    /// flush interrupted by a crash, are discarded.
+    ///
+    /// Returns [`Error::InvalidOptions`] if the options are inconsistent, or
+    /// [`Error::MissingFile`] if a page file committed to the table is
+    /// missing.
+    ///
+    /// [`Error::InvalidOptions`]: crate::Error::InvalidOptions
+    /// [`Error::MissingFile`]: crate::Error::MissingFile
     pub async fn open<P: AsRef<Path>>(env: E, path: P, options: Options) -> Result<Self> {
+        Self::open_with_splits(env, path, options, &[]).await
+    }
+
+    /// Opens a table in the path with the given options, and pre-splits it at
+    /// the split points if it is newly created.
+    ///
+    /// A new table starts with a leaf page for each range between the split
+    /// points, which avoids splitting pages repeatedly when loading keys that
+    /// are known to be uniformly distributed. The split points are ignored if
+    /// the table already exists.
+    ///
+    /// Returns [`Error::InvalidOptions`] if the options are inconsistent, or
+    /// if the split points are not sorted, distinct and non-empty.
+    ///
+    /// [`Error::InvalidOptions`]: crate::Error::InvalidOptions
+    pub async fn open_with_splits<P: AsRef<Path>>(
+        env: E,
+        path: P,
+        options: Options,
+        split_points: &[&[u8]],
+    ) -> Result<Self> {
+        Self::open_impl(env, path, options, split_points, None).await
+    }
+
+    pub(super) async fn open_impl<P: AsRef<Path>>(
+        env: E,
+        path: P,
+        options: Options,
+        split_points: &[&[u8]],
+        caches: Option<StoreCaches<E>>,
+    ) -> Result<Self> {
+        options.validate()?;
+        if split_points.first().map_or(false, |p| p.is_empty())
+            || split_points.windows(2).any(|w| w[0] >= w[1])
+        {
+            return Err(Error::InvalidOptions(
+                "split points must be sorted, distinct and non-empty".to_owned(),
+            ));
+        }
         let tree = Arc::new(Tree::new(options.clone()));
-        let store = PageStore::open(env, path, options.page_store).await?;
+        let scan_cache = (options.scan_cache_capacity > 0)
+            .then(|| Arc::new(ScanCache::new(options.scan_cache_capacity)));
+        let mut store = PageStore::open_with_caches(env, path, options.page_store, caches).await?;
+        let max_lsn = store.live_files().iter().map(|f| f.max_lsn).max();
+        tree.advance_latest_lsn(max_lsn.unwrap_or_default());
         let txn = tree.begin(store.guard());
-        txn.init().await?;
+        txn.init(split_points).await?;
+        if options.min_leaf_fill_ratio > 0.0 {
+            store.spawn_job(|guards, shutdown| tree.merge_job(guards, shutdown));
+        }
         Ok(Self {
             tree,
             store: Arc::new(store),
+            scan_cache,
         })
     }
 
+    /// Opens a table in the path read-only, as it was at the manifest version.
+    ///
+    /// See [`Table::manifest_version`] for how versions are numbered. Only
+    /// versions recorded in the current manifest file are available, which is
+    /// rolled on the first change after the table is opened, or when it grows
+    /// too large. The table directory is not modified, and writes to the
+    /// returned table fail with [`Error::ReadOnly`].
+    ///
+    /// Returns [`Error::InvalidManifestVersion`] if the version doesn't exist,
+    /// or [`Error::MissingFile`] if a page file of the version has been
+    /// removed by space reclaiming. Use [`Table::pin_live_files`] or
+    /// [`PageStoreOptions::disable_space_reclaiming`] to keep the files.
+    ///
+    /// [`Error::ReadOnly`]: crate::Error::ReadOnly
+    /// [`Error::InvalidManifestVersion`]: crate::Error::InvalidManifestVersion
+    /// [`Error::MissingFile`]: crate::Error::MissingFile
+    /// [`PageStoreOptions::disable_space_reclaiming`]: crate::PageStoreOptions::disable_space_reclaiming
+    pub async fn open_at_manifest_version<P: AsRef<Path>>(
+        env: E,
+        path: P,
+        options: Options,
+        version: usize,
+    ) -> Result<Self> {
+        options.validate()?;
+        let tree = Arc::new(Tree::new(options.clone()));
+        let scan_cache = (options.scan_cache_capacity > 0)
+            .then(|| Arc::new(ScanCache::new(options.scan_cache_capacity)));
+        let store = PageStore::open_at_version(env, path, options.page_store, version).await?;
+        let max_lsn = store.live_files().iter().map(|f| f.max_lsn).max();
+        tree.advance_latest_lsn(max_lsn.unwrap_or_default());
+        Ok(Self {
+            tree,
+            store: Arc::new(store),
+            scan_cache,
+        })
+    }
+
+    /// Rebuilds the mapping of the table in the path from its page files.
+    ///
+    /// This is a last-resort repair for a table whose manifest is lost or
+    /// inconsistent while the page files are intact. The table must not be
+    /// opened. All page files in the path are recorded in a new manifest, and
+    /// the mapping from page ids to pages, including the root, is recovered
+    /// from the page ids recorded in the files when the table is opened next
+    /// time. Files removed by space reclaiming but left on disk are included,
+    /// but their pages are shadowed by the newer copies.
+    ///
+    /// Returns the ids of page files that are skipped because their metadata
+    /// is unreadable. Key and LSN ranges of files are only kept in the
+    /// manifest, so they are lost, and [`Table::latest_lsn`] starts from zero
+    /// after the repair.
+    pub async fn rebuild_mapping<P: AsRef<Path>>(
+        env: E,
+        path: P,
+        options: Options,
+    ) -> Result<Vec<u32>> {
+        options.validate()?;
+        let skipped_files = PageStore::rebuild_manifest(env, path, &options.page_store).await?;
+        Ok(skipped_files)
+    }
+
+    /// Returns the UUID of the table.
+    ///
+    /// The id is generated when the table is created and persisted in the
+    /// manifest, so reopening the table returns the same id. A checkpoint of
+    /// the table has the same id as the table, which can be used to detect
+    /// a restore from a checkpoint of another table.
+    pub fn id(&self) -> Uuid {
+        self.store.table_id()
+    }
+
+    /// Returns the current version of the manifest.
+    ///
+    /// The version is advanced by every change of page files, such as
+    /// flushing and space reclaiming. It never decreases, even when the table
+    /// is reopened.
+    pub async fn manifest_version(&self) -> usize {
+        self.store.manifest_version().await
+    }
+
     /// Closes the table if this is the only reference to it.
     ///
     /// If this is not the only reference, returns [`Result::Err`] with this
@@ -44,6 +206,7 @@ impl<E: Env> Table<E> {
             Err(store) => Err(Self {
                 tree: self.tree,
                 store,
+                scan_cache: self.scan_cache,
             }),
         }
     }
@@ -53,25 +216,670 @@ impl<E: Env> Table<E> {
         self.tree.begin(self.store.guard())
     }
 
+    /// Begins a tree transaction to read with `lsn`.
+    ///
+    /// See [`Table::check_read_lsn`].
+    fn begin_read(&self, lsn: u64) -> Result<TreeTxn<'_, E>> {
+        self.check_read_lsn(lsn)?;
+        Ok(self.begin())
+    }
+
+    fn begin_with_timeout(&self, timeout: Duration) -> TreeTxn<'_, E> {
+        let mut guard = self.store.guard();
+        guard.set_deadline(Instant::now() + timeout);
+        self.tree.begin(guard)
+    }
+
     /// Returns a [`Guard`] that pins the table for user operations.
     pub fn pin(&self) -> Guard<'_, E> {
         Guard::new(self)
     }
 
+    /// Returns a [`TableCursor`] over the entries visible to `lsn`.
+    ///
+    /// The cursor is invalid until it is positioned with
+    /// [`TableCursor::seek`]. See [`Options::future_lsn_policy`] for cursors
+    /// with a LSN larger than [`Table::latest_lsn`].
+    pub fn cursor(&self, lsn: u64) -> Result<TableCursor<'_, E>> {
+        let txn = self.begin_read(lsn)?;
+        Ok(TableCursor::new(txn, self.tree.snapshot_at(lsn)))
+    }
+
     /// Gets the value corresponding to the key.
+    ///
+    /// See [`Options::future_lsn_policy`] for reads with a LSN larger than
+    /// [`Table::latest_lsn`].
     pub async fn get(&self, key: &[u8], lsn: u64) -> Result<Option<Vec<u8>>> {
+        let txn = self.begin_read(lsn)?;
         let key = Key::new(key, lsn);
+        let value = txn.get(key).await?;
+        Ok(value.map(|v| v.to_vec()))
+    }
+
+    /// Gets the value corresponding to the integer key.
+    ///
+    /// See [`Table::put_u64`] for how the key is encoded.
+    pub async fn get_u64(&self, key: u64, lsn: u64) -> Result<Option<Vec<u8>>> {
+        self.get(&IntKeyComparator::encode(key), lsn).await
+    }
+
+    /// Gets the latest value corresponding to the key.
+    ///
+    /// This reads all entries written so far, which is the same as reading
+    /// with [`Table::latest_lsn`] without racing with other writes.
+    pub async fn get_latest(&self, key: &[u8]) -> Result<Option<Vec<u8>>> {
+        let key = Key::new(key, u64::MAX);
         let txn = self.begin();
         let value = txn.get(key).await?;
         Ok(value.map(|v| v.to_vec()))
     }
 
+    /// Returns a [`Snapshot`] of the entries written so far.
+    ///
+    /// The snapshot reads with [`Table::latest_lsn`], so writes with smaller
+    /// LSNs should have completed before the snapshot is taken. Entries
+    /// visible to the snapshot are kept by [`Table::collect_tombstones`] until
+    /// the snapshot and its clones are dropped.
+    pub fn snapshot(&self) -> Snapshot {
+        let live = self.tree.snapshot();
+        Snapshot {
+            lsn: live.lsn(),
+            live: Some(Arc::new(live)),
+        }
+    }
+
+    /// Gets the values corresponding to the keys.
+    ///
+    /// All keys are read with one snapshot captured before the first read, so
+    /// the returned values are consistent with each other even if writes land
+    /// in the middle of the call.
+    pub async fn multi_get(&self, keys: &[&[u8]]) -> Result<Vec<Option<Vec<u8>>>> {
+        let snapshot = self.snapshot();
+        self.multi_get_with_snapshot(keys, &snapshot).await
+    }
+
+    /// Gets the values corresponding to the keys with the snapshot.
+    pub async fn multi_get_with_snapshot(
+        &self,
+        keys: &[&[u8]],
+        snapshot: &Snapshot,
+    ) -> Result<Vec<Option<Vec<u8>>>> {
+        let txn = self.begin();
+        let mut values = Vec::with_capacity(keys.len());
+        for key in keys {
+            let key = Key::new(key, snapshot.lsn());
+            let value = txn.get(key).await?;
+            values.push(value.map(|v| v.to_vec()));
+        }
+        Ok(values)
+    }
+
+    /// Reads the pages that contain the keys into the page cache, so that
+    /// subsequent reads of the keys are served from memory.
+    ///
+    /// Unlike [`Table::multi_get`], this returns no values, and failures are
+    /// only recorded in [`PrefetchStats`](crate::PrefetchStats), so the
+    /// prefetch can be left to run on its own.
+    pub async fn prefetch(&self, keys: &[&[u8]]) {
+        let txn = self.begin();
+        txn.prefetch_keys(keys).await;
+    }
+
+    /// Gets the value corresponding to the key within the timeout.
+    ///
+    /// Returns [`Error::Timeout`] if the get is still retrying after the
+    /// timeout.
+    pub async fn get_with_timeout(
+        &self,
+        key: &[u8],
+        lsn: u64,
+        timeout: Duration,
+    ) -> Result<Option<Vec<u8>>> {
+        self.check_read_lsn(lsn)?;
+        let key = Key::new(key, lsn);
+        let txn = self.begin_with_timeout(timeout);
+        let value = txn.get(key).await?;
+        Ok(value.map(|v| v.to_vec()))
+    }
+
+    /// Gets all retained versions of the key that are visible to `max_lsn`.
+    ///
+    /// Returns the LSN and value of each version, newest first. A deleted
+    /// version has no value. Versions that are not visible to
+    /// [`Table::safe_lsn`] may have been dropped.
+    pub async fn get_versions(
+        &self,
+        key: &[u8],
+        max_lsn: u64,
+    ) -> Result<Vec<(u64, Option<Vec<u8>>)>> {
+        let txn = self.begin_read(max_lsn)?;
+        let key = Key::new(key, max_lsn);
+        let versions = txn.get_versions(key).await?;
+        Ok(versions
+            .into_iter()
+            .map(|(lsn, value)| (lsn, value.map(|v| v.to_vec())))
+            .collect())
+    }
+
+    /// Returns the page file or map file that stores the version of the key
+    /// visible to `lsn`.
+    ///
+    /// Returns `None` if the version is only in a write buffer, or if no
+    /// version of the key is visible to `lsn`, including when the key is
+    /// deleted or covered by a range delete at `lsn`. The file may be rewritten
+    /// by space reclaiming at any time, so the result is only a hint for
+    /// debugging data placement.
+    pub async fn locate_key(&self, key: &[u8], lsn: u64) -> Result<Option<PickedFile>> {
+        let txn = self.begin_read(lsn)?;
+        let key = Key::new(key, lsn);
+        txn.locate(key).await
+    }
+
+    /// Returns the ids of page files in the table.
+    pub fn file_ids(&self) -> Vec<u32> {
+        self.store.guard().file_ids()
+    }
+
+    /// Returns the number of page files and map files in the table.
+    ///
+    /// This is cheaper than [`Table::live_files`] if only the counts are
+    /// needed.
+    pub fn file_count(&self) -> (usize, usize) {
+        self.store.file_count()
+    }
+
+    /// Returns an overview of the space used by the page files in the table.
+    ///
+    /// The reclaimable bytes are freed once space reclaiming rewrites the
+    /// files holding them.
+    pub fn space_report(&self) -> SpaceReport {
+        self.store.space_report()
+    }
+
+    /// Returns true if writes are stalled until write buffers are flushed.
+    ///
+    /// This is the case once all write buffers allowed are in use, so that
+    /// writes wait for a flush as soon as the active buffer is full.
+    ///
+    /// See [`PageStoreOptions::max_unflushed_files`] and
+    /// [`PageStoreOptions::max_write_buffers`].
+    ///
+    /// [`PageStoreOptions::max_unflushed_files`]:
+    /// crate::PageStoreOptions::max_unflushed_files
+    /// [`PageStoreOptions::max_write_buffers`]:
+    /// crate::PageStoreOptions::max_write_buffers
+    pub fn is_write_stalled(&self) -> bool {
+        self.store.is_write_stalled()
+    }
+
+    /// Returns the heat of the page files in the table, in ascending order of
+    /// file ids.
+    ///
+    /// The heat of a file estimates how many of its pages were read recently.
+    /// Page reads are sampled, and the heat of a file halves every minute if
+    /// it is not read, so hot files can be kept on fast storage. Reads served
+    /// from write buffers or issued by [`Table::warmup`] are not counted.
+    pub fn page_heat(&self) -> Vec<(u32, f64)> {
+        self.store.page_heat()
+    }
+
+    /// Returns the page files in the table.
+    ///
+    /// The files may be removed by space reclaiming at any time, use
+    /// [`Table::pin_live_files`] to keep them on disk.
+    pub fn live_files(&self) -> Vec<LiveFile> {
+        self.store.live_files()
+    }
+
+    /// Returns the page files in the table, and keeps them on disk until the
+    /// returned guard is dropped.
+    ///
+    /// The files are immutable, so they can be copied while the guard is held,
+    /// for example to replicate the table. Space reclaiming keeps running, but
+    /// the files it obsoletes are not removed until they are unpinned.
+    pub fn pin_live_files(&self) -> LiveFilesGuard {
+        self.store.pin_live_files()
+    }
+
+    /// Returns an iterator over entries physically stored in the page file.
+    ///
+    /// Only entries of active pages in the file are returned, regardless of
+    /// whether they are still visible to reads. A key may be returned more
+    /// than once, with the same or different LSNs. Returns an empty iterator
+    /// if the file does not exist.
+    pub async fn iter_file(&self, file_id: u32) -> Result<FileEntryIter> {
+        let txn = self.begin();
+        let mut entries = Vec::new();
+        for (k, v) in txn.file_entries(file_id).await? {
+            entries.push((k.raw.to_vec(), v.map(<[u8]>::to_vec), k.lsn));
+        }
+        Ok(FileEntryIter {
+            iter: entries.into_iter(),
+        })
+    }
+
+    /// Returns entries between `start` and `end` that are visible to `lsn`.
+    ///
+    /// If [`Options::scan_cache_capacity`] is positive, the result is cached
+    /// until an entry with a LSN not larger than `lsn` is written.
+    pub async fn scan(
+        &self,
+        start: Bound<&[u8]>,
+        end: Bound<&[u8]>,
+        lsn: u64,
+    ) -> Result<Vec<(Vec<u8>, Vec<u8>)>> {
+        self.check_read_lsn(lsn)?;
+        let Some(cache) = &self.scan_cache else {
+            let (entries, _) = self.scan_entries(start, end, lsn, usize::MAX).await?;
+            return Ok(entries);
+        };
+        match cache.lookup(start, end, lsn) {
+            Ok(entries) => Ok(entries),
+            Err(token) => {
+                let (entries, _) = self.scan_entries(start, end, lsn, usize::MAX).await?;
+                cache.insert(token, entries.clone());
+                Ok(entries)
+            }
+        }
+    }
+
+    /// Returns up to `limit` entries between `start` and `end` that are
+    /// visible to `lsn`.
+    ///
+    /// If there are more entries in the range, also returns a [`ResumeToken`]
+    /// to continue the scan with [`Table::scan_resume`]. Returns
+    /// [`Error::InvalidArgument`] if `limit` is 0, since an empty batch can't
+    /// tell whether the range has more entries.
+    pub async fn scan_batch(
+        &self,
+        start: Bound<&[u8]>,
+        end: Bound<&[u8]>,
+        lsn: u64,
+        limit: usize,
+    ) -> Result<(Vec<(Vec<u8>, Vec<u8>)>, Option<ResumeToken>)> {
+        if limit == 0 {
+            return Err(Error::InvalidArgument("scan batch limit is 0".to_owned()));
+        }
+        let (entries, truncated) = self.scan_entries(start, end, lsn, limit).await?;
+        let token = if truncated {
+            entries.last().map(|(k, _)| ResumeToken {
+                last_key: k.clone(),
+                lsn,
+            })
+        } else {
+            None
+        };
+        Ok((entries, token))
+    }
+
+    /// Continues a scan right after the last key returned with the token, at
+    /// the same LSN.
+    ///
+    /// This returns the same result as [`Table::scan_batch`] starting after
+    /// the last key. Returns [`Error::StaleLsn`] if the LSN of the token is
+    /// smaller than [`Table::safe_lsn`], since entries visible to it may have
+    /// been dropped.
+    pub async fn scan_resume(
+        &self,
+        token: &ResumeToken,
+        end: Bound<&[u8]>,
+        limit: usize,
+    ) -> Result<(Vec<(Vec<u8>, Vec<u8>)>, Option<ResumeToken>)> {
+        let safe_lsn = self.safe_lsn();
+        if token.lsn < safe_lsn {
+            return Err(Error::StaleLsn {
+                lsn: token.lsn,
+                safe_lsn,
+            });
+        }
+        let start = Bound::Excluded(token.last_key.as_slice());
+        self.scan_batch(start, end, token.lsn, limit).await
+    }
+
+    /// Returns the keys between `start` and `end` that are visible to `lsn`.
+    ///
+    /// This yields the same keys as [`Table::scan`] in the same order, but
+    /// values are not copied. Deleted keys are skipped.
+    pub async fn scan_keys(
+        &self,
+        start: Bound<&[u8]>,
+        end: Bound<&[u8]>,
+        lsn: u64,
+    ) -> Result<KeyIter> {
+        let (keys, _) = self
+            .scan_with(start, end, lsn, usize::MAX, |k, _| k.to_vec())
+            .await?;
+        Ok(KeyIter {
+            iter: keys.into_iter(),
+        })
+    }
+
+    /// Returns the entries whose keys start with `prefix` that are visible to
+    /// `lsn`, in descending order of keys.
+    ///
+    /// This yields the same entries as [`Table::scan`] over the keys under the
+    /// prefix, but in reverse order. Leaf pages are read backwards from the
+    /// end of the prefix, so the most recent entries under the prefix can be
+    /// found without reading the whole range first.
+    pub async fn scan_prefix_rev(
+        &self,
+        prefix: &[u8],
+        lsn: u64,
+    ) -> Result<Vec<(Vec<u8>, Vec<u8>)>> {
+        self.check_read_lsn(lsn)?;
+        let Some(end) = prefix_end(prefix) else {
+            // The keys under the prefix extend to the end of the table, which
+            // can only be reached forwards.
+            let (mut entries, _) = self
+                .scan_entries(Bound::Included(prefix), Bound::Unbounded, lsn, usize::MAX)
+                .await?;
+            entries.reverse();
+            return Ok(entries);
+        };
+        let txn = self.begin();
+        let mut entries = Vec::new();
+        let mut key = end;
+        loop {
+            let page = txn.read_leaf(&key, true, lsn).await?;
+            for (k, v) in page.entries.into_iter().rev() {
+                if k < page.start || page.end.map_or(false, |end| k >= end) {
+                    continue;
+                }
+                if k < key.as_slice() && k >= prefix {
+                    entries.push((k.to_vec(), v.to_vec()));
+                }
+            }
+            if page.start <= prefix {
+                break;
+            }
+            key = page.start.to_vec();
+        }
+        Ok(entries)
+    }
+
+    /// Returns [`Error::InvalidLsn`] if `lsn` is larger than
+    /// [`Table::latest_lsn`] and the reads are strict about it.
+    fn check_read_lsn(&self, lsn: u64) -> Result<()> {
+        if self.tree.future_lsn_policy() == FutureLsnPolicy::Strict {
+            let latest_lsn = self.latest_lsn();
+            if lsn > latest_lsn {
+                return Err(Error::InvalidLsn { lsn, latest_lsn });
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns up to `limit` entries, and whether there are more entries in
+    /// the range.
+    async fn scan_entries(
+        &self,
+        start: Bound<&[u8]>,
+        end: Bound<&[u8]>,
+        lsn: u64,
+        limit: usize,
+    ) -> Result<(ScanEntries, bool)> {
+        self.scan_with(start, end, lsn, limit, |k, v| (k.to_vec(), v.to_vec()))
+            .await
+    }
+
+    /// Returns up to `limit` items converted from entries with `f`, and
+    /// whether there are more entries in the range.
+    async fn scan_with<T, F>(
+        &self,
+        start: Bound<&[u8]>,
+        end: Bound<&[u8]>,
+        lsn: u64,
+        limit: usize,
+        mut f: F,
+    ) -> Result<(Vec<T>, bool)>
+    where
+        F: FnMut(&[u8], &[u8]) -> T,
+    {
+        let mut entries = Vec::new();
+        let mut truncated = false;
+        // Returns false if the key is beyond the end or the limit is reached.
+        let mut push = |k: &[u8], v: &[u8]| {
+            match end {
+                Bound::Included(end) if k > end => return false,
+                Bound::Excluded(end) if k >= end => return false,
+                _ => {}
+            }
+            if !matches!(start, Bound::Excluded(start) if k == start) {
+                if entries.len() == limit {
+                    truncated = true;
+                    return false;
+                }
+                entries.push(f(k, v));
+            }
+            true
+        };
+        let target = match start {
+            Bound::Included(start) | Bound::Excluded(start) => start,
+            Bound::Unbounded => &[],
+        };
+        let guard = self.pin();
+        let mut pages = guard.pages_at(lsn)?;
+        let mut done = !pages.seek(target).await?.all(|(k, v)| push(k, v));
+        while !done {
+            match pages.next().await? {
+                Some(mut page) => done = !page.all(|(k, v)| push(k, v)),
+                None => done = true,
+            }
+        }
+        Ok((entries, truncated))
+    }
+
+    /// Reads the pages that cover the ranges into the page cache.
+    ///
+    /// This warms up the page cache for subsequent reads, for example after
+    /// the table is reopened. It stops when the page cache is full. The bytes
+    /// read from page files are recorded in
+    /// [`ReadSourceStats::warmup_bytes`](crate::ReadSourceStats::warmup_bytes).
+    pub async fn warmup(&self, ranges: &[KeyBounds<'_>]) -> Result<()> {
+        let txn = self.begin();
+        for &(start, end) in ranges {
+            let start = match start {
+                Bound::Included(start) | Bound::Excluded(start) => start,
+                Bound::Unbounded => &[],
+            };
+            if !txn.warmup(start, end).await? {
+                break;
+            }
+        }
+        Ok(())
+    }
+
     /// Puts a key-value entry to the table.
+    ///
+    /// Both the key and the value can be empty.
     pub async fn put(&self, key: &[u8], lsn: u64, value: &[u8]) -> Result<()> {
         let key = Key::new(key, lsn);
         let value = Value::Put(value);
         let txn = self.begin();
         txn.write(key, value).await?;
+        self.finish_write(lsn).await;
+        Ok(())
+    }
+
+    /// Puts a key-value entry with an integer key to the table.
+    ///
+    /// The key is encoded with [`IntKeyComparator::encode`], so entries are
+    /// ordered by the numeric value of their keys.
+    pub async fn put_u64(&self, key: u64, lsn: u64, value: &[u8]) -> Result<()> {
+        self.put(&IntKeyComparator::encode(key), lsn, value).await
+    }
+
+    /// Puts a key-value entry to the table within the timeout.
+    ///
+    /// Returns [`Error::Timeout`] if the put is still retrying after the
+    /// timeout, or [`Error::WriteStall`] if it is waiting for write buffers to
+    /// be flushed. The entry is not written in either case.
+    pub async fn put_with_timeout(
+        &self,
+        key: &[u8],
+        lsn: u64,
+        value: &[u8],
+        timeout: Duration,
+    ) -> Result<()> {
+        let key = Key::new(key, lsn);
+        let value = Value::Put(value);
+        let txn = self.begin_with_timeout(timeout);
+        txn.write(key, value).await?;
+        self.finish_write(lsn).await;
+        Ok(())
+    }
+
+    /// Puts a key-value entry to the table and returns the previous value.
+    ///
+    /// The previous value is the one visible to `lsn` right before this put.
+    /// No other write can happen between reading the previous value and the
+    /// put.
+    pub async fn put_and_get_previous(
+        &self,
+        key: &[u8],
+        lsn: u64,
+        value: &[u8],
+    ) -> Result<Option<Vec<u8>>> {
+        let key = Key::new(key, lsn);
+        let value = Value::Put(value);
+        let txn = self.begin();
+        let previous = txn.write_and_get_previous(key, value).await?;
+        self.finish_write(lsn).await;
+        Ok(previous)
+    }
+
+    /// Puts a key-value entry to the table if no version of the key has a LSN
+    /// larger than `expected_max_lsn`.
+    ///
+    /// Returns false if the put is rejected because the key has been written
+    /// or deleted with a larger LSN, so that stale writes don't overwrite
+    /// newer ones. The check and the put are atomic with respect to other
+    /// writes to the key.
+    pub async fn put_if_lsn(
+        &self,
+        key: &[u8],
+        expected_max_lsn: u64,
+        new_lsn: u64,
+        value: &[u8],
+    ) -> Result<bool> {
+        let key = Key::new(key, new_lsn);
+        let value = Value::Put(value);
+        let txn = self.begin();
+        let written = txn.write_if_lsn(key, value, expected_max_lsn).await?;
+        if written {
+            self.finish_write(new_lsn).await;
+        }
+        Ok(written)
+    }
+
+    /// Puts sorted key-value entries to the table.
+    ///
+    /// Each entry is a key, a LSN and a value. Entries must be sorted by key,
+    /// and entries of the same key must be sorted by LSN, so that the entry
+    /// later in the input wins. The input is checked before any entry is
+    /// written.
+    ///
+    /// Returns [`Error::DuplicateKey`] if two entries have the same key and
+    /// LSN, or [`Error::UnsortedInput`] if the entries are not sorted.
+    pub async fn ingest_sorted(&self, entries: &[(&[u8], u64, &[u8])]) -> Result<()> {
+        for w in entries.windows(2) {
+            let ((k1, lsn1, _), (k2, lsn2, _)) = (w[0], w[1]);
+            match (k1.cmp(k2), lsn1.cmp(&lsn2)) {
+                (Ordering::Less, _) | (Ordering::Equal, Ordering::Less) => {}
+                (Ordering::Equal, Ordering::Equal) => {
+                    return Err(Error::DuplicateKey {
+                        key: k1.to_vec(),
+                        lsn: lsn1,
+                    });
+                }
+                _ => return Err(Error::UnsortedInput),
+            }
+        }
+
+        let txn = self.begin();
+        for &(key, lsn, value) in entries {
+            txn.write(Key::new(key, lsn), Value::Put(value)).await?;
+        }
+        if let Some(lsn) = entries.iter().map(|&(_, lsn, _)| lsn).min() {
+            self.finish_write(lsn).await;
+        }
+        Ok(())
+    }
+
+    /// Writes the entries visible to `lsn` to the writer, and returns the
+    /// number of entries written.
+    ///
+    /// Each entry is written as the length of the key, the key, the length of
+    /// the value and the value, where the lengths are little-endian `u32`s.
+    /// The dump doesn't depend on the format of page files, and can be loaded
+    /// into another table with [`Table::import`].
+    pub async fn export<W: Write>(&self, mut w: W, lsn: u64) -> Result<usize> {
+        let mut num_entries = 0;
+        let (mut entries, mut token) = self
+            .scan_batch(Bound::Unbounded, Bound::Unbounded, lsn, EXPORT_BATCH_SIZE)
+            .await?;
+        loop {
+            for (key, value) in &entries {
+                write_exported_entry(&mut w, key, value)?;
+            }
+            num_entries += entries.len();
+            let Some(next) = token else {
+                break;
+            };
+            (entries, token) = self
+                .scan_resume(&next, Bound::Unbounded, EXPORT_BATCH_SIZE)
+                .await?;
+        }
+        w.flush()?;
+        Ok(num_entries)
+    }
+
+    /// Reads entries written by [`Table::export`] from the reader and puts
+    /// them to the table at `lsn`, returns the number of entries read.
+    ///
+    /// Entries are put with [`Table::ingest_sorted`] in batches. Returns
+    /// [`Error::Corrupted`] if the input ends in the middle of an entry, in
+    /// which case the entries of the batches before are already written.
+    pub async fn import<R: Read>(&self, mut r: R, lsn: u64) -> Result<usize> {
+        let mut num_entries = 0;
+        let mut batch: Vec<(Vec<u8>, Vec<u8>)> = Vec::with_capacity(EXPORT_BATCH_SIZE);
+        loop {
+            let entry = read_exported_entry(&mut r)?;
+            let done = entry.is_none();
+            batch.extend(entry);
+            if done || batch.len() == EXPORT_BATCH_SIZE {
+                let entries: Vec<_> = batch
+                    .iter()
+                    .map(|(k, v)| (k.as_slice(), lsn, v.as_slice()))
+                    .collect();
+                self.ingest_sorted(&entries).await?;
+                num_entries += batch.len();
+                batch.clear();
+            }
+            if done {
+                return Ok(num_entries);
+            }
+        }
+    }
+
+    /// Merges the operand into the value of the key with
+    /// [`Options::merge_operator`].
+    ///
+    /// The operand is applied to the value visible to `lsn` right before this
+    /// merge, and the result is written as the value of the key at `lsn`. No
+    /// other write can happen between reading the value and the merge.
+    ///
+    /// Returns [`Error::InvalidOptions`] if no merge operator is configured.
+    pub async fn merge(&self, key: &[u8], lsn: u64, operand: &[u8]) -> Result<()> {
+        let Some(operator) = self.tree.merge_operator() else {
+            return Err(Error::InvalidOptions(
+                "merge_operator is not configured".to_owned(),
+            ));
+        };
+        let key = Key::new(key, lsn);
+        let txn = self.begin();
+        txn.merge(key, operand, operator).await?;
+        self.finish_write(lsn).await;
         Ok(())
     }
 
@@ -81,17 +889,124 @@ impl<E: Env> Table<E> {
         let value = Value::Delete;
         let txn = self.begin();
         txn.write(key, value).await?;
+        self.finish_write(lsn).await;
         Ok(())
     }
 
+    /// Moves the value of `from` to `to` at `lsn`, and returns whether `from`
+    /// has a value visible to `lsn`.
+    ///
+    /// `from` is deleted and `to` is put with its value, both at `lsn`. No
+    /// other write to `from` can happen between reading its value and
+    /// deleting it. If `lsn` is larger than [`Table::latest_lsn`] when the
+    /// rename starts, [`Table::latest_lsn`] stays below `lsn` until both keys
+    /// are written, even if writes with larger LSNs finish in between. So
+    /// reads at LSNs not larger than [`Table::latest_lsn`], like those of a
+    /// [`Snapshot`], see either `from` or `to` but never both or neither.
+    /// Reads with larger LSNs, like [`Table::get_latest`], may see the rename
+    /// in progress.
+    ///
+    /// If `from` has no value, it is still deleted at `lsn` and `to` is left
+    /// untouched. If `to` can't be written, the value of `from` is written
+    /// back before the error is returned.
+    pub async fn rename(&self, from: &[u8], to: &[u8], lsn: u64) -> Result<bool> {
+        let txn = self.begin();
+        let renamed = txn.rename(Key::new(from, lsn), Key::new(to, lsn)).await?;
+        self.finish_write(lsn).await;
+        Ok(renamed)
+    }
+
+    /// Deletes the entries with keys from `start` (inclusive) to `end`
+    /// (exclusive) from the table.
+    ///
+    /// The range shadows the versions of keys in it at or below `lsn`, so
+    /// entries written later with larger LSNs are still visible. It is
+    /// recorded without reading the entries, and collapsed into the pages
+    /// when they are consolidated.
+    ///
+    /// Does nothing if the range is empty.
+    pub async fn delete_range(&self, start: &[u8], end: &[u8], lsn: u64) -> Result<()> {
+        if start >= end {
+            return Ok(());
+        }
+        let txn = self.begin();
+        txn.delete_range(start, Some(end), lsn).await?;
+        self.finish_write(lsn).await;
+        Ok(())
+    }
+
+    /// Consolidates all leaf pages completely.
+    ///
+    /// Versions that are not visible to the safe LSN are dropped, and
+    /// [`Options::compaction_filter`] is applied to all entries.
+    pub async fn compact(&self) -> Result<()> {
+        let txn = self.begin();
+        txn.compact().await?;
+        // The filter may change entries visible to any LSN.
+        self.finish_write(0).await;
+        Ok(())
+    }
+
+    /// Consolidates all leaf pages completely to collect tombstones.
+    ///
+    /// Deletes are dropped only if they are visible to the oldest live
+    /// [`Snapshot`] or [`TableCursor`], or to [`Table::latest_lsn`] if there
+    /// is none, and no older versions of their keys are left. Live snapshots
+    /// and cursors still see the same entries afterwards, but other reads with
+    /// smaller LSNs may not, even if the LSNs are not smaller than
+    /// [`Table::safe_lsn`]. [`Options::compaction_filter`] is applied like
+    /// [`Table::compact`], and [`TombstoneStats`] records the collected
+    /// deletes.
+    pub async fn collect_tombstones(&self) -> Result<()> {
+        let txn = self.begin();
+        txn.collect_tombstones().await?;
+        // Entries visible to any LSN below the collected one may be dropped.
+        self.finish_write(0).await;
+        Ok(())
+    }
+
+    /// Completes a write with entries not smaller than `lsn`.
+    ///
+    /// Flushes the write buffer if [`PageStoreOptions::writebuf_disabled`] is
+    /// set.
+    ///
+    /// [`PageStoreOptions::writebuf_disabled`]: crate::PageStoreOptions::writebuf_disabled
+    async fn finish_write(&self, lsn: u64) {
+        self.invalidate_scans(lsn);
+        if self.store.writebuf_disabled() {
+            self.store.flush(&FlushOptions::default()).await;
+        }
+    }
+
+    fn invalidate_scans(&self, lsn: u64) {
+        if let Some(cache) = &self.scan_cache {
+            cache.invalidate(lsn);
+        }
+    }
+
     /// Returns the statistics of the table.
     pub fn stats(&self) -> TableStats {
+        let mut store = self.store.stats();
+        store.files.set_page_fill_factor(self.tree.page_size());
         TableStats {
             tree: self.tree.stats(),
-            store: self.store.stats(),
+            store,
+            scan_cache: self
+                .scan_cache
+                .as_ref()
+                .map(|cache| cache.stats())
+                .unwrap_or_default(),
         }
     }
 
+    /// Returns the largest LSN of entries written to the table.
+    ///
+    /// After the table is reopened, this only covers entries that were
+    /// flushed to page files.
+    pub fn latest_lsn(&self) -> u64 {
+        self.tree.latest_lsn()
+    }
+
     /// Returns the minimal LSN that the table can safely read with.
     ///
     /// The table guarantees that entries visible to the returned LSN are
@@ -114,10 +1029,101 @@ impl<E: Env> Table<E> {
         self.store.flush(opts).await;
     }
 
+    /// Flushes all write buffer data, and then copies the page files of the
+    /// table to `dir` as a checkpoint that can be opened as a table.
+    ///
+    /// The checkpoint contains every write that finished before the call.
+    /// Each write that races with the call is either included or excluded as
+    /// a whole. The directory must not exist or be empty.
+    pub async fn flush_and_checkpoint<P: AsRef<Path>>(&self, dir: P) -> Result<()> {
+        self.flush(&FlushOptions::default()).await;
+        self.store.checkpoint(dir.as_ref()).await?;
+        Ok(())
+    }
+
     /// Wait all pending reclaiming to finish.
     pub async fn wait_for_reclaiming(&self) {
         self.store.wait_for_reclaiming().await;
     }
+
+    /// Waits until no under-full leaf page is queued or being merged.
+    ///
+    /// See [`Options::min_leaf_fill_ratio`].
+    pub async fn wait_for_merging(&self) {
+        self.tree.wait_for_merging().await;
+    }
+
+    /// Waits until no flush or space reclaiming job is running or queued.
+    ///
+    /// This is useful to measure steady-state performance without background
+    /// interference. Write buffers sealed before or during the call are
+    /// waited to be flushed, but the active write buffer is not flushed. It
+    /// doesn't wait for flushes while the disk is full, and returns early
+    /// while reclaiming is paused by [`Table::pause_background`].
+    pub async fn wait_for_background_idle(&self) {
+        self.store.wait_for_background_idle().await;
+    }
+
+    /// Returns the files that space reclaiming would pick in order, with the
+    /// bytes freed by reclaiming each of them.
+    ///
+    /// This ranks all files with the current strategy without rewriting any
+    /// of them. The reclaimer processes files in the same order, but it stops
+    /// once the space usage is low enough.
+    pub async fn reclaim_dry_run(&self) -> Vec<(PickedFile, usize)> {
+        self.store.reclaim_dry_run().await
+    }
+
+    /// Rewrites the live pages of the file into a new file, and removes the
+    /// file once it is no longer in use.
+    ///
+    /// Page files in the file that are obsolete are dropped as a whole, and
+    /// only the live pages of the others are rewritten. This reclaims the
+    /// space of page files that are partially dead, which space reclaiming
+    /// only weighs as part of the whole file.
+    ///
+    /// The file is compacted regardless of its reclaim score, for example
+    /// one picked by [`Table::reclaim_dry_run`] or listed by
+    /// [`Table::live_files`]. If space reclaiming is compacting some files,
+    /// this waits for it to finish first, so a file is never compacted twice.
+    ///
+    /// Reads and writes are not blocked meanwhile. Reads are served from the
+    /// original file until the new file is committed to the manifest, and
+    /// from the new file afterwards.
+    ///
+    /// Returns [`Error::MissingFile`] if the file is not in the table, which
+    /// is also the case if it has been reclaimed already.
+    ///
+    /// [`Error::MissingFile`]: crate::Error::MissingFile
+    pub async fn compact_file(&self, file: PickedFile) -> Result<()> {
+        self.store.compact_file(file.file_id).await?;
+        Ok(())
+    }
+
+    /// Replaces the strategy to pick files for space reclaiming.
+    ///
+    /// The strategy is used by reclaim cycles started after this, a cycle in
+    /// progress finishes with the former strategy.
+    pub fn set_reclaim_strategy(&self, strategy: ReclaimStrategy) {
+        self.store.set_reclaim_strategy(strategy);
+    }
+
+    /// Pauses background space reclaiming, flush keeps running.
+    ///
+    /// Pausing is reentrant: reclaiming is resumed only after
+    /// [`Table::resume_background`] is called as many times as this. A reclaim
+    /// cycle in progress stops after the file it is rewriting.
+    /// [`Table::wait_for_reclaiming`] returns early while reclaiming is paused.
+    pub fn pause_background(&self) {
+        self.store.pause_reclaiming();
+    }
+
+    /// Resumes background space reclaiming paused by
+    /// [`Table::pause_background`]. It does nothing if reclaiming is not
+    /// paused.
+    pub fn resume_background(&self) {
+        self.store.resume_reclaiming();
+    }
 }
 
 /// A handle that holds some resources of a table for user operations.
@@ -144,13 +1150,32 @@ impl<'a, E: Env> Guard<'a, E> {
     /// On success, if the value is found, returns [`Option::Some`] with the
     /// value; if the value is not found, returns [`Option::None`].
     pub async fn get(&self, key: &[u8], lsn: u64) -> Result<Option<&[u8]>> {
+        self.table.check_read_lsn(lsn)?;
         let key = Key::new(key, lsn);
         Ok(self.txn.get(key).await?)
     }
 
     /// Returns an iterator over pages in the table.
     pub fn pages(&self) -> Pages<'_, 'a, E> {
-        Pages::new(&self.txn)
+        Pages::new(&self.txn, ReadOptions::default())
+    }
+
+    /// Returns an iterator over pages in the table at the given LSN.
+    ///
+    /// Only entries visible to the LSN are returned. Files referenced by the
+    /// guard are kept until the guard is dropped or re-pinned, so the
+    /// iterator returns a consistent view even if space reclaiming rewrites
+    /// these files in the meantime. Entries visible to the LSN are retained
+    /// only if the LSN is not smaller than [`Table::safe_lsn`]. See
+    /// [`Options::future_lsn_policy`] for a LSN larger than
+    /// [`Table::latest_lsn`].
+    pub fn pages_at(&self, lsn: u64) -> Result<Pages<'_, 'a, E>> {
+        self.table.check_read_lsn(lsn)?;
+        let options = ReadOptions {
+            max_lsn: lsn,
+            ..Default::default()
+        };
+        Ok(Pages::new(&self.txn, options))
     }
 }
 
@@ -160,16 +1185,287 @@ pub struct Pages<'a, 't: 'a, E: Env> {
 }
 
 impl<'a, 't: 'a, E: Env> Pages<'a, 't, E> {
-    fn new(txn: &'a TreeTxn<'t, E>) -> Self {
+    fn new(txn: &'a TreeTxn<'t, E>, options: ReadOptions) -> Self {
         Self {
-            iter: TreeIter::new(txn, ReadOptions::default()),
+            iter: TreeIter::new(txn, options),
         }
     }
 
+    /// Prefetches up to `n` pages ahead while the current page is read.
+    ///
+    /// This reduces the stalls of sequential scans over pages that are not
+    /// in memory. A value of 0 disables readahead.
+    pub fn with_readahead(mut self, n: usize) -> Self {
+        self.iter.set_readahead(n);
+        self
+    }
+
     /// Returns the next page in the table.
     pub async fn next(&mut self) -> Result<Option<PageIter<'_>>> {
         Ok(self.iter.next_page().await?)
     }
+
+    /// Positions the iterator at the page that contains `target`.
+    ///
+    /// Returns the page with its entries positioned at the first entry that
+    /// is at or after `target`. Subsequent calls to [`Pages::next`] return
+    /// pages after it.
+    pub async fn seek(&mut self, target: &[u8]) -> Result<PageIter<'_>> {
+        Ok(self.iter.seek(target).await?)
+    }
+}
+
+/// A cursor over the entries of a table that can step forward and backward.
+///
+/// The cursor pins the table like a [`Guard`], and reads the entries visible
+/// to an LSN, so it sees a consistent view until it is dropped. It becomes
+/// invalid when it steps past either end of the table, and is valid again
+/// once it is positioned with [`TableCursor::seek`].
+///
+/// Any number of cursors can be open at the same time, each with its own LSN.
+/// They don't block each other, writes, flushes or space reclaiming: the
+/// write buffers and page files that the pinned version refers to are only
+/// released after the cursor is dropped. So long-lived cursors hold on to
+/// memory and disk space, and should be dropped once they are done.
+/// Entries visible to the LSN are kept as long as it is not smaller than
+/// [`Table::safe_lsn`], and are never dropped by
+/// [`Table::collect_tombstones`] while the cursor is alive.
+pub struct TableCursor<'a, E: Env> {
+    txn: TreeTxn<'a, E>,
+    snapshot: LiveSnapshot,
+    // The range and entries of the current leaf page.
+    start: Vec<u8>,
+    end: Option<Vec<u8>>,
+    entries: Vec<(Vec<u8>, Vec<u8>)>,
+    // The cursor is invalid if the position is out of the entries.
+    pos: usize,
+}
+
+impl<'a, E: Env> TableCursor<'a, E> {
+    fn new(txn: TreeTxn<'a, E>, snapshot: LiveSnapshot) -> Self {
+        Self {
+            txn,
+            snapshot,
+            start: Vec::new(),
+            end: None,
+            entries: Vec::new(),
+            pos: 0,
+        }
+    }
+
+    /// Returns true if the cursor is positioned at an entry.
+    pub fn valid(&self) -> bool {
+        self.pos < self.entries.len()
+    }
+
+    /// Returns the key of the current entry.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the cursor is not valid.
+    pub fn key(&self) -> &[u8] {
+        &self.entries[self.pos].0
+    }
+
+    /// Returns the value of the current entry.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the cursor is not valid.
+    pub fn value(&self) -> &[u8] {
+        &self.entries[self.pos].1
+    }
+
+    /// Positions the cursor at the first entry that is at or after `target`.
+    ///
+    /// The cursor becomes invalid if there is no such entry.
+    pub async fn seek(&mut self, target: &[u8]) -> Result<()> {
+        self.load_page(target, false).await?;
+        self.pos = self.entries.partition_point(|(k, _)| k.as_slice() < target);
+        self.skip_to_next_page().await
+    }
+
+    /// Moves the cursor to the next entry.
+    ///
+    /// The cursor becomes invalid if it is at the last entry, and stays
+    /// invalid if it is not valid.
+    pub async fn next(&mut self) -> Result<()> {
+        if !self.valid() {
+            return Ok(());
+        }
+        self.pos += 1;
+        self.skip_to_next_page().await
+    }
+
+    /// Moves the cursor to the previous entry.
+    ///
+    /// The cursor becomes invalid if it is at the first entry, and stays
+    /// invalid if it is not valid.
+    pub async fn prev(&mut self) -> Result<()> {
+        if !self.valid() {
+            return Ok(());
+        }
+        if self.pos > 0 {
+            self.pos -= 1;
+            return Ok(());
+        }
+        // Moves to the last entry of the previous non-empty page.
+        loop {
+            if self.start.is_empty() {
+                self.entries.clear();
+                return Ok(());
+            }
+            let start = self.start.clone();
+            self.load_page(&start, true).await?;
+            if let Some(last) = self.entries.len().checked_sub(1) {
+                self.pos = last;
+                return Ok(());
+            }
+        }
+    }
+
+    // Moves to the first entry of the next non-empty page if the position is
+    // beyond the current page.
+    async fn skip_to_next_page(&mut self) -> Result<()> {
+        while self.pos == self.entries.len() {
+            let Some(end) = self.end.take() else {
+                self.entries.clear();
+                self.pos = 0;
+                return Ok(());
+            };
+            self.load_page(&end, false).await?;
+            self.pos = 0;
+        }
+        Ok(())
+    }
+
+    async fn load_page(&mut self, key: &[u8], before: bool) -> Result<()> {
+        let page = self.txn.read_leaf(key, before, self.snapshot.lsn()).await?;
+        self.entries = page
+            .entries
+            .into_iter()
+            .filter(|(k, _)| *k >= page.start && page.end.map_or(true, |end| *k < end))
+            .map(|(k, v)| (k.to_vec(), v.to_vec()))
+            .collect();
+        self.start = page.start.to_vec();
+        self.end = page.end.map(<[u8]>::to_vec);
+        Ok(())
+    }
+}
+
+/// A range of keys between a start bound and an end bound.
+pub type KeyBounds<'a> = (Bound<&'a [u8]>, Bound<&'a [u8]>);
+
+/// A token to continue a scan after the last returned key.
+///
+/// The token records the last key and the LSN of the scan. It can be encoded
+/// to bytes and sent to clients, for example to paginate the results of a
+/// scan.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ResumeToken {
+    last_key: Vec<u8>,
+    lsn: u64,
+}
+
+impl ResumeToken {
+    /// Returns the last key returned by the scan.
+    pub fn last_key(&self) -> &[u8] {
+        &self.last_key
+    }
+
+    /// Returns the LSN of the scan.
+    pub fn lsn(&self) -> u64 {
+        self.lsn
+    }
+
+    /// Encodes the token to bytes.
+    pub fn encode(&self) -> Vec<u8> {
+        let mut buf = Vec::with_capacity(8 + self.last_key.len());
+        buf.extend_from_slice(&self.lsn.to_le_bytes());
+        buf.extend_from_slice(&self.last_key);
+        buf
+    }
+
+    /// Decodes a token from bytes returned by [`ResumeToken::encode`].
+    ///
+    /// Returns [`Error::InvalidArgument`] if the bytes are not a valid token.
+    pub fn decode(buf: &[u8]) -> Result<Self> {
+        if buf.len() < 8 {
+            return Err(Error::InvalidArgument(format!(
+                "resume token of {} bytes is too short",
+                buf.len()
+            )));
+        }
+        let (lsn, last_key) = buf.split_at(8);
+        Ok(ResumeToken {
+            last_key: last_key.to_vec(),
+            lsn: u64::from_le_bytes(lsn.try_into().unwrap()),
+        })
+    }
+}
+
+/// A consistent view of a table at a given LSN.
+///
+/// Reads with the same snapshot see the same entries, regardless of writes
+/// with larger LSNs.
+#[derive(Clone)]
+pub struct Snapshot {
+    lsn: u64,
+    // Keeps the entries visible to the LSN from tombstone collection.
+    live: Option<Arc<LiveSnapshot>>,
+}
+
+impl Snapshot {
+    /// Creates a snapshot that reads with `lsn`.
+    ///
+    /// Unlike [`Table::snapshot`], the snapshot is not live, so
+    /// [`Table::collect_tombstones`] doesn't keep the entries visible to it.
+    pub fn new(lsn: u64) -> Self {
+        Self { lsn, live: None }
+    }
+
+    /// Returns the LSN of the snapshot.
+    pub fn lsn(&self) -> u64 {
+        self.lsn
+    }
+}
+
+impl std::fmt::Debug for Snapshot {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Snapshot")
+            .field("lsn", &self.lsn)
+            .field("live", &self.live.is_some())
+            .finish()
+    }
+}
+
+/// An iterator over entries in a page file.
+///
+/// Each entry is a tuple of the key, the value, and the LSN. A deleted entry
+/// has no value.
+pub struct FileEntryIter {
+    iter: std::vec::IntoIter<(Vec<u8>, Option<Vec<u8>>, u64)>,
+}
+
+impl Iterator for FileEntryIter {
+    type Item = (Vec<u8>, Option<Vec<u8>>, u64);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.iter.next()
+    }
+}
+
+/// An iterator over keys returned by [`Table::scan_keys`].
+pub struct KeyIter {
+    iter: std::vec::IntoIter<Vec<u8>>,
+}
+
+impl Iterator for KeyIter {
+    type Item = Vec<u8>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.iter.next()
+    }
 }
 
 /// Statstistic of a table.
@@ -179,6 +1475,8 @@ pub struct TableStats {
     pub tree: TreeStats,
     /// The stats of store.
     pub store: StoreStats,
+    /// The stats of cached scans.
+    pub scan_cache: ScanCacheStats,
 }
 
 impl TableStats {
@@ -187,6 +1485,7 @@ impl TableStats {
         TableStats {
             tree: self.tree.sub(&o.tree),
             store: self.store.sub(&o.store),
+            scan_cache: self.scan_cache.sub(&o.scan_cache),
         }
     }
 }
@@ -195,6 +1494,7 @@ impl std::fmt::Display for TableStats {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         self.tree.fmt(f)?;
         self.store.fmt(f)?;
+        self.scan_cache.fmt(f)?;
 
         let user_read_bytes = self.tree.success.read_bytes;
         let front_read_bytes = self.store.writebuf.read_file_bytes;
@@ -226,3 +1526,312 @@ impl std::fmt::Display for TableStats {
         )
     }
 }
+
+/// Returns the smallest key that is larger than all keys starting with
+/// `prefix`, or `None` if there is no such key.
+fn prefix_end(prefix: &[u8]) -> Option<Vec<u8>> {
+    let len = prefix.iter().rposition(|&b| b != u8::MAX)? + 1;
+    let mut end = prefix[..len].to_vec();
+    end[len - 1] += 1;
+    Some(end)
+}
+
+/// Writes an entry in the format of [`Table::export`].
+fn write_exported_entry<W: Write>(w: &mut W, key: &[u8], value: &[u8]) -> Result<()> {
+    for buf in [key, value] {
+        let len = u32::try_from(buf.len()).expect("entries are smaller than 4GiB");
+        w.write_all(&len.to_le_bytes())?;
+        w.write_all(buf)?;
+    }
+    Ok(())
+}
+
+/// Reads an entry written by [`write_exported_entry`], returns `None` at the
+/// end of the input.
+fn read_exported_entry<R: Read>(r: &mut R) -> Result<Option<(Vec<u8>, Vec<u8>)>> {
+    let mut len = [0; 4];
+    let mut filled = 0;
+    while filled < len.len() {
+        match r.read(&mut len[filled..]) {
+            Ok(0) if filled == 0 => return Ok(None),
+            Ok(0) => return Err(Error::Corrupted),
+            Ok(n) => filled += n,
+            Err(err) if err.kind() == std::io::ErrorKind::Interrupted => {}
+            Err(err) => return Err(err.into()),
+        }
+    }
+    let key = read_exported_bytes(r, u32::from_le_bytes(len))?;
+    read_exact_or_corrupted(r, &mut len)?;
+    let value = read_exported_bytes(r, u32::from_le_bytes(len))?;
+    Ok(Some((key, value)))
+}
+
+fn read_exported_bytes<R: Read>(r: &mut R, len: u32) -> Result<Vec<u8>> {
+    let mut buf = vec![0; len as usize];
+    read_exact_or_corrupted(r, &mut buf)?;
+    Ok(buf)
+}
+
+fn read_exact_or_corrupted<R: Read>(r: &mut R, buf: &mut [u8]) -> Result<()> {
+    r.read_exact(buf).map_err(|err| match err.kind() {
+        std::io::ErrorKind::UnexpectedEof => Error::Corrupted,
+        _ => err.into(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use tempfile::tempdir;
+
+    use super::*;
+    use crate::env::{Fault, Photon};
+
+    #[photonio::test]
+    async fn put_with_timeout_during_write_stall() {
+        let path = tempdir().unwrap();
+        let mut options = Options::default();
+        options.page_store.write_buffer_capacity = 1 << 16;
+        options.page_store.max_write_buffers = 2;
+        let table = Table::open(Photon, &path, options).await.unwrap();
+        table.put(&[0], 0, &[0]).await.unwrap();
+
+        // Stall flushes by holding a writer of the active write buffer.
+        let guard = table.store.guard();
+        let mut txn = guard.begin().await.unwrap();
+        txn.alloc_page(64).await.unwrap();
+
+        let timeout = Duration::from_millis(10);
+        let value = [0; 1 << 10];
+        let mut timed_out = false;
+        for i in 1..1024u64 {
+            let key = i.to_be_bytes();
+            match table.put_with_timeout(&key, i, &value, timeout).await {
+                Ok(()) => {}
+                Err(Error::WriteStall) => {
+                    timed_out = true;
+                    break;
+                }
+                Err(err) => panic!("unexpected error {err:?}"),
+            }
+        }
+        assert!(timed_out);
+        // Reads don't wait for write buffers.
+        let value = table.get_with_timeout(&[0], 0, timeout).await.unwrap();
+        assert_eq!(value, Some(vec![0]));
+
+        // Writes continue once the flush is unblocked.
+        drop(txn);
+        drop(guard);
+        table.put(&[1], 1, &[1]).await.unwrap();
+        table.close().await.unwrap();
+    }
+
+    #[photonio::test]
+    async fn max_unflushed_files() {
+        let path = tempdir().unwrap();
+        let mut options = Options::default();
+        options.page_size = 1 << 20;
+        options.page_chain_length = 1 << 10;
+        options.page_store.write_buffer_capacity = 1 << 16;
+        options.page_store.max_write_buffers = 8;
+        options.page_store.max_unflushed_files = Some(1);
+        let table = Table::open(Photon, &path, options).await.unwrap();
+        table.put(&[0], 0, &[0]).await.unwrap();
+        assert!(!table.is_write_stalled());
+
+        // Pause flushes by holding a writer of the active write buffer.
+        let guard = table.store.guard();
+        let mut txn = guard.begin().await.unwrap();
+        txn.alloc_page(64).await.unwrap();
+
+        let timeout = Duration::from_millis(10);
+        let value = [0; 1 << 10];
+        let mut stalled_at = None;
+        for i in 1..1024u64 {
+            let key = i.to_be_bytes();
+            match table.put_with_timeout(&key, i, &value, timeout).await {
+                Ok(()) => {}
+                Err(Error::WriteStall) => {
+                    stalled_at = Some(i);
+                    break;
+                }
+                Err(err) => panic!("unexpected error {err:?}"),
+            }
+        }
+        // Writes stall once the second buffer is full, far before all the
+        // eight buffers are used.
+        let stalled_at = stalled_at.expect("writes should stall");
+        assert!(stalled_at < 3 * 64, "{stalled_at}");
+        assert!(table.is_write_stalled());
+
+        // Writes recover once the flush is resumed.
+        drop(txn);
+        drop(guard);
+        let key = stalled_at.to_be_bytes();
+        table
+            .put_with_timeout(&key, stalled_at, &value, Duration::from_secs(10))
+            .await
+            .unwrap();
+        table.flush(&FlushOptions::default()).await;
+        let deadline = Instant::now() + Duration::from_secs(10);
+        while table.is_write_stalled() {
+            assert!(Instant::now() < deadline, "writes are still stalled");
+            photonio::task::yield_now().await;
+        }
+        table.close().await.unwrap();
+    }
+
+    #[photonio::test]
+    async fn write_during_slow_flush() {
+        let path = tempdir().unwrap();
+        let mut options = Options::default();
+        // Avoid consolidations and splits, so that each put takes a bit more
+        // than the size of its value in the write buffer.
+        options.page_size = 1 << 20;
+        options.page_chain_length = 1 << 10;
+        options.page_store.write_buffer_capacity = 1 << 16;
+        options.page_store.max_write_buffers = 2;
+        let table = Table::open(Photon, &path, options).await.unwrap();
+        table.put(&[0], 0, &[0]).await.unwrap();
+
+        // Stall flushes by holding a writer of the active write buffer.
+        let guard = table.store.guard();
+        let mut txn = guard.begin().await.unwrap();
+        txn.alloc_page(64).await.unwrap();
+
+        // Writes fill the first buffer and continue into the second one, while
+        // the first one can't be flushed.
+        const N: u64 = 96;
+        let timeout = Duration::from_secs(1);
+        let value = [1; 1 << 10];
+        for i in 1..N {
+            let key = i.to_be_bytes();
+            table
+                .put_with_timeout(&key, i, &value, timeout)
+                .await
+                .unwrap();
+        }
+        assert_eq!(table.stats().store.writebuf.read_in_file, 0);
+
+        // Reads see entries in both the sealed and the active buffer.
+        assert_eq!(table.get(&[0], 0).await.unwrap(), Some(vec![0]));
+        for i in 1..N {
+            let key = i.to_be_bytes();
+            assert_eq!(table.get(&key, i).await.unwrap(), Some(value.to_vec()));
+        }
+
+        drop(txn);
+        drop(guard);
+        table.close().await.unwrap();
+    }
+
+    #[photonio::test]
+    async fn flush_with_disk_full() {
+        let path = tempdir().unwrap();
+        let mut options = Options::default();
+        options.page_size = 1 << 20;
+        options.page_chain_length = 1 << 10;
+        options.page_store.write_buffer_capacity = 1 << 16;
+        options.page_store.max_write_buffers = 2;
+        let env = Fault::new(Photon);
+        let table = Table::open(env.clone(), &path, options.clone())
+            .await
+            .unwrap();
+        let value = [1; 1 << 10];
+        for i in 0..32u64 {
+            table.put(&i.to_be_bytes(), i, &value).await.unwrap();
+        }
+        table.flush(&FlushOptions::default()).await;
+        let live_files = table.live_files();
+        assert!(!live_files.is_empty());
+
+        // Writes are rejected once a flush runs out of disk space.
+        env.set_space(1 << 10);
+        let timeout = Duration::from_millis(10);
+        let deadline = Instant::now() + Duration::from_secs(10);
+        let mut i = 32u64;
+        loop {
+            assert!(Instant::now() < deadline, "writes are still accepted");
+            let key = i.to_be_bytes();
+            match table.put_with_timeout(&key, i, &value, timeout).await {
+                Ok(()) => i += 1,
+                Err(Error::DiskFull) => break,
+                Err(Error::WriteStall) => photonio::task::yield_now().await,
+                Err(err) => panic!("unexpected error {err:?}"),
+            }
+        }
+        assert!(matches!(
+            table.put(&i.to_be_bytes(), i, &value).await,
+            Err(Error::DiskFull)
+        ));
+
+        // The partial file of the failed flush is removed.
+        let num_page_files = std::fs::read_dir(&path)
+            .unwrap()
+            .filter(|entry| {
+                let name = entry.as_ref().unwrap().file_name();
+                name.to_str().unwrap().starts_with("map_")
+            })
+            .count();
+        assert_eq!(num_page_files, live_files.len());
+        assert_eq!(table.live_files().len(), live_files.len());
+        // Entries written before are still readable.
+        for j in 0..i {
+            let key = j.to_be_bytes();
+            assert_eq!(table.get(&key, j).await.unwrap(), Some(value.to_vec()));
+        }
+
+        // Writes resume once space is freed.
+        env.set_space(u64::MAX);
+        let deadline = Instant::now() + Duration::from_secs(10);
+        loop {
+            let key = i.to_be_bytes();
+            match table.put_with_timeout(&key, i, &value, timeout).await {
+                Ok(()) => break,
+                Err(Error::DiskFull | Error::WriteStall) => {
+                    assert!(Instant::now() < deadline, "writes are still rejected");
+                    photonio::task::yield_now().await;
+                }
+                Err(err) => panic!("unexpected error {err:?}"),
+            }
+        }
+        table.close().await.unwrap();
+
+        let table = Table::open(Photon, &path, options).await.unwrap();
+        for j in 0..=i {
+            let key = j.to_be_bytes();
+            assert_eq!(table.get(&key, j).await.unwrap(), Some(value.to_vec()));
+        }
+        table.close().await.unwrap();
+    }
+
+    #[photonio::test]
+    async fn read_retries() {
+        let path = tempdir().unwrap();
+        let mut options = Options::default();
+        options.page_store.prepopulate_cache_on_flush = false;
+        options.page_store.disable_space_reclaiming = true;
+        options.page_store.read_retries = 1;
+        let env = Fault::new(Photon);
+        let table = Table::open(env.clone(), &path, options.clone())
+            .await
+            .unwrap();
+        table.put(b"k", 1, b"v").await.unwrap();
+        table.flush(&FlushOptions::default()).await;
+
+        // The first read of the page fails, and the retry succeeds.
+        env.fail_reads(1);
+        assert_eq!(table.get(b"k", 1).await.unwrap(), Some(b"v".to_vec()));
+        assert_eq!(table.stats().store.read_source.read_retries, 1);
+        table.close().await.unwrap();
+
+        // The error is returned without retries.
+        options.page_store.read_retries = 0;
+        let table = Table::open(env.clone(), &path, options).await.unwrap();
+        env.fail_reads(1);
+        assert!(matches!(table.get(b"k", 1).await, Err(Error::Io(_))));
+        assert_eq!(table.stats().store.read_source.read_retries, 0);
+        assert_eq!(table.get(b"k", 1).await.unwrap(), Some(b"v".to_vec()));
+        table.close().await.unwrap();
+    }
+}
@@ -11,15 +11,22 @@
 
 use std::{
     future::Future,
-    ops::Deref,
+    io::{Read, Write},
+    iter::FusedIterator,
+    ops::{Bound, Deref},
     path::Path,
     pin::Pin,
     task::{Context, Poll},
+    time::Duration,
 };
 
 use futures::task::noop_waker_ref;
+use uuid::Uuid;
 
-use crate::{env::Std, raw, PageIter, Result, TableOptions};
+use crate::{
+    env::Std, raw, EngineOptions, FileEntryIter, KeyBounds, KeyIter, PageIter, PickedFile, Result,
+    ResumeToken, Snapshot, TableOptions,
+};
 
 /// A reference to a latch-free, log-structured table that stores sorted
 /// key-value entries.
@@ -38,6 +45,49 @@ impl Table {
         Ok(Self(table))
     }
 
+    /// Opens a table in the path with the given options, and pre-splits it at
+    /// the split points if it is newly created.
+    ///
+    /// This is a synchronous version of [`raw::Table::open_with_splits`] with
+    /// the [`Std`] environment.
+    pub fn open_with_splits<P: AsRef<Path>>(
+        path: P,
+        options: TableOptions,
+        split_points: &[&[u8]],
+    ) -> Result<Self> {
+        let table = poll(raw::Table::open_with_splits(
+            Std,
+            path,
+            options,
+            split_points,
+        ))?;
+        Ok(Self(table))
+    }
+
+    /// Opens a table in the path read-only, as it was at the manifest version.
+    ///
+    /// This is a synchronous version of
+    /// [`raw::Table::open_at_manifest_version`] with the [`Std`]
+    /// environment.
+    pub fn open_at_manifest_version<P: AsRef<Path>>(
+        path: P,
+        options: TableOptions,
+        version: usize,
+    ) -> Result<Self> {
+        let table = poll(raw::Table::open_at_manifest_version(
+            Std, path, options, version,
+        ))?;
+        Ok(Self(table))
+    }
+
+    /// Rebuilds the mapping of the table in the path from its page files.
+    ///
+    /// This is a synchronous version of [`raw::Table::rebuild_mapping`] with
+    /// the [`Std`] environment.
+    pub fn rebuild_mapping<P: AsRef<Path>>(path: P, options: TableOptions) -> Result<Vec<u32>> {
+        poll(raw::Table::rebuild_mapping(Std, path, options))
+    }
+
     /// Closes the table if this is the only reference to it.
     ///
     /// This is a synchronous version of [`raw::Table::close`].
@@ -50,6 +100,27 @@ impl Table {
         Guard(self.0.pin())
     }
 
+    /// Returns a [`TableCursor`] over the entries visible to `lsn`.
+    ///
+    /// See [`raw::Table::cursor`].
+    pub fn cursor(&self, lsn: u64) -> Result<TableCursor<'_>> {
+        self.0.cursor(lsn).map(TableCursor)
+    }
+
+    /// Returns the UUID of the table.
+    ///
+    /// See [`raw::Table::id`].
+    pub fn id(&self) -> Uuid {
+        self.0.id()
+    }
+
+    /// Returns the current version of the manifest.
+    ///
+    /// This is a synchronous version of [`raw::Table::manifest_version`].
+    pub fn manifest_version(&self) -> usize {
+        poll(self.0.manifest_version())
+    }
+
     /// Gets the value corresponding to the key.
     ///
     /// This is a synchronous version of [`raw::Table::get`].
@@ -57,6 +128,163 @@ impl Table {
         poll(self.0.get(key, lsn))
     }
 
+    /// Gets the value corresponding to the integer key.
+    ///
+    /// This is a synchronous version of [`raw::Table::get_u64`].
+    pub fn get_u64(&self, key: u64, lsn: u64) -> Result<Option<Vec<u8>>> {
+        poll(self.0.get_u64(key, lsn))
+    }
+
+    /// Gets the latest value corresponding to the key.
+    ///
+    /// This is a synchronous version of [`raw::Table::get_latest`].
+    pub fn get_latest(&self, key: &[u8]) -> Result<Option<Vec<u8>>> {
+        poll(self.0.get_latest(key))
+    }
+
+    /// Returns a [`Snapshot`] of the entries written so far.
+    ///
+    /// This is the same as [`raw::Table::snapshot`].
+    pub fn snapshot(&self) -> Snapshot {
+        self.0.snapshot()
+    }
+
+    /// Gets the values corresponding to the keys with one snapshot.
+    ///
+    /// This is a synchronous version of [`raw::Table::multi_get`].
+    pub fn multi_get(&self, keys: &[&[u8]]) -> Result<Vec<Option<Vec<u8>>>> {
+        poll(self.0.multi_get(keys))
+    }
+
+    /// Gets the values corresponding to the keys with the snapshot.
+    ///
+    /// This is a synchronous version of
+    /// [`raw::Table::multi_get_with_snapshot`].
+    pub fn multi_get_with_snapshot(
+        &self,
+        keys: &[&[u8]],
+        snapshot: &Snapshot,
+    ) -> Result<Vec<Option<Vec<u8>>>> {
+        poll(self.0.multi_get_with_snapshot(keys, snapshot))
+    }
+
+    /// Gets the value corresponding to the key within the timeout.
+    ///
+    /// This is a synchronous version of [`raw::Table::get_with_timeout`].
+    pub fn get_with_timeout(
+        &self,
+        key: &[u8],
+        lsn: u64,
+        timeout: Duration,
+    ) -> Result<Option<Vec<u8>>> {
+        poll(self.0.get_with_timeout(key, lsn, timeout))
+    }
+
+    /// Gets all retained versions of the key that are visible to `max_lsn`.
+    ///
+    /// This is a synchronous version of [`raw::Table::get_versions`].
+    pub fn get_versions(&self, key: &[u8], max_lsn: u64) -> Result<Vec<(u64, Option<Vec<u8>>)>> {
+        poll(self.0.get_versions(key, max_lsn))
+    }
+
+    /// Returns the file that stores the version of the key visible to `lsn`.
+    ///
+    /// This is a synchronous version of [`raw::Table::locate_key`].
+    pub fn locate_key(&self, key: &[u8], lsn: u64) -> Result<Option<PickedFile>> {
+        poll(self.0.locate_key(key, lsn))
+    }
+
+    /// Returns an iterator over entries physically stored in the page file.
+    ///
+    /// This is a synchronous version of [`raw::Table::iter_file`].
+    pub fn iter_file(&self, file_id: u32) -> Result<FileEntryIter> {
+        poll(self.0.iter_file(file_id))
+    }
+
+    /// Returns entries between `start` and `end` that are visible to `lsn`.
+    ///
+    /// This is a synchronous version of [`raw::Table::scan`].
+    pub fn scan(
+        &self,
+        start: Bound<&[u8]>,
+        end: Bound<&[u8]>,
+        lsn: u64,
+    ) -> Result<Vec<(Vec<u8>, Vec<u8>)>> {
+        poll(self.0.scan(start, end, lsn))
+    }
+
+    /// Returns an iterator over entries between `start` and `end` that are
+    /// visible to `lsn`.
+    ///
+    /// Entries are read in batches with [`Table::scan_batch`] and
+    /// [`Table::scan_resume`] as the iterator advances, so the entries are not
+    /// all held in memory at once. An error is yielded as an [`Err`] item,
+    /// after which the iterator returns [`None`].
+    pub fn scan_iter(&self, start: Bound<&[u8]>, end: Bound<&[u8]>, lsn: u64) -> ScanIter<'_> {
+        ScanIter {
+            table: &self.0,
+            end: to_owned_bound(end),
+            lsn,
+            batch: Vec::new().into_iter(),
+            next: Some(ScanNext::Start(to_owned_bound(start))),
+        }
+    }
+
+    /// Returns the keys between `start` and `end` that are visible to `lsn`.
+    ///
+    /// This is a synchronous version of [`raw::Table::scan_keys`].
+    pub fn scan_keys(&self, start: Bound<&[u8]>, end: Bound<&[u8]>, lsn: u64) -> Result<KeyIter> {
+        poll(self.0.scan_keys(start, end, lsn))
+    }
+
+    /// Returns the entries whose keys start with `prefix` that are visible to
+    /// `lsn`, in descending order of keys.
+    ///
+    /// This is a synchronous version of [`raw::Table::scan_prefix_rev`].
+    pub fn scan_prefix_rev(&self, prefix: &[u8], lsn: u64) -> Result<Vec<(Vec<u8>, Vec<u8>)>> {
+        poll(self.0.scan_prefix_rev(prefix, lsn))
+    }
+
+    /// Returns up to `limit` entries between `start` and `end` that are
+    /// visible to `lsn`, and a token to continue the scan.
+    ///
+    /// This is a synchronous version of [`raw::Table::scan_batch`].
+    pub fn scan_batch(
+        &self,
+        start: Bound<&[u8]>,
+        end: Bound<&[u8]>,
+        lsn: u64,
+        limit: usize,
+    ) -> Result<(Vec<(Vec<u8>, Vec<u8>)>, Option<ResumeToken>)> {
+        poll(self.0.scan_batch(start, end, lsn, limit))
+    }
+
+    /// Continues a scan right after the last key returned with the token.
+    ///
+    /// This is a synchronous version of [`raw::Table::scan_resume`].
+    pub fn scan_resume(
+        &self,
+        token: &ResumeToken,
+        end: Bound<&[u8]>,
+        limit: usize,
+    ) -> Result<(Vec<(Vec<u8>, Vec<u8>)>, Option<ResumeToken>)> {
+        poll(self.0.scan_resume(token, end, limit))
+    }
+
+    /// Reads the pages that contain the keys into the page cache.
+    ///
+    /// This is a synchronous version of [`raw::Table::prefetch`].
+    pub fn prefetch(&self, keys: &[&[u8]]) {
+        poll(self.0.prefetch(keys))
+    }
+
+    /// Reads the pages that cover the ranges into the page cache.
+    ///
+    /// This is a synchronous version of [`raw::Table::warmup`].
+    pub fn warmup(&self, ranges: &[KeyBounds<'_>]) -> Result<()> {
+        poll(self.0.warmup(ranges))
+    }
+
     /// Puts a key-value entry to the table.
     ///
     /// This is a synchronous version of [`raw::Table::put`].
@@ -64,12 +292,132 @@ impl Table {
         poll(self.0.put(key, lsn, value))
     }
 
+    /// Puts a key-value entry with an integer key to the table.
+    ///
+    /// This is a synchronous version of [`raw::Table::put_u64`].
+    pub fn put_u64(&self, key: u64, lsn: u64, value: &[u8]) -> Result<()> {
+        poll(self.0.put_u64(key, lsn, value))
+    }
+
+    /// Puts a key-value entry to the table within the timeout.
+    ///
+    /// This is a synchronous version of [`raw::Table::put_with_timeout`].
+    pub fn put_with_timeout(
+        &self,
+        key: &[u8],
+        lsn: u64,
+        value: &[u8],
+        timeout: Duration,
+    ) -> Result<()> {
+        poll(self.0.put_with_timeout(key, lsn, value, timeout))
+    }
+
+    /// Puts a key-value entry to the table and returns the previous value.
+    ///
+    /// This is a synchronous version of [`raw::Table::put_and_get_previous`].
+    pub fn put_and_get_previous(
+        &self,
+        key: &[u8],
+        lsn: u64,
+        value: &[u8],
+    ) -> Result<Option<Vec<u8>>> {
+        poll(self.0.put_and_get_previous(key, lsn, value))
+    }
+
+    /// Puts a key-value entry to the table if no version of the key has a LSN
+    /// larger than `expected_max_lsn`.
+    ///
+    /// This is a synchronous version of [`raw::Table::put_if_lsn`].
+    pub fn put_if_lsn(
+        &self,
+        key: &[u8],
+        expected_max_lsn: u64,
+        new_lsn: u64,
+        value: &[u8],
+    ) -> Result<bool> {
+        poll(self.0.put_if_lsn(key, expected_max_lsn, new_lsn, value))
+    }
+
+    /// Puts sorted key-value entries to the table.
+    ///
+    /// This is a synchronous version of [`raw::Table::ingest_sorted`].
+    pub fn ingest_sorted(&self, entries: &[(&[u8], u64, &[u8])]) -> Result<()> {
+        poll(self.0.ingest_sorted(entries))
+    }
+
+    /// Writes the entries visible to `lsn` to the writer.
+    ///
+    /// This is a synchronous version of [`raw::Table::export`].
+    pub fn export<W: Write>(&self, w: W, lsn: u64) -> Result<usize> {
+        poll(self.0.export(w, lsn))
+    }
+
+    /// Reads entries written by [`Table::export`] and puts them at `lsn`.
+    ///
+    /// This is a synchronous version of [`raw::Table::import`].
+    pub fn import<R: Read>(&self, r: R, lsn: u64) -> Result<usize> {
+        poll(self.0.import(r, lsn))
+    }
+
+    /// Merges the operand into the value of the key.
+    ///
+    /// This is a synchronous version of [`raw::Table::merge`].
+    pub fn merge(&self, key: &[u8], lsn: u64, operand: &[u8]) -> Result<()> {
+        poll(self.0.merge(key, lsn, operand))
+    }
+
     /// Deletes the entry corresponding to the key from the table.
     ///
     /// This is a synchronous version of [`raw::Table::delete`].
     pub fn delete(&self, key: &[u8], lsn: u64) -> Result<()> {
         poll(self.0.delete(key, lsn))
     }
+
+    /// Moves the value of `from` to `to` at `lsn`, and returns whether `from`
+    /// has a value visible to `lsn`.
+    ///
+    /// This is a synchronous version of [`raw::Table::rename`].
+    pub fn rename(&self, from: &[u8], to: &[u8], lsn: u64) -> Result<bool> {
+        poll(self.0.rename(from, to, lsn))
+    }
+
+    /// Deletes the entries with keys from `start` (inclusive) to `end`
+    /// (exclusive) from the table.
+    ///
+    /// This is a synchronous version of [`raw::Table::delete_range`].
+    pub fn delete_range(&self, start: &[u8], end: &[u8], lsn: u64) -> Result<()> {
+        poll(self.0.delete_range(start, end, lsn))
+    }
+
+    /// Consolidates all leaf pages completely.
+    ///
+    /// This is a synchronous version of [`raw::Table::compact`].
+    pub fn compact(&self) -> Result<()> {
+        poll(self.0.compact())
+    }
+
+    /// Consolidates all leaf pages completely to collect tombstones.
+    ///
+    /// This is a synchronous version of [`raw::Table::collect_tombstones`].
+    pub fn collect_tombstones(&self) -> Result<()> {
+        poll(self.0.collect_tombstones())
+    }
+
+    /// Rewrites the live pages of the file into a new file, and removes the
+    /// file once it is no longer in use.
+    ///
+    /// This is a synchronous version of [`raw::Table::compact_file`].
+    pub fn compact_file(&self, file: PickedFile) -> Result<()> {
+        poll(self.0.compact_file(file))
+    }
+
+    /// Flushes all write buffer data, and then checkpoints the table to the
+    /// directory.
+    ///
+    /// This is a synchronous version of [`raw::Table::flush_and_checkpoint`].
+    pub fn flush_and_checkpoint<P: AsRef<Path>>(&self, dir: P) -> Result<()> {
+        poll(self.0.flush_and_checkpoint(dir))
+    }
 }
 
 impl Deref for Table {
@@ -95,6 +443,13 @@ impl<'a> Guard<'a> {
     pub fn pages(&self) -> Pages<'_, 'a> {
         Pages(self.0.pages())
     }
+
+    /// Returns an iterator over pages in the table at the given LSN.
+    ///
+    /// See [`raw::Guard::pages_at`].
+    pub fn pages_at(&self, lsn: u64) -> Result<Pages<'_, 'a>> {
+        self.0.pages_at(lsn).map(Pages)
+    }
 }
 
 impl<'a> Deref for Guard<'a> {
@@ -109,6 +464,13 @@ impl<'a> Deref for Guard<'a> {
 pub struct Pages<'a, 't>(raw::Pages<'a, 't, Std>);
 
 impl<'a, 't> Pages<'a, 't> {
+    /// Prefetches up to `n` pages ahead while the current page is read.
+    ///
+    /// This is the same as [`raw::Pages::with_readahead`].
+    pub fn with_readahead(self, n: usize) -> Self {
+        Pages(self.0.with_readahead(n))
+    }
+
     /// Returns the next page in the table.
     ///
     /// This is a synchronous version of [`raw::Pages::next`].
@@ -117,6 +479,13 @@ impl<'a, 't> Pages<'a, 't> {
         // TODO: should we implement the [`std::iter::Iterator`] trait?
         poll(self.0.next())
     }
+
+    /// Positions the iterator at the page that contains `target`.
+    ///
+    /// This is a synchronous version of [`raw::Pages::seek`].
+    pub fn seek(&mut self, target: &[u8]) -> Result<PageIter<'_>> {
+        poll(self.0.seek(target))
+    }
 }
 
 impl<'a, 't> Deref for Pages<'a, 't> {
@@ -127,6 +496,139 @@ impl<'a, 't> Deref for Pages<'a, 't> {
     }
 }
 
+/// A cursor over the entries of a table that can step forward and backward.
+pub struct TableCursor<'a>(raw::TableCursor<'a, Std>);
+
+impl<'a> TableCursor<'a> {
+    /// Positions the cursor at the first entry that is at or after `target`.
+    ///
+    /// This is a synchronous version of [`raw::TableCursor::seek`].
+    pub fn seek(&mut self, target: &[u8]) -> Result<()> {
+        poll(self.0.seek(target))
+    }
+
+    /// Moves the cursor to the next entry.
+    ///
+    /// This is a synchronous version of [`raw::TableCursor::next`].
+    #[allow(clippy::should_implement_trait)]
+    pub fn next(&mut self) -> Result<()> {
+        poll(self.0.next())
+    }
+
+    /// Moves the cursor to the previous entry.
+    ///
+    /// This is a synchronous version of [`raw::TableCursor::prev`].
+    pub fn prev(&mut self) -> Result<()> {
+        poll(self.0.prev())
+    }
+}
+
+impl<'a> Deref for TableCursor<'a> {
+    type Target = raw::TableCursor<'a, Std>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+/// The number of entries read by [`ScanIter`] at a time.
+const SCAN_BATCH_SIZE: usize = 256;
+
+/// An iterator over entries returned by [`Table::scan_iter`].
+pub struct ScanIter<'a> {
+    table: &'a raw::Table<Std>,
+    end: Bound<Vec<u8>>,
+    lsn: u64,
+    batch: std::vec::IntoIter<(Vec<u8>, Vec<u8>)>,
+    /// Where to read the next batch from, or `None` if the scan is done.
+    next: Option<ScanNext>,
+}
+
+enum ScanNext {
+    Start(Bound<Vec<u8>>),
+    Resume(ResumeToken),
+}
+
+impl<'a> Iterator for ScanIter<'a> {
+    type Item = Result<(Vec<u8>, Vec<u8>)>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(entry) = self.batch.next() {
+                return Some(Ok(entry));
+            }
+            let end = as_slice_bound(&self.end);
+            let result = match self.next.take()? {
+                ScanNext::Start(start) => poll(self.table.scan_batch(
+                    as_slice_bound(&start),
+                    end,
+                    self.lsn,
+                    SCAN_BATCH_SIZE,
+                )),
+                ScanNext::Resume(token) => {
+                    poll(self.table.scan_resume(&token, end, SCAN_BATCH_SIZE))
+                }
+            };
+            match result {
+                Ok((entries, token)) => {
+                    self.batch = entries.into_iter();
+                    self.next = token.map(ScanNext::Resume);
+                }
+                Err(err) => return Some(Err(err)),
+            }
+        }
+    }
+}
+
+impl<'a> FusedIterator for ScanIter<'a> {}
+
+fn to_owned_bound(bound: Bound<&[u8]>) -> Bound<Vec<u8>> {
+    match bound {
+        Bound::Included(key) => Bound::Included(key.to_vec()),
+        Bound::Excluded(key) => Bound::Excluded(key.to_vec()),
+        Bound::Unbounded => Bound::Unbounded,
+    }
+}
+
+fn as_slice_bound(bound: &Bound<Vec<u8>>) -> Bound<&[u8]> {
+    match bound {
+        Bound::Included(key) => Bound::Included(key),
+        Bound::Excluded(key) => Bound::Excluded(key),
+        Bound::Unbounded => Bound::Unbounded,
+    }
+}
+
+/// An engine that opens multiple tables sharing the same caches.
+///
+/// This is the same as [`raw::Engine`] with the [`Std`] environment.
+#[derive(Clone, Debug)]
+pub struct Engine(raw::Engine<Std>);
+
+impl Engine {
+    /// Creates an engine with the given options.
+    ///
+    /// This is the same as [`raw::Engine::new`] with the [`Std`] environment.
+    pub fn new(options: EngineOptions) -> Self {
+        Self(raw::Engine::new(Std, options))
+    }
+
+    /// Opens a table in the path with the given options.
+    ///
+    /// This is a synchronous version of [`raw::Engine::open_table`].
+    pub fn open_table<P: AsRef<Path>>(&self, path: P, options: TableOptions) -> Result<Table> {
+        let table = poll(self.0.open_table(path, options))?;
+        Ok(Table(table))
+    }
+}
+
+impl Deref for Engine {
+    type Target = raw::Engine<Std>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
 fn poll<F: Future>(mut future: F) -> F::Output {
     let cx = &mut Context::from_waker(noop_waker_ref());
     loop {
@@ -138,3 +640,79 @@ fn poll<F: Future>(mut future: F) -> F::Output {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use tempfile::tempdir;
+
+    use super::*;
+    use crate::{ChecksumType, Error, FlushOptions};
+
+    const N: u64 = 1 << 10;
+
+    fn options() -> TableOptions {
+        let mut options = TableOptions::default();
+        options.page_store.page_checksum_type = ChecksumType::CRC32;
+        options
+    }
+
+    fn put_entries(table: &Table) {
+        for i in 0..N {
+            let key = i.to_be_bytes();
+            table.put(&key, 1, &key).unwrap();
+        }
+    }
+
+    #[test]
+    fn scan_iter() {
+        let path = tempdir().unwrap();
+        let table = Table::open(&path, options()).unwrap();
+        put_entries(&table);
+
+        let entries = table
+            .scan_iter(Bound::Unbounded, Bound::Unbounded, 1)
+            .collect::<Result<Vec<_>>>()
+            .unwrap();
+        let expect: Vec<_> = (0..N)
+            .map(|i| (i.to_be_bytes().to_vec(), i.to_be_bytes().to_vec()))
+            .collect();
+        assert_eq!(entries, expect);
+
+        let start = 10u64.to_be_bytes();
+        let end = 20u64.to_be_bytes();
+        let evens: Vec<_> = table
+            .scan_iter(Bound::Included(&start), Bound::Excluded(&end), 1)
+            .map(|entry| entry.unwrap().0)
+            .filter(|key| key[7] % 2 == 0)
+            .collect();
+        assert_eq!(evens.len(), 5);
+        table.close().unwrap();
+    }
+
+    #[test]
+    fn scan_iter_with_corrupted_page() {
+        let path = tempdir().unwrap();
+        let table = Table::open(&path, options()).unwrap();
+        put_entries(&table);
+        poll(table.flush(&FlushOptions::default()));
+        let files = table.live_files();
+        assert!(!files.is_empty());
+        table.close().unwrap();
+
+        // Pages are stored from the start of page files, and all of them are
+        // read by a full scan.
+        for file in &files {
+            let mut buf = std::fs::read(&file.path).unwrap();
+            buf[..64].iter_mut().for_each(|b| *b = !*b);
+            std::fs::write(&file.path, buf).unwrap();
+        }
+
+        let table = Table::open(&path, options()).unwrap();
+        let mut iter = table.scan_iter(Bound::Unbounded, Bound::Unbounded, 1);
+        let err = iter.by_ref().find(Result::is_err);
+        assert!(matches!(err, Some(Err(Error::Corrupted))));
+        assert!(iter.next().is_none());
+        drop(iter);
+        table.close().unwrap();
+    }
+}
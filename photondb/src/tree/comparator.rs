@@ -0,0 +1,56 @@
+use std::cmp::Ordering;
+
+/// A comparator for 64-bit unsigned integer keys.
+///
+/// Tables order keys bytewise, so integer keys are encoded in big-endian to
+/// make the numeric order match the key order.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct IntKeyComparator;
+
+impl IntKeyComparator {
+    /// Encodes an integer to a key.
+    pub fn encode(k: u64) -> [u8; 8] {
+        k.to_be_bytes()
+    }
+
+    /// Decodes a key returned by [`IntKeyComparator::encode`].
+    ///
+    /// Returns `None` if the key is not 8 bytes long.
+    pub fn decode(key: &[u8]) -> Option<u64> {
+        key.try_into().ok().map(u64::from_be_bytes)
+    }
+
+    /// Compares two encoded keys.
+    ///
+    /// This is the same as comparing the keys bytewise.
+    pub fn compare(a: &[u8], b: &[u8]) -> Ordering {
+        a.cmp(b)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn int_key_order() {
+        let keys = [
+            0,
+            1,
+            0xFF,
+            0x100,
+            0x7FFF_FFFF_FFFF_FFFF,
+            0x8000_0000_0000_0000,
+            u64::MAX,
+        ];
+        for (i, a) in keys.iter().enumerate() {
+            let ka = IntKeyComparator::encode(*a);
+            assert_eq!(IntKeyComparator::decode(&ka), Some(*a));
+            for b in &keys[i..] {
+                let kb = IntKeyComparator::encode(*b);
+                assert_eq!(IntKeyComparator::compare(&ka, &kb), a.cmp(b));
+            }
+        }
+        assert_eq!(IntKeyComparator::decode(b"short"), None);
+    }
+}
@@ -0,0 +1,40 @@
+use std::{fmt, sync::Arc};
+
+/// The decision of a [`CompactionFilter`] on an entry.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum FilterDecision {
+    /// Keeps the entry.
+    Keep,
+    /// Removes the entry.
+    Remove,
+    /// Replaces the value of the entry.
+    ChangeValue(Vec<u8>),
+}
+
+/// A callback to drop or transform entries when pages are consolidated.
+///
+/// The callback is invoked with the key and value of each put entry that is
+/// rewritten. It may be invoked more than once for the same entry, and it must
+/// not block.
+#[derive(Clone)]
+pub struct CompactionFilter(Arc<dyn Fn(&[u8], &[u8]) -> FilterDecision + Send + Sync>);
+
+impl CompactionFilter {
+    /// Creates a compaction filter with the callback.
+    pub fn new<F>(f: F) -> Self
+    where
+        F: Fn(&[u8], &[u8]) -> FilterDecision + Send + Sync + 'static,
+    {
+        Self(Arc::new(f))
+    }
+
+    pub(super) fn filter(&self, key: &[u8], value: &[u8]) -> FilterDecision {
+        (self.0)(key, value)
+    }
+}
+
+impl fmt::Debug for CompactionFilter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("CompactionFilter").finish_non_exhaustive()
+    }
+}
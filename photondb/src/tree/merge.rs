@@ -0,0 +1,65 @@
+use std::fmt;
+
+/// An operator that combines a merge operand with the existing value of a
+/// key.
+///
+/// Operands are applied when they are written, so the result of each merge
+/// is stored as a regular value.
+pub trait MergeOperator: Send + Sync + fmt::Debug {
+    /// Returns the new value of the key after applying the operand to the
+    /// existing value, if any.
+    fn merge(&self, key: &[u8], existing: Option<&[u8]>, operand: &[u8]) -> Vec<u8>;
+}
+
+/// A [`MergeOperator`] that appends operands to the existing value.
+///
+/// This is useful for log-style keys that only grow at the end.
+#[derive(Clone, Debug, Default)]
+pub struct AppendMergeOperator {
+    separator: Option<u8>,
+}
+
+impl AppendMergeOperator {
+    /// Creates an operator that concatenates operands without separators.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Creates an operator that puts the separator between operands.
+    pub fn with_separator(separator: u8) -> Self {
+        Self {
+            separator: Some(separator),
+        }
+    }
+}
+
+impl MergeOperator for AppendMergeOperator {
+    fn merge(&self, _: &[u8], existing: Option<&[u8]>, operand: &[u8]) -> Vec<u8> {
+        let Some(existing) = existing else {
+            return operand.to_vec();
+        };
+        let mut value = Vec::with_capacity(existing.len() + 1 + operand.len());
+        value.extend_from_slice(existing);
+        if let Some(sep) = self.separator {
+            value.push(sep);
+        }
+        value.extend_from_slice(operand);
+        value
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn append_merge_operator() {
+        let op = AppendMergeOperator::new();
+        assert_eq!(op.merge(b"k", None, b"a"), b"a");
+        assert_eq!(op.merge(b"k", Some(b"a"), b"b"), b"ab");
+
+        let op = AppendMergeOperator::with_separator(b',');
+        assert_eq!(op.merge(b"k", None, b"a"), b"a");
+        assert_eq!(op.merge(b"k", Some(b"a,b"), b"c"), b"a,b,c");
+    }
+}
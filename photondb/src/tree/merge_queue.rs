@@ -0,0 +1,90 @@
+use std::sync::{
+    atomic::{AtomicBool, Ordering},
+    Mutex,
+};
+
+use rustc_hash::FxHashMap;
+
+use crate::util::notify::Notify;
+
+/// Under-full leaf pages waiting to be merged with their right siblings by
+/// the merge job.
+#[derive(Default)]
+pub(super) struct MergeQueue {
+    enabled: AtomicBool,
+    state: Mutex<State>,
+    // Wakes up the merge job when pages are queued.
+    queued: Notify,
+    // Wakes up the waiters when the merge job is idle.
+    idle: Notify,
+}
+
+#[derive(Default)]
+struct State {
+    // The queued pages with the start keys of their ranges.
+    pages: FxHashMap<u64, Vec<u8>>,
+    running: bool,
+}
+
+impl MergeQueue {
+    /// Accepts pages from now on, since a merge job has been started.
+    pub(super) fn enable(&self) {
+        self.enabled.store(true, Ordering::Release);
+    }
+
+    /// Queues the page if it is not queued yet.
+    ///
+    /// Does nothing if there is no merge job.
+    pub(super) fn push(&self, id: u64, start: &[u8]) {
+        if !self.enabled.load(Ordering::Acquire) {
+            return;
+        }
+        let mut state = self.state.lock().expect("Poisoned");
+        state.pages.entry(id).or_insert_with(|| start.to_owned());
+        self.queued.notify_one();
+    }
+
+    /// Waits until some pages are queued, and takes all of them.
+    ///
+    /// The job is running until [`MergeQueue::finish`] is called.
+    pub(super) async fn take(&self) -> Vec<(u64, Vec<u8>)> {
+        loop {
+            {
+                let mut state = self.state.lock().expect("Poisoned");
+                if !state.pages.is_empty() {
+                    state.running = true;
+                    return state.pages.drain().collect();
+                }
+            }
+            self.queued.notified().await;
+        }
+    }
+
+    /// Marks the job as not running, and wakes up the waiters if no more
+    /// pages are queued.
+    pub(super) fn finish(&self) {
+        let mut state = self.state.lock().expect("Poisoned");
+        state.running = false;
+        if state.pages.is_empty() {
+            self.idle.notify_waiters();
+        }
+    }
+
+    /// Waits until no page is queued or being merged.
+    pub(super) async fn wait_idle(&self) {
+        let notified = self.idle.notified();
+        futures::pin_mut!(notified);
+        loop {
+            // Make sure that no wake-up is lost.
+            notified.as_mut().enable();
+            {
+                let state = self.state.lock().expect("Poisoned");
+                if state.pages.is_empty() && !state.running {
+                    return;
+                }
+            }
+            notified.as_mut().await;
+            notified.set(self.idle.notified());
+        }
+    }
+}
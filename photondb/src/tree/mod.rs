@@ -1,11 +1,26 @@
 use std::{
+    collections::{BTreeMap, VecDeque},
     fmt,
-    sync::atomic::{AtomicU64, Ordering},
+    future::Future,
+    ops::Bound,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, Mutex,
+    },
 };
 
-use log::trace;
+use log::{trace, warn};
+use rustc_hash::FxHashSet;
 
-use crate::{env::Env, page::*, page_store::*};
+use crate::{
+    env::Env,
+    page::*,
+    page_store::*,
+    util::{
+        shutdown::{with_shutdown, Shutdown},
+        yield_now,
+    },
+};
 
 mod page;
 pub use page::PageIter;
@@ -13,15 +28,37 @@ use page::*;
 
 mod stats;
 use stats::AtomicStats;
-pub use stats::TreeStats;
+pub use stats::{CasStats, PrefetchStats, ReadaheadStats, TombstoneStats, TreeStats};
 
 mod options;
-pub use options::{Options, ReadOptions, WriteOptions};
+pub use options::{
+    CasBackoff, ConsolidationMode, FutureLsnPolicy, Options, OptionsBuilder, ReadOptions,
+    WriteOptions,
+};
+
+mod merge;
+pub use merge::{AppendMergeOperator, MergeOperator};
+
+mod filter;
+pub use filter::{CompactionFilter, FilterDecision};
+
+mod comparator;
+pub use comparator::IntKeyComparator;
+
+mod merge_queue;
+use merge_queue::MergeQueue;
 
 pub(crate) struct Tree {
     options: Options,
     stats: AtomicStats,
     safe_lsn: AtomicU64,
+    latest_lsn: AtomicU64,
+    // The LSNs of writes that are not published yet, with the number of such
+    // writes at each LSN.
+    unpublished_lsns: Mutex<BTreeMap<u64, usize>>,
+    // The LSNs of live snapshots, with the number of snapshots at each LSN.
+    live_snapshots: Mutex<BTreeMap<u64, usize>>,
+    merge_queue: MergeQueue,
 }
 
 impl Tree {
@@ -30,6 +67,10 @@ impl Tree {
             options,
             stats: AtomicStats::default(),
             safe_lsn: AtomicU64::new(0),
+            latest_lsn: AtomicU64::new(0),
+            unpublished_lsns: Mutex::default(),
+            live_snapshots: Mutex::default(),
+            merge_queue: MergeQueue::default(),
         }
     }
 
@@ -41,10 +82,133 @@ impl Tree {
         self.stats.snapshot()
     }
 
+    pub(crate) fn merge_operator(&self) -> Option<&dyn MergeOperator> {
+        self.options.merge_operator.as_deref()
+    }
+
+    pub(crate) fn page_size(&self) -> usize {
+        self.options.page_size
+    }
+
+    pub(crate) fn future_lsn_policy(&self) -> FutureLsnPolicy {
+        self.options.future_lsn_policy
+    }
+
     pub(crate) fn safe_lsn(&self) -> u64 {
         self.safe_lsn.load(Ordering::Acquire)
     }
 
+    /// Returns the latest LSN of the tree.
+    ///
+    /// The latest LSN stays below the LSNs of writes that are not published
+    /// yet, even if other writes with larger LSNs have finished.
+    pub(crate) fn latest_lsn(&self) -> u64 {
+        let unpublished = self.unpublished_lsns.lock().expect("Poisoned");
+        self.published_lsn(&unpublished)
+    }
+
+    fn published_lsn(&self, unpublished: &BTreeMap<u64, usize>) -> u64 {
+        let latest = self.latest_lsn.load(Ordering::Acquire);
+        match unpublished.keys().next() {
+            Some(&lsn) => latest.min(lsn - 1),
+            None => latest,
+        }
+    }
+
+    /// Advances the latest LSN to `lsn` if it is larger.
+    pub(crate) fn advance_latest_lsn(&self, lsn: u64) {
+        self.latest_lsn.fetch_max(lsn, Ordering::AcqRel);
+    }
+
+    /// Keeps [`Tree::latest_lsn`] below `lsn` until the returned guard is
+    /// dropped, so that multiple writes at `lsn` are published at once.
+    ///
+    /// This has no effect if `lsn` is not larger than the latest LSN, since
+    /// the writes are visible to reads at the latest LSN anyway.
+    fn hold_latest_lsn(&self, lsn: u64) -> UnpublishedLsn<'_> {
+        let mut unpublished = self.unpublished_lsns.lock().expect("Poisoned");
+        if lsn <= self.published_lsn(&unpublished) {
+            return UnpublishedLsn {
+                tree: self,
+                lsn: None,
+            };
+        }
+        *unpublished.entry(lsn).or_default() += 1;
+        UnpublishedLsn {
+            tree: self,
+            lsn: Some(lsn),
+        }
+    }
+
+    /// Returns a job that merges the under-full leaf pages left by
+    /// consolidations, until the shutdown.
+    ///
+    /// Leaf pages are only queued to be merged once this is called.
+    pub(crate) fn merge_job<E: Env>(
+        self: &Arc<Self>,
+        guards: GuardFactory<E>,
+        mut shutdown: Shutdown,
+    ) -> impl Future<Output = ()> + Send + 'static {
+        self.merge_queue.enable();
+        let tree = self.clone();
+        async move {
+            let queue = &tree.merge_queue;
+            loop {
+                let Some(pages) = with_shutdown(&mut shutdown, queue.take()).await else {
+                    break;
+                };
+                for (id, start) in pages {
+                    if shutdown.is_terminated() {
+                        break;
+                    }
+                    let txn = tree.begin(guards.guard());
+                    match txn.merge_leaf_page(id, &start).await {
+                        Ok(()) | Err(Error::Again) => {}
+                        Err(err) => warn!("Merge page {id} failed: {err:?}"),
+                    }
+                }
+                queue.finish();
+            }
+        }
+    }
+
+    /// Waits until no leaf page is queued or being merged.
+    pub(crate) async fn wait_for_merging(&self) {
+        self.merge_queue.wait_idle().await;
+    }
+
+    /// Returns a snapshot at the latest LSN, which is live until it is
+    /// dropped.
+    pub(crate) fn snapshot(self: &Arc<Self>) -> LiveSnapshot {
+        let mut live = self.live_snapshots.lock().expect("Poisoned");
+        // The latest LSN is read with the lock held, so that tombstone
+        // collection can't start from a larger LSN before the snapshot is live.
+        let lsn = self.latest_lsn();
+        *live.entry(lsn).or_default() += 1;
+        LiveSnapshot {
+            tree: self.clone(),
+            lsn,
+        }
+    }
+
+    /// Returns a snapshot at `lsn`, which is live until it is dropped.
+    pub(crate) fn snapshot_at(self: &Arc<Self>, lsn: u64) -> LiveSnapshot {
+        let mut live = self.live_snapshots.lock().expect("Poisoned");
+        *live.entry(lsn).or_default() += 1;
+        LiveSnapshot {
+            tree: self.clone(),
+            lsn,
+        }
+    }
+
+    /// Returns the LSN of the oldest live snapshot, or the latest LSN if it is
+    /// smaller or there is no live snapshot.
+    fn oldest_snapshot_lsn(&self) -> u64 {
+        let live = self.live_snapshots.lock().expect("Poisoned");
+        let latest = self.latest_lsn();
+        live.keys().next().map_or(latest, |&lsn| lsn.min(latest))
+    }
+
     pub(crate) fn set_safe_lsn(&self, lsn: u64) {
         loop {
             let safe_lsn = self.safe_lsn.load(Ordering::Acquire);
@@ -63,6 +227,50 @@ impl Tree {
     }
 }
 
+/// Keeps [`Tree::latest_lsn`] below an LSN until it is dropped.
+struct UnpublishedLsn<'a> {
+    tree: &'a Tree,
+    lsn: Option<u64>,
+}
+
+impl Drop for UnpublishedLsn<'_> {
+    fn drop(&mut self) {
+        let Some(lsn) = self.lsn else {
+            return;
+        };
+        let mut unpublished = self.tree.unpublished_lsns.lock().expect("Poisoned");
+        let count = unpublished.get_mut(&lsn).expect("the LSN must be held");
+        *count -= 1;
+        if *count == 0 {
+            unpublished.remove(&lsn);
+        }
+    }
+}
+
+/// Keeps the entries visible to an LSN from tombstone collection until it is
+/// dropped.
+pub(crate) struct LiveSnapshot {
+    tree: Arc<Tree>,
+    lsn: u64,
+}
+
+impl LiveSnapshot {
+    pub(crate) fn lsn(&self) -> u64 {
+        self.lsn
+    }
+}
+
+impl Drop for LiveSnapshot {
+    fn drop(&mut self) {
+        let mut live = self.tree.live_snapshots.lock().expect("Poisoned");
+        let count = live.get_mut(&self.lsn).expect("the snapshot must be live");
+        *count -= 1;
+        if *count == 0 {
+            live.remove(&self.lsn);
+        }
+    }
+}
+
 impl fmt::Debug for Tree {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         f.debug_struct("Tree")
@@ -83,7 +291,10 @@ impl<'a, E: Env> TreeTxn<'a, E> {
     }
 
     /// Initializes the tree if it is not initialized yet.
-    pub(crate) async fn init(&self) -> Result<()> {
+    ///
+    /// If there are split points, the tree starts with a leaf page for each
+    /// range between them. The split points must be sorted and distinct.
+    pub(crate) async fn init(&self, split_points: &[&[u8]]) -> Result<()> {
         let addr = self.guard.page_addr(ROOT_ID);
         if addr != 0 {
             return Ok(());
@@ -92,20 +303,50 @@ impl<'a, E: Env> TreeTxn<'a, E> {
         // Insert an empty data page as the root.
         let iter: ItemIter<(Key, Value)> = None.into();
         let builder = SortedPageBuilder::new(PageTier::Leaf, PageKind::Data).with_iter(iter);
-        let mut txn = self.guard.begin().await;
+        let mut txn = self.guard.begin().await?;
         let (new_addr, mut new_page) = txn.alloc_page(builder.size()).await?;
         builder.build(&mut new_page);
         let root_id = txn.insert_page(new_addr);
         assert_eq!(root_id, ROOT_ID);
         txn.commit();
 
+        if split_points.is_empty() {
+            return Ok(());
+        }
+
+        // Replace the root with an index of empty leaf pages, the same way as
+        // splitting the root.
+        let mut txn = self.guard.begin().await?;
+        let mut index = Vec::with_capacity(split_points.len() + 1);
+        for &start in [[].as_slice()].iter().chain(split_points) {
+            let iter: ItemIter<(Key, Value)> = None.into();
+            let builder = SortedPageBuilder::new(PageTier::Leaf, PageKind::Data).with_iter(iter);
+            let (new_addr, mut new_page) = txn.alloc_page(builder.size()).await?;
+            builder.build(&mut new_page);
+            let id = txn.insert_page(new_addr);
+            index.push((start, Index::new(id, 0)));
+        }
+        let builder = SortedPageBuilder::new(PageTier::Inner, PageKind::Data)
+            .with_layout(self.tree.options.page_layout)
+            .with_slice(&index);
+        let (new_addr, mut new_page) = txn.alloc_page(builder.size()).await?;
+        builder.build(&mut new_page);
+        let addr = self.guard.page_addr(ROOT_ID);
+        txn.replace_page(ROOT_ID, addr, new_addr, &[addr])
+            .await
+            .map_err(|_| Error::Again)?;
+        trace!("init root with {} leaf pages", index.len());
+
         Ok(())
     }
 
     /// Gets the value corresponding to the key.
     pub(crate) async fn get(&self, key: Key<'_>) -> Result<Option<&[u8]>> {
         let (view, _) = self.find_leaf(key.raw).await?;
-        let value = self.find_value(&key, &view).await?;
+        let value = match self.find_value(&key, &view).await? {
+            Some(value) => self.read_value(value).await?,
+            None => None,
+        };
 
         let key_size = key.len() as u64;
         let value_size = value.map(|v| v.len()).unwrap_or_default() as u64;
@@ -118,18 +359,411 @@ impl<'a, E: Env> TreeTxn<'a, E> {
         Ok(value)
     }
 
+    /// Returns the file that stores the version of the key visible to the
+    /// key's LSN.
+    ///
+    /// Returns `None` if there is no such version, it is deleted, or it is in
+    /// a write buffer.
+    pub(crate) async fn locate(&self, key: Key<'_>) -> Result<Option<PickedFile>> {
+        let (view, _) = self.find_leaf(key.raw).await?;
+        let mut found = None;
+        let mut range_deletes: Vec<RangeDelete> = Vec::new();
+        self.walk_page(
+            view.addr,
+            |addr, page, _| {
+                match page.kind() {
+                    PageKind::Data => {
+                        let page = ValuePageRef::from(page);
+                        let index = match page.rank(&key) {
+                            Ok(i) => i,
+                            Err(i) => i,
+                        };
+                        if let Some((k, v)) = page.get(index) {
+                            if k.raw == key.raw {
+                                // The version may be deleted by a newer range delete.
+                                let deleted =
+                                    range_deletes.iter().any(|range| range.shadows(&k, key.lsn));
+                                if v != Value::Delete && !deleted {
+                                    found = Some(addr);
+                                }
+                                return true;
+                            }
+                        }
+                    }
+                    PageKind::RangeDelete => {
+                        range_deletes.push(range_delete_from_page(page));
+                    }
+                    PageKind::Split | PageKind::Overflow => {}
+                }
+                false
+            },
+            CacheOption::default(),
+        )
+        .await?;
+        Ok(found.and_then(|addr| self.guard.locate_page(addr)))
+    }
+
+    /// Gets all retained versions of the key that are visible to the key's LSN.
+    ///
+    /// Returns the LSN and value of each version, newest first. A deleted
+    /// version has no value.
+    pub(crate) async fn get_versions(&self, key: Key<'_>) -> Result<Vec<(u64, Option<&[u8]>)>> {
+        let (view, _) = self.find_leaf(key.raw).await?;
+        let mut iter: MergingPageIter<Key, Value> = self.iter_page(&view).await?;
+        let range_deletes = iter.take_range_deletes();
+        iter.seek(&key);
+
+        let mut versions = Vec::new();
+        let mut read_bytes = 0;
+        for (k, v) in iter {
+            if k.raw != key.raw {
+                break;
+            }
+            // A version may be written again by a newer delta, which shadows it.
+            if versions.last().map_or(false, |&(lsn, _)| lsn == k.lsn) {
+                continue;
+            }
+            let value = self.read_value(v).await?;
+            read_bytes += k.len() + value.map(|v| v.len()).unwrap_or_default();
+            versions.push((k.lsn, value));
+        }
+        // A range delete is reported as a deleted version if it shadows any
+        // version of the key.
+        for range in range_deletes {
+            let lsn = range.lsn();
+            if lsn > key.lsn || !range.covers(key.raw) {
+                continue;
+            }
+            if versions.last().map_or(true, |&(oldest, _)| oldest > lsn) {
+                continue;
+            }
+            match versions.binary_search_by(|&(v, _)| lsn.cmp(&v)) {
+                Ok(i) => versions[i].1 = None,
+                Err(i) => versions.insert(i, (lsn, None)),
+            }
+        }
+        self.tree.stats.success.read_bytes.add(read_bytes as u64);
+
+        Ok(versions)
+    }
+
+    /// Reads the pages into the page cache concurrently.
+    ///
+    /// Failures are logged and recorded in [`ReadaheadStats`] instead of
+    /// returned, since the pages are read again when they are scanned.
+    async fn prefetch_pages(&self, ids: &[u64]) {
+        let prefetches = ids.iter().map(|&id| {
+            let addr = self.guard.page_addr(id);
+            self.walk_page(addr, |_, _, _| false, CacheOption::default())
+        });
+        let results = futures::future::join_all(prefetches).await;
+        let stats = &self.tree.stats.readahead;
+        stats.prefetch_pages.add(ids.len() as u64);
+        for (id, result) in ids.iter().zip(results) {
+            if let Err(err) = result {
+                warn!("Prefetch page {id} failed: {err:?}");
+                stats.prefetch_failures.inc();
+            }
+        }
+    }
+
+    /// Reads the leaf pages that cover the keys into the page cache
+    /// concurrently.
+    ///
+    /// Failures are recorded in [`PrefetchStats`] instead of returned.
+    pub(crate) async fn prefetch_keys(&self, keys: &[&[u8]]) {
+        let prefetches = keys.iter().map(|key| self.prefetch_key(key));
+        let results = futures::future::join_all(prefetches).await;
+        let stats = &self.tree.stats.prefetch;
+        stats.keys.add(keys.len() as u64);
+        for result in results {
+            match result {
+                Ok(pages) => stats.pages.add(pages as u64),
+                Err(_) => stats.failures.inc(),
+            }
+        }
+    }
+
+    /// Reads the leaf page that covers the key into the page cache.
+    ///
+    /// Returns the number of pages read.
+    async fn prefetch_key(&self, key: &[u8]) -> Result<usize> {
+        let (view, _) = self.find_leaf(key).await?;
+        let mut pages = 0;
+        self.walk_page(
+            view.addr,
+            |_, _, _| {
+                pages += 1;
+                false
+            },
+            CacheOption::default(),
+        )
+        .await?;
+        Ok(pages)
+    }
+
+    /// Reads the leaf pages that cover keys from `start` to `end` into the page
+    /// cache.
+    ///
+    /// Returns false if it stops early because the page cache is full.
+    pub(crate) async fn warmup(&self, start: &[u8], end: Bound<&[u8]>) -> Result<bool> {
+        let mut key = start;
+        loop {
+            if self.guard.page_cache_is_full() {
+                return Ok(false);
+            }
+            let (view, _) = self.find_leaf(key).await?;
+            match self
+                .walk_page(view.addr, |_, _, _| false, CacheOption::WARMUP)
+                .await
+            {
+                Ok(()) => {}
+                Err(Error::MemoryLimit) => return Ok(false),
+                Err(err) => return Err(err),
+            }
+            let Some(next) = view.range.and_then(|range| range.end) else {
+                return Ok(true);
+            };
+            match end {
+                Bound::Included(end) if next > end => return Ok(true),
+                Bound::Excluded(end) if next >= end => return Ok(true),
+                _ => key = next,
+            }
+        }
+    }
+
+    /// Reads the leaf page that covers the key, or the leaf page that covers
+    /// the keys right before the key if `before` is true.
+    ///
+    /// Returns the range of the page and its entries visible to `lsn`. The key
+    /// must not be empty if `before` is true.
+    pub(crate) async fn read_leaf(
+        &self,
+        key: &[u8],
+        before: bool,
+        lsn: u64,
+    ) -> Result<LeafPage<'_>> {
+        let (view, _) = if before {
+            self.find_leaf_before(key).await?
+        } else {
+            self.find_leaf(key).await?
+        };
+        let range = view.range.unwrap_or(ROOT_RANGE);
+        let mut iter = self.iter_page(&view).await?;
+        let overflow_values = self.read_overflow_values(&mut iter).await?;
+        let entries = PageIter::new(iter, lsn)
+            .with_overflow_values(overflow_values)
+            .collect();
+        Ok(LeafPage {
+            start: range.start,
+            end: range.end,
+            entries,
+        })
+    }
+
+    /// Consolidates all leaf pages completely.
+    ///
+    /// Obsolete versions are dropped and [`Options::compaction_filter`] is
+    /// applied to every entry in the tree.
+    pub(crate) async fn compact(&self) -> Result<()> {
+        self.compact_at(self.tree.safe_lsn()).await
+    }
+
+    /// Consolidates all leaf pages completely, and drops the deletes and
+    /// obsolete versions that no live snapshot can see.
+    ///
+    /// Reads with LSNs smaller than the oldest live snapshot may miss versions
+    /// afterwards, even if the LSNs are not smaller than the safe LSN.
+    pub(crate) async fn collect_tombstones(&self) -> Result<()> {
+        self.compact_at(self.tree.oldest_snapshot_lsn()).await
+    }
+
+    /// Consolidates all leaf pages completely, and keeps the versions visible
+    /// to reads at or after `safe_lsn`.
+    async fn compact_at(&self, safe_lsn: u64) -> Result<()> {
+        let mut key: &[u8] = &[];
+        loop {
+            let view = loop {
+                let (view, _) = self.find_leaf(key).await?;
+                match self.consolidate_leaf_page(view, true, safe_lsn).await {
+                    Ok(view) => break view,
+                    Err(Error::Again) => {
+                        self.guard.check_deadline()?;
+                        continue;
+                    }
+                    Err(e) => return Err(e),
+                }
+            };
+            let Some(next) = view.range.and_then(|range| range.end) else {
+                return Ok(());
+            };
+            key = next;
+        }
+    }
+
+    /// Returns the entries of the active leaf data pages stored in the file.
+    ///
+    /// Entries are returned in the order of pages in the file, so a key may
+    /// appear more than once if it is stored in multiple pages. A deleted
+    /// entry has no value.
+    pub(crate) async fn file_entries(&self, file_id: u32) -> Result<Vec<(Key<'_>, Option<&[u8]>)>> {
+        let mut entries = Vec::new();
+        let mut read_bytes = 0;
+        for addr in self.guard.file_pages(file_id) {
+            let (page, _) = self.guard.read_page(addr, CacheOption::default()).await?;
+            if !page.tier().is_leaf() || !page.kind().is_data() {
+                continue;
+            }
+            for (k, v) in SortedPageIter::new(ValuePageRef::from(page)) {
+                read_bytes += k.len() + v.len();
+                entries.push((k, self.read_value(v).await?));
+            }
+        }
+        self.tree.stats.success.read_bytes.add(read_bytes as u64);
+        Ok(entries)
+    }
+
     /// Writes the key-value pair to the tree.
     pub(crate) async fn write(&self, key: Key<'_>, value: Value<'_>) -> Result<()> {
+        self.write_impl(key, value, false, None, None).await?;
+        Ok(())
+    }
+
+    /// Writes the key-value pair to the tree if no version of the key has a
+    /// LSN larger than `max_lsn`.
+    ///
+    /// Returns false if the write is rejected. Like
+    /// [`TreeTxn::write_and_get_previous`], the write fails over if the page
+    /// is changed after the versions are checked.
+    pub(crate) async fn write_if_lsn(
+        &self,
+        key: Key<'_>,
+        value: Value<'_>,
+        max_lsn: u64,
+    ) -> Result<bool> {
+        let outcome = self
+            .write_impl(key, value, false, None, Some(max_lsn))
+            .await?;
+        Ok(matches!(outcome, WriteOutcome::Written(_)))
+    }
+
+    /// Writes the key-value pair to the tree and returns the value visible to
+    /// the key right before the write.
+    ///
+    /// The write fails over if the page is changed after the previous value is
+    /// read, so no other write can happen in between.
+    pub(crate) async fn write_and_get_previous(
+        &self,
+        key: Key<'_>,
+        value: Value<'_>,
+    ) -> Result<Option<Vec<u8>>> {
+        self.write_impl(key, value, true, None, None)
+            .await?
+            .into_previous()
+    }
+
+    /// Merges the operand into the value of the key with the operator.
+    ///
+    /// The merged value is written as a regular value. Like
+    /// [`TreeTxn::write_and_get_previous`], the write fails over if the page
+    /// is changed after the existing value is read.
+    pub(crate) async fn merge(
+        &self,
+        key: Key<'_>,
+        operand: &[u8],
+        operator: &dyn MergeOperator,
+    ) -> Result<()> {
+        self.write_impl(key, Value::Put(operand), false, Some(operator), None)
+            .await?;
+        Ok(())
+    }
+
+    /// Moves the value of `from` to `to`, and returns false if `from` has no
+    /// value visible to its LSN.
+    ///
+    /// `from` is deleted and its previous value is read atomically, like
+    /// [`TreeTxn::write_and_get_previous`]. Both keys are written at the same
+    /// LSN, which [`Tree::latest_lsn`] stays below until both writes finish,
+    /// even if other writes with larger LSNs finish in between, so reads at
+    /// LSNs up to the latest one never see both keys or neither of them.
+    ///
+    /// If `to` can't be written, the value of `from` is written back.
+    pub(crate) async fn rename(&self, from: Key<'_>, to: Key<'_>) -> Result<bool> {
+        if from.raw == to.raw {
+            return Ok(self.get(from).await?.is_some());
+        }
+        debug_assert_eq!(from.lsn, to.lsn);
+        let unpublished = self.tree.hold_latest_lsn(from.lsn);
+        let previous = self
+            .write_unpublished(from, Value::Delete, true, None, None)
+            .await?
+            .into_previous()?;
+        if let Some(value) = &previous {
+            if let Err(err) = self
+                .write_unpublished(to, Value::Put(value), false, None, None)
+                .await
+            {
+                // A newer delta of the same key and LSN shadows the delete.
+                if let Err(undo_err) = self
+                    .write_unpublished(from, Value::Put(value), false, None, None)
+                    .await
+                {
+                    warn!("Undo the delete of renamed key {from:?} failed: {undo_err:?}");
+                }
+                return Err(err);
+            }
+        }
+        self.tree.advance_latest_lsn(from.lsn);
+        drop(unpublished);
+        Ok(previous.is_some())
+    }
+
+    async fn write_impl(
+        &self,
+        key: Key<'_>,
+        value: Value<'_>,
+        get_previous: bool,
+        merge: Option<&dyn MergeOperator>,
+        max_lsn: Option<u64>,
+    ) -> Result<WriteOutcome> {
+        let outcome = self
+            .write_unpublished(key, value, get_previous, merge, max_lsn)
+            .await?;
+        if !matches!(outcome, WriteOutcome::Rejected) {
+            self.tree.advance_latest_lsn(key.lsn);
+        }
+        Ok(outcome)
+    }
+
+    /// Writes the key-value pair without advancing the latest LSN of the tree.
+    async fn write_unpublished(
+        &self,
+        key: Key<'_>,
+        value: Value<'_>,
+        get_previous: bool,
+        merge: Option<&dyn MergeOperator>,
+        max_lsn: Option<u64>,
+    ) -> Result<WriteOutcome> {
         let bytes = key.len() + value.len();
+        let mut retries = 0;
         loop {
-            match self.try_write(key, value).await {
-                Ok(_) => {
+            match self
+                .try_write(key, value, get_previous, merge, max_lsn)
+                .await
+            {
+                Ok(WriteOutcome::Rejected) => return Ok(WriteOutcome::Rejected),
+                Ok(outcome) => {
                     self.tree.stats.success.write.inc();
                     self.tree.stats.success.write_bytes.add(bytes as u64);
-                    return Ok(());
+                    if value.is_delete() {
+                        self.tree.stats.tombstones.added.inc();
+                    }
+                    return Ok(outcome);
                 }
                 Err(Error::Again) => {
                     self.tree.stats.conflict.write.inc();
+                    self.guard.check_deadline()?;
+                    self.backoff(&mut retries).await?;
                     continue;
                 }
                 Err(e) => return Err(e),
@@ -137,19 +771,205 @@ impl<'a, E: Env> TreeTxn<'a, E> {
         }
     }
 
-    async fn try_write(&self, key: Key<'_>, value: Value<'_>) -> Result<()> {
+    async fn try_write(
+        &self,
+        key: Key<'_>,
+        value: Value<'_>,
+        get_previous: bool,
+        merge: Option<&dyn MergeOperator>,
+        max_lsn: Option<u64>,
+    ) -> Result<WriteOutcome> {
         let (mut view, _) = self.find_leaf(key.raw).await?;
 
         // Try to split the page before every write to avoid starving the split
         // operation due to contentions.
-        if self.should_split_page(&view.page) && self.split_page(view.clone()).await.is_ok() {
+        if self.should_split_page(&view).await? && self.split_page(view.clone()).await.is_ok() {
             return Err(Error::Again);
         }
 
+        if let Some(max_lsn) = max_lsn {
+            let latest = self.find_latest_lsn(key.raw, &view).await?;
+            if latest.map_or(false, |lsn| lsn > max_lsn) {
+                return Ok(WriteOutcome::Rejected);
+            }
+        }
+
+        let read_previous = get_previous || merge.is_some();
+        let previous = if read_previous {
+            match self.find_value(&key, &view).await? {
+                Some(value) => self.read_value(value).await?.map(<[u8]>::to_vec),
+                None => None,
+            }
+        } else {
+            None
+        };
+        let merged = match (merge, value) {
+            (Some(op), Value::Put(operand)) => {
+                Some(op.merge(key.raw, previous.as_deref(), operand))
+            }
+            _ => None,
+        };
+        let value = merged.as_deref().map_or(value, Value::Put);
+
+        // Store a large value in overflow pages, and only their addresses in the delta.
+        let overflow_pages = match value {
+            Value::Put(v) if self.is_overflow_value(v) => self.write_overflow_pages(v).await?,
+            _ => Vec::new(),
+        };
+        let encoded_pages = OverflowValue::encode_pages(&overflow_pages);
+        let value = if overflow_pages.is_empty() {
+            value
+        } else {
+            Value::Overflow(OverflowValue::new(value.len() as u64, &encoded_pages))
+        };
+
         // Build a delta page with the given key-value pair.
         let delta = (key, value);
         let builder = SortedPageBuilder::new(PageTier::Leaf, PageKind::Data).with_item(delta);
-        let mut txn = self.guard.begin().await;
+        // The write may depend on the previous value or versions read from the
+        // page.
+        if let Err(err) = self
+            .update_leaf_page(&mut view, builder, !read_previous && max_lsn.is_none())
+            .await
+        {
+            if !overflow_pages.is_empty() {
+                // The pages are leaked if this fails, which only wastes some space.
+                if let Err(dealloc_err) = self.dealloc_overflow_pages(&overflow_pages).await {
+                    warn!("Deallocate overflow pages {overflow_pages:?} failed: {dealloc_err:?}");
+                }
+            }
+            return Err(err);
+        }
+
+        // Try to consolidate the page if it is too long or its deltas are too large.
+        if self.should_consolidate_page(&view.page) || self.has_large_deltas(&view.page) {
+            let _ = self.consolidate_and_restructure_page(view).await;
+        }
+        Ok(WriteOutcome::Written(previous))
+    }
+
+    /// Returns true if the value should be stored in overflow pages.
+    fn is_overflow_value(&self, value: &[u8]) -> bool {
+        self.tree
+            .options
+            .overflow_value_size
+            .map_or(false, |size| value.len() > size)
+    }
+
+    /// Writes the value into overflow pages of at most
+    /// [`Options::page_size`] bytes each.
+    ///
+    /// Returns the ids and addresses of the pages in order.
+    async fn write_overflow_pages(&self, value: &[u8]) -> Result<Vec<(u64, u64)>> {
+        let mut txn = self.guard.begin().await?;
+        let mut pages = Vec::new();
+        for chunk in value.chunks(self.tree.options.page_size) {
+            let builder = OverflowPageBuilder::new(chunk);
+            let (addr, mut page) = txn.alloc_page(builder.size()).await?;
+            builder.build(&mut page);
+            let id = txn.insert_page(addr);
+            pages.push((id, addr));
+        }
+        txn.commit();
+        Ok(pages)
+    }
+
+    /// Deallocates overflow pages and their ids, which must not be referenced
+    /// by any page.
+    async fn dealloc_overflow_pages(&self, pages: &[(u64, u64)]) -> Result<()> {
+        let addrs: Vec<u64> = pages.iter().map(|&(_, addr)| addr).collect();
+        loop {
+            let mut txn = self.guard.begin().await?;
+            txn.dealloc_page_ids(pages.iter().map(|&(id, _)| id));
+            match txn.dealloc_pages(&addrs).await {
+                Err(Error::Again) => self.guard.check_deadline()?,
+                result => return result,
+            }
+        }
+    }
+
+    /// Deletes the keys from `start` to `end` (exclusive) at the LSN.
+    ///
+    /// A range delete delta is prepended to every leaf page that covers part
+    /// of the range. The deltas shadow the versions at or below the LSN, and
+    /// they are collapsed into the pages when the pages are consolidated.
+    pub(crate) async fn delete_range(
+        &self,
+        start: &[u8],
+        end: Option<&[u8]>,
+        lsn: u64,
+    ) -> Result<()> {
+        let mut key = start;
+        loop {
+            let mut retries = 0;
+            let next = loop {
+                match self.try_delete_range(key, end, lsn).await {
+                    Ok(next) => break next,
+                    Err(Error::Again) => {
+                        self.tree.stats.conflict.write.inc();
+                        self.guard.check_deadline()?;
+                        self.backoff(&mut retries).await?;
+                        continue;
+                    }
+                    Err(e) => return Err(e),
+                }
+            };
+            self.tree.stats.success.write.inc();
+            match next {
+                Some(next) if end.map_or(true, |end| next < end) => key = next,
+                _ => break,
+            }
+        }
+        self.tree.advance_latest_lsn(lsn);
+        Ok(())
+    }
+
+    /// Deletes the part of the range covered by the leaf page of `start`.
+    ///
+    /// Returns the end of the page range.
+    async fn try_delete_range(
+        &self,
+        start: &[u8],
+        end: Option<&[u8]>,
+        lsn: u64,
+    ) -> Result<Option<&[u8]>> {
+        let (mut view, _) = self.find_leaf(start).await?;
+        let range = view.range.expect("leaf page must have a range");
+
+        // Clip the range to the page, so that the delta never covers keys that
+        // are moved into the page later.
+        let start = start.max(range.start);
+        let end = match (end, range.end) {
+            (Some(a), Some(b)) => Some(a.min(b)),
+            (a, b) => a.or(b),
+        };
+        let delta = (Key::new(start, lsn), end.unwrap_or_default());
+        let builder =
+            SortedPageBuilder::new(PageTier::Leaf, PageKind::RangeDelete).with_item(delta);
+        self.update_leaf_page(&mut view, builder, true).await?;
+
+        if self.should_consolidate_page(&view.page) {
+            let _ = self.consolidate_and_restructure_page(view).await;
+        }
+        Ok(range.end)
+    }
+
+    /// Prepends a delta page built by the builder to the leaf page.
+    ///
+    /// If `retry` is true, the update is retried on conflicts as long as the
+    /// page epoch remains the same.
+    async fn update_leaf_page<I, K, V>(
+        &self,
+        view: &mut PageView<'_>,
+        builder: SortedPageBuilder<I>,
+        retry: bool,
+    ) -> Result<()>
+    where
+        I: RewindableIterator<Item = (K, V)>,
+        K: SortedPageKey,
+        V: SortedPageValue,
+    {
+        let mut txn = self.guard.begin().await?;
         let (new_addr, mut new_page) = txn.alloc_page(builder.size()).await?;
         builder.build(&mut new_page);
 
@@ -164,13 +984,18 @@ impl<'a, E: Env> TreeTxn<'a, E> {
                     view.page = new_page.info();
                     break;
                 }
-                Err(None) => return Err(Error::Again),
+                Err(None) => {
+                    self.tree.stats.cas.retries.inc();
+                    return Err(Error::Again);
+                }
                 Err(Some((_txn, addr))) => {
+                    self.tree.stats.cas.retries.inc();
                     // The page has been updated by other transactions.
                     // We can keep retrying as long as the page epoch remains
                     // the same. However, this doesn't work for the root
                     // because we split the root without updating its epoch.
-                    if view.id != ROOT_ID {
+                    // Nor does it work if the caller doesn't allow it.
+                    if view.id != ROOT_ID && retry {
                         let page = self.guard.read_page_info(addr)?;
                         if page.epoch() == view.page.epoch() {
                             txn = _txn;
@@ -183,10 +1008,24 @@ impl<'a, E: Env> TreeTxn<'a, E> {
                 }
             }
         }
+        Ok(())
+    }
 
-        // Try to consolidate the page if it is too long.
-        if self.should_consolidate_page(&view.page) {
-            let _ = self.consolidate_and_restructure_page(view).await;
+    /// Backs off before retrying a conflicted write, according to
+    /// [`Options::cas_backoff`].
+    ///
+    /// Returns [`Error::Busy`] if the write has been retried too many times.
+    async fn backoff(&self, retries: &mut u32) -> Result<()> {
+        let Some(backoff) = &self.tree.options.cas_backoff else {
+            return Ok(());
+        };
+        *retries += 1;
+        if backoff.max_retries > 0 && *retries > backoff.max_retries {
+            self.tree.stats.cas.busy.inc();
+            return Err(Error::Busy);
+        }
+        for _ in 0..backoff.yields(*retries) {
+            yield_now().await;
         }
         Ok(())
     }
@@ -207,14 +1046,32 @@ impl<'a, E: Env> TreeTxn<'a, E> {
     ///
     /// Returns the leaf page and its parent.
     async fn find_leaf(&self, key: &[u8]) -> Result<(PageView<'_>, Option<PageView<'_>>)> {
+        self.find_leaf_impl(key, false).await
+    }
+
+    /// Finds the leaf page that covers the keys right before the key, which
+    /// must not be empty.
+    ///
+    /// Returns the leaf page and its parent.
+    async fn find_leaf_before(&self, key: &[u8]) -> Result<(PageView<'_>, Option<PageView<'_>>)> {
+        debug_assert!(!key.is_empty());
+        self.find_leaf_impl(key, true).await
+    }
+
+    async fn find_leaf_impl(
+        &self,
+        key: &[u8],
+        before: bool,
+    ) -> Result<(PageView<'_>, Option<PageView<'_>>)> {
         loop {
-            match self.try_find_leaf(key).await {
+            match self.try_find_leaf(key, before).await {
                 Ok((view, parent)) => {
                     self.tree.stats.success.read.inc();
                     return Ok((view, parent));
                 }
                 Err(Error::Again) => {
                     self.tree.stats.conflict.read.inc();
+                    self.guard.check_deadline()?;
                     continue;
                 }
                 Err(e) => return Err(e),
@@ -222,7 +1079,11 @@ impl<'a, E: Env> TreeTxn<'a, E> {
         }
     }
 
-    async fn try_find_leaf(&self, key: &[u8]) -> Result<(PageView<'_>, Option<PageView<'_>>)> {
+    async fn try_find_leaf(
+        &self,
+        key: &[u8],
+        before: bool,
+    ) -> Result<(PageView<'_>, Option<PageView<'_>>)> {
         // The index, range, and parent of the current page, starting from the root.
         let mut index = ROOT_INDEX;
         let mut range = ROOT_RANGE;
@@ -257,7 +1118,7 @@ impl<'a, E: Env> TreeTxn<'a, E> {
             }
             // Find the child page that may contain the key.
             let (child_index, child_range) = self
-                .find_child(key, &view)
+                .find_child(key, &view, before)
                 .await?
                 .expect("child page must exist");
             index = child_index;
@@ -296,13 +1157,19 @@ impl<'a, E: Env> TreeTxn<'a, E> {
     {
         let mut builder = MergingIterBuilder::with_capacity(view.page.chain_len() as usize);
         let mut range_limit = None;
+        let mut range_deletes = Vec::new();
+        let mut has_overflow_values = false;
         self.walk_page(
             view.addr,
             |_, page, _| {
                 match page.kind() {
                     PageKind::Data => {
+                        has_overflow_values |= page.has_overflow_values();
                         builder.add(SortedPageIter::from(page));
                     }
+                    PageKind::RangeDelete => {
+                        range_deletes.push(range_delete_from_page(page));
+                    }
                     PageKind::Split => {
                         // The split key we first encountered must be the smallest.
                         #[cfg(debug_assertions)]
@@ -315,42 +1182,143 @@ impl<'a, E: Env> TreeTxn<'a, E> {
                             range_limit = Some(split_key);
                         }
                     }
+                    PageKind::Overflow => {}
                 }
                 false
             },
             CacheOption::default(),
         )
         .await?;
-        Ok(MergingPageIter::new(builder.build(), range_limit))
+        Ok(MergingPageIter::new(builder.build(), range_limit)
+            .with_range_deletes(range_deletes)
+            .with_overflow_values(has_overflow_values))
+    }
+
+    /// Reads the content of a value, which is `None` for a delete.
+    ///
+    /// The content of an overflow value is kept by the guard, so it lives as
+    /// long as the pages read by the guard.
+    async fn read_value<'g>(&'g self, value: Value<'g>) -> Result<Option<&'g [u8]>> {
+        match value {
+            Value::Put(value) => Ok(Some(value)),
+            Value::Delete => Ok(None),
+            Value::Overflow(value) => {
+                let content = self.read_overflow_value(value).await?;
+                Ok(Some(self.guard.keep_value(content)))
+            }
+        }
+    }
+
+    /// Reads the overflow pages of the value and concatenates their content.
+    async fn read_overflow_value(&self, value: OverflowValue<'_>) -> Result<Vec<u8>> {
+        let mut content = Vec::with_capacity(value.len as usize);
+        for addr in value.addrs() {
+            let (page, _) = self.guard.read_page(addr, CacheOption::default()).await?;
+            content.extend_from_slice(OverflowPageRef::from(page).data());
+        }
+        debug_assert_eq!(content.len() as u64, value.len);
+        Ok(content)
+    }
+
+    /// Reads all overflow values in the pages of the iterator.
+    ///
+    /// The iterator is rewound afterwards.
+    async fn read_overflow_values<'g>(
+        &'g self,
+        iter: &mut MergingPageIter<'g, Key<'g>, Value<'g>>,
+    ) -> Result<OverflowValues<'g>> {
+        let mut values = OverflowValues::default();
+        if !iter.has_overflow_values() {
+            return Ok(values);
+        }
+        let overflows: Vec<_> = (&mut *iter)
+            .filter_map(|(_, v)| match v {
+                Value::Overflow(v) => Some(v),
+                _ => None,
+            })
+            .collect();
+        iter.rewind();
+        for value in overflows {
+            let content = self.read_overflow_value(value).await?;
+            values.insert(value.raw_pages(), self.guard.keep_value(content));
+        }
+        Ok(values)
+    }
+
+    /// Returns the largest LSN of the versions of the key in the page,
+    /// including deletes and range deletes that cover the key.
+    async fn find_latest_lsn(&self, raw: &[u8], view: &PageView<'_>) -> Result<Option<u64>> {
+        let key = Key::new(raw, u64::MAX);
+        let mut latest = None;
+        self.walk_page(
+            view.addr,
+            |_, page, _| {
+                let lsn = match page.kind() {
+                    PageKind::Data => {
+                        let page = ValuePageRef::from(page);
+                        let index = match page.rank(&key) {
+                            Ok(i) => i,
+                            Err(i) => i,
+                        };
+                        page.get(index)
+                            .filter(|(k, _)| k.raw == raw)
+                            .map(|(k, _)| k.lsn)
+                    }
+                    PageKind::RangeDelete => {
+                        let range = range_delete_from_page(page);
+                        range.covers(raw).then(|| range.lsn())
+                    }
+                    // Split and overflow pages hold no versions of keys.
+                    PageKind::Split | PageKind::Overflow => None,
+                };
+                latest = latest.max(lsn);
+                false
+            },
+            CacheOption::default(),
+        )
+        .await?;
+        Ok(latest)
     }
 
     /// Finds the value corresponding to the key from the page.
+    ///
+    /// The value is either a put or an overflow value.
     async fn find_value<'g>(
         &'g self,
         key: &Key<'_>,
         view: &PageView<'g>,
-    ) -> Result<Option<&'g [u8]>> {
+    ) -> Result<Option<Value<'g>>> {
+        self.guard.record_delta_chain(view.page.chain_len());
         let mut value = None;
+        let mut range_deletes: Vec<RangeDelete> = Vec::new();
         self.walk_page(
             view.addr,
             |_, page, _| {
                 debug_assert!(page.tier().is_leaf());
-                // We only care about data pages here.
-                if page.kind().is_data() {
-                    let page = ValuePageRef::from(page);
-                    let index = match page.rank(key) {
-                        Ok(i) => i,
-                        Err(i) => i,
-                    };
-                    if let Some((k, v)) = page.get(index) {
-                        if k.raw == key.raw {
-                            debug_assert!(k.lsn <= key.lsn);
-                            if let Value::Put(v) = v {
-                                value = Some(v);
+                match page.kind() {
+                    PageKind::Data => {
+                        let page = ValuePageRef::from(page);
+                        let index = match page.rank(key) {
+                            Ok(i) => i,
+                            Err(i) => i,
+                        };
+                        if let Some((k, v)) = page.get(index) {
+                            if k.raw == key.raw {
+                                debug_assert!(k.lsn <= key.lsn);
+                                // The version may be deleted by a newer range delete.
+                                let deleted =
+                                    range_deletes.iter().any(|range| range.shadows(&k, key.lsn));
+                                if v != Value::Delete && !deleted {
+                                    value = Some(v);
+                                }
+                                return true;
                             }
-                            return true;
                         }
                     }
+                    PageKind::RangeDelete => {
+                        range_deletes.push(range_delete_from_page(page));
+                    }
+                    PageKind::Split | PageKind::Overflow => {}
                 }
                 false
             },
@@ -360,13 +1328,16 @@ impl<'a, E: Env> TreeTxn<'a, E> {
         Ok(value)
     }
 
-    /// Finds the child page that may contain the key from the page.
+    /// Finds the child page that may contain the key from the page, or the
+    /// child page that covers the keys right before the key if `before` is
+    /// true.
     ///
     /// Returns the index and range of the child page.
     async fn find_child<'g>(
         &'g self,
         key: &[u8],
         view: &PageView<'g>,
+        before: bool,
     ) -> Result<Option<(Index, Range<'g>)>> {
         let mut child = None;
         self.walk_page(
@@ -379,9 +1350,12 @@ impl<'a, E: Env> TreeTxn<'a, E> {
                     // Find the two items that enclose the key.
                     let (left, right) = match page.rank(&key) {
                         // The `i` item is equal to the key, so the range is [i, i + 1).
-                        Ok(i) => (page.get(i), i.checked_add(1).and_then(|i| page.get(i))),
-                        // The `i` item is greater than the key, so the range is [i - 1, i).
-                        Err(i) => (i.checked_sub(1).and_then(|i| page.get(i)), page.get(i)),
+                        Ok(i) if !before => {
+                            (page.get(i), i.checked_add(1).and_then(|i| page.get(i)))
+                        }
+                        // The `i` item is greater than or equal to the key, so the range
+                        // [i - 1, i) covers the keys right before it.
+                        Ok(i) | Err(i) => (i.checked_sub(1).and_then(|i| page.get(i)), page.get(i)),
                     };
                     if let Some((start, index)) = left {
                         if index != NULL_INDEX {
@@ -428,21 +1402,26 @@ impl<'a, E: Env> TreeTxn<'a, E> {
             .read_page(view.addr, CacheOption::default())
             .await?;
         let page = SortedPageRef::<K, V>::from(page);
-        let Some((split_key, _, right_iter)) = page.into_split_iter() else {
-            return Ok(());
+        let mid = self.split_point(&view, page.len());
+        let Some((split_key, left_iter, right_iter)) = page.into_split_iter_at(mid) else {
+            // There is no separator around the split point, report it so that writes
+            // don't retry the split forever.
+            return Err(Error::InvalidArgument);
         };
+        let split_raw = self.split_separator(view.page.tier(), &split_key, &left_iter);
 
-        let mut txn = self.guard.begin().await;
+        let mut txn = self.guard.begin().await?;
         // Build and insert the right page.
         let right_id = {
-            let builder =
-                SortedPageBuilder::new(view.page.tier(), PageKind::Data).with_iter(right_iter);
+            let builder = SortedPageBuilder::new(view.page.tier(), PageKind::Data)
+                .with_layout(self.tree.options.page_layout)
+                .with_iter(right_iter);
             let (new_addr, mut new_page) = txn.alloc_page(builder.size()).await?;
             builder.build(&mut new_page);
             txn.insert_page(new_addr)
         };
         // Build a delta page with the right index.
-        let delta = (split_key.as_raw(), Index::new(right_id, 0));
+        let delta = (split_raw, Index::new(right_id, 0));
         let builder = SortedPageBuilder::new(view.page.tier(), PageKind::Split).with_item(delta);
         let (new_addr, mut new_page) = txn.alloc_page(builder.size()).await?;
         builder.build(&mut new_page);
@@ -466,6 +1445,43 @@ impl<'a, E: Env> TreeTxn<'a, E> {
         Ok(())
     }
 
+    // Returns the index of the item to split the page with `len` items around.
+    //
+    // The rightmost leaf page is split with the configured ratio, so that the left
+    // half stays fuller under sequential inserts.
+    fn split_point(&self, view: &PageView<'_>, len: usize) -> usize {
+        let rightmost = view.range.map_or(false, |range| range.end.is_none());
+        if !view.page.tier().is_leaf() || !rightmost {
+            return len / 2;
+        }
+        let mid = (len as f64 * self.tree.options.split_ratio) as usize;
+        mid.min(len.saturating_sub(1))
+    }
+
+    // Returns the raw separator that routes keys to the right half of a split.
+    //
+    // Only leaf separators can be truncated, since the separator of an inner page
+    // must match the first index in its right half.
+    fn split_separator<'k, K, V>(
+        &self,
+        tier: PageTier,
+        split_key: &'k K,
+        left_iter: &SortedPageRangeIter<'_, K, V>,
+    ) -> &'k [u8]
+    where
+        K: SortedPageKey,
+        V: SortedPageValue,
+    {
+        let raw = split_key.as_raw();
+        if !self.tree.options.truncate_split_separator || !tier.is_leaf() {
+            return raw;
+        }
+        match left_iter.last_item() {
+            Some((left, _)) => shortest_separator(left.as_raw(), raw),
+            None => raw,
+        }
+    }
+
     async fn split_root_impl<K, V>(&self, view: PageView<'_>) -> Result<()>
     where
         K: SortedPageKey,
@@ -480,23 +1496,29 @@ impl<'a, E: Env> TreeTxn<'a, E> {
             .read_page(view.addr, CacheOption::default())
             .await?;
         let page = SortedPageRef::<K, V>::from(page);
-        let Some((split_key, left_iter, right_iter)) = page.into_split_iter() else {
-            return Ok(());
+        let mid = self.split_point(&view, page.len());
+        let Some((split_key, left_iter, right_iter)) = page.into_split_iter_at(mid) else {
+            // There is no separator around the split point, report it so that writes
+            // don't retry the split forever.
+            return Err(Error::InvalidArgument);
         };
+        let split_raw = self.split_separator(view.page.tier(), &split_key, &left_iter);
 
-        let mut txn = self.guard.begin().await;
+        let mut txn = self.guard.begin().await?;
         // Build and insert the left page.
         let left_id = {
-            let builder =
-                SortedPageBuilder::new(view.page.tier(), PageKind::Data).with_iter(left_iter);
+            let builder = SortedPageBuilder::new(view.page.tier(), PageKind::Data)
+                .with_layout(self.tree.options.page_layout)
+                .with_iter(left_iter);
             let (new_addr, mut new_page) = txn.alloc_page(builder.size()).await?;
             builder.build(&mut new_page);
             txn.insert_page(new_addr)
         };
         // Build and insert the right page.
         let right_id = {
-            let builder =
-                SortedPageBuilder::new(view.page.tier(), PageKind::Data).with_iter(right_iter);
+            let builder = SortedPageBuilder::new(view.page.tier(), PageKind::Data)
+                .with_layout(self.tree.options.page_layout)
+                .with_iter(right_iter);
             let (new_addr, mut new_page) = txn.alloc_page(builder.size()).await?;
             builder.build(&mut new_page);
             txn.insert_page(new_addr)
@@ -504,9 +1526,11 @@ impl<'a, E: Env> TreeTxn<'a, E> {
         // Build a delta page with the right index.
         let delta = [
             ([].as_slice(), Index::new(left_id, 0)),
-            (split_key.as_raw(), Index::new(right_id, 0)),
+            (split_raw, Index::new(right_id, 0)),
         ];
-        let builder = SortedPageBuilder::new(PageTier::Inner, PageKind::Data).with_slice(&delta);
+        let builder = SortedPageBuilder::new(PageTier::Inner, PageKind::Data)
+            .with_layout(self.tree.options.page_layout)
+            .with_slice(&delta);
         let (new_addr, mut new_page) = txn.alloc_page(builder.size()).await?;
         builder.build(&mut new_page);
         // Replace and deallocate the original root.
@@ -522,10 +1546,200 @@ impl<'a, E: Env> TreeTxn<'a, E> {
             })
     }
 
+    /// Merges the leaf page queued by a consolidation with its right sibling,
+    /// if it is still under-full.
+    ///
+    /// The page is found by the start of its range, since the merge requires
+    /// its parent.
+    async fn merge_leaf_page(&self, id: u64, start: &[u8]) -> Result<()> {
+        let (mut view, parent) = self.find_leaf(start).await?;
+        // The page may have been merged into its left sibling.
+        if view.id != id {
+            return Ok(());
+        }
+        let Some(parent) = parent else {
+            return Ok(());
+        };
+        if view.page.chain_next() != 0 {
+            view = self.consolidate_page(view).await?;
+        }
+        if !self.should_merge_page(&view.page) {
+            return Ok(());
+        }
+        self.merge_page(view, parent).await
+    }
+
+    // Merges a leaf base page with its right sibling under the same parent.
+    //
+    // Both pages are frozen first by installing empty deltas with new epochs, so
+    // that no one can modify them through the parent anymore. Then the parent is
+    // updated to route the range of the right page to the left page. Finally, the
+    // left page is replaced with a page that contains the entries of both.
+    //
+    // If the parent is changed before it is updated, both pages are unfrozen and
+    // the merge fails.
+    async fn merge_page(&self, left: PageView<'_>, parent: PageView<'_>) -> Result<()> {
+        // The parent may have been split since the left page was found.
+        let epoch = parent.page.epoch();
+        let parent = self.page_view(parent.id, parent.range).await?;
+        if parent.page.epoch() != epoch {
+            return Err(Error::Again);
+        }
+        let info = self
+            .collect_consolidation_info::<&[u8], Index>(&parent, false)
+            .await?;
+        let mut entries: Vec<_> = MergingInnerPageIter::new(info.iter).collect();
+        let left_index = Index::new(left.id, left.page.epoch());
+        let Some(i) = entries.iter().position(|(_, index)| *index == left_index) else {
+            return Err(Error::Again);
+        };
+        let Some(&(_, right_index)) = entries.get(i + 1) else {
+            return Ok(());
+        };
+        let mut right = self.page_view(right_index.id, None).await?;
+        if right.page.epoch() != right_index.epoch {
+            return Err(Error::Again);
+        }
+        if right.page.chain_next() != 0 {
+            right = self.consolidate_page(right).await?;
+            if right.page.chain_next() != 0 {
+                return Ok(());
+            }
+        }
+        if left.page.size() + right.page.size() > self.tree.options.page_size {
+            return Ok(());
+        }
+        // The merged page must not be split again because of too many entries.
+        if let Some(max_entries) = self.tree.options.leaf_target_entries {
+            if self.page_entries(&left).await? + self.page_entries(&right).await? > max_entries {
+                return Ok(());
+            }
+        }
+
+        self.guard.check_writable()?;
+        self.guard.check_deadline()?;
+        let right_frozen = self.freeze_page(&right, right.page.epoch() + 1).await?;
+        let left_frozen = match self.freeze_page(&left, left.page.epoch() + 1).await {
+            Ok(addr) => addr,
+            Err(err) => {
+                self.unfreeze_page(&right, right_frozen).await?;
+                return Err(err);
+            }
+        };
+
+        // Route the range of the right page to the left page.
+        let merged_epoch = left.page.epoch() + 2;
+        entries[i].1 = Index::new(left.id, merged_epoch);
+        entries.remove(i + 1);
+        let builder = SortedPageBuilder::new(PageTier::Inner, PageKind::Data)
+            .with_layout(self.tree.options.page_layout)
+            .with_slice(&entries);
+        let result = async {
+            let mut txn = self.guard.begin().await?;
+            let (new_addr, mut new_page) = txn.alloc_page(builder.size()).await?;
+            builder.build(&mut new_page);
+            new_page.set_epoch(parent.page.epoch());
+            new_page.set_chain_len(info.last_page.chain_len());
+            new_page.set_chain_next(info.last_page.chain_next());
+            txn.replace_page(parent.id, parent.addr, new_addr, &info.page_addrs)
+                .await
+        }
+        .await;
+        if let Err(err) = result {
+            self.unfreeze_page(&left, left_frozen).await?;
+            self.unfreeze_page(&right, right_frozen).await?;
+            self.tree.stats.conflict.merge_page.inc();
+            return Err(err);
+        }
+
+        // No one else can access the frozen pages now, so we must finish the merge.
+        let dealloc_addrs = [left_frozen, left.addr, right_frozen, right.addr];
+        loop {
+            let (left_page, _) = self
+                .guard
+                .read_page(left.addr, CacheOption::default())
+                .await?;
+            let (right_page, _) = self
+                .guard
+                .read_page(right.addr, CacheOption::default())
+                .await?;
+            let mut builder = MergingIterBuilder::with_capacity(2);
+            builder.add(SortedPageIter::<Key, Value>::from(left_page));
+            builder.add(SortedPageIter::<Key, Value>::from(right_page));
+            let iter = MergingPageIter::new(builder.build(), None);
+            let builder = SortedPageBuilder::new(PageTier::Leaf, PageKind::Data)
+                .with_layout(self.tree.options.page_layout)
+                .with_iter(iter);
+            let mut txn = self.guard.begin_without_deadline().await;
+            let result = async {
+                let (new_addr, mut new_page) = txn.alloc_page(builder.size()).await?;
+                builder.build(&mut new_page);
+                new_page.set_epoch(merged_epoch);
+                // The right page is not referenced by the parent anymore.
+                txn.dealloc_page_ids([right.id]);
+                txn.replace_page(left.id, left_frozen, new_addr, &dealloc_addrs)
+                    .await
+            }
+            .await;
+            match result {
+                Ok(()) => break,
+                Err(Error::Again) => continue,
+                Err(err) => return Err(err),
+            }
+        }
+        trace!("merge page {:?} with {:?}", left, right);
+        self.tree.stats.success.merge_page.inc();
+        Ok(())
+    }
+
+    // Installs an empty delta with the epoch on the page.
+    //
+    // Returns the address of the delta.
+    async fn freeze_page(&self, view: &PageView<'_>, epoch: u64) -> Result<u64> {
+        let iter: ItemIter<(Key, Value)> = None.into();
+        let builder = SortedPageBuilder::new(view.page.tier(), PageKind::Data).with_iter(iter);
+        // Frozen pages must be unfrozen or merged, so this must not time out.
+        let mut txn = self.guard.begin_without_deadline().await;
+        let (new_addr, mut new_page) = txn.alloc_page(builder.size()).await?;
+        builder.build(&mut new_page);
+        new_page.set_epoch(epoch);
+        new_page.set_chain_len(view.page.chain_len().saturating_add(1));
+        new_page.set_chain_next(view.addr);
+        txn.update_page(view.id, view.addr, new_addr).map_err(|_| {
+            self.tree.stats.conflict.merge_page.inc();
+            Error::Again
+        })?;
+        Ok(new_addr)
+    }
+
+    // Restores the epoch of a page frozen at the address.
+    async fn unfreeze_page(&self, view: &PageView<'_>, frozen_addr: u64) -> Result<()> {
+        let frozen = PageView {
+            id: view.id,
+            addr: frozen_addr,
+            page: self.guard.read_page_info(frozen_addr)?,
+            range: None,
+        };
+        loop {
+            // No one else can modify a frozen page, so this only fails when the
+            // write buffer is full.
+            match self.freeze_page(&frozen, view.page.epoch()).await {
+                Ok(_) => return Ok(()),
+                Err(Error::Again) => continue,
+                Err(err) => return Err(err),
+            }
+        }
+    }
+
     /// Reconciles any conflicts on the page.
     async fn reconcile_page(&self, view: PageView<'_>, parent: Option<PageView<'_>>) -> Result<()> {
         let result = match view.page.kind() {
-            PageKind::Data => Ok(()),
+            PageKind::Data | PageKind::RangeDelete => {
+                // The page is being merged, which will be finished by the merging
+                // thread, so give it a chance to run.
+                yield_now().await;
+                Ok(())
+            }
             PageKind::Split => {
                 if let Some(parent) = parent {
                     self.reconcile_split_page(view, parent).await
@@ -533,6 +1747,8 @@ impl<'a, E: Env> TreeTxn<'a, E> {
                     Err(Error::InvalidArgument)
                 }
             }
+            // Overflow pages are never linked into the tree.
+            PageKind::Overflow => Err(Error::Corrupted),
         };
         match result {
             Ok(_) => {
@@ -578,7 +1794,7 @@ impl<'a, E: Env> TreeTxn<'a, E> {
             vec![(left_key, left_index), (split_key, split_index)]
         };
         let builder = SortedPageBuilder::new(PageTier::Inner, PageKind::Data).with_slice(&delta);
-        let mut txn = self.guard.begin().await;
+        let mut txn = self.guard.begin().await?;
         let (new_addr, mut new_page) = txn.alloc_page(builder.size()).await?;
         builder.build(&mut new_page);
         // Update the parent page with the delta.
@@ -601,43 +1817,205 @@ impl<'a, E: Env> TreeTxn<'a, E> {
     }
 
     /// Consolidates delta pages on the page chain.
+    ///
+    /// Leaf pages are consolidated completely in [`ConsolidationMode::Full`].
     async fn consolidate_page<'g>(&'g self, view: PageView<'g>) -> Result<PageView<'g>> {
         match view.page.tier() {
             PageTier::Leaf => {
-                let safe_lsn = self.tree.safe_lsn();
-                self.consolidate_page_impl(view, |iter| MergingLeafPageIter::new(iter, safe_lsn))
+                let full = self.tree.options.consolidation_mode == ConsolidationMode::Full;
+                self.consolidate_leaf_page(view, full, self.tree.safe_lsn())
                     .await
             }
             PageTier::Inner => {
-                self.consolidate_page_impl(view, MergingInnerPageIter::new)
+                self.consolidate_page_impl(view, false, |iter, _| MergingInnerPageIter::new(iter))
                     .await
             }
         }
     }
 
+    /// Consolidates delta pages on the leaf page chain, and keeps the versions
+    /// visible to reads at or after `safe_lsn`.
+    ///
+    /// If `force_complete` is true, the whole chain is consolidated.
+    async fn consolidate_leaf_page<'g>(
+        &'g self,
+        view: PageView<'g>,
+        force_complete: bool,
+        safe_lsn: u64,
+    ) -> Result<PageView<'g>> {
+        if let Some(filter) = &self.tree.options.compaction_filter {
+            return self
+                .consolidate_leaf_page_with_filter(view, force_complete, safe_lsn, filter)
+                .await;
+        }
+        self.consolidate_page_impl(view, force_complete, |mut iter, complete| {
+            let range_deletes = iter.take_range_deletes();
+            let iter = MergingLeafPageIter::new(iter, safe_lsn);
+            // Deletes must be kept if there are older versions left in the chain.
+            let iter = if complete { iter } else { iter.keep_deletes() };
+            CollapsingLeafPageIter::new(iter, range_deletes, safe_lsn)
+        })
+        .await
+    }
+
+    /// Consolidates delta pages on the leaf page chain, and applies the filter
+    /// to the consolidated entries.
+    async fn consolidate_leaf_page_with_filter<'g>(
+        &'g self,
+        view: PageView<'g>,
+        force_complete: bool,
+        safe_lsn: u64,
+        filter: &CompactionFilter,
+    ) -> Result<PageView<'g>> {
+        let mut info = self
+            .collect_consolidation_info(&view, force_complete)
+            .await?;
+        let complete = info.last_page.chain_next() == 0;
+        // Overflow values are read to be passed to the filter.
+        let overflow_pages = if info.iter.has_overflow_values() {
+            collect_overflow_pages(&mut info.iter)
+        } else {
+            FxHashSet::default()
+        };
+        let overflow_values = self.read_overflow_values(&mut info.iter).await?;
+        let range_deletes = info.iter.take_range_deletes();
+        let mut iter = MergingLeafPageIter::new(info.iter, safe_lsn);
+        if !complete {
+            iter = iter.keep_deletes();
+        }
+        let mut iter = CollapsingLeafPageIter::new(iter, range_deletes, safe_lsn);
+        let items = iter
+            .by_ref()
+            .map(|(k, v)| {
+                let decision = match v {
+                    Value::Put(value) => filter.filter(k.raw, value),
+                    Value::Delete => FilterDecision::Keep,
+                    Value::Overflow(value) => {
+                        filter.filter(k.raw, overflow_values[value.raw_pages()])
+                    }
+                };
+                (k, v, decision)
+            })
+            .collect::<Vec<_>>();
+        let input_deletes = iter.input_deletes();
+        let mut entries = Vec::with_capacity(items.len());
+        for (i, (k, v, decision)) in items.iter().enumerate() {
+            let v = match decision {
+                FilterDecision::Keep => *v,
+                FilterDecision::ChangeValue(value) => Value::Put(value),
+                FilterDecision::Remove => {
+                    // Older versions of the key, in this page or left in the chain, must be
+                    // shadowed by a delete.
+                    let has_older = items
+                        .get(i + 1)
+                        .map_or(false, |(next, ..)| next.raw == k.raw);
+                    if complete && !has_older {
+                        continue;
+                    }
+                    Value::Delete
+                }
+            };
+            entries.push((*k, v));
+        }
+        // Overflow pages of the dropped or changed values are deallocated with the
+        // delta pages.
+        let mut page_addrs = info.page_addrs;
+        let mut overflow_ids = Vec::new();
+        if !overflow_pages.is_empty() {
+            let kept = collect_overflow_pages(&mut SliceIter::new(&entries));
+            for &(id, addr) in overflow_pages.difference(&kept) {
+                overflow_ids.push(id);
+                page_addrs.push(addr);
+            }
+        }
+        let builder = SortedPageBuilder::new(PageTier::Leaf, PageKind::Data)
+            .with_layout(self.tree.options.page_layout)
+            .with_iter(SliceIter::new(&entries));
+        let output_deletes = builder.num_deletes();
+        let view = self
+            .replace_consolidated_page(view, builder, &info.last_page, &page_addrs, &overflow_ids)
+            .await?;
+        self.tree
+            .stats
+            .tombstones
+            .consolidate(input_deletes, output_deletes);
+        Ok(view)
+    }
+
     async fn consolidate_page_impl<'g, F, I, K, V>(
         &'g self,
-        mut view: PageView<'g>,
+        view: PageView<'g>,
+        force_complete: bool,
         f: F,
     ) -> Result<PageView<'g>>
     where
-        F: Fn(MergingPageIter<'g, K, V>) -> I,
-        I: RewindableIterator<Item = (K, V)>,
+        F: Fn(MergingPageIter<'g, K, V>, bool) -> I,
+        I: ConsolidatingIter<Item = (K, V)>,
         K: SortedPageKey,
         V: SortedPageValue,
     {
         // Collect information for this consolidation.
-        let info = self.collect_consolidation_info(&view).await?;
-        let iter = f(info.iter);
-        let builder = SortedPageBuilder::new(view.page.tier(), PageKind::Data).with_iter(iter);
-        let mut txn = self.guard.begin().await;
+        let mut info = self
+            .collect_consolidation_info(&view, force_complete)
+            .await?;
+        let complete = info.last_page.chain_next() == 0;
+        let overflow_pages = if info.iter.has_overflow_values() {
+            collect_overflow_pages(&mut info.iter)
+        } else {
+            FxHashSet::default()
+        };
+        let mut iter = f(info.iter, complete);
+        // Overflow pages of the dropped values are deallocated with the delta pages.
+        let mut page_addrs = info.page_addrs;
+        let mut overflow_ids = Vec::new();
+        if !overflow_pages.is_empty() {
+            let kept = collect_overflow_pages(&mut iter);
+            for &(id, addr) in overflow_pages.difference(&kept) {
+                overflow_ids.push(id);
+                page_addrs.push(addr);
+            }
+        }
+        let builder = SortedPageBuilder::new(view.page.tier(), PageKind::Data)
+            .with_layout(self.tree.options.page_layout)
+            .with_iter(iter);
+        // Deletes are counted as the builder goes through the merged entries.
+        let input_deletes = builder.iter().map_or(0, ConsolidatingIter::input_deletes);
+        let output_deletes = builder.num_deletes();
+        let view = self
+            .replace_consolidated_page(view, builder, &info.last_page, &page_addrs, &overflow_ids)
+            .await?;
+        self.tree
+            .stats
+            .tombstones
+            .consolidate(input_deletes, output_deletes);
+        Ok(view)
+    }
+
+    /// Replaces the consolidated pages of the chain with a page built by the
+    /// builder.
+    async fn replace_consolidated_page<'g, I, K, V>(
+        &'g self,
+        mut view: PageView<'g>,
+        builder: SortedPageBuilder<I>,
+        last_page: &PageInfo,
+        page_addrs: &[u64],
+        overflow_ids: &[u64],
+    ) -> Result<PageView<'g>>
+    where
+        I: RewindableIterator<Item = (K, V)>,
+        K: SortedPageKey,
+        V: SortedPageValue,
+    {
+        let mut txn = self.guard.begin().await?;
         let (new_addr, mut new_page) = txn.alloc_page(builder.size()).await?;
         builder.build(&mut new_page);
         new_page.set_epoch(view.page.epoch());
-        new_page.set_chain_len(info.last_page.chain_len());
-        new_page.set_chain_next(info.last_page.chain_next());
-        // Update the page and deallocate the consolidated delta pages.
-        txn.replace_page(view.id, view.addr, new_addr, &info.page_addrs)
+        new_page.set_chain_len(last_page.chain_len());
+        new_page.set_chain_next(last_page.chain_next());
+        // Update the page and deallocate the consolidated delta pages, and the ids of
+        // the dropped overflow pages.
+        txn.dealloc_page_ids(overflow_ids.iter().copied());
+        txn.replace_page(view.id, view.addr, new_addr, page_addrs)
             .await
             .map(|_| {
                 trace!("consolidate page {:?}", view);
@@ -653,9 +2031,12 @@ impl<'a, E: Env> TreeTxn<'a, E> {
     }
 
     /// Collects some information to consolidate a page.
+    ///
+    /// If `force_complete` is false, only part of the chain may be collected.
     async fn collect_consolidation_info<'g, K, V>(
         &'g self,
         view: &PageView<'g>,
+        force_complete: bool,
     ) -> Result<ConsolidationInfo<'g, K, V>>
     where
         K: SortedPageKey,
@@ -667,6 +2048,8 @@ impl<'a, E: Env> TreeTxn<'a, E> {
         let mut last_page = view.page.clone();
         let mut page_addrs = Vec::with_capacity(chain_len);
         let mut range_limit = None;
+        let mut range_deletes = Vec::new();
+        let mut has_overflow_values = false;
         let opt = CacheOption::default().set_refill_cold_when_not_full(true);
         self.walk_page(
             view.addr,
@@ -675,11 +2058,15 @@ impl<'a, E: Env> TreeTxn<'a, E> {
                     PageKind::Data => {
                         // Inner pages can not do partial consolidations because of the
                         // placeholders. This is fine since inner pages
-                        // doesn't consolidate as often as leaf pages.
+                        // doesn't consolidate as often as leaf pages. Range deletes must
+                        // be collapsed into all versions they shadow, so they require
+                        // complete consolidations too.
                         if page.tier().is_leaf()
+                            && !force_complete
                             && builder.len() >= 2
                             && page_size < page.size() / 2
                             && range_limit.is_none()
+                            && range_deletes.is_empty()
                             && !self.should_consolidate_page(&page.info())
                         {
                             return true;
@@ -687,6 +2074,7 @@ impl<'a, E: Env> TreeTxn<'a, E> {
                         if let Some(ctoken) = ctoken {
                             ctoken.return_cache_as_old();
                         }
+                        has_overflow_values |= page.has_overflow_values();
                         builder.add(SortedPageIter::from(page));
                         page_size += page.size();
                     }
@@ -696,6 +2084,10 @@ impl<'a, E: Env> TreeTxn<'a, E> {
                             range_limit = Some(split_key);
                         }
                     }
+                    PageKind::RangeDelete => {
+                        range_deletes.push(range_delete_from_page(page));
+                    }
+                    PageKind::Overflow => {}
                 }
                 last_page = page.info();
                 page_addrs.push(addr);
@@ -704,7 +2096,9 @@ impl<'a, E: Env> TreeTxn<'a, E> {
             opt,
         )
         .await?;
-        let iter = MergingPageIter::new(builder.build(), range_limit);
+        let iter = MergingPageIter::new(builder.build(), range_limit)
+            .with_range_deletes(range_deletes)
+            .with_overflow_values(has_overflow_values);
         Ok(ConsolidationInfo {
             iter,
             last_page,
@@ -715,21 +2109,62 @@ impl<'a, E: Env> TreeTxn<'a, E> {
     /// Consolidates and restructures a page.
     async fn consolidate_and_restructure_page<'g>(&'g self, mut view: PageView<'g>) -> Result<()> {
         view = self.consolidate_page(view).await?;
-        // Try to split the page if it is too large.
-        if self.should_split_page(&view.page) {
+        // Try to split the page if it is too large. If it is too small, leave it
+        // to the merge job.
+        if self.should_split_page(&view).await? {
             let _ = self.split_page(view).await;
+        } else if self.should_merge_page(&view.page) {
+            if let Some(range) = view.range {
+                self.tree.merge_queue.push(view.id, range.start);
+            }
         }
         Ok(())
     }
 
     // Returns true if the page should be split.
-    fn should_split_page(&self, page: &PageInfo) -> bool {
-        let mut max_size = self.tree.options.page_size;
-        if page.tier().is_inner() {
+    //
+    // A base page is split if it exceeds either the size or the number of entries.
+    async fn should_split_page(&self, view: &PageView<'_>) -> Result<bool> {
+        let page = &view.page;
+        if page.chain_next() != 0 {
+            return Ok(false);
+        }
+        let options = &self.tree.options;
+        let (max_size, max_entries) = match page.tier() {
+            PageTier::Leaf => (options.page_size, options.leaf_target_entries),
             // Adjust the page size for inner pages.
-            max_size /= 2;
+            PageTier::Inner => (options.page_size / 2, options.index_target_entries),
+        };
+        if page.size() > max_size {
+            return Ok(true);
+        }
+        match max_entries {
+            Some(max_entries) => Ok(self.page_entries(view).await? > max_entries),
+            None => Ok(false),
         }
-        page.size() > max_size && page.chain_next() == 0
+    }
+
+    // Returns the number of entries in the base page.
+    async fn page_entries(&self, view: &PageView<'_>) -> Result<usize> {
+        let (page, _) = self
+            .guard
+            .read_page(view.addr, CacheOption::default())
+            .await?;
+        let len = match page.tier() {
+            PageTier::Leaf => ValuePageRef::from(page).len(),
+            PageTier::Inner => IndexPageRef::from(page).len(),
+        };
+        Ok(len)
+    }
+
+    // Returns true if the page is a leaf base page that should be merged with its
+    // right sibling.
+    fn should_merge_page(&self, page: &PageInfo) -> bool {
+        let min_size = self.tree.options.min_leaf_fill_ratio * self.tree.options.page_size as f64;
+        page.tier().is_leaf()
+            && page.kind().is_data()
+            && page.chain_next() == 0
+            && (page.size() as f64) < min_size
     }
 
     // Returns true if the page should be consolidated.
@@ -741,6 +2176,41 @@ impl<'a, E: Env> TreeTxn<'a, E> {
         }
         page.chain_len() as usize > max_chain_len.max(1)
     }
+
+    // Returns true if the delta pages of the page are too large compared to its
+    // base page.
+    //
+    // This sums the sizes recorded in the page infos of the chain, which is
+    // only done when the chain length reaches a power of two, so that the cost
+    // is amortized to a constant per write. It is best-effort: a page info
+    // that can't be read means the page is not consolidated for now.
+    fn has_large_deltas(&self, page: &PageInfo) -> bool {
+        let Some(ratio) = self.tree.options.consolidate_delta_bytes_ratio else {
+            return false;
+        };
+        if page.chain_len() < 2 || !page.chain_len().is_power_of_two() {
+            return false;
+        }
+        let mut delta_size = 0;
+        let mut page = page.clone();
+        while page.chain_next() != 0 {
+            if page.kind().is_data() {
+                delta_size += page.size();
+            }
+            page = match self.guard.read_page_info(page.chain_next()) {
+                Ok(info) => info,
+                Err(_) => return false,
+            };
+        }
+        delta_size as f64 > ratio * page.size() as f64
+    }
+}
+
+/// The entries of a leaf page and the range of keys it covers.
+pub(crate) struct LeafPage<'a> {
+    pub(crate) start: &'a [u8],
+    pub(crate) end: Option<&'a [u8]>,
+    pub(crate) entries: Vec<(&'a [u8], &'a [u8])>,
 }
 
 /// An iterator over leaf pages in a tree.
@@ -749,6 +2219,9 @@ pub(crate) struct TreeIter<'a, 't: 'a, E: Env> {
     options: ReadOptions,
     inner_iter: Option<MergingInnerPageIter<'a>>,
     inner_next: Option<&'a [u8]>,
+    // The number of pages to prefetch ahead and the pages already prefetched.
+    readahead: usize,
+    ahead: VecDeque<(&'a [u8], Index)>,
 }
 
 impl<'a, 't: 'a, E: Env> TreeIter<'a, 't, E> {
@@ -758,13 +2231,23 @@ impl<'a, 't: 'a, E: Env> TreeIter<'a, 't, E> {
             options,
             inner_iter: None,
             inner_next: Some(&[]),
+            readahead: 0,
+            ahead: VecDeque::new(),
         }
     }
 
-    async fn seek(&mut self, target: &[u8]) -> Result<PageIter<'_>> {
+    /// Prefetches the next `readahead` pages while the current page is read.
+    pub(crate) fn set_readahead(&mut self, readahead: usize) {
+        self.readahead = readahead;
+    }
+
+    pub(crate) async fn seek(&mut self, target: &[u8]) -> Result<PageIter<'_>> {
+        self.ahead.clear();
         let (view, parent) = self.txn.find_leaf(target).await?;
-        let iter = self.txn.iter_page(&view).await?;
-        let mut leaf_iter = PageIter::new(iter, self.options.max_lsn);
+        let mut iter = self.txn.iter_page(&view).await?;
+        let overflow_values = self.txn.read_overflow_values(&mut iter).await?;
+        let mut leaf_iter =
+            PageIter::new(iter, self.options.max_lsn).with_overflow_values(overflow_values);
         leaf_iter.seek(target);
         if let Some(parent) = parent {
             let iter = self.txn.iter_page(&parent).await?;
@@ -784,12 +2267,39 @@ impl<'a, 't: 'a, E: Env> TreeIter<'a, 't, E> {
     pub(crate) async fn next_page(&mut self) -> Result<Option<PageIter<'_>>> {
         let mut inner_next = self.inner_next.take();
         if let Some(inner_iter) = self.inner_iter.as_mut() {
-            if let Some((start, index)) = inner_iter.next() {
+            let next = match self.ahead.pop_front() {
+                Some(next) => {
+                    self.txn.tree.stats.readahead.prefetch_hits.inc();
+                    Some(next)
+                }
+                None => {
+                    if self.readahead > 0 {
+                        self.txn.tree.stats.readahead.prefetch_misses.inc();
+                    }
+                    inner_iter.next()
+                }
+            };
+            if let Some((start, index)) = next {
+                let mut prefetch_ids = Vec::new();
+                while self.ahead.len() < self.readahead {
+                    let Some(next) = inner_iter.next() else { break };
+                    prefetch_ids.push(next.1.id);
+                    self.ahead.push_back(next);
+                }
                 let view = self.txn.page_view(index.id, None).await?;
                 if view.page.epoch() == index.epoch {
-                    let iter = self.txn.iter_page(&view).await?;
+                    // Reads the page and prefetches the following pages concurrently.
+                    let (iter, _) = futures::future::join(
+                        self.txn.iter_page(&view),
+                        self.txn.prefetch_pages(&prefetch_ids),
+                    )
+                    .await;
+                    let mut iter = iter?;
+                    let overflow_values = self.txn.read_overflow_values(&mut iter).await?;
                     self.inner_next = inner_next;
-                    return Ok(Some(PageIter::new(iter, self.options.max_lsn)));
+                    let iter = PageIter::new(iter, self.options.max_lsn)
+                        .with_overflow_values(overflow_values);
+                    return Ok(Some(iter));
                 } else {
                     // The page epoch has changed, we need to restart from this.
                     inner_next = Some(start);
@@ -806,6 +2316,27 @@ impl<'a, 't: 'a, E: Env> TreeIter<'a, 't, E> {
     }
 }
 
+/// The outcome of a write to the tree.
+enum WriteOutcome {
+    /// The write is applied, with the previous value if it is read.
+    Written(Option<Vec<u8>>),
+    /// The write is rejected by its condition.
+    Rejected,
+}
+
+impl WriteOutcome {
+    /// Returns the previous value of a write without a condition.
+    ///
+    /// Only writes with a condition can be rejected, so a rejected write is
+    /// reported as [`Error::InvalidArgument`].
+    fn into_previous(self) -> Result<Option<Vec<u8>>> {
+        match self {
+            WriteOutcome::Written(previous) => Ok(previous),
+            WriteOutcome::Rejected => Err(Error::InvalidArgument),
+        }
+    }
+}
+
 struct ConsolidationInfo<'a, K, V>
 where
     K: SortedPageKey,
@@ -816,9 +2347,37 @@ where
     page_addrs: Vec<u64>,
 }
 
+/// Collects the ids and addresses of overflow pages referenced by the values
+/// of the iterator.
+///
+/// The iterator is rewound afterwards.
+fn collect_overflow_pages<I, K, V>(iter: &mut I) -> FxHashSet<(u64, u64)>
+where
+    I: RewindableIterator<Item = (K, V)>,
+    V: SortedPageValue,
+{
+    let mut pages = FxHashSet::default();
+    for (_, v) in &mut *iter {
+        if let Some(value) = v.as_overflow() {
+            pages.extend(value.pages());
+        }
+    }
+    iter.rewind();
+    pages
+}
+
 fn split_delta_from_page(page: PageRef<'_>) -> (&[u8], Index) {
     debug_assert!(page.kind().is_split());
     IndexPageRef::from(page)
         .get(0)
         .expect("split page delta must exist")
 }
+
+fn range_delete_from_page(page: PageRef<'_>) -> RangeDelete<'_> {
+    debug_assert!(page.kind().is_range_delete());
+    let (start, end) = RangeDeletePageRef::from(page)
+        .get(0)
+        .expect("range delete delta must exist");
+    let end = (!end.is_empty()).then_some(end);
+    RangeDelete::new(start.raw, end, start.lsn)
+}
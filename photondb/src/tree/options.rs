@@ -1,4 +1,7 @@
-use crate::PageStoreOptions;
+use std::{sync::Arc, time::Duration};
+
+use super::{CompactionFilter, MergeOperator};
+use crate::{page::PageLayout, Error, EvictCallback, PageHasher, PageStoreOptions, Result};
 
 /// Options to configure a table.
 #[non_exhaustive]
@@ -17,6 +20,143 @@ pub struct Options {
     /// Default: 4
     pub page_chain_length: usize,
 
+    /// The maximum number of entries in a leaf page before it is split.
+    ///
+    /// A leaf page is split when either its size exceeds
+    /// [`Options::page_size`] or its number of entries exceeds this, whichever
+    /// is hit first. Every retained version of a key counts as an entry. If
+    /// this is `None`, only the size is considered.
+    ///
+    /// Default: None
+    pub leaf_target_entries: Option<usize>,
+
+    /// The maximum number of child indexes in an inner page before it is
+    /// split.
+    ///
+    /// This works like [`Options::leaf_target_entries`] for inner pages,
+    /// which controls the fanout of the tree.
+    ///
+    /// Default: None
+    pub index_target_entries: Option<usize>,
+
+    /// The layout of items in pages built by consolidations and splits.
+    ///
+    /// Pages with either layout can be read, so this can be changed across
+    /// restarts.
+    ///
+    /// Default: [`PageLayout::Interleaved`]
+    pub page_layout: PageLayout,
+
+    /// The size above which a value is stored in overflow pages instead of
+    /// the data page.
+    ///
+    /// An overflow value is split into chunks of [`Options::page_size`] and
+    /// only the addresses of the chunks are stored with the key, so a single
+    /// large value doesn't bloat the pages around it. Values written before
+    /// this is set are still readable, but once overflow values are written,
+    /// older versions of the library can't read the table.
+    ///
+    /// Default: None
+    pub overflow_value_size: Option<usize>,
+
+    /// The ratio of accumulated delta bytes to the base page size before a
+    /// leaf page is consolidated.
+    ///
+    /// A page is consolidated if either its chain length exceeds
+    /// [`Options::page_chain_length`] or the size of its delta pages exceeds
+    /// `ratio * base_page_size`. If this is `None`, only the chain length is
+    /// considered. The size of delta pages is only checked each time the
+    /// chain length doubles, to keep writes cheap.
+    ///
+    /// Default: None
+    pub consolidate_delta_bytes_ratio: Option<f64>,
+
+    /// How much of a page chain is rebuilt when a page is consolidated.
+    ///
+    /// See [`ConsolidationMode`] for the trade-off between the modes.
+    ///
+    /// Default: [`ConsolidationMode::Lazy`]
+    pub consolidation_mode: ConsolidationMode,
+
+    /// How reads with a LSN larger than [`Table::latest_lsn`] are handled.
+    ///
+    /// This applies to all reads with a given LSN, including gets, scans,
+    /// cursors and pinned guards.
+    ///
+    /// Default: [`FutureLsnPolicy::Allow`]
+    ///
+    /// [`Table::latest_lsn`]: crate::Table::latest_lsn
+    pub future_lsn_policy: FutureLsnPolicy,
+
+    /// If true, separators of leaf page splits are truncated to the shortest
+    /// prefix that still separates the two halves.
+    ///
+    /// This makes index entries shorter when keys share long prefixes.
+    ///
+    /// Default: false
+    pub truncate_split_separator: bool,
+
+    /// The maximum number of scan results cached by [`Table::scan`].
+    ///
+    /// A cached scan is invalidated when an entry with a LSN not larger than
+    /// the scan LSN is written. If this is 0, scans are not cached.
+    ///
+    /// Default: 0
+    ///
+    /// [`Table::scan`]: crate::Table::scan
+    pub scan_cache_capacity: usize,
+
+    /// The ratio of the page size below which a consolidated leaf page is
+    /// merged with its right sibling.
+    ///
+    /// Two adjacent leaf pages under the same parent are merged only if their
+    /// combined size fits in one page. Merges run in a background job rather
+    /// than on writes, see [`Table::wait_for_merging`]. If this is 0, leaf
+    /// pages are never merged.
+    ///
+    /// Default: 0
+    ///
+    /// [`Table::wait_for_merging`]: crate::Table::wait_for_merging
+    pub min_leaf_fill_ratio: f64,
+
+    /// The ratio of entries kept in the left half when the rightmost leaf page
+    /// is split.
+    ///
+    /// Other pages are always split in the middle. A ratio like 0.9 leaves
+    /// the left pages fuller when keys are inserted in increasing order, since
+    /// they never grow again.
+    ///
+    /// Default: 0.5
+    pub split_ratio: f64,
+
+    /// The operator to apply operands written by [`Table::merge`].
+    ///
+    /// Merges are rejected if this is `None`.
+    ///
+    /// Default: None
+    ///
+    /// [`Table::merge`]: crate::Table::merge
+    pub merge_operator: Option<Arc<dyn MergeOperator>>,
+
+    /// The filter to drop or transform entries when leaf pages are
+    /// consolidated.
+    ///
+    /// Use [`Table::compact`] to apply the filter to all entries.
+    ///
+    /// Default: None
+    ///
+    /// [`Table::compact`]: crate::Table::compact
+    pub compaction_filter: Option<CompactionFilter>,
+
+    /// The backoff between retries of writes that conflict with concurrent
+    /// updates to the same page.
+    ///
+    /// If this is `None`, conflicted writes are retried immediately until
+    /// they succeed.
+    ///
+    /// Default: None
+    pub cas_backoff: Option<CasBackoff>,
+
     /// Options for the underlying page store.
     pub page_store: PageStoreOptions,
 }
@@ -26,11 +166,361 @@ impl Default for Options {
         Self {
             page_size: 8 << 10,
             page_chain_length: 4,
+            leaf_target_entries: None,
+            index_target_entries: None,
+            page_layout: PageLayout::Interleaved,
+            overflow_value_size: None,
+            consolidate_delta_bytes_ratio: None,
+            consolidation_mode: ConsolidationMode::Lazy,
+            future_lsn_policy: FutureLsnPolicy::Allow,
+            truncate_split_separator: false,
+            scan_cache_capacity: 0,
+            min_leaf_fill_ratio: 0.0,
+            split_ratio: 0.5,
+            merge_operator: None,
+            compaction_filter: None,
+            cas_backoff: None,
             page_store: PageStoreOptions::default(),
         }
     }
 }
 
+impl Options {
+    /// Returns a builder to construct validated options.
+    pub fn builder() -> OptionsBuilder {
+        OptionsBuilder::default()
+    }
+
+    /// Checks that the options are consistent with each other.
+    pub(crate) fn validate(&self) -> Result<()> {
+        let invalid = |msg: String| Err(Error::InvalidOptions(msg));
+        let store = &self.page_store;
+        if self.page_size == 0 {
+            return invalid("page_size must be positive".into());
+        }
+        // A page with less than two entries can't be split.
+        if let Some(entries) = self.leaf_target_entries.filter(|&n| n < 2) {
+            return invalid(format!("leaf_target_entries {entries} must be at least 2"));
+        }
+        if let Some(entries) = self.index_target_entries.filter(|&n| n < 2) {
+            return invalid(format!("index_target_entries {entries} must be at least 2"));
+        }
+        if self.overflow_value_size == Some(0) {
+            return invalid("overflow_value_size must be positive".into());
+        }
+        if let Some(ratio) = self.consolidate_delta_bytes_ratio {
+            if ratio.is_nan() || ratio < 0.0 {
+                return invalid(format!(
+                    "consolidate_delta_bytes_ratio {ratio} must not be negative"
+                ));
+            }
+        }
+        let ratio = self.min_leaf_fill_ratio;
+        if !(0.0..=1.0).contains(&ratio) {
+            return invalid(format!(
+                "min_leaf_fill_ratio {ratio} must be between 0 and 1"
+            ));
+        }
+        let ratio = self.split_ratio;
+        if !(ratio > 0.0 && ratio < 1.0) {
+            return invalid(format!("split_ratio {ratio} must be between 0 and 1"));
+        }
+        if let Some(backoff) = &self.cas_backoff {
+            if backoff.initial_yields > backoff.max_yields {
+                return invalid(format!(
+                    "cas_backoff initial_yields {} must not be larger than max_yields {}",
+                    backoff.initial_yields, backoff.max_yields
+                ));
+            }
+        }
+        if !store.write_buffer_capacity.is_power_of_two() {
+            return invalid(format!(
+                "write_buffer_capacity {} must be a power of two",
+                store.write_buffer_capacity
+            ));
+        }
+        if store.max_open_files == 0 || store.max_open_files < -1 {
+            return invalid(format!(
+                "max_open_files {} must be positive or -1",
+                store.max_open_files
+            ));
+        }
+        if store.max_write_buffers == 0 {
+            return invalid("max_write_buffers must be positive".into());
+        }
+        if store.max_unflushed_files == Some(0) {
+            return invalid("max_unflushed_files must be positive".into());
+        }
+        if store.index_cache_capacity == Some(0) {
+            return invalid("index_cache_capacity must be positive".into());
+        }
+        if let Some(budget) = store.memory_budget {
+            let writebuf_size = store.max_write_buffers * store.write_buffer_capacity as usize;
+            let reserved = writebuf_size + store.index_cache_capacity.unwrap_or(0);
+            if budget <= reserved {
+                return invalid(format!(
+                    "memory_budget {budget} must be larger than the size of write buffers and index cache {reserved}"
+                ));
+            }
+        } else if store.data_cache_capacity() < self.page_size {
+            return invalid(format!(
+                "cache_capacity {} without index_cache_capacity {} must not be smaller than page_size {}",
+                store.cache_capacity,
+                store.index_cache_capacity.unwrap_or(0),
+                self.page_size
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// A bounded exponential backoff between retries of conflicted writes.
+///
+/// The task yields `initial_yields` times before the first retry, and the
+/// number of yields doubles on each retry up to `max_yields`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct CasBackoff {
+    /// The number of yields before the first retry.
+    pub initial_yields: u32,
+    /// The maximum number of yields between two retries.
+    pub max_yields: u32,
+    /// The maximum number of retries before a write fails with
+    /// [`Error::Busy`], or 0 to retry without limit.
+    pub max_retries: u32,
+}
+
+impl CasBackoff {
+    /// Returns the number of yields before the `retry`-th retry, starting
+    /// from 1.
+    pub(super) fn yields(&self, retry: u32) -> u32 {
+        let shift = retry.saturating_sub(1).min(31);
+        self.initial_yields
+            .saturating_mul(1 << shift)
+            .min(self.max_yields)
+    }
+}
+
+impl Default for CasBackoff {
+    fn default() -> Self {
+        Self {
+            initial_yields: 1,
+            max_yields: 64,
+            max_retries: 0,
+        }
+    }
+}
+
+/// How much of a page chain is rebuilt when a page is consolidated.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ConsolidationMode {
+    /// Only the delta pages are folded while they are small compared to the
+    /// base page, which is kept as it is.
+    ///
+    /// Consolidations write less, but deletes are kept in the folded delta
+    /// page and reads have to go through it until the base page is rebuilt.
+    #[default]
+    Lazy,
+    /// The whole chain, including the base page, is rebuilt into a compact
+    /// base page on every consolidation.
+    ///
+    /// Deletes and obsolete versions are dropped as soon as possible, which
+    /// keeps base pages small and reads short, but every consolidation
+    /// rewrites the whole page.
+    Full,
+}
+
+/// How reads with a LSN larger than the latest LSN of the table are handled.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum FutureLsnPolicy {
+    /// The read is served with the LSN as is.
+    ///
+    /// It sees all entries with LSNs not larger than the LSN, including those
+    /// of writes in flight that are not covered by the latest LSN yet, so
+    /// reading twice with the same LSN may return different results.
+    #[default]
+    Allow,
+    /// The read is rejected with [`Error::InvalidLsn`].
+    Strict,
+}
+
+/// A builder to construct [`Options`] with validation.
+#[derive(Clone, Debug, Default)]
+pub struct OptionsBuilder {
+    options: Options,
+}
+
+impl OptionsBuilder {
+    /// Sets [`Options::page_size`].
+    pub fn page_size(mut self, page_size: usize) -> Self {
+        self.options.page_size = page_size;
+        self
+    }
+
+    /// Sets [`Options::page_chain_length`].
+    pub fn page_chain_length(mut self, page_chain_length: usize) -> Self {
+        self.options.page_chain_length = page_chain_length;
+        self
+    }
+
+    /// Sets [`Options::leaf_target_entries`].
+    pub fn leaf_target_entries(mut self, entries: usize) -> Self {
+        self.options.leaf_target_entries = Some(entries);
+        self
+    }
+
+    /// Sets [`Options::index_target_entries`].
+    pub fn index_target_entries(mut self, entries: usize) -> Self {
+        self.options.index_target_entries = Some(entries);
+        self
+    }
+
+    /// Sets [`Options::page_layout`].
+    pub fn page_layout(mut self, layout: PageLayout) -> Self {
+        self.options.page_layout = layout;
+        self
+    }
+
+    /// Sets [`Options::overflow_value_size`].
+    pub fn overflow_value_size(mut self, size: usize) -> Self {
+        self.options.overflow_value_size = Some(size);
+        self
+    }
+
+    /// Sets [`Options::consolidate_delta_bytes_ratio`].
+    pub fn consolidate_delta_bytes_ratio(mut self, ratio: f64) -> Self {
+        self.options.consolidate_delta_bytes_ratio = Some(ratio);
+        self
+    }
+
+    /// Sets [`Options::consolidation_mode`].
+    pub fn consolidation_mode(mut self, mode: ConsolidationMode) -> Self {
+        self.options.consolidation_mode = mode;
+        self
+    }
+
+    /// Sets [`Options::future_lsn_policy`].
+    pub fn future_lsn_policy(mut self, policy: FutureLsnPolicy) -> Self {
+        self.options.future_lsn_policy = policy;
+        self
+    }
+
+    /// Sets [`Options::truncate_split_separator`].
+    pub fn truncate_split_separator(mut self, truncate: bool) -> Self {
+        self.options.truncate_split_separator = truncate;
+        self
+    }
+
+    /// Sets [`Options::scan_cache_capacity`].
+    pub fn scan_cache_capacity(mut self, capacity: usize) -> Self {
+        self.options.scan_cache_capacity = capacity;
+        self
+    }
+
+    /// Sets [`Options::min_leaf_fill_ratio`].
+    pub fn min_leaf_fill_ratio(mut self, ratio: f64) -> Self {
+        self.options.min_leaf_fill_ratio = ratio;
+        self
+    }
+
+    /// Sets [`Options::split_ratio`].
+    pub fn split_ratio(mut self, ratio: f64) -> Self {
+        self.options.split_ratio = ratio;
+        self
+    }
+
+    /// Sets [`Options::merge_operator`].
+    pub fn merge_operator(mut self, operator: Arc<dyn MergeOperator>) -> Self {
+        self.options.merge_operator = Some(operator);
+        self
+    }
+
+    /// Sets [`Options::compaction_filter`].
+    pub fn compaction_filter(mut self, filter: CompactionFilter) -> Self {
+        self.options.compaction_filter = Some(filter);
+        self
+    }
+
+    /// Sets [`Options::cas_backoff`].
+    pub fn cas_backoff(mut self, backoff: CasBackoff) -> Self {
+        self.options.cas_backoff = Some(backoff);
+        self
+    }
+
+    /// Sets [`Options::page_store`].
+    pub fn page_store(mut self, page_store: PageStoreOptions) -> Self {
+        self.options.page_store = page_store;
+        self
+    }
+
+    /// Sets [`PageStoreOptions::write_buffer_capacity`].
+    pub fn write_buffer_capacity(mut self, capacity: u32) -> Self {
+        self.options.page_store.write_buffer_capacity = capacity;
+        self
+    }
+
+    /// Sets [`PageStoreOptions::max_write_buffers`].
+    pub fn max_write_buffers(mut self, max_write_buffers: usize) -> Self {
+        self.options.page_store.max_write_buffers = max_write_buffers;
+        self
+    }
+
+    /// Sets [`PageStoreOptions::max_unflushed_files`].
+    pub fn max_unflushed_files(mut self, max_unflushed_files: usize) -> Self {
+        self.options.page_store.max_unflushed_files = Some(max_unflushed_files);
+        self
+    }
+
+    /// Sets [`PageStoreOptions::writebuf_disabled`].
+    pub fn writebuf_disabled(mut self, disabled: bool) -> Self {
+        self.options.page_store.writebuf_disabled = disabled;
+        self
+    }
+
+    /// Sets [`PageStoreOptions::memory_budget`].
+    pub fn memory_budget(mut self, budget: usize) -> Self {
+        self.options.page_store.memory_budget = Some(budget);
+        self
+    }
+
+    /// Sets [`PageStoreOptions::hasher`].
+    pub fn hasher(mut self, hasher: PageHasher) -> Self {
+        self.options.page_store.hasher = hasher;
+        self
+    }
+
+    /// Sets [`PageStoreOptions::on_evict`].
+    pub fn on_evict(mut self, on_evict: EvictCallback) -> Self {
+        self.options.page_store.on_evict = Some(on_evict);
+        self
+    }
+
+    /// Sets [`PageStoreOptions::cache_capacity`].
+    pub fn cache_capacity(mut self, capacity: usize) -> Self {
+        self.options.page_store.cache_capacity = capacity;
+        self
+    }
+
+    /// Sets [`PageStoreOptions::index_cache_capacity`].
+    pub fn index_cache_capacity(mut self, capacity: usize) -> Self {
+        self.options.page_store.index_cache_capacity = Some(capacity);
+        self
+    }
+
+    /// Sets [`PageStoreOptions::stats_log_interval`].
+    pub fn stats_log_interval(mut self, interval: Duration) -> Self {
+        self.options.page_store.stats_log_interval = interval;
+        self
+    }
+
+    /// Builds the options.
+    ///
+    /// Returns [`Error::InvalidOptions`] if the options are inconsistent.
+    pub fn build(self) -> Result<Options> {
+        self.options.validate()?;
+        Ok(self.options)
+    }
+}
+
 /// Options to configure the behavior of reads.
 #[non_exhaustive]
 #[derive(Clone, Debug)]
@@ -61,3 +551,92 @@ impl Default for ReadOptions {
 #[non_exhaustive]
 #[derive(Clone, Debug)]
 pub struct WriteOptions {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn build_options() {
+        let options = Options::builder()
+            .page_size(4 << 10)
+            .cache_capacity(1 << 20)
+            .build()
+            .unwrap();
+        assert_eq!(options.page_size, 4 << 10);
+        assert_eq!(options.page_store.cache_capacity, 1 << 20);
+
+        let err = Options::builder()
+            .write_buffer_capacity(3 << 20)
+            .build()
+            .unwrap_err();
+        assert!(matches!(err, Error::InvalidOptions(_)));
+        assert!(err.to_string().contains("write_buffer_capacity"));
+
+        let err = Options::builder()
+            .page_size(8 << 10)
+            .cache_capacity(4 << 10)
+            .build()
+            .unwrap_err();
+        assert!(err.to_string().contains("cache_capacity"));
+
+        let err = Options::builder()
+            .page_size(8 << 10)
+            .cache_capacity(1 << 20)
+            .index_cache_capacity(1 << 20)
+            .build()
+            .unwrap_err();
+        assert!(err.to_string().contains("index_cache_capacity"));
+
+        let err = Options::builder()
+            .consolidate_delta_bytes_ratio(-1.0)
+            .build()
+            .unwrap_err();
+        assert!(err.to_string().contains("consolidate_delta_bytes_ratio"));
+
+        let err = Options::builder()
+            .min_leaf_fill_ratio(1.5)
+            .build()
+            .unwrap_err();
+        assert!(err.to_string().contains("min_leaf_fill_ratio"));
+
+        let err = Options::builder().split_ratio(1.0).build().unwrap_err();
+        assert!(err.to_string().contains("split_ratio"));
+
+        let err = Options::builder()
+            .leaf_target_entries(1)
+            .build()
+            .unwrap_err();
+        assert!(err.to_string().contains("leaf_target_entries"));
+
+        let err = Options::builder()
+            .write_buffer_capacity(1 << 20)
+            .max_write_buffers(8)
+            .memory_budget(8 << 20)
+            .build()
+            .unwrap_err();
+        assert!(err.to_string().contains("memory_budget"));
+
+        let err = Options::builder()
+            .cas_backoff(CasBackoff {
+                initial_yields: 8,
+                max_yields: 4,
+                max_retries: 0,
+            })
+            .build()
+            .unwrap_err();
+        assert!(err.to_string().contains("cas_backoff"));
+    }
+
+    #[test]
+    fn cas_backoff_yields() {
+        let backoff = CasBackoff {
+            initial_yields: 2,
+            max_yields: 20,
+            max_retries: 0,
+        };
+        let yields: Vec<_> = (1..=5).map(|retry| backoff.yields(retry)).collect();
+        assert_eq!(yields, [2, 4, 8, 16, 20]);
+        assert_eq!(backoff.yields(u32::MAX), 20);
+    }
+}
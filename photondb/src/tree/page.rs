@@ -1,3 +1,7 @@
+use std::{cmp::Ordering, collections::VecDeque};
+
+use rustc_hash::FxHashMap;
+
 use crate::{page::*, page_store::*};
 
 /// The root id is fixed to the minimal id in the page store.
@@ -15,11 +19,46 @@ pub(super) struct PageView<'a> {
     pub(super) range: Option<Range<'a>>,
 }
 
+/// A range of keys deleted at an LSN.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub(super) struct RangeDelete<'a> {
+    start: &'a [u8],
+    end: Option<&'a [u8]>,
+    lsn: u64,
+}
+
+impl<'a> RangeDelete<'a> {
+    pub(super) fn new(start: &'a [u8], end: Option<&'a [u8]>, lsn: u64) -> Self {
+        Self { start, end, lsn }
+    }
+
+    pub(super) fn lsn(&self) -> u64 {
+        self.lsn
+    }
+
+    /// Returns true if the raw key is in the range.
+    pub(super) fn covers(&self, raw: &[u8]) -> bool {
+        raw >= self.start && self.end.map_or(true, |end| raw < end)
+    }
+
+    /// Returns true if the version of the key is deleted for reads at
+    /// `read_lsn`.
+    ///
+    /// A range delete shadows the versions at or below its LSN.
+    pub(super) fn shadows(&self, key: &Key<'_>, read_lsn: u64) -> bool {
+        key.lsn <= self.lsn && self.lsn <= read_lsn && self.covers(key.raw)
+    }
+}
+
+/// The content of overflow values in a page, keyed by their encoded pages.
+pub(super) type OverflowValues<'a> = FxHashMap<&'a [u8], &'a [u8]>;
+
 /// An iterator over user entries in a page.
 pub struct PageIter<'a> {
     iter: MergingPageIter<'a, Key<'a>, Value<'a>>,
     read_lsn: u64,
     last_raw: Option<&'a [u8]>,
+    overflow_values: OverflowValues<'a>,
 }
 
 impl<'a> PageIter<'a> {
@@ -28,9 +67,18 @@ impl<'a> PageIter<'a> {
             iter,
             read_lsn,
             last_raw: None,
+            overflow_values: OverflowValues::default(),
         }
     }
 
+    /// Sets the overflow values read from the page.
+    ///
+    /// This is required if the page has overflow values.
+    pub(super) fn with_overflow_values(mut self, overflow_values: OverflowValues<'a>) -> Self {
+        self.overflow_values = overflow_values;
+        self
+    }
+
     /// Positions the iterator at the first item that is at or after `target`.
     pub fn seek(&mut self, target: &[u8]) {
         self.iter.seek(&Key::new(target, self.read_lsn));
@@ -52,8 +100,20 @@ impl<'a> Iterator for PageIter<'a> {
                 }
             }
             self.last_raw = Some(k.raw);
-            if let Value::Put(value) = v {
-                return Some((k.raw, value));
+            if self.iter.is_range_deleted(&k, self.read_lsn) {
+                continue;
+            }
+            match v {
+                Value::Put(value) => return Some((k.raw, value)),
+                Value::Delete => {}
+                Value::Overflow(value) => {
+                    let value = self
+                        .overflow_values
+                        .get(value.raw_pages())
+                        .copied()
+                        .expect("overflow values must be read before iteration");
+                    return Some((k.raw, value));
+                }
             }
         }
         None
@@ -67,6 +127,9 @@ where
 {
     iter: MergingIter<SortedPageIter<'a, K, V>>,
     range_limit: Option<&'a [u8]>,
+    range_deletes: Vec<RangeDelete<'a>>,
+    has_overflow_values: bool,
+    num_deletes: usize,
 }
 
 impl<'a, K, V> MergingPageIter<'a, K, V>
@@ -78,7 +141,54 @@ where
         iter: MergingIter<SortedPageIter<'a, K, V>>,
         range_limit: Option<&'a [u8]>,
     ) -> Self {
-        Self { iter, range_limit }
+        Self {
+            iter,
+            range_limit,
+            range_deletes: Vec::new(),
+            has_overflow_values: false,
+            num_deletes: 0,
+        }
+    }
+
+    /// Marks that some of the pages have overflow values.
+    pub(super) fn with_overflow_values(mut self, has_overflow_values: bool) -> Self {
+        self.has_overflow_values = has_overflow_values;
+        self
+    }
+
+    /// Returns true if some of the pages have overflow values.
+    pub(super) fn has_overflow_values(&self) -> bool {
+        self.has_overflow_values
+    }
+
+    /// Sets the range deletes collected from the page chain.
+    pub(super) fn with_range_deletes(mut self, range_deletes: Vec<RangeDelete<'a>>) -> Self {
+        self.range_deletes = range_deletes;
+        self
+    }
+
+    /// Takes the range deletes out of the iterator.
+    pub(super) fn take_range_deletes(&mut self) -> Vec<RangeDelete<'a>> {
+        std::mem::take(&mut self.range_deletes)
+    }
+
+    /// Returns the number of deletes returned since the iterator was rewound
+    /// or positioned.
+    pub(super) fn num_deletes(&self) -> usize {
+        self.num_deletes
+    }
+}
+
+impl<'a, V> MergingPageIter<'a, Key<'a>, V>
+where
+    V: SortedPageValue,
+{
+    /// Returns true if the version of the key is deleted by any range delete
+    /// of the page for reads at `read_lsn`.
+    pub(super) fn is_range_deleted(&self, key: &Key<'_>, read_lsn: u64) -> bool {
+        self.range_deletes
+            .iter()
+            .any(|range| range.shadows(key, read_lsn))
     }
 }
 
@@ -98,6 +208,9 @@ where
                 return None;
             }
         }
+        if v.is_delete() {
+            self.num_deletes += 1;
+        }
         Some((k, v))
     }
 }
@@ -109,6 +222,7 @@ where
 {
     fn rewind(&mut self) {
         self.iter.rewind();
+        self.num_deletes = 0;
     }
 }
 
@@ -117,6 +231,7 @@ where
     V: SortedPageValue,
 {
     fn seek(&mut self, target: &Key<'_>) -> bool {
+        self.num_deletes = 0;
         self.iter.seek(target)
     }
 }
@@ -126,16 +241,26 @@ where
     V: SortedPageValue,
 {
     fn seek(&mut self, target: &[u8]) -> bool {
+        self.num_deletes = 0;
         self.iter.seek(target)
     }
 }
 
+/// An iterator that consolidates the entries of a page chain.
+pub(super) trait ConsolidatingIter: RewindableIterator {
+    /// Returns the number of deletes read from the page chain since the
+    /// iterator was rewound.
+    fn input_deletes(&self) -> usize;
+}
+
 /// An iterator that merges multiple leaf delta pages for consolidation.
 pub(super) struct MergingLeafPageIter<'a> {
     iter: MergingPageIter<'a, Key<'a>, Value<'a>>,
     safe_lsn: u64,
     last_raw: Option<&'a [u8]>,
+    last_lsn: u64,
     skip_same_raw: bool,
+    keep_deletes: bool,
 }
 
 impl<'a> MergingLeafPageIter<'a> {
@@ -144,9 +269,21 @@ impl<'a> MergingLeafPageIter<'a> {
             iter,
             safe_lsn,
             last_raw: None,
+            last_lsn: 0,
             skip_same_raw: false,
+            keep_deletes: false,
         }
     }
+
+    /// Keeps the deletes that are visible to the safe LSN.
+    ///
+    /// This is required if the iterator doesn't cover all versions of the
+    /// page, otherwise the deleted versions left in the page would be visible
+    /// again.
+    pub(super) fn keep_deletes(mut self) -> Self {
+        self.keep_deletes = true;
+        self
+    }
 }
 
 impl<'a> Iterator for MergingLeafPageIter<'a> {
@@ -156,10 +293,12 @@ impl<'a> Iterator for MergingLeafPageIter<'a> {
         for (k, v) in &mut self.iter {
             if let Some(last) = self.last_raw {
                 if k.raw == last {
-                    // Skip versions of the same raw.
-                    if self.skip_same_raw {
+                    // Skip versions of the same raw, and versions shadowed by newer deltas
+                    // of the same key.
+                    if self.skip_same_raw || k.lsn == self.last_lsn {
                         continue;
                     }
+                    self.last_lsn = k.lsn;
                     // Output versions that are visible to the safe LSN.
                     if k.lsn > self.safe_lsn {
                         return Some((k, v));
@@ -167,18 +306,19 @@ impl<'a> Iterator for MergingLeafPageIter<'a> {
                     // This is the oldest version visible to the safe LSN.
                     self.skip_same_raw = true;
                     match v {
-                        Value::Put(_) => return Some((k, v)),
-                        Value::Delete => continue,
+                        Value::Delete if !self.keep_deletes => continue,
+                        _ => return Some((k, v)),
                     }
                 }
             }
             // This is the latest version of this raw.
             self.last_raw = Some(k.raw);
+            self.last_lsn = k.lsn;
             self.skip_same_raw = k.lsn <= self.safe_lsn;
             match v {
                 // If the latest version is a delete and all older versions are not visible to the
                 // safe LSN, we can skip all of them.
-                Value::Delete if k.lsn <= self.safe_lsn => {
+                Value::Delete if k.lsn <= self.safe_lsn && !self.keep_deletes => {
                     continue;
                 }
                 _ => return Some((k, v)),
@@ -188,6 +328,12 @@ impl<'a> Iterator for MergingLeafPageIter<'a> {
     }
 }
 
+impl<'a> ConsolidatingIter for MergingLeafPageIter<'a> {
+    fn input_deletes(&self) -> usize {
+        self.iter.num_deletes()
+    }
+}
+
 impl<'a> RewindableIterator for MergingLeafPageIter<'a> {
     fn rewind(&mut self) {
         self.iter.rewind();
@@ -204,17 +350,130 @@ impl<'a> SeekableIterator<Key<'_>> for MergingLeafPageIter<'a> {
     }
 }
 
+/// An iterator that collapses range deletes into the entries of a leaf page
+/// for consolidation.
+///
+/// Versions deleted for all reads at or after the safe LSN are dropped. Other
+/// deleted versions are shadowed by deletes at the LSNs of the range deletes,
+/// so the range deletes themselves can be dropped.
+pub(super) struct CollapsingLeafPageIter<'a> {
+    iter: MergingLeafPageIter<'a>,
+    range_deletes: Vec<RangeDelete<'a>>,
+    safe_lsn: u64,
+    peeked: Option<(Key<'a>, Value<'a>)>,
+    output: VecDeque<(Key<'a>, Value<'a>)>,
+}
+
+impl<'a> CollapsingLeafPageIter<'a> {
+    pub(super) fn new(
+        iter: MergingLeafPageIter<'a>,
+        range_deletes: Vec<RangeDelete<'a>>,
+        safe_lsn: u64,
+    ) -> Self {
+        Self {
+            iter,
+            range_deletes,
+            safe_lsn,
+            peeked: None,
+            output: VecDeque::new(),
+        }
+    }
+
+    fn collapse(&mut self, raw: &'a [u8], versions: Vec<(Key<'a>, Value<'a>)>) {
+        let mut lsns = self
+            .range_deletes
+            .iter()
+            .filter(|range| range.covers(raw))
+            .map(|range| range.lsn)
+            .collect::<Vec<_>>();
+        lsns.sort_unstable_by(|a, b| b.cmp(a));
+        lsns.dedup();
+        // Versions at or below this LSN are not visible to any read.
+        let dropped_lsn = lsns.iter().copied().find(|&lsn| lsn <= self.safe_lsn);
+        let mut pending = lsns
+            .into_iter()
+            .filter(|&lsn| lsn > self.safe_lsn)
+            .peekable();
+        for (k, v) in versions {
+            if dropped_lsn.map_or(false, |lsn| k.lsn <= lsn) {
+                break;
+            }
+            let mut shadowed = false;
+            while let Some(lsn) = pending.next_if(|&lsn| lsn >= k.lsn) {
+                self.output.push_back((Key::new(raw, lsn), Value::Delete));
+                shadowed |= lsn == k.lsn;
+            }
+            if !shadowed {
+                self.output.push_back((k, v));
+            }
+        }
+    }
+}
+
+impl<'a> Iterator for CollapsingLeafPageIter<'a> {
+    type Item = (Key<'a>, Value<'a>);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(item) = self.output.pop_front() {
+                return Some(item);
+            }
+            let (k, v) = self.peeked.take().or_else(|| self.iter.next())?;
+            if !self.range_deletes.iter().any(|range| range.covers(k.raw)) {
+                return Some((k, v));
+            }
+            let mut versions = vec![(k, v)];
+            for (next_k, next_v) in &mut self.iter {
+                if next_k.raw != k.raw {
+                    self.peeked = Some((next_k, next_v));
+                    break;
+                }
+                versions.push((next_k, next_v));
+            }
+            self.collapse(k.raw, versions);
+        }
+    }
+}
+
+impl<'a> ConsolidatingIter for CollapsingLeafPageIter<'a> {
+    fn input_deletes(&self) -> usize {
+        self.iter.input_deletes()
+    }
+}
+
+impl<'a> RewindableIterator for CollapsingLeafPageIter<'a> {
+    fn rewind(&mut self) {
+        self.iter.rewind();
+        self.peeked = None;
+        self.output.clear();
+    }
+}
+
+/// Returns the shortest prefix of `right` that is greater than `left`.
+///
+/// `left` must be less than `right`, so that the returned separator `sep`
+/// satisfies `left < sep <= right`.
+pub(super) fn shortest_separator<'a>(left: &[u8], right: &'a [u8]) -> &'a [u8] {
+    debug_assert!(left < right);
+    let common = left.iter().zip(right).take_while(|(a, b)| a == b).count();
+    &right[..(common + 1).min(right.len())]
+}
+
+type IndexPrecedence = fn(&Index, &Index) -> Ordering;
+
 /// An iterator that merges multiple inner delta pages for consolidation.
 pub(super) struct MergingInnerPageIter<'a> {
-    iter: MergingPageIter<'a, &'a [u8], Index>,
-    last_raw: Option<&'a [u8]>,
+    iter: MergeIter<MergingPageIter<'a, &'a [u8], Index>, IndexPrecedence>,
 }
 
 impl<'a> MergingInnerPageIter<'a> {
     pub(super) fn new(iter: MergingPageIter<'a, &'a [u8], Index>) -> Self {
+        // Placeholders are overwritten by any older index of the same key.
+        fn precedence(a: &Index, b: &Index) -> Ordering {
+            (*a != NULL_INDEX).cmp(&(*b != NULL_INDEX))
+        }
         Self {
-            iter,
-            last_raw: None,
+            iter: MergeIter::new(iter, precedence),
         }
     }
 }
@@ -228,29 +487,26 @@ impl<'a> Iterator for MergingInnerPageIter<'a> {
             if index == NULL_INDEX {
                 continue;
             }
-            // Skip overwritten indexes
-            if let Some(last) = self.last_raw {
-                if start == last {
-                    continue;
-                }
-            }
-            self.last_raw = Some(start);
             return Some((start, index));
         }
         None
     }
 }
 
+impl<'a> ConsolidatingIter for MergingInnerPageIter<'a> {
+    fn input_deletes(&self) -> usize {
+        0
+    }
+}
+
 impl<'a> RewindableIterator for MergingInnerPageIter<'a> {
     fn rewind(&mut self) {
         self.iter.rewind();
-        self.last_raw = None;
     }
 }
 
 impl<'a> SeekableIterator<[u8]> for MergingInnerPageIter<'a> {
     fn seek(&mut self, target: &[u8]) -> bool {
-        self.last_raw = None;
         self.iter.seek(target)
     }
 }
@@ -326,6 +582,30 @@ mod tests {
         }
     }
 
+    #[test]
+    fn page_iter_with_range_deletes() {
+        let data = vec![
+            (Key::new(&[1], 1), Value::Put(&[1])),
+            (Key::new(&[3], 4), Value::Put(&[4])),
+            (Key::new(&[3], 1), Value::Put(&[1])),
+            (Key::new(&[5], 2), Value::Put(&[2])),
+        ];
+        let owned_page = OwnedSortedPage::from_slice(&data);
+        let range_deletes = vec![RangeDelete::new(&[2], Some(&[5]), 2)];
+
+        let lsn_expect = [
+            (1, as_slice(&[([1], [1]), ([3], [1])])),
+            (2, as_slice(&[([1], [1]), ([5], [2])])),
+            (4, as_slice(&[([1], [1]), ([3], [4]), ([5], [2])])),
+        ];
+        for (lsn, expect) in lsn_expect {
+            let merging_iter = build_merging_iter([owned_page.as_iter()], None)
+                .with_range_deletes(range_deletes.clone());
+            let iter = PageIter::new(merging_iter, lsn);
+            assert_eq!(iter.collect::<Vec<_>>(), expect);
+        }
+    }
+
     #[test]
     fn merging_page_iter() {
         let data = raw_slice(&[[1], [3], [5]]);
@@ -399,6 +679,85 @@ mod tests {
             assert_eq!(iter.next(), Some(data[0]));
             assert_eq!(iter.next(), Some(data[1]));
         }
+
+        {
+            // A newer delta of the same key shadows the older one.
+            let newer = OwnedSortedPage::from_slice(&[(Key::new(&[1], 2), Value::Put(&[4]))]);
+            let merging_iter = build_merging_iter([newer.as_iter(), owned_page.as_iter()], None);
+            let iter = MergingLeafPageIter::new(merging_iter, 0);
+            assert_eq!(
+                iter.take(3).collect::<Vec<_>>(),
+                [data[0], (Key::new(&[1], 2), Value::Put(&[4])), data[2]]
+            );
+        }
+    }
+
+    #[test]
+    fn merging_leaf_page_iter_keep_deletes() {
+        let data = [
+            (Key::new(&[1], 3), Value::Put(&[3])),
+            (Key::new(&[1], 1), Value::Put(&[1])),
+            (Key::new(&[3], 3), Value::Delete),
+            (Key::new(&[3], 1), Value::Put(&[1])),
+            (Key::new(&[5], 2), Value::Delete),
+        ];
+        let owned_page = OwnedSortedPage::from_slice(&data);
+        let merging_iter = build_merging_iter([owned_page.as_iter()], None);
+        // The deletes shadow older versions that are not in the iterator.
+        let iter = MergingLeafPageIter::new(merging_iter, 3).keep_deletes();
+        assert_eq!(iter.collect::<Vec<_>>(), [data[0], data[2], data[4]]);
+    }
+
+    #[test]
+    fn collapsing_leaf_page_iter() {
+        let data = vec![
+            (Key::new(&[1], 5), Value::Put(&[5])),
+            (Key::new(&[1], 3), Value::Put(&[3])),
+            (Key::new(&[1], 1), Value::Put(&[1])),
+            (Key::new(&[3], 2), Value::Put(&[2])),
+            (Key::new(&[5], 1), Value::Put(&[1])),
+        ];
+        let owned_page = OwnedSortedPage::from_slice(&data);
+        let range_deletes = vec![
+            RangeDelete::new(&[0], Some(&[4]), 4),
+            RangeDelete::new(&[1], Some(&[2]), 1),
+        ];
+
+        let lsn_expect = [
+            (
+                0,
+                vec![
+                    data[0],
+                    (Key::new(&[1], 4), Value::Delete),
+                    data[1],
+                    (Key::new(&[1], 1), Value::Delete),
+                    (Key::new(&[3], 4), Value::Delete),
+                    data[3],
+                    data[4],
+                ],
+            ),
+            (
+                2,
+                vec![
+                    data[0],
+                    (Key::new(&[1], 4), Value::Delete),
+                    data[1],
+                    (Key::new(&[3], 4), Value::Delete),
+                    data[3],
+                    data[4],
+                ],
+            ),
+            (4, vec![data[0], data[4]]),
+        ];
+        for (lsn, expect) in lsn_expect {
+            let merging_iter = build_merging_iter([owned_page.as_iter()], None);
+            let iter = MergingLeafPageIter::new(merging_iter, lsn);
+            let mut iter = CollapsingLeafPageIter::new(iter, range_deletes.clone(), lsn);
+            for _ in 0..2 {
+                assert_eq!((&mut iter).collect::<Vec<_>>(), expect);
+                iter.rewind();
+            }
+        }
     }
 
     #[test]
@@ -456,4 +815,19 @@ mod tests {
             assert_eq!(iter.next(), Some(([1].as_slice(), Index::new(1, 1))));
         }
     }
+
+    #[test]
+    fn shortest_separator_between_keys() {
+        let cases: [(&[u8], &[u8], &[u8]); 5] = [
+            (b"abc", b"abd", b"abd"),
+            (b"abc1234", b"abd5678", b"abd"),
+            (b"abc", b"abcdef", b"abcd"),
+            (b"", b"a", b"a"),
+            (b"a", b"b123", b"b"),
+        ];
+        for (left, right, sep) in cases {
+            assert_eq!(shortest_separator(left, right), sep);
+            assert!(left < sep && sep <= right);
+        }
+    }
 }
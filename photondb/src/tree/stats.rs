@@ -9,6 +9,14 @@ pub struct TreeStats {
     pub success: TxnStats,
     /// Statistics of conflicted transactions.
     pub conflict: TxnStats,
+    /// Statistics of scan readahead.
+    pub readahead: ReadaheadStats,
+    /// Statistics of point lookup prefetches.
+    pub prefetch: PrefetchStats,
+    /// Statistics of contended page updates.
+    pub cas: CasStats,
+    /// Statistics of deletes stored in leaf pages.
+    pub tombstones: TombstoneStats,
 }
 
 impl TreeStats {
@@ -17,6 +25,10 @@ impl TreeStats {
         Self {
             success: self.success.sub(&o.success),
             conflict: self.conflict.sub(&o.conflict),
+            readahead: self.readahead.sub(&o.readahead),
+            prefetch: self.prefetch.sub(&o.prefetch),
+            cas: self.cas.sub(&o.cas),
+            tombstones: self.tombstones.sub(&o.tombstones),
         }
     }
 }
@@ -28,6 +40,7 @@ impl Display for TreeStats {
             "TreeStats_success: read: {}, \
                 write: {}, \
                 split_page: {}, \
+                merge_page: {}, \
                 reconcile_page: {}, \
                 consolidate_page: {}, \
                 read_bytes: {}, \
@@ -35,6 +48,7 @@ impl Display for TreeStats {
             self.success.read,
             self.success.write,
             self.success.split_page,
+            self.success.merge_page,
             self.success.reconcile_page,
             self.success.consolidate_page,
             self.success.read_bytes,
@@ -45,13 +59,41 @@ impl Display for TreeStats {
             "TreeStats_conflict: read: {}, \
                 write: {}, \
                 split_page: {}, \
+                merge_page: {}, \
                 reconcile_page: {}, \
                 consolidate_page: {}",
             self.conflict.read,
             self.conflict.write,
             self.conflict.split_page,
+            self.conflict.merge_page,
             self.conflict.reconcile_page,
             self.conflict.consolidate_page
+        )?;
+        writeln!(
+            f,
+            "TreeStats_readahead: prefetch_pages: {}, \
+                prefetch_hits: {}, \
+                prefetch_misses: {}, \
+                prefetch_failures: {}",
+            self.readahead.prefetch_pages,
+            self.readahead.prefetch_hits,
+            self.readahead.prefetch_misses,
+            self.readahead.prefetch_failures
+        )?;
+        writeln!(
+            f,
+            "TreeStats_prefetch: keys: {}, pages: {}, failures: {}",
+            self.prefetch.keys, self.prefetch.pages, self.prefetch.failures
+        )?;
+        writeln!(
+            f,
+            "TreeStats_cas: retries: {}, busy: {}",
+            self.cas.retries, self.cas.busy
+        )?;
+        writeln!(
+            f,
+            "TreeStats_tombstones: live_tombstones: {}, tombstones_collected: {}",
+            self.tombstones.live_tombstones, self.tombstones.tombstones_collected
         )
     }
 }
@@ -60,6 +102,10 @@ impl Display for TreeStats {
 pub(super) struct AtomicStats {
     pub(super) success: AtomicTxnStats,
     pub(super) conflict: AtomicTxnStats,
+    pub(super) readahead: AtomicReadaheadStats,
+    pub(super) prefetch: AtomicPrefetchStats,
+    pub(super) cas: AtomicCasStats,
+    pub(super) tombstones: AtomicTombstoneStats,
 }
 
 impl AtomicStats {
@@ -67,6 +113,10 @@ impl AtomicStats {
         TreeStats {
             success: self.success.snapshot(),
             conflict: self.conflict.snapshot(),
+            readahead: self.readahead.snapshot(),
+            prefetch: self.prefetch.snapshot(),
+            cas: self.cas.snapshot(),
+            tombstones: self.tombstones.snapshot(),
         }
     }
 }
@@ -77,6 +127,7 @@ pub struct TxnStats {
     pub read: u64,
     pub write: u64,
     pub split_page: u64,
+    pub merge_page: u64,
     pub reconcile_page: u64,
     pub consolidate_page: u64,
     pub rewrite_page: u64,
@@ -91,6 +142,7 @@ pub(super) struct AtomicTxnStats {
     pub(super) read_bytes: Counter,
     pub(super) write_bytes: Counter,
     pub(super) split_page: Counter,
+    pub(super) merge_page: Counter,
     pub(super) reconcile_page: Counter,
     pub(super) consolidate_page: Counter,
     pub(super) rewrite_page: Counter,
@@ -104,6 +156,7 @@ impl AtomicTxnStats {
             read_bytes: self.read_bytes.get(),
             write_bytes: self.write_bytes.get(),
             split_page: self.split_page.get(),
+            merge_page: self.merge_page.get(),
             reconcile_page: self.reconcile_page.get(),
             consolidate_page: self.consolidate_page.get(),
             rewrite_page: self.rewrite_page.get(),
@@ -119,9 +172,178 @@ impl TxnStats {
             read_bytes: self.read_bytes.wrapping_sub(o.read_bytes),
             write_bytes: self.write_bytes.wrapping_sub(o.write_bytes),
             split_page: self.split_page.wrapping_sub(o.split_page),
+            merge_page: self.merge_page.wrapping_sub(o.merge_page),
             reconcile_page: self.reconcile_page.wrapping_sub(o.reconcile_page),
             consolidate_page: self.consolidate_page.wrapping_sub(o.consolidate_page),
             rewrite_page: self.rewrite_page.wrapping_sub(o.rewrite_page),
         }
     }
 }
+
+/// Statistics of scan readahead.
+#[derive(Clone, Debug, Default)]
+pub struct ReadaheadStats {
+    /// The number of pages prefetched ahead of scans.
+    pub prefetch_pages: u64,
+    /// The number of pages returned by scans that had been prefetched.
+    pub prefetch_hits: u64,
+    /// The number of pages returned by scans with readahead that had not been
+    /// prefetched.
+    pub prefetch_misses: u64,
+    /// The number of pages whose prefetches failed.
+    pub prefetch_failures: u64,
+}
+
+impl ReadaheadStats {
+    pub(super) fn sub(&self, o: &ReadaheadStats) -> ReadaheadStats {
+        ReadaheadStats {
+            prefetch_pages: self.prefetch_pages.wrapping_sub(o.prefetch_pages),
+            prefetch_hits: self.prefetch_hits.wrapping_sub(o.prefetch_hits),
+            prefetch_misses: self.prefetch_misses.wrapping_sub(o.prefetch_misses),
+            prefetch_failures: self.prefetch_failures.wrapping_sub(o.prefetch_failures),
+        }
+    }
+}
+
+#[derive(Default)]
+pub(super) struct AtomicReadaheadStats {
+    pub(super) prefetch_pages: Counter,
+    pub(super) prefetch_hits: Counter,
+    pub(super) prefetch_misses: Counter,
+    pub(super) prefetch_failures: Counter,
+}
+
+impl AtomicReadaheadStats {
+    pub(super) fn snapshot(&self) -> ReadaheadStats {
+        ReadaheadStats {
+            prefetch_pages: self.prefetch_pages.get(),
+            prefetch_hits: self.prefetch_hits.get(),
+            prefetch_misses: self.prefetch_misses.get(),
+            prefetch_failures: self.prefetch_failures.get(),
+        }
+    }
+}
+
+/// Statistics of point lookup prefetches.
+#[derive(Clone, Debug, Default)]
+pub struct PrefetchStats {
+    /// The number of keys prefetched.
+    pub keys: u64,
+    /// The number of pages read by prefetches.
+    pub pages: u64,
+    /// The number of keys whose prefetches failed.
+    pub failures: u64,
+}
+
+impl PrefetchStats {
+    pub(super) fn sub(&self, o: &PrefetchStats) -> PrefetchStats {
+        PrefetchStats {
+            keys: self.keys.wrapping_sub(o.keys),
+            pages: self.pages.wrapping_sub(o.pages),
+            failures: self.failures.wrapping_sub(o.failures),
+        }
+    }
+}
+
+#[derive(Default)]
+pub(super) struct AtomicPrefetchStats {
+    pub(super) keys: Counter,
+    pub(super) pages: Counter,
+    pub(super) failures: Counter,
+}
+
+impl AtomicPrefetchStats {
+    pub(super) fn snapshot(&self) -> PrefetchStats {
+        PrefetchStats {
+            keys: self.keys.get(),
+            pages: self.pages.get(),
+            failures: self.failures.get(),
+        }
+    }
+}
+
+/// Statistics of contended page updates.
+#[derive(Clone, Debug, Default)]
+pub struct CasStats {
+    /// The number of page updates that failed because the page was changed
+    /// by others.
+    pub retries: u64,
+    /// The number of writes that failed with [`Error::Busy`] after too many
+    /// retries.
+    ///
+    /// [`Error::Busy`]: crate::Error::Busy
+    pub busy: u64,
+}
+
+impl CasStats {
+    pub(super) fn sub(&self, o: &CasStats) -> CasStats {
+        CasStats {
+            retries: self.retries.wrapping_sub(o.retries),
+            busy: self.busy.wrapping_sub(o.busy),
+        }
+    }
+}
+
+#[derive(Default)]
+pub(super) struct AtomicCasStats {
+    pub(super) retries: Counter,
+    pub(super) busy: Counter,
+}
+
+impl AtomicCasStats {
+    pub(super) fn snapshot(&self) -> CasStats {
+        CasStats {
+            retries: self.retries.get(),
+            busy: self.busy.get(),
+        }
+    }
+}
+
+/// Statistics of deletes stored in leaf pages.
+#[derive(Clone, Debug, Default)]
+pub struct TombstoneStats {
+    /// The number of deletes left in leaf pages.
+    ///
+    /// This only accounts for deletes written or collected since the table
+    /// was opened.
+    pub live_tombstones: u64,
+    /// The number of deletes dropped by consolidations.
+    pub tombstones_collected: u64,
+}
+
+impl TombstoneStats {
+    pub(super) fn sub(&self, o: &TombstoneStats) -> TombstoneStats {
+        TombstoneStats {
+            live_tombstones: self.live_tombstones,
+            tombstones_collected: self
+                .tombstones_collected
+                .wrapping_sub(o.tombstones_collected),
+        }
+    }
+}
+
+#[derive(Default)]
+pub(super) struct AtomicTombstoneStats {
+    pub(super) added: Counter,
+    pub(super) collected: Counter,
+}
+
+impl AtomicTombstoneStats {
+    /// Records a consolidation that turns `input` deletes into `output`
+    /// deletes.
+    pub(super) fn consolidate(&self, input: usize, output: usize) {
+        if output > input {
+            self.added.add((output - input) as u64);
+        } else {
+            self.collected.add((input - output) as u64);
+        }
+    }
+
+    pub(super) fn snapshot(&self) -> TombstoneStats {
+        let collected = self.collected.get();
+        TombstoneStats {
+            live_tombstones: self.added.get().saturating_sub(collected),
+            tombstones_collected: collected,
+        }
+    }
+}
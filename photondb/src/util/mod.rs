@@ -4,3 +4,19 @@ pub(crate) mod latch;
 pub(crate) mod linked_list;
 pub(crate) mod notify;
 pub(crate) mod shutdown;
+
+/// Yields the current task once.
+///
+/// Unlike `photonio::task::yield_now`, this works with any runtime.
+pub(crate) async fn yield_now() {
+    let mut yielded = false;
+    futures::future::poll_fn(|cx| {
+        if yielded {
+            return std::task::Poll::Ready(());
+        }
+        yielded = true;
+        cx.waker().wake_by_ref();
+        std::task::Poll::Pending
+    })
+    .await
+}
@@ -41,6 +41,7 @@ function display_usage() {
   echo -e "\tWRITES\t\t\t\tNumber of writes for which the test runs"
   echo -e "\tWRITE_BUFFER_SIZE_MB\t\tThe size of the write buffer in MB (default: 128)"
   echo -e "\tDISABLE_SPACE_RECLAIMING\tDisable space reclamation (default: false)"
+  echo -e "\tSEPARATED_PAGE_LAYOUT\t\tStore keys and values of a page separately (default: false)"
   echo -e "\tMAX_SPACE_AMP\t\t\tThe max percent of space amplification to reclaim space (default: 10)"
   echo -e "\tSPACE_USED_HIGH\t\t\tThe space watermark which the DB needed to reclaim, in bytes (default: 100G)"
   echo -e "\tUSE_O_DIRECT\t\t\tUse O_DIRECT for user reads and compaction"
@@ -94,6 +95,7 @@ stats_interval_seconds=${STATS_INTERVAL_SECONDS:-60}
 report_interval_seconds=${REPORT_INTERVAL_SECONDS:-1}
 max_space_amp=${MAX_SPACE_AMP:-10}
 disable_space_reclaiming=${DISABLE_SPACE_RECLAIMING:-0}
+separated_page_layout=${SEPARATED_PAGE_LAYOUT:-0}
 
 # o_direct_flags=""
 # if [ ! -z $USE_O_DIRECT ]; then
@@ -128,6 +130,10 @@ if [ $disable_space_reclaiming = 1 ] || [ $disable_space_reclaiming = true ]; th
     const_params="$const_params --disable-space-reclaiming"
 fi
 
+if [ $separated_page_layout = 1 ] || [ $separated_page_layout = true ]; then
+    const_params="$const_params --separated-page-layout"
+fi
+
 # You probably don't want to set both --writes and --duration
 if [ $duration -gt 0 ]; then
   const_params="$const_params --duration=$duration"